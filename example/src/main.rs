@@ -42,9 +42,7 @@ use std::{env, fs::File, io::Read};
 use hlskit::{
     VideoInputType,
     backends::{ffmpeg_backend::FfmpegBackend, gstreamer_backend::GStreamerBackend},
-    models::hls_video_processing_settings::{
-        FfmpegVideoProcessingPreset, HlsVideoProcessingSettings,
-    },
+    models::hls_video_processing_settings::HlsVideoProcessingSettings,
     prelude::VideoProcessor,
     process_video, process_video_from_path,
 };
@@ -52,27 +50,9 @@ use hlskit::{
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let output_profiles = vec![
-        HlsVideoProcessingSettings::new(
-            (1920, 1080),
-            28,
-            None, // no custom audio code - defaulting to AAC
-            None, // no custom audio bitrate
-            FfmpegVideoProcessingPreset::Fast,
-        ),
-        HlsVideoProcessingSettings::new(
-            (1280, 720),
-            28,
-            None, // no custom audio code - defaulting to AAC
-            None, // no custom audio bitrate
-            FfmpegVideoProcessingPreset::Fast,
-        ),
-        HlsVideoProcessingSettings::new(
-            (854, 480),
-            28,
-            None, // no custom audio code - defaulting to AAC
-            None, // no custom audio bitrate
-            FfmpegVideoProcessingPreset::Fast,
-        ),
+        HlsVideoProcessingSettings::p1080(),
+        HlsVideoProcessingSettings::p720(),
+        HlsVideoProcessingSettings::p480(),
     ];
 
     println!("Processing video from memory");
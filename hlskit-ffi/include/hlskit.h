@@ -0,0 +1,103 @@
+// SPDX-License-Identifier: LGPL-3.0-only
+/*
+ * Copyright © 2025 The HlsKit Project
+ *
+ * This software is licensed under the GNU Lesser General Public License v3.0 (LGPLv3).
+ * All contributions adhere to the LGPLv3 and the HlsKit Contributor License Agreement (CLA).
+ * A copy of the LGPLv3 can be found at https://www.gnu.org/licenses/lgpl-3.0.html
+ *
+ * HlsKit Contributor License Agreement
+ *
+ * By contributing to or modifying HlsKit, you agree to the following terms:
+ *
+ * 1. Collective Ownership:
+ * The HlsKit project incorporates original code and all contributions as a collective work,
+ * licensed under LGPLv3. Once submitted, contributions become part of the shared HlsKit
+ * ecosystem and cannot be reclaimed, reassigned, or withdrawn. Contributions to your own
+ * forks remain yours unless submitted here, at which point they join this collective whole under LGPLv3.
+ *
+ * 2. Definition of Contribution:
+ * You are considered a contributor if you modify the library in any form (including forks,
+ * wrappers, libraries, or extensions that alter its behavior), whether or not you submit
+ * your changes directly to this repository. All such modifications are part of the broader
+ * HlsKit ecosystem and are subject to this CLA.
+ *
+ * 3. Distribution of Modifications:
+ * If you distribute a modified version of HlsKit, you must license your modifications under
+ * LGPLv3 (with source code available as required by the license) and ensure they are
+ * adoptable by the HlsKit ecosystem (publicly available and compatible).
+ *
+ * 4. Networked Use of Modifications:
+ * If you use a modified version of HlsKit in a networked application, you must provide the
+ * source code of your modifications under LGPLv3 and notify the HlsKit project
+ * (e.g., via email to [higashikataengels@icloud.com]). This does not apply to the use of
+ * the unmodified library in proprietary software, which remains permissible under LGPLv3.
+ *
+ * 5. Scope:
+ * These terms apply to all contributions and modifications derived from the HlsKit project.
+ * The use of the unmodified library in proprietary software is governed solely by the LGPLv3.
+ */
+
+#ifndef HLSKIT_H
+#define HLSKIT_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define HLSKIT_OK 0
+/* A required pointer was null, or a string wasn't valid UTF-8 */
+#define HLSKIT_ERROR_INVALID_ARGUMENT 1
+#define HLSKIT_ERROR_PROCESSING 2
+/* HlsKit panicked; the message is still available from hlskit_last_error */
+#define HLSKIT_ERROR_PANIC 3
+
+/* A processed video: master playlist, media playlists and segments, held in memory */
+typedef struct HlsKitVideo HlsKitVideo;
+
+/*
+ * Called on the thread that called hlskit_process_video while it runs; percent is negative
+ * when the source duration is unknown
+ */
+typedef void (*HlsKitProgressCallback)(int32_t stream_index, int32_t width, int32_t height,
+                                       double percent, void *user_data);
+
+/*
+ * Encodes the video at input_path into HLS, in memory, storing the result in *out_video.
+ * config_path names a TOML, YAML or JSON processing config with the ladder and encryption;
+ * when NULL, the 1080p ladder is used. A config without profiles, or one that doesn't parse,
+ * fails with HLSKIT_ERROR_INVALID_ARGUMENT. progress may be NULL. Returns HLSKIT_OK or an
+ * error code, hlskit_last_error describing the failure. Free the result with hlskit_video_free.
+ */
+int32_t hlskit_process_video(const char *input_path, const char *config_path,
+                             HlsKitProgressCallback progress, void *user_data,
+                             HlsKitVideo **out_video);
+
+/* The message of the last failed call on this thread, NULL when it succeeded */
+const char *hlskit_last_error(void);
+
+void hlskit_video_free(HlsKitVideo *video);
+
+/* Accessors; returned data and strings live as long as the video */
+const uint8_t *hlskit_video_master_playlist(const HlsKitVideo *video, size_t *len);
+size_t hlskit_video_resolution_count(const HlsKitVideo *video);
+int32_t hlskit_video_resolution(const HlsKitVideo *video, size_t index, int32_t *width,
+                                int32_t *height);
+const char *hlskit_video_playlist_name(const HlsKitVideo *video, size_t index);
+const uint8_t *hlskit_video_playlist(const HlsKitVideo *video, size_t index, size_t *len);
+size_t hlskit_video_segment_count(const HlsKitVideo *video, size_t index);
+const char *hlskit_video_segment_name(const HlsKitVideo *video, size_t index, size_t segment);
+const uint8_t *hlskit_video_segment(const HlsKitVideo *video, size_t index, size_t segment,
+                                    size_t *len);
+
+/* Writes every playlist and segment under dir, creating it as needed */
+int32_t hlskit_video_write_to_dir(const HlsKitVideo *video, const char *dir);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* HLSKIT_H */
@@ -38,40 +38,149 @@
  * The use of the unmodified library in proprietary software is governed solely by the LGPLv3.
  */
 
-use std::path::Path;
+use std::{
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use futures::future::try_join_all;
+use tokio::sync::Semaphore;
 
 use crate::{
     models::{
-        hls_video::HlsVideoResolution, hls_video_processing_settings::HlsVideoProcessingSettings,
+        chunked_encoding::{ChunkedEncoding, SourceChunk},
+        encoder_version::EncoderVersion,
+        hls_video_manifest::HlsVideoResolutionManifest,
+        hls_video_processing_settings::{EncodeMode, HlsVideoProcessingSettings, SegmentType},
+        processing_plan::ProfilePlan,
+        profile_progress::ProfileProgress,
+        resource_usage::ResourceUsage,
     },
     tools::{
-        command_runner::run_command, ffmpeg_command_builder::FfmpegCommandBuilder,
-        hlskit_error::HlsKitError, internals::hls_output_config::HlsOutputEncryptionConfig,
-        segment_tools::read_playlist_and_segments,
+        binary_tools::{
+            detect_version, parse_ffmpeg_version, resolve_binary, FFMPEG_PATH_ENV,
+            MIN_FFMPEG_VERSION,
+        },
+        chunk_tools::join_chunk_renditions,
+        codec_tools::rendition_codecs,
+        command_runner::run_command_with_context,
+        ffmpeg_command_builder::{ffmpeg_video_encoder, FfmpegCommand, FfmpegCommandBuilder},
+        ffmpeg_progress::{FfmpegProgressParser, FfmpegProgressUpdate},
+        ffmpeg_stderr::classify_ffmpeg_error,
+        hlskit_error::HlsKitError,
+        internals::hls_output_config::HlsOutputEncryptionConfig,
+        probe_tools::probe_keyframes,
+        processing_context::ProcessingContext,
+        segment_tools::{playlist_segment_paths, SegmentWatcher},
     },
     traits::video_processing_backend::VideoProcessingBackend,
     VideoProcessorEncryptionSettings,
 };
 
-#[derive(Default)]
-pub struct FfmpegBackend;
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FfmpegBackend {
+    binary: PathBuf,
+    single_decode: bool,
+    chunked_encoding: Option<ChunkedEncoding>,
+}
 
-impl VideoProcessingBackend for FfmpegBackend {
-    async fn process_profile(
+impl Default for FfmpegBackend {
+    /// Uses `$HLSKIT_FFMPEG_PATH` when set, `ffmpeg` from PATH otherwise
+    fn default() -> Self {
+        Self {
+            binary: resolve_binary(FFMPEG_PATH_ENV, "ffmpeg"),
+            single_decode: false,
+            chunked_encoding: None,
+        }
+    }
+}
+
+impl FfmpegBackend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Runs this ffmpeg binary instead of the one from the environment or PATH
+    pub fn with_binary<P: AsRef<Path>>(mut self, binary: P) -> Self {
+        self.binary = binary.as_ref().to_path_buf();
+        self
+    }
+
+    pub fn binary(&self) -> &Path {
+        &self.binary
+    }
+
+    /// Encodes every profile of a job in one ffmpeg run, decoding the source once and splitting
+    /// it with `filter_complex` instead of decoding it again for every profile. Per-profile
+    /// failures, timeouts and `before_profile` hooks then apply to the whole run
+    pub fn with_single_decode(mut self, single_decode: bool) -> Self {
+        self.single_decode = single_decode;
+        self
+    }
+
+    /// Encodes long sources in keyframe-aligned chunks side by side, joining them into each
+    /// rendition afterwards. Only MPEG-TS transcodes without scene-aware segmentation or
+    /// `before_profile` hooks are split, other profiles are encoded in one run
+    pub fn with_chunked_encoding(mut self, chunked_encoding: ChunkedEncoding) -> Self {
+        self.chunked_encoding = Some(chunked_encoding);
+        self
+    }
+
+    /// Detects the installed version, failing when the binary is missing or too old
+    pub async fn version(&self) -> Result<EncoderVersion, HlsKitError> {
+        detect_version(
+            &self.binary,
+            ["-version"],
+            parse_ffmpeg_version,
+            MIN_FFMPEG_VERSION,
+        )
+        .await
+    }
+
+    /// The command encoding `profile` on its own, with its plan
+    fn profile_command(
         &self,
-        input: String,
+        input: &str,
         profile: &HlsVideoProcessingSettings,
         output_dir: &Path,
         stream_index: i32,
         encryption: Option<&VideoProcessorEncryptionSettings>,
-    ) -> Result<HlsVideoResolution, HlsKitError> {
+        context: &ProcessingContext,
+    ) -> Result<(FfmpegCommand, ProfilePlan), HlsKitError> {
+        let (mut command_builder, mut plan) = self.profile_builder(
+            input,
+            profile,
+            output_dir,
+            stream_index,
+            encryption,
+            context,
+        );
+        let command = command_builder.build_command()?;
+        plan.command = command.to_args();
+
+        Ok((command, plan))
+    }
+
+    /// The command builder for `profile`, with a plan still missing the command
+    fn profile_builder(
+        &self,
+        input: &str,
+        profile: &HlsVideoProcessingSettings,
+        output_dir: &Path,
+        stream_index: i32,
+        encryption: Option<&VideoProcessorEncryptionSettings>,
+        context: &ProcessingContext,
+    ) -> (FfmpegCommandBuilder, ProfilePlan) {
         let (width, height) = profile.resolution;
 
         let segment_filename = format!(
-            "{}/data_{}_%03d.ts",
+            "{}/data_{}_%03d.{}",
             output_dir.to_str().unwrap(),
-            stream_index
+            stream_index,
+            profile.segment_type.extension()
         );
+        let init_segment_name = format!("init_{stream_index}.mp4");
 
         let playlist_filename = format!(
             "{}/playlist_{}.m3u8",
@@ -86,32 +195,425 @@ impl VideoProcessingBackend for FfmpegBackend {
 
         let encryption_key_url = encryption.map(|enc| enc.encryption_key_url.as_str());
 
-        let command = FfmpegCommandBuilder::new()
-            .input(&input)
+        // Segments are cut on the planned scene keyframes, which are at least the minimum
+        // segment duration apart
+        let adjustments = context.source_adjustments();
+        let segment_duration = context
+            .scene_segmentation()
+            .filter(|_| !adjustments.scene_keyframes.is_empty())
+            .map_or(10, |segmentation| segmentation.hls_time());
+
+        let mut command_builder = FfmpegCommandBuilder::new()
+            .binary(&self.binary)
+            .input(input)
             .dimensions(width, height)
             .crf(profile.constant_rate_factor)
             .preset(profile.preset.value())
+            .video_codec(profile.video_codec)
             .enable_hls(
                 &segment_filename,
                 None, // Default playlist type
                 encryption_key_url,
                 encryption_settings,
-                10, // Segment duration in seconds
+                segment_duration,
             )
-            .output(&playlist_filename)
-            .build()?;
+            .output(&playlist_filename);
+
+        if context.wants_segment_updates() {
+            command_builder = command_builder.enable_progress();
+        }
+
+        if !adjustments.scene_keyframes.is_empty() {
+            command_builder = command_builder.force_keyframes(&adjustments.scene_keyframes);
+        } else if let Some(interval) = profile.keyframe_interval {
+            command_builder = command_builder.keyframe_interval(interval);
+        }
+
+        if adjustments.deinterlaced {
+            command_builder = command_builder.deinterlace();
+        }
+
+        if let Some(frame_rate) = adjustments.frame_rate_for(profile) {
+            command_builder = command_builder.constant_frame_rate(frame_rate);
+        }
+
+        command_builder = command_builder.audio(profile.audio_codec, profile.audio_bitrate);
+
+        if context.audio_sync().is_enabled() {
+            command_builder = command_builder.audio_sync(context.audio_sync());
+        }
+
+        if adjustments.tone_maps(profile) {
+            command_builder = command_builder.tone_map_to_sdr();
+        }
+        command_builder = command_builder.video_range(adjustments.video_range_for(profile));
+
+        if let Some(threads) = context.resource_limits().threads {
+            command_builder = command_builder.threads(threads);
+        }
+
+        if let Some(chunk) = context.source_chunk() {
+            command_builder = command_builder.source_chunk(chunk);
+        }
 
-        // Execute the FFmpeg command
-        run_command(&command).await?;
+        if profile.encode_mode == EncodeMode::Passthrough {
+            command_builder = command_builder.copy_streams();
+        }
 
-        // Read the generated playlist and segments into memory
-        let resolution = read_playlist_and_segments(
-            &playlist_filename,
-            &segment_filename,
-            profile.resolution,
+        if profile.segment_type == SegmentType::Fmp4 {
+            command_builder = command_builder.fmp4(&init_segment_name);
+        }
+
+        if !profile.extra_args.is_empty() {
+            let extra_args: Vec<&str> = profile.extra_args.iter().map(String::as_str).collect();
+            command_builder = command_builder.extra_args(&extra_args);
+        }
+
+        let plan = ProfilePlan {
             stream_index,
+            resolution: profile.resolution,
+            command: Vec::new(),
+            playlist_name: format!("playlist_{stream_index}.m3u8"),
+            playlist_path: playlist_filename.into(),
+            segment_pattern: segment_filename,
+            init_segment_path: (profile.segment_type == SegmentType::Fmp4)
+                .then(|| output_dir.join(init_segment_name)),
+        };
+
+        (command_builder, plan)
+    }
+
+    fn report_progress(
+        context: &ProcessingContext,
+        plan: &ProfilePlan,
+        update: &FfmpegProgressUpdate,
+    ) {
+        let out_time = update.out_time.unwrap_or_default();
+        context.report_progress(ProfileProgress {
+            stream_index: plan.stream_index,
+            resolution: plan.resolution,
+            frame: update.frame,
+            out_time,
+            speed: update.speed,
+            percent: if update.finished {
+                Some(100.0)
+            } else {
+                context.percent_of(out_time)
+            },
+            eta: None,
+        });
+    }
+
+    /// The chunks to encode `profile` in, `None` when it is encoded in one run
+    async fn source_chunks(
+        &self,
+        input: &str,
+        profile: &HlsVideoProcessingSettings,
+        context: &ProcessingContext,
+    ) -> Option<Vec<SourceChunk>> {
+        let chunked_encoding = self.chunked_encoding?;
+        let duration = context
+            .source_duration()
+            .filter(|duration| chunked_encoding.applies_to(*duration))?;
+        if profile.encode_mode != EncodeMode::Transcode
+            || profile.segment_type != SegmentType::MpegTs
+            || !context.source_adjustments().scene_keyframes.is_empty()
+            || context.hooks().has_before_profile()
+            || context.source_chunk().is_some()
+        {
+            return None;
+        }
+
+        let keyframes = match probe_keyframes(input).await {
+            Ok(keyframes) => keyframes,
+            Err(e) => {
+                tracing::warn!("Encoding in one run, source keyframes unavailable: {}", e);
+                return None;
+            }
+        };
+        let chunks = chunked_encoding.plan_chunks(&keyframes, duration);
+        (chunks.len() > 1).then_some(chunks)
+    }
+
+    /// Encodes `chunks` of the source side by side, each into its own directory, then joins
+    /// them into the rendition of `plan`
+    async fn encode_chunked(
+        &self,
+        input: &str,
+        profile: &HlsVideoProcessingSettings,
+        plan: ProfilePlan,
+        encryption: Option<&VideoProcessorEncryptionSettings>,
+        context: &ProcessingContext,
+        chunks: Vec<SourceChunk>,
+    ) -> Result<HlsVideoResolutionManifest, HlsKitError> {
+        let output_dir = plan.playlist_path.parent().unwrap_or(Path::new(""));
+        let chunks_dir = output_dir.join(format!("chunks_{}", plan.stream_index));
+
+        let mut commands = Vec::with_capacity(chunks.len());
+        let mut chunk_playlists = Vec::with_capacity(chunks.len());
+        for (index, chunk) in chunks.iter().enumerate() {
+            let chunk_dir = chunks_dir.join(format!("{index:03}"));
+            tokio::fs::create_dir_all(&chunk_dir).await?;
+
+            let (command_builder, chunk_plan) = self.profile_builder(
+                input,
+                profile,
+                &chunk_dir,
+                plan.stream_index,
+                encryption,
+                context,
+            );
+            commands.push(
+                command_builder
+                    .source_chunk(*chunk)
+                    .build_command()?
+                    .to_args(),
+            );
+            chunk_playlists.push(chunk_plan.playlist_path);
+        }
+
+        let started = Instant::now();
+        let chunked_encoding = self.chunked_encoding.unwrap_or_default();
+        let semaphore = Semaphore::new(chunked_encoding.max_parallel_chunks);
+        let progress = Mutex::new(vec![Duration::ZERO; chunks.len()]);
+
+        let mut encodes = Vec::with_capacity(commands.len());
+        for (index, command) in commands.iter().enumerate() {
+            encodes.push(Self::encode_chunk(
+                command, index, &semaphore, &progress, &plan, context,
+            ));
+        }
+        let mut resource_usage = ResourceUsage::default();
+        for usage in try_join_all(encodes).await? {
+            resource_usage.merge_concurrent(&usage);
+        }
+        resource_usage.wall_time = started.elapsed();
+
+        join_chunk_renditions(&chunk_playlists, &plan.segment_pattern, &plan.playlist_path).await?;
+        tokio::fs::remove_dir_all(&chunks_dir).await?;
+
+        Self::report_progress(
+            context,
+            &plan,
+            &FfmpegProgressUpdate {
+                out_time: context.source_duration(),
+                finished: true,
+                ..Default::default()
+            },
+        );
+        let stream_index = plan.stream_index;
+        let manifest = Self::rendition_manifest(plan, profile, resource_usage, context).await?;
+        context.report_segments_written(stream_index, manifest.segment_paths.clone());
+
+        Ok(manifest)
+    }
+
+    /// Runs the command encoding one chunk once `semaphore` lets it, reporting the progress of
+    /// every chunk together as the profile's
+    async fn encode_chunk(
+        command: &[String],
+        chunk_index: usize,
+        semaphore: &Semaphore,
+        progress: &Mutex<Vec<Duration>>,
+        plan: &ProfilePlan,
+        context: &ProcessingContext,
+    ) -> Result<ResourceUsage, HlsKitError> {
+        let _permit = semaphore
+            .acquire()
+            .await
+            .expect("semaphore is never closed");
+
+        let mut progress_parser = FfmpegProgressParser::new();
+        run_command_with_context(command, context, |line| {
+            let Some(update) = progress_parser.parse_line(line) else {
+                return;
+            };
+            let Ok(mut chunk_times) = progress.lock() else {
+                return;
+            };
+            if let Some(out_time) = update.out_time {
+                chunk_times[chunk_index] = out_time;
+            }
+            let out_time = chunk_times.iter().sum();
+            drop(chunk_times);
+
+            Self::report_progress(
+                context,
+                plan,
+                &FfmpegProgressUpdate {
+                    out_time: Some(out_time),
+                    ..Default::default()
+                },
+            );
+        })
+        .await
+        .map_err(classify_ffmpeg_error)
+    }
+
+    async fn rendition_manifest(
+        plan: ProfilePlan,
+        profile: &HlsVideoProcessingSettings,
+        resource_usage: ResourceUsage,
+        context: &ProcessingContext,
+    ) -> Result<HlsVideoResolutionManifest, HlsKitError> {
+        let video_range = context.source_adjustments().video_range_for(profile);
+        let encoder = ffmpeg_video_encoder(profile.video_codec, video_range);
+        Ok(HlsVideoResolutionManifest {
+            resolution: plan.resolution,
+            segment_paths: playlist_segment_paths(&plan.playlist_path).await?,
+            codecs: rendition_codecs(
+                encoder,
+                profile,
+                plan.resolution,
+                video_range,
+                context.source_has_audio() != Some(false),
+                context,
+            ),
+            playlist_name: plan.playlist_name,
+            playlist_path: plan.playlist_path,
+            video_range,
+            frame_rate: context.source_adjustments().frame_rate_for(profile),
+            quality: None,
+            encoder: encoder.to_string(),
+            resource_usage,
+            encryption_iv: None,
+        })
+    }
+}
+
+impl VideoProcessingBackend for FfmpegBackend {
+    fn name(&self) -> &'static str {
+        "ffmpeg"
+    }
+
+    fn plan_profile(
+        &self,
+        input: &str,
+        profile: &HlsVideoProcessingSettings,
+        output_dir: &Path,
+        stream_index: i32,
+        encryption: Option<&VideoProcessorEncryptionSettings>,
+        context: &ProcessingContext,
+    ) -> Result<ProfilePlan, HlsKitError> {
+        self.profile_command(
+            input,
+            profile,
+            output_dir,
+            stream_index,
+            encryption,
+            context,
+        )
+        .map(|(_, plan)| plan)
+    }
+
+    async fn encode_profile(
+        &self,
+        input: String,
+        profile: &HlsVideoProcessingSettings,
+        output_dir: &Path,
+        stream_index: i32,
+        encryption: Option<&VideoProcessorEncryptionSettings>,
+        context: &ProcessingContext,
+    ) -> Result<HlsVideoResolutionManifest, HlsKitError> {
+        let mut plan = self.plan_profile(
+            &input,
+            profile,
+            output_dir,
+            stream_index,
+            encryption,
+            context,
         )?;
+        context.hooks().run_before_profile(&mut plan)?;
+
+        if let Some(chunks) = self.source_chunks(&input, profile, context).await {
+            return self
+                .encode_chunked(&input, profile, plan, encryption, context, chunks)
+                .await;
+        }
+
+        // Execute the FFmpeg command, forwarding progress updates as they arrive
+        let mut progress_parser = FfmpegProgressParser::new();
+        let mut segment_watcher = SegmentWatcher::new(&plan.segment_pattern)
+            .with_init_segment(plan.init_segment_path.clone());
+        let resource_usage = run_command_with_context(&plan.command, context, |line| {
+            if let Some(update) = progress_parser.parse_line(line) {
+                Self::report_progress(context, &plan, &update);
+                context.report_segments_written(stream_index, segment_watcher.poll());
+            }
+        })
+        .await
+        .map_err(classify_ffmpeg_error)?;
+
+        context.report_segments_written(stream_index, segment_watcher.finish());
+
+        Self::rendition_manifest(plan, profile, resource_usage, context).await
+    }
+
+    fn encodes_together(&self, profiles: &[HlsVideoProcessingSettings]) -> bool {
+        self.single_decode
+            && profiles.len() > 1
+            && profiles
+                .iter()
+                .all(|profile| profile.encode_mode == EncodeMode::Transcode)
+    }
+
+    /// Decodes the source once, splitting it into an encode and HLS output per profile
+    async fn encode_profiles(
+        &self,
+        input: String,
+        profiles: &[HlsVideoProcessingSettings],
+        output_dir: &Path,
+        encryption: Option<&VideoProcessorEncryptionSettings>,
+        context: &ProcessingContext,
+    ) -> Result<Vec<HlsVideoResolutionManifest>, HlsKitError> {
+        let mut commands = Vec::with_capacity(profiles.len());
+        let mut plans = Vec::with_capacity(profiles.len());
+        for (index, profile) in profiles.iter().enumerate() {
+            let (command, plan) = self
+                .profile_command(
+                    &input,
+                    profile,
+                    output_dir,
+                    index as i32,
+                    profile.effective_encryption(encryption),
+                    context,
+                )
+                .map_err(|e| e.with_profile(index as i32, profile.resolution))?;
+            commands.push(command);
+            plans.push(plan);
+        }
+
+        let command = FfmpegCommand::split_args(&commands);
+
+        let mut segment_watchers: Vec<SegmentWatcher> = plans
+            .iter()
+            .map(|plan| {
+                SegmentWatcher::new(&plan.segment_pattern)
+                    .with_init_segment(plan.init_segment_path.clone())
+            })
+            .collect();
+        let mut progress_parser = FfmpegProgressParser::new();
+        // The outputs share the decoded stream, so one progress report stands for all of them
+        let resource_usage = run_command_with_context(&command, context, |line| {
+            if let Some(update) = progress_parser.parse_line(line) {
+                for (plan, segment_watcher) in plans.iter().zip(&mut segment_watchers) {
+                    Self::report_progress(context, plan, &update);
+                    context.report_segments_written(plan.stream_index, segment_watcher.poll());
+                }
+            }
+        })
+        .await
+        .map_err(classify_ffmpeg_error)?;
+
+        let mut manifests = Vec::with_capacity(plans.len());
+        for ((plan, profile), mut segment_watcher) in
+            plans.into_iter().zip(profiles).zip(segment_watchers)
+        {
+            context.report_segments_written(plan.stream_index, segment_watcher.finish());
+            // Usage of the shared run, there is no per-output figure
+            manifests.push(Self::rendition_manifest(plan, profile, resource_usage, context).await?);
+        }
 
-        Ok(resolution)
+        Ok(manifests)
     }
 }
@@ -38,40 +38,116 @@
  * The use of the unmodified library in proprietary software is governed solely by the LGPLv3.
  */
 
-use std::path::Path;
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
 
 use crate::{
     models::{
-        hls_video::HlsVideoResolution, hls_video_processing_settings::HlsVideoProcessingSettings,
+        dynamic_range::VideoRange,
+        encoder_version::EncoderVersion,
+        hls_video_manifest::HlsVideoResolutionManifest,
+        hls_video_processing_settings::{EncodeMode, HlsVideoProcessingSettings, SegmentType},
+        processing_plan::ProfilePlan,
+        profile_progress::ProfileProgress,
+        resource_usage::ResourceUsage,
     },
     tools::{
-        command_runner::run_command, gstreamer_command_builder::GStreamerCommandBuilder,
-        hlskit_error::HlsKitError, internals::hls_output_config::HlsOutputEncryptionConfig,
-        segment_tools::read_playlist_and_segments,
+        binary_tools::{
+            detect_version, parse_gstreamer_version, resolve_binary, GST_LAUNCH_PATH_ENV,
+            MIN_GSTREAMER_VERSION,
+        },
+        codec_tools::rendition_codecs,
+        command_runner::run_command_with_context,
+        gstreamer_command_builder::{
+            gstreamer_video_encoder, GStreamerCommand, GStreamerCommandBuilder,
+        },
+        gstreamer_progress::{GStreamerProgressParser, GStreamerProgressUpdate},
+        gstreamer_stderr::classify_gstreamer_error,
+        hlskit_error::HlsKitError,
+        processing_context::ProcessingContext,
+        segment_tools::{playlist_segment_paths, SegmentWatcher},
     },
     traits::video_processing_backend::VideoProcessingBackend,
     VideoProcessorEncryptionSettings,
 };
 
-#[derive(Default)]
-pub struct GStreamerBackend;
+#[cfg(feature = "encryption")]
+use crate::tools::encryption_tools::encrypt_rendition_files;
 
-impl VideoProcessingBackend for GStreamerBackend {
-    async fn process_profile(
+/// Encodes through `gst-launch-1.0`. In-memory inputs are written to the pipeline's stdin and
+/// read with `fdsrc` unless the job has to read the source more than once
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GStreamerBackend {
+    binary: PathBuf,
+}
+
+impl Default for GStreamerBackend {
+    /// Uses `$HLSKIT_GST_LAUNCH_PATH` when set, `gst-launch-1.0` from PATH otherwise
+    fn default() -> Self {
+        Self {
+            binary: resolve_binary(GST_LAUNCH_PATH_ENV, "gst-launch-1.0"),
+        }
+    }
+}
+
+impl GStreamerBackend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Runs this gst-launch binary instead of the one from the environment or PATH
+    pub fn with_binary<P: AsRef<Path>>(mut self, binary: P) -> Self {
+        self.binary = binary.as_ref().to_path_buf();
+        self
+    }
+
+    pub fn binary(&self) -> &Path {
+        &self.binary
+    }
+
+    /// Detects the installed version, failing when the binary is missing or too old
+    pub async fn version(&self) -> Result<EncoderVersion, HlsKitError> {
+        detect_version(
+            &self.binary,
+            ["--version"],
+            parse_gstreamer_version,
+            MIN_GSTREAMER_VERSION,
+        )
+        .await
+    }
+
+    /// The pipeline encoding `profile` on its own, with its plan
+    fn profile_command(
         &self,
-        input: String,
+        input: &str,
         profile: &HlsVideoProcessingSettings,
         output_dir: &Path,
         stream_index: i32,
         encryption: Option<&VideoProcessorEncryptionSettings>,
-    ) -> Result<HlsVideoResolution, HlsKitError> {
+        context: &ProcessingContext,
+    ) -> Result<(GStreamerCommand, ProfilePlan), HlsKitError> {
+        if profile.encode_mode == EncodeMode::Passthrough {
+            return Err(HlsKitError::UnsupportedOperation {
+                error: "GStreamer backend does not support passthrough profiles".to_string(),
+            });
+        }
+        if context.source_chunk().is_some() {
+            return Err(HlsKitError::UnsupportedOperation {
+                error: "GStreamer backend does not encode source chunks".to_string(),
+            });
+        }
+
         let (width, height) = profile.resolution;
 
         let segment_filename = format!(
-            "{}/data_{}_%03d.ts",
+            "{}/data_{}_%03d.{}",
             output_dir.to_str().unwrap(),
-            stream_index
+            stream_index,
+            profile.segment_type.extension()
         );
+        let init_segment_name = format!("init_{stream_index}.mp4");
 
         let playlist_filename = format!(
             "{}/playlist_{}.m3u8",
@@ -79,45 +155,355 @@ impl VideoProcessingBackend for GStreamerBackend {
             stream_index
         );
 
-        let encryption_settings = encryption.map(|enc| HlsOutputEncryptionConfig {
-            encryption_key_path: enc.encryption_key_path.clone(),
-            iv: enc.iv.clone(),
-        });
-
-        let encryption_key_url = encryption.map(|enc| enc.encryption_key_url.as_str());
+        // hlssink can't encrypt, segments are encrypted once written, see `encode_profile`
+        if encryption.is_some() && cfg!(not(feature = "encryption")) {
+            return Err(HlsKitError::UnsupportedOperation {
+                error: "GStreamer backend encryption requires the `encryption` feature".to_string(),
+            });
+        }
 
-        let command = GStreamerCommandBuilder::new()
-            .input(&input)
+        let mut command_builder = GStreamerCommandBuilder::new()
+            .binary(&self.binary)
+            .input(input)
             .dimensions(width, height)
             .bitrate(profile.constant_rate_factor)
+            .preset(profile.preset)
+            .video_codec(profile.video_codec)
             .enable_hls(
                 &segment_filename,
                 None, // Default playlist type
-                encryption_key_url,
-                encryption_settings,
+                None,
                 10, // Segment duration in seconds
             )
-            .output(&playlist_filename)
-            .build()?;
+            .output(&playlist_filename);
 
-        let gtreamer_pipeline: &Vec<String> = &command
-            .iter()
-            .flat_map(|arg| {
-                arg.split_whitespace()
-                    .map(|s| s.to_string())
-                    .collect::<Vec<_>>()
-            })
-            .collect();
+        if context.wants_segment_updates() {
+            command_builder = command_builder.enable_progress();
+        }
+
+        let adjustments = context.source_adjustments();
+        if adjustments.deinterlaced {
+            command_builder = command_builder.deinterlace();
+        }
+
+        if let Some(frame_rate) = adjustments.frame_rate_for(profile) {
+            command_builder = command_builder.constant_frame_rate(frame_rate);
+        }
+
+        if !adjustments.scene_keyframes.is_empty() {
+            tracing::warn!("Scene-aware segmentation is ignored by the GStreamer backend");
+        }
+
+        if let Some(interval) = profile.keyframe_interval {
+            let frame_rate = adjustments
+                .frame_rate_for(profile)
+                .map(|frame_rate| frame_rate.as_f64())
+                .or(context.source_frame_rate());
+            match frame_rate {
+                Some(frame_rate) => {
+                    let frames = (interval.as_secs_f64() * frame_rate).round() as u32;
+                    command_builder = command_builder.max_keyframe_distance(frames);
+                }
+                None => tracing::warn!(
+                    "Unknown frame rate, the keyframe interval is ignored by the GStreamer backend"
+                ),
+            }
+        }
+
+        if context.audio_sync().is_enabled() {
+            tracing::warn!("Audio sync corrections are ignored by the GStreamer backend");
+        }
+
+        if adjustments.hdr_source.is_some() {
+            tracing::warn!(
+                "The GStreamer backend can neither tone-map nor keep HDR, colors will be off"
+            );
+        }
+
+        if let Some(threads) = context.resource_limits().threads {
+            command_builder = command_builder.threads(threads);
+        }
+
+        // Like ffmpeg, encode the source's audio when it has some
+        match context.source_has_audio() {
+            Some(true) => {
+                command_builder = command_builder.audio(profile.audio_codec, profile.audio_bitrate)
+            }
+            Some(false) => {}
+            None => {
+                tracing::warn!("Source audio unknown, the GStreamer backend encodes video only")
+            }
+        }
+
+        if profile.segment_type == SegmentType::Fmp4 {
+            command_builder =
+                command_builder.fmp4(&output_dir.join(&init_segment_name).to_string_lossy());
+        }
+
+        if !profile.extra_args.is_empty() {
+            tracing::warn!("Extra ffmpeg arguments are ignored by the GStreamer backend");
+        }
+
+        let command = command_builder.build_command()?;
+
+        let plan = ProfilePlan {
+            stream_index,
+            resolution: profile.resolution,
+            command: launch_args(command.to_args()),
+            playlist_name: format!("playlist_{stream_index}.m3u8"),
+            playlist_path: playlist_filename.into(),
+            segment_pattern: segment_filename,
+            init_segment_path: (profile.segment_type == SegmentType::Fmp4)
+                .then(|| output_dir.join(init_segment_name)),
+        };
+
+        Ok((command, plan))
+    }
+
+    /// Forwards a progressreport message as the same progress event ffmpeg produces
+    fn report_progress(
+        context: &ProcessingContext,
+        plan: &ProfilePlan,
+        update: &GStreamerProgressUpdate,
+        started: Instant,
+    ) {
+        let out_time = update.out_time.unwrap_or_default();
+        let elapsed = started.elapsed().as_secs_f64();
+        context.report_progress(ProfileProgress {
+            stream_index: plan.stream_index,
+            resolution: plan.resolution,
+            frame: None,
+            out_time,
+            speed: (update.out_time.is_some() && elapsed > 0.0)
+                .then(|| out_time.as_secs_f64() / elapsed),
+            percent: context.percent_of(out_time).or(update.percent),
+            eta: None,
+        });
+    }
+
+    fn report_done(context: &ProcessingContext, plan: &ProfilePlan) {
+        if context.wants_progress() {
+            context.report_progress(ProfileProgress {
+                stream_index: plan.stream_index,
+                resolution: plan.resolution,
+                frame: None,
+                out_time: context.source_duration().unwrap_or(Duration::ZERO),
+                speed: None,
+                percent: Some(100.0),
+                eta: None,
+            });
+        }
+    }
+
+    /// Whether the rendition of `profile` carries the source's audio, which fMP4 output leaves
+    /// out
+    fn encodes_audio(profile: &HlsVideoProcessingSettings, context: &ProcessingContext) -> bool {
+        context.source_has_audio() == Some(true) && profile.segment_type != SegmentType::Fmp4
+    }
+
+    /// Encrypts the rendition written by `plan` if needed and reports its last segments
+    async fn finish_rendition(
+        plan: ProfilePlan,
+        profile: &HlsVideoProcessingSettings,
+        encryption: Option<&VideoProcessorEncryptionSettings>,
+        mut segment_watcher: SegmentWatcher,
+        resource_usage: ResourceUsage,
+        context: &ProcessingContext,
+    ) -> Result<HlsVideoResolutionManifest, HlsKitError> {
+        Self::report_done(context, &plan);
+
+        #[cfg(feature = "encryption")]
+        if let Some(encryption) = encryption {
+            encrypt_rendition_files(&plan.playlist_path, encryption)?;
+        }
+        #[cfg(not(feature = "encryption"))]
+        let _ = encryption;
+
+        context.report_segments_written(plan.stream_index, segment_watcher.finish());
 
-        run_command(gtreamer_pipeline).await?;
+        let encoder = gstreamer_video_encoder(profile.video_codec);
+        Ok(HlsVideoResolutionManifest {
+            resolution: plan.resolution,
+            segment_paths: playlist_segment_paths(&plan.playlist_path).await?,
+            codecs: rendition_codecs(
+                encoder,
+                profile,
+                plan.resolution,
+                VideoRange::Sdr,
+                Self::encodes_audio(profile, context),
+                context,
+            ),
+            playlist_name: plan.playlist_name,
+            playlist_path: plan.playlist_path,
+            video_range: VideoRange::Sdr,
+            frame_rate: context.source_adjustments().frame_rate_for(profile),
+            quality: None,
+            encoder: encoder.to_string(),
+            resource_usage,
+            encryption_iv: None,
+        })
+    }
+}
+
+/// gst-launch expects every pipeline token as its own argument
+fn launch_args(args: Vec<String>) -> Vec<String> {
+    args.iter()
+        .flat_map(|arg| arg.split_whitespace().map(|s| s.to_string()))
+        .collect()
+}
 
-        let resolution = read_playlist_and_segments(
-            &playlist_filename,
-            &segment_filename,
-            profile.resolution,
+impl VideoProcessingBackend for GStreamerBackend {
+    fn name(&self) -> &'static str {
+        "gstreamer"
+    }
+
+    fn reads_stdin(&self) -> bool {
+        true
+    }
+
+    fn plan_profile(
+        &self,
+        input: &str,
+        profile: &HlsVideoProcessingSettings,
+        output_dir: &Path,
+        stream_index: i32,
+        encryption: Option<&VideoProcessorEncryptionSettings>,
+        context: &ProcessingContext,
+    ) -> Result<ProfilePlan, HlsKitError> {
+        self.profile_command(
+            input,
+            profile,
+            output_dir,
             stream_index,
+            encryption,
+            context,
+        )
+        .map(|(_, plan)| plan)
+    }
+
+    async fn encode_profile(
+        &self,
+        input: String,
+        profile: &HlsVideoProcessingSettings,
+        output_dir: &Path,
+        stream_index: i32,
+        encryption: Option<&VideoProcessorEncryptionSettings>,
+        context: &ProcessingContext,
+    ) -> Result<HlsVideoResolutionManifest, HlsKitError> {
+        let mut plan = self.plan_profile(
+            &input,
+            profile,
+            output_dir,
+            stream_index,
+            encryption,
+            context,
         )?;
+        context.hooks().run_before_profile(&mut plan)?;
+
+        let mut segment_watcher = SegmentWatcher::new(&plan.segment_pattern)
+            .with_init_segment(plan.init_segment_path.clone());
+        let progress_parser = GStreamerProgressParser::new();
+        let started = Instant::now();
+        let resource_usage = run_command_with_context(&plan.command, context, |line| {
+            if let Some(update) = progress_parser.parse_line(line) {
+                Self::report_progress(context, &plan, &update, started);
+                // Encrypted renditions are only final once encrypted, after the run
+                if encryption.is_none() {
+                    context.report_segments_written(stream_index, segment_watcher.poll());
+                }
+            }
+        })
+        .await
+        .map_err(classify_gstreamer_error)?;
+
+        Self::finish_rendition(
+            plan,
+            profile,
+            encryption,
+            segment_watcher,
+            resource_usage,
+            context,
+        )
+        .await
+    }
+
+    fn encodes_together(&self, profiles: &[HlsVideoProcessingSettings]) -> bool {
+        profiles.len() > 1
+    }
+
+    /// Decodes the source once, teeing it into a scale, encode and sink branch per profile
+    async fn encode_profiles(
+        &self,
+        input: String,
+        profiles: &[HlsVideoProcessingSettings],
+        output_dir: &Path,
+        encryption: Option<&VideoProcessorEncryptionSettings>,
+        context: &ProcessingContext,
+    ) -> Result<Vec<HlsVideoResolutionManifest>, HlsKitError> {
+        let mut commands = Vec::with_capacity(profiles.len());
+        let mut plans = Vec::with_capacity(profiles.len());
+        for (index, profile) in profiles.iter().enumerate() {
+            let (command, plan) = self
+                .profile_command(
+                    &input,
+                    profile,
+                    output_dir,
+                    index as i32,
+                    profile.effective_encryption(encryption),
+                    context,
+                )
+                .map_err(|e| e.with_profile(index as i32, profile.resolution))?;
+            commands.push(command);
+            plans.push(plan);
+        }
+
+        let command = launch_args(GStreamerCommand::tee_args(&commands));
+
+        let mut segment_watchers: Vec<SegmentWatcher> = plans
+            .iter()
+            .map(|plan| {
+                SegmentWatcher::new(&plan.segment_pattern)
+                    .with_init_segment(plan.init_segment_path.clone())
+            })
+            .collect();
+        let progress_parser = GStreamerProgressParser::new();
+        let started = Instant::now();
+        // The branches share the decoded stream, so one progressreport stands for all of them
+        let resource_usage = run_command_with_context(&command, context, |line| {
+            if let Some(update) = progress_parser.parse_line(line) {
+                for ((plan, profile), segment_watcher) in
+                    plans.iter().zip(profiles).zip(&mut segment_watchers)
+                {
+                    Self::report_progress(context, plan, &update, started);
+                    if profile.effective_encryption(encryption).is_none() {
+                        context.report_segments_written(plan.stream_index, segment_watcher.poll());
+                    }
+                }
+            }
+        })
+        .await
+        .map_err(classify_gstreamer_error)?;
+
+        let mut manifests = Vec::with_capacity(plans.len());
+        for ((plan, profile), segment_watcher) in
+            plans.into_iter().zip(profiles).zip(segment_watchers)
+        {
+            let stream_index = plan.stream_index;
+            manifests.push(
+                Self::finish_rendition(
+                    plan,
+                    profile,
+                    profile.effective_encryption(encryption),
+                    segment_watcher,
+                    // Usage of the shared run, there is no per-branch figure
+                    resource_usage,
+                    context,
+                )
+                .await
+                .map_err(|e| e.with_profile(stream_index, profile.resolution))?,
+            );
+        }
 
-        Ok(resolution)
+        Ok(manifests)
     }
 }
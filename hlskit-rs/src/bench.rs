@@ -0,0 +1,326 @@
+// SPDX-License-Identifier: LGPL-3.0-only
+/*
+ * Copyright © 2025 The HlsKit Project
+ *
+ * This software is licensed under the GNU Lesser General Public License v3.0 (LGPLv3).
+ * All contributions adhere to the LGPLv3 and the HlsKit Contributor License Agreement (CLA).
+ * A copy of the LGPLv3 can be found at https://www.gnu.org/licenses/lgpl-3.0.html
+ *
+ * HlsKit Contributor License Agreement
+ *
+ * By contributing to or modifying HlsKit, you agree to the following terms:
+ *
+ * 1. Collective Ownership:
+ * The HlsKit project incorporates original code and all contributions as a collective work,
+ * licensed under LGPLv3. Once submitted, contributions become part of the shared HlsKit
+ * ecosystem and cannot be reclaimed, reassigned, or withdrawn. Contributions to your own
+ * forks remain yours unless submitted here, at which point they join this collective whole under LGPLv3.
+ *
+ * 2. Definition of Contribution:
+ * You are considered a contributor if you modify the library in any form (including forks,
+ * wrappers, libraries, or extensions that alter its behavior), whether or not you submit
+ * your changes directly to this repository. All such modifications are part of the broader
+ * HlsKit ecosystem and are subject to this CLA.
+ *
+ * 3. Distribution of Modifications:
+ * If you distribute a modified version of HlsKit, you must license your modifications under
+ * LGPLv3 (with source code available as required by the license) and ensure they are
+ * adoptable by the HlsKit ecosystem (publicly available and compatible).
+ *
+ * 4. Networked Use of Modifications:
+ * If you use a modified version of HlsKit in a networked application, you must provide the
+ * source code of your modifications under LGPLv3 and notify the HlsKit project
+ * (e.g., via email to [higashikataengels@icloud.com]). This does not apply to the use of
+ * the unmodified library in proprietary software, which remains permissible under LGPLv3.
+ *
+ * 5. Scope:
+ * These terms apply to all contributions and modifications derived from the HlsKit project.
+ * The use of the unmodified library in proprietary software is governed solely by the LGPLv3.
+ */
+
+use std::{path::Path, time::Duration};
+
+use crate::{
+    backends::{ffmpeg_backend::FfmpegBackend, gstreamer_backend::GStreamerBackend},
+    models::{
+        hls_video_processing_settings::HlsVideoProcessingSettings,
+        hls_video_report::HlsVideoReport, resource_usage::ResourceUsage,
+    },
+    tools::{
+        hlskit_error::HlsKitError,
+        internals::pipeline::{NoSink, ProcessingJob},
+        processing_context::ProcessingContext,
+        quality_tools::run_ffmpeg,
+    },
+    traits::video_processing_backend::VideoProcessingBackend,
+    VideoInputType,
+};
+
+const SOURCE_FILE_NAME: &str = "source.mp4";
+
+/// The test pattern every run encodes, generated with ffmpeg's `testsrc2` and `sine` sources
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SyntheticSource {
+    pub resolution: (i32, i32),
+    pub frame_rate: u32,
+    pub duration: Duration,
+}
+
+impl Default for SyntheticSource {
+    fn default() -> Self {
+        Self {
+            resolution: (1920, 1080),
+            frame_rate: 30,
+            duration: Duration::from_secs(10),
+        }
+    }
+}
+
+impl SyntheticSource {
+    pub fn frame_count(&self) -> u64 {
+        (self.duration.as_secs_f64() * self.frame_rate as f64).round() as u64
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BenchBackend {
+    Ffmpeg(FfmpegBackend),
+    GStreamer(GStreamerBackend),
+}
+
+impl BenchBackend {
+    pub fn name(&self) -> &'static str {
+        match self {
+            BenchBackend::Ffmpeg(backend) => backend.name(),
+            BenchBackend::GStreamer(backend) => backend.name(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BenchLadder {
+    pub name: String,
+    pub profiles: Vec<HlsVideoProcessingSettings>,
+}
+
+/// What to benchmark; by default the 1080p ladder on ffmpeg, once, over a 10s 1080p30 source
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BenchSpec {
+    source: SyntheticSource,
+    ladders: Vec<BenchLadder>,
+    backends: Vec<BenchBackend>,
+    runs: usize,
+}
+
+impl Default for BenchSpec {
+    fn default() -> Self {
+        Self {
+            source: SyntheticSource::default(),
+            ladders: Vec::new(),
+            backends: Vec::new(),
+            runs: 1,
+        }
+    }
+}
+
+impl BenchSpec {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_source(mut self, source: SyntheticSource) -> Self {
+        self.source = source;
+        self
+    }
+
+    pub fn with_ladder(mut self, name: &str, profiles: Vec<HlsVideoProcessingSettings>) -> Self {
+        self.ladders.push(BenchLadder {
+            name: name.to_string(),
+            profiles,
+        });
+        self
+    }
+
+    pub fn with_backend(mut self, backend: BenchBackend) -> Self {
+        self.backends.push(backend);
+        self
+    }
+
+    /// Encodes every ladder `runs` times on every backend, to average out noise
+    pub fn with_runs(mut self, runs: usize) -> Self {
+        self.runs = runs.max(1);
+        self
+    }
+
+    fn ladders(&self) -> Vec<BenchLadder> {
+        if self.ladders.is_empty() {
+            return vec![BenchLadder {
+                name: "ladder_1080p".to_string(),
+                profiles: HlsVideoProcessingSettings::ladder_1080p(),
+            }];
+        }
+
+        self.ladders.clone()
+    }
+
+    fn backends(&self) -> Vec<BenchBackend> {
+        if self.backends.is_empty() {
+            return vec![BenchBackend::Ffmpeg(FfmpegBackend::default())];
+        }
+
+        self.backends.clone()
+    }
+}
+
+/// One encode of a ladder on a backend
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BenchResult {
+    pub backend: String,
+    pub ladder: String,
+    /// Numbers the runs of the same ladder and backend, starting at 0
+    pub run: usize,
+    pub resource_usage: ResourceUsage,
+    /// Seconds of source encoded per second of wall time, for the whole ladder
+    pub speed: f64,
+    /// Frames produced per second of wall time, summed over every rendition
+    pub frames_per_second: f64,
+    /// CPU time over wall time, e.g. 4.0 when four cores were kept busy
+    pub cpu_utilization: f64,
+    pub report: HlsVideoReport,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BenchReport {
+    pub source: SyntheticSource,
+    pub results: Vec<BenchResult>,
+}
+
+impl BenchReport {
+    /// Average speed of every run of `ladder` on `backend`
+    pub fn average_speed(&self, backend: &str, ladder: &str) -> Option<f64> {
+        let speeds: Vec<f64> = self
+            .results
+            .iter()
+            .filter(|result| result.backend == backend && result.ladder == ladder)
+            .map(|result| result.speed)
+            .collect();
+
+        (!speeds.is_empty()).then(|| speeds.iter().sum::<f64>() / speeds.len() as f64)
+    }
+}
+
+/// Generates the synthetic source once, then encodes every ladder of `spec` on every backend,
+/// one run at a time so runs never compete for the CPU
+pub async fn run(spec: BenchSpec) -> Result<BenchReport, HlsKitError> {
+    let workdir = tempfile::tempdir()?;
+    let source_path = workdir.path().join(SOURCE_FILE_NAME);
+    generate_source(&spec.source, &source_path).await?;
+    let input = VideoInputType::FilePath(source_path.to_string_lossy().to_string());
+
+    let mut results = Vec::new();
+    for backend in spec.backends() {
+        for ladder in spec.ladders() {
+            for run in 0..spec.runs {
+                tracing::info!(backend = backend.name(), ladder = %ladder.name, run, "Benchmarking");
+                let output_dir = tempfile::tempdir_in(workdir.path())?;
+                let encoded = match &backend {
+                    BenchBackend::Ffmpeg(backend) => {
+                        encode(backend, &input, &ladder.profiles, output_dir.path()).await?
+                    }
+                    BenchBackend::GStreamer(backend) => {
+                        encode(backend, &input, &ladder.profiles, output_dir.path()).await?
+                    }
+                };
+
+                results.push(bench_result(&spec.source, &ladder, run, encoded));
+            }
+        }
+    }
+
+    Ok(BenchReport {
+        source: spec.source,
+        results,
+    })
+}
+
+async fn generate_source(source: &SyntheticSource, path: &Path) -> Result<(), HlsKitError> {
+    let (width, height) = source.resolution;
+    let duration = format!("{:.3}", source.duration.as_secs_f64());
+    let args = [
+        "-y".to_string(),
+        "-f".to_string(),
+        "lavfi".to_string(),
+        "-i".to_string(),
+        format!(
+            "testsrc2=size={width}x{height}:rate={}:duration={duration}",
+            source.frame_rate
+        ),
+        "-f".to_string(),
+        "lavfi".to_string(),
+        "-i".to_string(),
+        format!("sine=frequency=1000:duration={duration}"),
+        "-c:v".to_string(),
+        "libx264".to_string(),
+        "-preset".to_string(),
+        "ultrafast".to_string(),
+        "-pix_fmt".to_string(),
+        "yuv420p".to_string(),
+        "-c:a".to_string(),
+        "aac".to_string(),
+        "-shortest".to_string(),
+        path.to_string_lossy().to_string(),
+    ];
+
+    run_ffmpeg(&args).await.map(|_| ())
+}
+
+async fn encode<B: VideoProcessingBackend>(
+    backend: &B,
+    input: &VideoInputType,
+    profiles: &[HlsVideoProcessingSettings],
+    output_dir: &Path,
+) -> Result<(HlsVideoReport, ResourceUsage), HlsKitError> {
+    let manifest = ProcessingJob {
+        input,
+        output_profiles: profiles,
+        encryption: None,
+        backend,
+        context: &ProcessingContext::default(),
+    }
+    .process_to_dir(output_dir, None::<&NoSink>)
+    .await?;
+
+    Ok((manifest.report, manifest.resource_usage))
+}
+
+fn bench_result(
+    source: &SyntheticSource,
+    ladder: &BenchLadder,
+    run: usize,
+    (report, resource_usage): (HlsVideoReport, ResourceUsage),
+) -> BenchResult {
+    let wall_time = resource_usage.wall_time.as_secs_f64();
+    let per_second = |amount: f64| {
+        if wall_time > 0.0 {
+            amount / wall_time
+        } else {
+            0.0
+        }
+    };
+
+    BenchResult {
+        backend: report.backend.clone(),
+        ladder: ladder.name.clone(),
+        run,
+        resource_usage,
+        speed: per_second(source.duration.as_secs_f64()),
+        frames_per_second: per_second(
+            (source.frame_count() * report.renditions.len() as u64) as f64,
+        ),
+        cpu_utilization: per_second(resource_usage.cpu_time().as_secs_f64()),
+        report,
+    }
+}
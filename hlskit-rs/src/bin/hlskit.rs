@@ -0,0 +1,245 @@
+// SPDX-License-Identifier: LGPL-3.0-only
+/*
+ * Copyright © 2025 The HlsKit Project
+ *
+ * This software is licensed under the GNU Lesser General Public License v3.0 (LGPLv3).
+ * All contributions adhere to the LGPLv3 and the HlsKit Contributor License Agreement (CLA).
+ * A copy of the LGPLv3 can be found at https://www.gnu.org/licenses/lgpl-3.0.html
+ *
+ * HlsKit Contributor License Agreement
+ *
+ * By contributing to or modifying HlsKit, you agree to the following terms:
+ *
+ * 1. Collective Ownership:
+ * The HlsKit project incorporates original code and all contributions as a collective work,
+ * licensed under LGPLv3. Once submitted, contributions become part of the shared HlsKit
+ * ecosystem and cannot be reclaimed, reassigned, or withdrawn. Contributions to your own
+ * forks remain yours unless submitted here, at which point they join this collective whole under LGPLv3.
+ *
+ * 2. Definition of Contribution:
+ * You are considered a contributor if you modify the library in any form (including forks,
+ * wrappers, libraries, or extensions that alter its behavior), whether or not you submit
+ * your changes directly to this repository. All such modifications are part of the broader
+ * HlsKit ecosystem and are subject to this CLA.
+ *
+ * 3. Distribution of Modifications:
+ * If you distribute a modified version of HlsKit, you must license your modifications under
+ * LGPLv3 (with source code available as required by the license) and ensure they are
+ * adoptable by the HlsKit ecosystem (publicly available and compatible).
+ *
+ * 4. Networked Use of Modifications:
+ * If you use a modified version of HlsKit in a networked application, you must provide the
+ * source code of your modifications under LGPLv3 and notify the HlsKit project
+ * (e.g., via email to [higashikataengels@icloud.com]). This does not apply to the use of
+ * the unmodified library in proprietary software, which remains permissible under LGPLv3.
+ *
+ * 5. Scope:
+ * These terms apply to all contributions and modifications derived from the HlsKit project.
+ * The use of the unmodified library in proprietary software is governed solely by the LGPLv3.
+ */
+
+use std::{
+    path::{Path, PathBuf},
+    process::ExitCode,
+    time::Duration,
+};
+
+use clap::{Parser, Subcommand, ValueEnum};
+use hlskit::{
+    backends::{ffmpeg_backend::FfmpegBackend, gstreamer_backend::GStreamerBackend},
+    models::{
+        hls_video_manifest::HlsVideoManifest,
+        hls_video_processing_settings::HlsVideoProcessingSettings,
+        processing_config::ProcessingConfig, profile_progress::ProfileProgress,
+    },
+    prelude::VideoProcessor,
+    tools::{
+        hlskit_error::HlsKitError,
+        ingest_tools::{repackage_remote_hls, RemoteIngestMode},
+        output_validation::{validate_output, OutputValidation},
+        probe_tools::probe_source,
+    },
+    traits::video_processing_backend::VideoProcessingBackend,
+    VideoInputType,
+};
+
+/// Converts videos to HLS and inspects HLS outputs
+#[derive(Debug, Parser)]
+#[command(name = "hlskit", version)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Encodes a video into an HLS ladder
+    Process {
+        input: String,
+        /// Directory receiving the master playlist, media playlists and segments
+        #[arg(short, long)]
+        output: PathBuf,
+        #[command(flatten)]
+        ladder: LadderArgs,
+        #[arg(long, value_enum, default_value_t = Backend::Ffmpeg)]
+        backend: Backend,
+    },
+    /// Prints what ffprobe knows about a video, as JSON
+    Probe { input: String },
+    /// Checks an HLS directory, exiting with 1 when errors are found
+    Validate {
+        dir: PathBuf,
+        /// Expected duration of every rendition, in seconds
+        #[arg(long)]
+        source_duration: Option<f64>,
+    },
+    /// Packages the HLS asset behind a master playlist URL again
+    Repackage {
+        master_url: String,
+        #[arg(short, long)]
+        output: PathBuf,
+        /// Re-encodes the best variant into the configured ladder instead of copying every
+        /// variant
+        #[arg(long)]
+        reencode: bool,
+        #[command(flatten)]
+        ladder: LadderArgs,
+    },
+}
+
+#[derive(Debug, clap::Args)]
+struct LadderArgs {
+    /// TOML, YAML or JSON file with the profiles and encryption, see `ProcessingConfig`
+    #[arg(short, long)]
+    config: Option<PathBuf>,
+    /// Ladder of the config file to use
+    #[arg(long, default_value = "default")]
+    ladder: String,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Backend {
+    Ffmpeg,
+    Gstreamer,
+}
+
+impl LadderArgs {
+    /// The config file, or the 1080p ladder without encryption when none is given
+    fn load(&self) -> Result<(Vec<HlsVideoProcessingSettings>, ProcessingConfig), HlsKitError> {
+        let Some(path) = &self.config else {
+            return Ok((
+                HlsVideoProcessingSettings::ladder_1080p(),
+                ProcessingConfig::default(),
+            ));
+        };
+
+        let config = ProcessingConfig::from_file(path)?;
+        let profiles = config
+            .ladder(&self.ladder)
+            .filter(|profiles| !profiles.is_empty())
+            .ok_or_else(|| HlsKitError::ConfigError {
+                error: format!("{} has no ladder '{}'", path.display(), self.ladder),
+            })?
+            .to_vec();
+
+        Ok((profiles, config))
+    }
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() -> ExitCode {
+    match run(Cli::parse().command).await {
+        Ok(exit_code) => exit_code,
+        Err(e) => {
+            eprintln!("error: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn run(command: Command) -> Result<ExitCode, HlsKitError> {
+    match command {
+        Command::Process {
+            input,
+            output,
+            ladder,
+            backend,
+        } => {
+            let (profiles, config) = ladder.load()?;
+            let manifest = match backend {
+                Backend::Ffmpeg => {
+                    process::<FfmpegBackend>(input, profiles, config, &output).await?
+                }
+                Backend::Gstreamer => {
+                    process::<GStreamerBackend>(input, profiles, config, &output).await?
+                }
+            };
+            print_json(&manifest.report)?;
+        }
+        Command::Probe { input } => print_json(&probe_source(&input).await?)?,
+        Command::Validate {
+            dir,
+            source_duration,
+        } => {
+            let source_duration = source_duration.map(Duration::from_secs_f64);
+            let report = validate_output(&dir, source_duration, &OutputValidation::new()).await?;
+            println!("{report}");
+            if !report.is_valid() {
+                return Ok(ExitCode::FAILURE);
+            }
+        }
+        Command::Repackage {
+            master_url,
+            output,
+            reencode,
+            ladder,
+        } => {
+            let mode = match reencode {
+                true => RemoteIngestMode::Reencode(ladder.load()?.0),
+                false => RemoteIngestMode::CodecCopy,
+            };
+            let hls_video = repackage_remote_hls(&master_url, mode, &output).await?;
+            eprintln!(
+                "Repackaged {} renditions into {}",
+                hls_video.resolutions.len(),
+                output.display()
+            );
+        }
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
+
+async fn process<B: VideoProcessingBackend + Default>(
+    input: String,
+    profiles: Vec<HlsVideoProcessingSettings>,
+    config: ProcessingConfig,
+    output_dir: &Path,
+) -> Result<HlsVideoManifest, HlsKitError> {
+    let mut processor = VideoProcessor::<B, VideoInputType>::new()
+        .with_video_input(VideoInputType::FilePath(input))
+        .with_output_profiles(profiles)
+        .with_progress_callback(print_progress);
+    if let Some(encryption) = config.encryption {
+        processor = processor.with_encryption(encryption);
+    }
+
+    processor.process_video_to_dir(output_dir).await
+}
+
+fn print_progress(progress: ProfileProgress) {
+    let (width, height) = progress.resolution;
+    match progress.percent {
+        Some(percent) => eprintln!("{width}x{height}: {percent:.1}%"),
+        None => eprintln!("{width}x{height}: {:.1}s", progress.out_time.as_secs_f64()),
+    }
+}
+
+fn print_json<T: serde::Serialize>(value: &T) -> Result<(), HlsKitError> {
+    let json = serde_json::to_string_pretty(value).map_err(|e| HlsKitError::ConfigError {
+        error: e.to_string(),
+    })?;
+    println!("{json}");
+
+    Ok(())
+}
@@ -0,0 +1,116 @@
+// SPDX-License-Identifier: LGPL-3.0-only
+/*
+ * Copyright © 2025 The HlsKit Project
+ *
+ * This software is licensed under the GNU Lesser General Public License v3.0 (LGPLv3).
+ * All contributions adhere to the LGPLv3 and the HlsKit Contributor License Agreement (CLA).
+ * A copy of the LGPLv3 can be found at https://www.gnu.org/licenses/lgpl-3.0.html
+ *
+ * HlsKit Contributor License Agreement
+ *
+ * By contributing to or modifying HlsKit, you agree to the following terms:
+ *
+ * 1. Collective Ownership:
+ * The HlsKit project incorporates original code and all contributions as a collective work,
+ * licensed under LGPLv3. Once submitted, contributions become part of the shared HlsKit
+ * ecosystem and cannot be reclaimed, reassigned, or withdrawn. Contributions to your own
+ * forks remain yours unless submitted here, at which point they join this collective whole under LGPLv3.
+ *
+ * 2. Definition of Contribution:
+ * You are considered a contributor if you modify the library in any form (including forks,
+ * wrappers, libraries, or extensions that alter its behavior), whether or not you submit
+ * your changes directly to this repository. All such modifications are part of the broader
+ * HlsKit ecosystem and are subject to this CLA.
+ *
+ * 3. Distribution of Modifications:
+ * If you distribute a modified version of HlsKit, you must license your modifications under
+ * LGPLv3 (with source code available as required by the license) and ensure they are
+ * adoptable by the HlsKit ecosystem (publicly available and compatible).
+ *
+ * 4. Networked Use of Modifications:
+ * If you use a modified version of HlsKit in a networked application, you must provide the
+ * source code of your modifications under LGPLv3 and notify the HlsKit project
+ * (e.g., via email to [higashikataengels@icloud.com]). This does not apply to the use of
+ * the unmodified library in proprietary software, which remains permissible under LGPLv3.
+ *
+ * 5. Scope:
+ * These terms apply to all contributions and modifications derived from the HlsKit project.
+ * The use of the unmodified library in proprietary software is governed solely by the LGPLv3.
+ */
+
+use std::{future::Future, path::Path};
+
+use tokio::runtime::Builder;
+
+use crate::{
+    models::{
+        hls_video::HlsVideo, hls_video_manifest::HlsVideoManifest,
+        hls_video_processing_settings::HlsVideoProcessingSettings,
+    },
+    tools::hlskit_error::HlsKitError,
+    traits::output_sink::OutputSink,
+    VideoInputType,
+};
+
+/// Drives `future` to completion on a private current-thread runtime, e.g.
+/// `VideoProcessor::process_video`. Like every function of this module, it panics when called
+/// from inside an async context.
+pub fn block_on<F, T>(future: F) -> Result<T, HlsKitError>
+where
+    F: Future<Output = Result<T, HlsKitError>>,
+{
+    Builder::new_current_thread()
+        .enable_all()
+        .build()?
+        .block_on(future)
+}
+
+pub fn process_video(
+    input_bytes: Vec<u8>,
+    output_profiles: Vec<HlsVideoProcessingSettings>,
+) -> Result<HlsVideo, HlsKitError> {
+    block_on(crate::process_video(input_bytes, output_profiles))
+}
+
+pub fn process_video_from_path(
+    video_path: &str,
+    output_profiles: Vec<HlsVideoProcessingSettings>,
+) -> Result<HlsVideo, HlsKitError> {
+    block_on(crate::process_video_from_path(video_path, output_profiles))
+}
+
+pub fn process_video_with_encrypted_segments(
+    input_bytes: Vec<u8>,
+    output_profiles: Vec<HlsVideoProcessingSettings>,
+    encryption_key_url: String,
+    encryption_key_path: String,
+    iv: Option<String>,
+) -> Result<HlsVideo, HlsKitError> {
+    block_on(crate::process_video_with_encrypted_segments(
+        input_bytes,
+        output_profiles,
+        encryption_key_url,
+        encryption_key_path,
+        iv,
+    ))
+}
+
+pub fn process_video_to_dir<P: AsRef<Path>>(
+    input: VideoInputType,
+    output_profiles: Vec<HlsVideoProcessingSettings>,
+    output_dir: P,
+) -> Result<HlsVideoManifest, HlsKitError> {
+    block_on(crate::process_video_to_dir(
+        input,
+        output_profiles,
+        output_dir,
+    ))
+}
+
+pub fn process_video_to_sink<K: OutputSink>(
+    input: VideoInputType,
+    output_profiles: Vec<HlsVideoProcessingSettings>,
+    sink: &K,
+) -> Result<(), HlsKitError> {
+    block_on(crate::process_video_to_sink(input, output_profiles, sink))
+}
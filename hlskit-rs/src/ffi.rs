@@ -0,0 +1,466 @@
+// SPDX-License-Identifier: LGPL-3.0-only
+/*
+ * Copyright © 2025 The HlsKit Project
+ *
+ * This software is licensed under the GNU Lesser General Public License v3.0 (LGPLv3).
+ * All contributions adhere to the LGPLv3 and the HlsKit Contributor License Agreement (CLA).
+ * A copy of the LGPLv3 can be found at https://www.gnu.org/licenses/lgpl-3.0.html
+ *
+ * HlsKit Contributor License Agreement
+ *
+ * By contributing to or modifying HlsKit, you agree to the following terms:
+ *
+ * 1. Collective Ownership:
+ * The HlsKit project incorporates original code and all contributions as a collective work,
+ * licensed under LGPLv3. Once submitted, contributions become part of the shared HlsKit
+ * ecosystem and cannot be reclaimed, reassigned, or withdrawn. Contributions to your own
+ * forks remain yours unless submitted here, at which point they join this collective whole under LGPLv3.
+ *
+ * 2. Definition of Contribution:
+ * You are considered a contributor if you modify the library in any form (including forks,
+ * wrappers, libraries, or extensions that alter its behavior), whether or not you submit
+ * your changes directly to this repository. All such modifications are part of the broader
+ * HlsKit ecosystem and are subject to this CLA.
+ *
+ * 3. Distribution of Modifications:
+ * If you distribute a modified version of HlsKit, you must license your modifications under
+ * LGPLv3 (with source code available as required by the license) and ensure they are
+ * adoptable by the HlsKit ecosystem (publicly available and compatible).
+ *
+ * 4. Networked Use of Modifications:
+ * If you use a modified version of HlsKit in a networked application, you must provide the
+ * source code of your modifications under LGPLv3 and notify the HlsKit project
+ * (e.g., via email to [higashikataengels@icloud.com]). This does not apply to the use of
+ * the unmodified library in proprietary software, which remains permissible under LGPLv3.
+ *
+ * 5. Scope:
+ * These terms apply to all contributions and modifications derived from the HlsKit project.
+ * The use of the unmodified library in proprietary software is governed solely by the LGPLv3.
+ */
+
+use std::{
+    cell::RefCell,
+    ffi::{c_char, c_void, CStr, CString},
+    panic::{catch_unwind, AssertUnwindSafe},
+    ptr,
+};
+
+use crate::{
+    backends::ffmpeg_backend::FfmpegBackend,
+    blocking::block_on,
+    models::{
+        hls_video::HlsVideo, hls_video_processing_settings::HlsVideoProcessingSettings,
+        processing_config::ProcessingConfig, profile_progress::ProfileProgress,
+    },
+    prelude::VideoProcessor,
+    tools::hlskit_error::HlsKitError,
+    VideoInputType,
+};
+
+pub const HLSKIT_OK: i32 = 0;
+/// A required pointer was null, a string wasn't valid UTF-8 or the config file is unusable
+pub const HLSKIT_ERROR_INVALID_ARGUMENT: i32 = 1;
+pub const HLSKIT_ERROR_PROCESSING: i32 = 2;
+/// HlsKit panicked; the message is still available from `hlskit_last_error`
+pub const HLSKIT_ERROR_PANIC: i32 = 3;
+
+/// Called on the thread that called `hlskit_process_video` while it runs; `percent` is
+/// negative when the source duration is unknown
+pub type HlsKitProgressCallback = Option<
+    extern "C" fn(stream_index: i32, width: i32, height: i32, percent: f64, user_data: *mut c_void),
+>;
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// A processed video handed to C, keeping NUL-terminated copies of every file name
+pub struct HlsKitVideo {
+    video: HlsVideo,
+    playlist_names: Vec<CString>,
+    segment_names: Vec<Vec<CString>>,
+}
+
+impl HlsKitVideo {
+    fn new(video: HlsVideo) -> Self {
+        let playlist_names = video
+            .resolutions
+            .iter()
+            .map(|resolution| c_string(&resolution.playlist_name))
+            .collect();
+        let segment_names = video
+            .resolutions
+            .iter()
+            .map(|resolution| {
+                resolution
+                    .segments
+                    .iter()
+                    .map(|segment| c_string(&segment.segment_name))
+                    .collect()
+            })
+            .collect();
+
+        Self {
+            video,
+            playlist_names,
+            segment_names,
+        }
+    }
+}
+
+/// Hands `user_data` to the progress handler, which only ever runs on the calling thread
+struct ProgressTarget {
+    callback: extern "C" fn(i32, i32, i32, f64, *mut c_void),
+    user_data: *mut c_void,
+}
+
+unsafe impl Send for ProgressTarget {}
+unsafe impl Sync for ProgressTarget {}
+
+impl ProgressTarget {
+    fn report(&self, progress: ProfileProgress) {
+        let (width, height) = progress.resolution;
+        (self.callback)(
+            progress.stream_index,
+            width,
+            height,
+            progress.percent.unwrap_or(-1.0),
+            self.user_data,
+        );
+    }
+}
+
+/// Encodes the video at `input_path` into HLS, in memory, storing the result in `*out_video`.
+/// `config_path` names a TOML, YAML or JSON `ProcessingConfig` with the ladder and encryption;
+/// when null, the 1080p ladder is used. A config without profiles, or one that doesn't parse,
+/// fails with `HLSKIT_ERROR_INVALID_ARGUMENT`. `progress` may be null.
+///
+/// # Safety
+///
+/// `input_path` and a non-null `config_path` must be NUL-terminated strings and `out_video`
+/// must be valid for writes. Free the result with `hlskit_video_free`.
+#[no_mangle]
+pub unsafe extern "C" fn hlskit_process_video(
+    input_path: *const c_char,
+    config_path: *const c_char,
+    progress: HlsKitProgressCallback,
+    user_data: *mut c_void,
+    out_video: *mut *mut HlsKitVideo,
+) -> i32 {
+    if input_path.is_null() || out_video.is_null() {
+        return fail(
+            HLSKIT_ERROR_INVALID_ARGUMENT,
+            "input_path and out_video are required",
+        );
+    }
+    let Ok(input_path) = CStr::from_ptr(input_path).to_str() else {
+        return fail(
+            HLSKIT_ERROR_INVALID_ARGUMENT,
+            "input_path is not valid UTF-8",
+        );
+    };
+    let config_path = match config_path.is_null() {
+        true => None,
+        false => match CStr::from_ptr(config_path).to_str() {
+            Ok(config_path) => Some(config_path),
+            Err(_) => {
+                return fail(
+                    HLSKIT_ERROR_INVALID_ARGUMENT,
+                    "config_path is not valid UTF-8",
+                )
+            }
+        },
+    };
+    let progress = progress.map(|callback| ProgressTarget {
+        callback,
+        user_data,
+    });
+
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        process_video(input_path, config_path, progress)
+    }));
+    match result {
+        Ok(Ok(video)) => {
+            *out_video = Box::into_raw(Box::new(HlsKitVideo::new(video)));
+            clear_last_error();
+            HLSKIT_OK
+        }
+        Ok(Err(e @ HlsKitError::ConfigError { .. })) => {
+            fail(HLSKIT_ERROR_INVALID_ARGUMENT, &e.to_string())
+        }
+        Ok(Err(e)) => fail(HLSKIT_ERROR_PROCESSING, &e.to_string()),
+        Err(_) => fail(
+            HLSKIT_ERROR_PANIC,
+            "hlskit panicked while processing the video",
+        ),
+    }
+}
+
+fn process_video(
+    input_path: &str,
+    config_path: Option<&str>,
+    progress: Option<ProgressTarget>,
+) -> Result<HlsVideo, HlsKitError> {
+    let config = match config_path {
+        Some(config_path) => {
+            let config = ProcessingConfig::from_file(config_path)?;
+            if config.profiles.is_empty() {
+                return Err(HlsKitError::ConfigError {
+                    error: format!("{config_path} has no profiles"),
+                });
+            }
+            config
+        }
+        None => ProcessingConfig {
+            profiles: HlsVideoProcessingSettings::ladder_1080p(),
+            ..Default::default()
+        },
+    };
+
+    let mut processor = VideoProcessor::<FfmpegBackend, VideoInputType>::new()
+        .with_video_input(VideoInputType::FilePath(input_path.to_string()))
+        .with_output_profiles(config.profiles);
+    if let Some(encryption) = config.encryption {
+        processor = processor.with_encryption(encryption);
+    }
+    if let Some(progress) = progress {
+        processor = processor.with_progress_callback(move |update| progress.report(update));
+    }
+
+    block_on(processor.process_video())
+}
+
+/// The message of the last failed call on this thread, null when it succeeded. Valid until the
+/// next call on this thread
+#[no_mangle]
+pub extern "C" fn hlskit_last_error() -> *const c_char {
+    LAST_ERROR.with(|last_error| {
+        last_error
+            .borrow()
+            .as_ref()
+            .map_or(ptr::null(), |error| error.as_ptr())
+    })
+}
+
+/// # Safety
+///
+/// `video` must come from `hlskit_process_video` and not be used afterwards; null is ignored.
+#[no_mangle]
+pub unsafe extern "C" fn hlskit_video_free(video: *mut HlsKitVideo) {
+    if !video.is_null() {
+        drop(Box::from_raw(video));
+    }
+}
+
+/// The master playlist, `*len` receiving its length in bytes
+///
+/// # Safety
+///
+/// `video` must be a live handle and `len` valid for writes. The data lives as long as `video`.
+#[no_mangle]
+pub unsafe extern "C" fn hlskit_video_master_playlist(
+    video: *const HlsKitVideo,
+    len: *mut usize,
+) -> *const u8 {
+    bytes_out(&(&*video).video.master_m3u8_data, len)
+}
+
+/// # Safety
+///
+/// `video` must be a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn hlskit_video_resolution_count(video: *const HlsKitVideo) -> usize {
+    (&*video).video.resolutions.len()
+}
+
+/// Writes the width and height of rendition `index`, failing when out of range
+///
+/// # Safety
+///
+/// `video` must be a live handle, `width` and `height` valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn hlskit_video_resolution(
+    video: *const HlsKitVideo,
+    index: usize,
+    width: *mut i32,
+    height: *mut i32,
+) -> i32 {
+    let Some(resolution) = (&*video).video.resolutions.get(index) else {
+        return fail(
+            HLSKIT_ERROR_INVALID_ARGUMENT,
+            "rendition index out of range",
+        );
+    };
+    (*width, *height) = resolution.resolution;
+
+    HLSKIT_OK
+}
+
+/// Name of the media playlist of rendition `index`, null when out of range
+///
+/// # Safety
+///
+/// `video` must be a live handle. The string lives as long as `video`.
+#[no_mangle]
+pub unsafe extern "C" fn hlskit_video_playlist_name(
+    video: *const HlsKitVideo,
+    index: usize,
+) -> *const c_char {
+    (&*video)
+        .playlist_names
+        .get(index)
+        .map_or(ptr::null(), |name| name.as_ptr())
+}
+
+/// Media playlist of rendition `index`, null when out of range
+///
+/// # Safety
+///
+/// `video` must be a live handle and `len` valid for writes. The data lives as long as `video`.
+#[no_mangle]
+pub unsafe extern "C" fn hlskit_video_playlist(
+    video: *const HlsKitVideo,
+    index: usize,
+    len: *mut usize,
+) -> *const u8 {
+    match (&*video).video.resolutions.get(index) {
+        Some(resolution) => bytes_out(&resolution.playlist_data, len),
+        None => ptr::null(),
+    }
+}
+
+/// Number of segments of rendition `index`, 0 when out of range
+///
+/// # Safety
+///
+/// `video` must be a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn hlskit_video_segment_count(
+    video: *const HlsKitVideo,
+    index: usize,
+) -> usize {
+    (&*video)
+        .video
+        .resolutions
+        .get(index)
+        .map_or(0, |resolution| resolution.segments.len())
+}
+
+/// Name of segment `segment` of rendition `index`, null when out of range
+///
+/// # Safety
+///
+/// `video` must be a live handle. The string lives as long as `video`.
+#[no_mangle]
+pub unsafe extern "C" fn hlskit_video_segment_name(
+    video: *const HlsKitVideo,
+    index: usize,
+    segment: usize,
+) -> *const c_char {
+    (&*video)
+        .segment_names
+        .get(index)
+        .and_then(|names| names.get(segment))
+        .map_or(ptr::null(), |name| name.as_ptr())
+}
+
+/// Data of segment `segment` of rendition `index`, null when out of range
+///
+/// # Safety
+///
+/// `video` must be a live handle and `len` valid for writes. The data lives as long as `video`.
+#[no_mangle]
+pub unsafe extern "C" fn hlskit_video_segment(
+    video: *const HlsKitVideo,
+    index: usize,
+    segment: usize,
+    len: *mut usize,
+) -> *const u8 {
+    match (&*video)
+        .video
+        .resolutions
+        .get(index)
+        .and_then(|resolution| resolution.segments.get(segment))
+    {
+        Some(segment) => bytes_out(&segment.segment_data, len),
+        None => ptr::null(),
+    }
+}
+
+/// Writes every playlist and segment under `dir`, creating it as needed
+///
+/// # Safety
+///
+/// `video` must be a live handle and `dir` a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn hlskit_video_write_to_dir(
+    video: *const HlsKitVideo,
+    dir: *const c_char,
+) -> i32 {
+    if dir.is_null() {
+        return fail(HLSKIT_ERROR_INVALID_ARGUMENT, "dir is required");
+    }
+    let Ok(dir) = CStr::from_ptr(dir).to_str() else {
+        return fail(HLSKIT_ERROR_INVALID_ARGUMENT, "dir is not valid UTF-8");
+    };
+
+    match (&*video).video.write_to_dir(dir) {
+        Ok(()) => {
+            clear_last_error();
+            HLSKIT_OK
+        }
+        Err(e) => fail(HLSKIT_ERROR_PROCESSING, &e.to_string()),
+    }
+}
+
+unsafe fn bytes_out(data: &[u8], len: *mut usize) -> *const u8 {
+    if !len.is_null() {
+        *len = data.len();
+    }
+    data.as_ptr()
+}
+
+/// File names never contain NUL bytes; should one slip through, the name is cut there
+fn c_string(value: &str) -> CString {
+    let value = value.split('\0').next().unwrap_or_default();
+    CString::new(value).unwrap_or_default()
+}
+
+fn fail(code: i32, error: &str) -> i32 {
+    LAST_ERROR.with(|last_error| *last_error.borrow_mut() = Some(c_string(error)));
+    code
+}
+
+fn clear_last_error() {
+    LAST_ERROR.with(|last_error| *last_error.borrow_mut() = None);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn config_without_profiles_is_an_invalid_argument() {
+        let dir = tempfile::tempdir().unwrap();
+        let config_path = dir.path().join("hlskit.toml");
+        std::fs::write(&config_path, "[[ladders.mobile]]\nresolution = [640, 360]\nconstant_rate_factor = 28\npreset = \"VeryFast\"\n").unwrap();
+
+        let input_path = CString::new("input.mp4").unwrap();
+        let config_path = CString::new(config_path.to_str().unwrap()).unwrap();
+        let mut video = ptr::null_mut();
+        let status = unsafe {
+            hlskit_process_video(
+                input_path.as_ptr(),
+                config_path.as_ptr(),
+                None,
+                ptr::null_mut(),
+                &mut video,
+            )
+        };
+
+        assert_eq!(status, HLSKIT_ERROR_INVALID_ARGUMENT);
+        assert!(video.is_null());
+        let error = unsafe { CStr::from_ptr(hlskit_last_error()) };
+        assert!(error.to_str().unwrap().contains("has no profiles"));
+    }
+}
@@ -39,34 +39,58 @@
  */
 
 use std::io::{Read, Write};
-use std::{ffi::OsStr, fs, path::PathBuf};
+use std::{
+    ffi::OsStr,
+    fs,
+    path::{Path, PathBuf},
+};
 
-use futures::future::try_join_all;
 use models::{
-    hls_video::{HlsVideo, HlsVideoResolution},
+    chunked_encoding::ChunkedEncoding,
+    hls_artifact::HlsArtifact,
+    hls_video::HlsVideo,
+    hls_video_manifest::HlsVideoManifest,
     hls_video_processing_settings::HlsVideoProcessingSettings,
+    processing_plan::ProcessingPlan,
+    work_unit::{WorkUnit, WorkUnitResult},
+};
+use tokio::{sync::mpsc, task::JoinHandle};
+use tools::{
+    hlskit_error::HlsKitError,
+    internals::pipeline::{NoSink, ProcessingJob},
+    processing_context::ProcessingContext,
 };
-
-use tempfile::TempDir;
-use tools::{hlskit_error::HlsKitError, m3u8_tools::generate_master_playlist};
 
 use crate::backends::ffmpeg_backend::FfmpegBackend;
-use crate::traits::video_processing_backend::VideoProcessingBackend;
+use crate::sinks::channel_sink::ChannelSink;
+use crate::traits::output_sink::OutputSink;
 use crate::{
     tools::hlskit_error::VideoValidatableErrors,
     traits::video_validatable::{VideoInputPathGuard, VideoValidatable},
 };
 
 pub mod backends;
+#[cfg(feature = "bench")]
+pub mod bench;
+#[cfg(feature = "blocking")]
+pub mod blocking;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+
+const STREAMING_CHANNEL_CAPACITY: usize = 32;
 
 #[cfg(feature = "native-bindings")]
 pub mod bindings;
 
 pub mod models;
+#[cfg(feature = "job-queue")]
+pub mod queue;
+pub mod sinks;
 pub mod tools;
 pub mod traits;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum VideoInputType {
     InMemoryFile(Vec<u8>),
     FilePath(String),
@@ -74,40 +98,99 @@ pub enum VideoInputType {
 
 impl VideoValidatable for VideoInputType {
     fn validate(&self) -> Result<VideoInputPathGuard, VideoValidatableErrors> {
-        fn is_valid_magic_bytes(buf: &[u8], ext: &str) -> bool {
-            match ext {
-                "mp4" | "mov" => buf.len() >= 8 && &buf[4..8] == b"ftyp",
-                "mkv" => buf.len() >= 4 && &buf[0..4] == b"\x1A\x45\xDF\xA3",
-                "avi" => buf.len() >= 12 && &buf[0..4] == b"RIFF" && &buf[8..12] == b"AVI ",
-                _ => false,
+        self.validate_with_temp_dir(None)
+    }
+
+    fn validate_in(&self, temp_dir: &Path) -> Result<VideoInputPathGuard, VideoValidatableErrors> {
+        self.validate_with_temp_dir(Some(temp_dir))
+    }
+
+    fn source_path(&self) -> Option<&str> {
+        match self {
+            VideoInputType::FilePath(path) => Some(path),
+            VideoInputType::InMemoryFile(_) => None,
+        }
+    }
+
+    fn validate_in_memory(&self) -> Option<Result<&[u8], VideoValidatableErrors>> {
+        match self {
+            VideoInputType::InMemoryFile(video_data) => {
+                Some(check_in_memory(video_data).map(|_| video_data.as_slice()))
             }
+            VideoInputType::FilePath(_) => None,
         }
+    }
+}
 
-        let valid_video_extensions = ["mp4", "mkv", "avi", "mov"];
+const VALID_VIDEO_EXTENSIONS: [&str; 16] = [
+    "mp4", "m4v", "mov", "3gp", "3g2", "mkv", "webm", "avi", "flv", "wmv", "asf", "ts", "m2ts",
+    "mts", "mpg", "mpeg",
+];
 
-        match &self {
-            VideoInputType::InMemoryFile(video_data) => {
-                if video_data.is_empty() {
-                    return Err(VideoValidatableErrors::EmptyVideoInput);
-                }
+/// Rejects empty inputs and inputs matching the signature of no supported container
+fn check_in_memory(video_data: &[u8]) -> Result<(), VideoValidatableErrors> {
+    if video_data.is_empty() {
+        return Err(VideoValidatableErrors::EmptyVideoInput);
+    }
 
-                let mut valid = false;
-                for ext in &valid_video_extensions {
-                    if is_valid_magic_bytes(video_data, ext) {
-                        valid = true;
-                        break;
-                    }
-                }
+    if !VALID_VIDEO_EXTENSIONS
+        .iter()
+        .any(|ext| is_valid_magic_bytes(video_data, ext))
+    {
+        return Err(VideoValidatableErrors::InvalidFormat);
+    }
 
-                if !valid {
-                    return Err(VideoValidatableErrors::InvalidFormat);
-                }
+    Ok(())
+}
 
-                let mut temp_file = tempfile::NamedTempFile::new().map_err(|_| {
-                    VideoValidatableErrors::InvalidVideoInput {
+fn is_valid_magic_bytes(buf: &[u8], ext: &str) -> bool {
+    // MPEG-TS packets are 188 bytes and start with a 0x47 sync byte, M2TS adds a
+    // 4-byte timestamp in front of each
+    let ts_sync = |offset: usize| {
+        let packet_len = offset + 188;
+        buf.len() > offset + packet_len && buf[offset] == 0x47 && buf[offset + packet_len] == 0x47
+    };
+
+    match ext {
+        "mp4" | "mov" | "m4v" | "3gp" | "3g2" => buf.len() >= 8 && &buf[4..8] == b"ftyp",
+        "mkv" | "webm" => buf.len() >= 4 && &buf[0..4] == b"\x1A\x45\xDF\xA3",
+        "avi" => buf.len() >= 12 && &buf[0..4] == b"RIFF" && &buf[8..12] == b"AVI ",
+        "flv" => buf.len() >= 4 && &buf[0..3] == b"FLV" && buf[3] == 0x01,
+        "wmv" | "asf" => {
+            buf.len() >= 16
+                && buf[0..16]
+                    == [
+                        0x30, 0x26, 0xB2, 0x75, 0x8E, 0x66, 0xCF, 0x11, 0xA6, 0xD9, 0x00, 0xAA,
+                        0x00, 0x62, 0xCE, 0x6C,
+                    ]
+        }
+        "ts" => ts_sync(0),
+        "m2ts" | "mts" => ts_sync(4),
+        "mpg" | "mpeg" => {
+            buf.len() >= 4
+                && (&buf[0..4] == b"\x00\x00\x01\xBA" || &buf[0..4] == b"\x00\x00\x01\xB3")
+        }
+        _ => false,
+    }
+}
+
+impl VideoInputType {
+    fn validate_with_temp_dir(
+        &self,
+        temp_dir: Option<&Path>,
+    ) -> Result<VideoInputPathGuard, VideoValidatableErrors> {
+        match &self {
+            VideoInputType::InMemoryFile(video_data) => {
+                check_in_memory(video_data)?;
+
+                let temp_file = match temp_dir {
+                    Some(temp_dir) => tempfile::NamedTempFile::new_in(temp_dir),
+                    None => tempfile::NamedTempFile::new(),
+                };
+                let mut temp_file =
+                    temp_file.map_err(|_| VideoValidatableErrors::InvalidVideoInput {
                         error: "Failed to create temp file".to_string(),
-                    }
-                })?;
+                    })?;
 
                 temp_file.write_all(video_data).map_err(|_| {
                     VideoValidatableErrors::InvalidVideoInput {
@@ -145,31 +228,29 @@ impl VideoValidatable for VideoInputType {
                     .unwrap_or("invalid")
                     .to_lowercase();
 
-                if !valid_video_extensions.contains(&ext.as_str()) {
+                if !VALID_VIDEO_EXTENSIONS.contains(&ext.as_str()) {
                     return Err(VideoValidatableErrors::InvalidVideoInput {
                         error: "The given video hasn't a valid extension".to_string(),
                     });
                 }
 
-                let mut file = match std::fs::File::open(&pathbuf) {
+                let file = match std::fs::File::open(&pathbuf) {
                     Ok(f) => f,
                     Err(_) => {
                         return Err(VideoValidatableErrors::FileNotFound);
                     }
                 };
 
-                let mut buf = [0u8; 16];
+                // Enough for two M2TS packets
+                let mut buf = Vec::with_capacity(512);
 
-                let n = match file.read(&mut buf) {
-                    Ok(n) => n,
-                    Err(_) => {
-                        return Err(VideoValidatableErrors::InvalidVideoInput {
-                            error: "Failed to read video file for validation".to_string(),
-                        });
-                    }
-                };
+                if file.take(512).read_to_end(&mut buf).is_err() {
+                    return Err(VideoValidatableErrors::InvalidVideoInput {
+                        error: "Failed to read video file for validation".to_string(),
+                    });
+                }
 
-                if !is_valid_magic_bytes(&buf[..n], &ext) {
+                if !is_valid_magic_bytes(&buf, &ext) {
                     return Err(VideoValidatableErrors::InvalidFormat);
                 }
 
@@ -189,6 +270,7 @@ impl Default for VideoInputType {
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct VideoProcessorEncryptionSettings {
     pub encryption_key_url: String,
     pub encryption_key_path: String,
@@ -199,13 +281,65 @@ pub async fn process_video(
     input_bytes: Vec<u8>,
     output_profiles: Vec<HlsVideoProcessingSettings>,
 ) -> Result<HlsVideo, HlsKitError> {
-    let backend = FfmpegBackend;
-    process_video_internal::<FfmpegBackend>(
-        VideoInputType::InMemoryFile(input_bytes),
-        output_profiles,
-        None,
-        backend,
-    )
+    let backend = FfmpegBackend::default();
+    ProcessingJob {
+        input: &VideoInputType::InMemoryFile(input_bytes),
+        output_profiles: &output_profiles,
+        encryption: None,
+        backend: &backend,
+        context: &ProcessingContext::default(),
+    }
+    .process()
+    .await
+}
+
+/// Returns the ffmpeg commands and output layout `process_video_to_dir` would use, running nothing
+pub fn plan_video_to_dir<P: AsRef<Path>>(
+    input: VideoInputType,
+    output_profiles: Vec<HlsVideoProcessingSettings>,
+    output_dir: P,
+) -> Result<ProcessingPlan, HlsKitError> {
+    let backend = FfmpegBackend::default();
+    ProcessingJob {
+        input: &input,
+        output_profiles: &output_profiles,
+        encryption: None,
+        backend: &backend,
+        context: &ProcessingContext::default(),
+    }
+    .plan(output_dir.as_ref())
+}
+
+/// Splits the encode of `input` into work units for other machines, see
+/// `tools::work_units::merge_results` for putting the results back together
+pub async fn plan_work_units(
+    input: VideoInputType,
+    output_profiles: Vec<HlsVideoProcessingSettings>,
+    chunked_encoding: Option<ChunkedEncoding>,
+) -> Result<Vec<WorkUnit>, HlsKitError> {
+    let backend = FfmpegBackend::default();
+    ProcessingJob {
+        input: &input,
+        output_profiles: &output_profiles,
+        encryption: None,
+        backend: &backend,
+        context: &ProcessingContext::default(),
+    }
+    .work_units(chunked_encoding)
+    .await
+}
+
+/// Encodes a unit planned by `plan_work_units` with the default ffmpeg backend
+pub async fn process_work_unit(unit: &WorkUnit) -> Result<WorkUnitResult, HlsKitError> {
+    let backend = FfmpegBackend::default();
+    ProcessingJob {
+        input: &VideoInputType::FilePath(unit.input.clone()),
+        output_profiles: &[],
+        encryption: None,
+        backend: &backend,
+        context: &ProcessingContext::default(),
+    }
+    .process_work_unit(unit)
     .await
 }
 
@@ -213,13 +347,15 @@ pub async fn process_video_from_path(
     video_path: &str,
     output_profiles: Vec<HlsVideoProcessingSettings>,
 ) -> Result<HlsVideo, HlsKitError> {
-    let backend = FfmpegBackend;
-    process_video_internal::<FfmpegBackend>(
-        VideoInputType::FilePath(video_path.to_string()),
-        output_profiles,
-        None,
-        backend,
-    )
+    let backend = FfmpegBackend::default();
+    ProcessingJob {
+        input: &VideoInputType::FilePath(video_path.to_string()),
+        output_profiles: &output_profiles,
+        encryption: None,
+        backend: &backend,
+        context: &ProcessingContext::default(),
+    }
+    .process()
     .await
 }
 
@@ -230,97 +366,142 @@ pub async fn process_video_with_encrypted_segments(
     encryption_key_path: String,
     iv: Option<String>,
 ) -> Result<HlsVideo, HlsKitError> {
-    let backend = FfmpegBackend;
-    let encryption = Some(VideoProcessorEncryptionSettings {
+    let backend = FfmpegBackend::default();
+    let encryption = VideoProcessorEncryptionSettings {
         encryption_key_url,
         encryption_key_path,
         iv,
-    });
-    process_video_internal::<FfmpegBackend>(
-        VideoInputType::InMemoryFile(input_bytes),
-        output_profiles,
-        encryption,
-        backend,
-    )
+    };
+    ProcessingJob {
+        input: &VideoInputType::InMemoryFile(input_bytes),
+        output_profiles: &output_profiles,
+        encryption: Some(&encryption),
+        backend: &backend,
+        context: &ProcessingContext::default(),
+    }
+    .process()
     .await
 }
 
-// Internal helper function to avoid code duplication
-async fn process_video_internal<V: VideoProcessingBackend>(
+/// Processes the video straight into `output_dir`, leaving playlists and segments on disk
+/// instead of loading them into memory.
+pub async fn process_video_to_dir<P: AsRef<Path>>(
     input: VideoInputType,
     output_profiles: Vec<HlsVideoProcessingSettings>,
-    encryption: Option<VideoProcessorEncryptionSettings>,
-    backend: V,
-) -> Result<HlsVideo, HlsKitError> {
-    let input_dir_guard = &input.validate()?;
+    output_dir: P,
+) -> Result<HlsVideoManifest, HlsKitError> {
+    let backend = FfmpegBackend::default();
+    let output_dir = output_dir.as_ref();
+    fs::create_dir_all(output_dir)?;
 
-    let temp_file_guard = input_dir_guard.temp_file.as_ref();
+    ProcessingJob {
+        input: &input,
+        output_profiles: &output_profiles,
+        encryption: None,
+        backend: &backend,
+        context: &ProcessingContext::default(),
+    }
+    .process_to_dir(output_dir, None::<&NoSink>)
+    .await
+}
 
-    let input_path = match temp_file_guard {
-        Some(temp_file) => temp_file.path().to_string_lossy().to_string(),
-        None => input_dir_guard.path.clone(),
-    };
+/// Processes the video and forwards every playlist and segment to `sink` as each profile finishes
+pub async fn process_video_to_sink<K: OutputSink>(
+    input: VideoInputType,
+    output_profiles: Vec<HlsVideoProcessingSettings>,
+    sink: &K,
+) -> Result<(), HlsKitError> {
+    let backend = FfmpegBackend::default();
+    ProcessingJob {
+        input: &input,
+        output_profiles: &output_profiles,
+        encryption: None,
+        backend: &backend,
+        context: &ProcessingContext::default(),
+    }
+    .process_to_sink(sink)
+    .await
+}
 
-    let output_dir = TempDir::new()?;
-    let output_dir_path = output_dir.path();
+/// Processes the video in a background task, yielding playlists and segments over the returned
+/// receiver as soon as each profile finishes. The join handle reports the outcome of the job.
+pub fn process_video_streaming(
+    input: VideoInputType,
+    output_profiles: Vec<HlsVideoProcessingSettings>,
+) -> (
+    mpsc::Receiver<HlsArtifact>,
+    JoinHandle<Result<(), HlsKitError>>,
+) {
+    let (sink, receiver) = ChannelSink::channel(STREAMING_CHANNEL_CAPACITY);
 
-    let tasks: Vec<_> = output_profiles
-        .iter()
-        .enumerate()
-        .map(|(index, profile)| {
-            backend.process_profile(
-                input_path.clone(),
-                profile,
-                output_dir_path,
-                index as i32,
-                encryption.as_ref(),
-            )
-        })
-        .collect();
-
-    let resolution_results: Vec<HlsVideoResolution> = try_join_all(tasks).await?;
-
-    let master_m3u8_data = generate_master_playlist(
-        output_dir_path,
-        resolution_results
-            .iter()
-            .map(|result| result.resolution)
-            .collect(),
-        resolution_results
-            .iter()
-            .map(|result| result.playlist_name.as_str())
-            .collect(),
-    )
-    .await?;
-
-    let hls_video = HlsVideo {
-        master_m3u8_data,
-        resolutions: resolution_results,
-    };
+    let handle = tokio::spawn(async move {
+        let backend = FfmpegBackend::default();
+        ProcessingJob {
+            input: &input,
+            output_profiles: &output_profiles,
+            encryption: None,
+            backend: &backend,
+            context: &ProcessingContext::default(),
+        }
+        .process_to_sink(&sink)
+        .await
+    });
 
-    fs::remove_dir_all(output_dir_path)?;
-    Ok(hls_video)
+    (receiver, handle)
 }
 
 #[cfg(feature = "zenpulse-api")]
 pub mod prelude {
-    use std::fs;
-
-    use futures::future::try_join_all;
-    use tempfile::TempDir;
+    use std::{
+        fs,
+        path::{Path, PathBuf},
+        time::Duration,
+    };
 
     use crate::{
         models::{
-            hls_video::{HlsVideo, HlsVideoResolution},
+            audio_sync::AudioSync,
+            chunked_encoding::ChunkedEncoding,
+            content_check::ContentQc,
+            deinterlace_policy::DeinterlacePolicy,
+            frame_rate_policy::FrameRatePolicy,
+            hls_video::HlsVideo,
+            hls_video_manifest::{HlsVideoManifest, HlsVideoResolutionManifest},
             hls_video_processing_settings::HlsVideoProcessingSettings,
+            job_event::JobEvent,
+            ladder::Ladder,
+            orientation_policy::OrientationPolicy,
+            path_policy::PathPolicy,
+            processing_plan::{ProcessingPlan, ProfilePlan},
+            profile_progress::ProfileProgress,
+            quality_scores::QualityMetric,
+            resource_limits::ResourceLimits,
+            retention_policy::RetentionPolicy,
+            scene_segmentation::SceneSegmentation,
+            upscale_policy::UpscalePolicy,
+            work_unit::{WorkUnit, WorkUnitResult},
+        },
+        tools::{
+            hlskit_error::HlsKitError,
+            internals::pipeline::{NoSink, ProcessingJob},
+            ladder_generator::LadderGenerator,
+            output_validation::OutputValidation,
+            per_title::PerTitle,
+            processing_context::{CancellationToken, ProcessingContext},
         },
-        tools::{hlskit_error::HlsKitError, m3u8_tools::generate_master_playlist},
         traits::{
+            command_interceptor::CommandInterceptor, key_provider::KeyProvider,
+            output_sink::OutputSink, sandbox_policy::SandboxPolicy,
             video_processing_backend::VideoProcessingBackend, video_validatable::VideoValidatable,
         },
         VideoProcessorEncryptionSettings,
     };
 
+    #[cfg(feature = "webhooks")]
+    use crate::tools::webhook_notifier::WebhookNotifier;
+    #[cfg(feature = "cache")]
+    use crate::traits::result_cache::ResultCache;
+
     #[derive(Debug, Clone, PartialEq, Eq)]
     pub struct VideoProcessor<B, S>
     where
@@ -331,6 +512,7 @@ pub mod prelude {
         output_profiles: Vec<HlsVideoProcessingSettings>,
         encryption_string: Option<VideoProcessorEncryptionSettings>,
         backend: B,
+        context: ProcessingContext,
     }
 
     impl<B, S> Default for VideoProcessor<B, S>
@@ -344,6 +526,7 @@ pub mod prelude {
                 output_profiles: Default::default(),
                 encryption_string: Default::default(),
                 backend: Default::default(),
+                context: Default::default(),
             }
         }
     }
@@ -373,56 +556,572 @@ pub mod prelude {
             self
         }
 
+        /// Receives every lifecycle event of the job: profile start/progress/completion,
+        /// written segments and the final job outcome
+        pub fn with_progress_handler<F>(mut self, handler: F) -> Self
+        where
+            F: Fn(JobEvent) + Send + Sync + 'static,
+        {
+            self.context = self.context.with_event_handler(handler);
+            self
+        }
+
+        /// POSTs the job's lifecycle events to a webhook as they happen
+        #[cfg(feature = "webhooks")]
+        pub fn with_webhook(mut self, notifier: WebhookNotifier) -> Self {
+            self.context = self.context.with_webhook(notifier);
+            self
+        }
+
+        /// Serves `process` from `cache` when the same input was already processed with the
+        /// same settings, e.g. `DirResultCache`. Encrypted jobs are never cached
+        #[cfg(feature = "cache")]
+        pub fn with_result_cache<C: ResultCache + 'static>(mut self, cache: C) -> Self {
+            self.context = self.context.with_result_cache(cache);
+            self
+        }
+
+        /// Aborts processing, killing running encoders and cleaning temporary files, once
+        /// `token` is cancelled. The job then fails with `HlsKitError::Cancelled`.
+        pub fn with_cancellation_token(mut self, token: CancellationToken) -> Self {
+            self.context = self.context.with_cancellation_token(token);
+            self
+        }
+
+        /// Constrains the threads, niceness, CPU affinity and cgroup of every encoder process
+        pub fn with_resource_limits(mut self, limits: ResourceLimits) -> Self {
+            self.context = self.context.with_resource_limits(limits);
+            self
+        }
+
+        /// Skips the check that the output volume has room for the estimated output
+        pub fn without_disk_preflight(mut self) -> Self {
+            self.context = self.context.without_disk_preflight();
+            self
+        }
+
+        /// Kills the job once its segments take more than `bytes` on disk
+        pub fn with_disk_quota(mut self, bytes: u64) -> Self {
+            self.context = self.context.with_disk_quota(bytes);
+            self
+        }
+
+        /// Leaves the temporary work directory on disk so failed or suspicious encodes can be
+        /// inspected
+        pub fn with_keep_workdir(mut self, keep: bool) -> Self {
+            self.context = self.context.with_keep_workdir(keep);
+            self
+        }
+
+        /// Decides whether the temporary work directory survives successful or failed jobs
+        pub fn with_retention_policy(mut self, policy: RetentionPolicy) -> Self {
+            self.context = self.context.with_retention_policy(policy);
+            self
+        }
+
+        /// Runs `hook` before each profile is encoded, with its command and outputs editable
+        pub fn with_before_profile_hook<F>(mut self, hook: F) -> Self
+        where
+            F: Fn(&mut ProfilePlan) -> Result<(), HlsKitError> + Send + Sync + 'static,
+        {
+            self.context = self
+                .context
+                .map_hooks(|hooks| hooks.with_before_profile(hook));
+            self
+        }
+
+        /// Runs `hook` once each profile is encoded, before its files reach the sink
+        pub fn with_after_profile_hook<F>(mut self, hook: F) -> Self
+        where
+            F: Fn(&mut HlsVideoResolutionManifest) -> Result<(), HlsKitError>
+                + Send
+                + Sync
+                + 'static,
+        {
+            self.context = self
+                .context
+                .map_hooks(|hooks| hooks.with_after_profile(hook));
+            self
+        }
+
+        /// Runs `hook` once the master playlist is built, before it is written to disk or
+        /// reaches the sink. Edits to `master_playlist` end up in the output
+        pub fn with_after_master_playlist_hook<F>(mut self, hook: F) -> Self
+        where
+            F: Fn(&mut HlsVideoManifest) -> Result<(), HlsKitError> + Send + Sync + 'static,
+        {
+            self.context = self
+                .context
+                .map_hooks(|hooks| hooks.with_after_master_playlist(hook));
+            self
+        }
+
+        /// Lets `interceptor` rewrite or veto every encoder command before it is spawned
+        pub fn with_command_interceptor<I: CommandInterceptor + 'static>(
+            mut self,
+            interceptor: I,
+        ) -> Self {
+            self.context = self.context.with_command_interceptor(interceptor);
+            self
+        }
+
+        /// Keeps temporary input copies and work directories in private per-job directories
+        /// (0700 directories, 0600 files on unix) instead of the shared temp dir
+        pub fn with_private_temp_files(mut self, private: bool) -> Self {
+            self.context = self.context.with_private_temp_files(private);
+            self
+        }
+
+        /// Decodes the start and end of the input's video stream with ffprobe before encoding,
+        /// so truncated or audio-only files fail validation instead of mid-encode
+        pub fn with_deep_validation(mut self, deep_validation: bool) -> Self {
+            self.context = self.context.with_deep_validation(deep_validation);
+            self
+        }
+
+        /// Drops or clamps profiles larger than the probed source, so a 720p upload isn't
+        /// upscaled to 1080p or 4K
+        pub fn with_upscale_policy(mut self, policy: UpscalePolicy) -> Self {
+            self.context = self.context.with_upscale_policy(policy);
+            self
+        }
+
+        /// Adapts profile dimensions to the orientation of the probed source. Portrait sources
+        /// get their landscape profiles swapped by default (`OrientationPolicy::Swap`)
+        pub fn with_orientation_policy(mut self, policy: OrientationPolicy) -> Self {
+            self.context = self.context.with_orientation_policy(policy);
+            self
+        }
+
+        /// Deinterlaces interlaced sources before scaling. `DeinterlacePolicy::Auto`, the
+        /// default, decides from the probed field order or the `idet` filter
+        pub fn with_deinterlace_policy(mut self, policy: DeinterlacePolicy) -> Self {
+            self.context = self.context.with_deinterlace_policy(policy);
+            self
+        }
+
+        /// Converts variable frame rate sources to a constant rate so audio stays in sync.
+        /// `FrameRatePolicy::Auto`, the default, only does so when the probe detects VFR
+        pub fn with_frame_rate_policy(mut self, policy: FrameRatePolicy) -> Self {
+            self.context = self.context.with_frame_rate_policy(policy);
+            self
+        }
+
+        /// Corrects drifting or offset audio while packaging, e.g.
+        /// `AudioSync::new().with_resample(true).with_offset_ms(-120)`
+        pub fn with_audio_sync(mut self, audio_sync: AudioSync) -> Self {
+            self.context = self.context.with_audio_sync(audio_sync);
+            self
+        }
+
+        /// Scores every rendition against the source after encoding (VMAF, PSNR and/or SSIM),
+        /// reported in the `quality` of each rendition. Measurement failures are logged, they
+        /// don't fail the job
+        pub fn with_quality_metrics(mut self, metrics: &[QualityMetric]) -> Self {
+            self.context = self.context.with_quality_metrics(metrics);
+            self
+        }
+
+        /// Picks the CRF of every rendition for this title from sampled probe encodes scored with
+        /// VMAF, instead of the configured one. Needs ffmpeg built with libvmaf; renditions whose
+        /// analysis fails keep their CRF
+        pub fn with_per_title(mut self, per_title: PerTitle) -> Self {
+            self.context = self.context.with_per_title(per_title);
+            self
+        }
+
+        /// Starts segments on scene cuts found in the source instead of every 10 seconds,
+        /// keeping their duration between the configured bounds. Segmentation falls back to
+        /// fixed durations when the source cannot be analyzed
+        pub fn with_scene_segmentation(mut self, segmentation: SceneSegmentation) -> Self {
+            self.context = self.context.with_scene_segmentation(segmentation);
+            self
+        }
+
+        /// Checks the produced playlists and segments once encoded, reporting the findings in
+        /// the manifest's `validation`. Errors fail the job before the master playlist reaches
+        /// the sink unless `OutputValidation::report_only` is set
+        pub fn with_output_validation(mut self, validation: OutputValidation) -> Self {
+            self.context = self.context.with_output_validation(validation);
+            self
+        }
+
+        /// Measures how much of the source is black or silent before encoding, failing with
+        /// `HlsKitError::ContentRejected` when `qc` rejects it. The measurement is reported in
+        /// the manifest's `content_check`
+        pub fn with_content_qc(mut self, qc: ContentQc) -> Self {
+            self.context = self.context.with_content_qc(qc);
+            self
+        }
+
+        /// Computes the SHA-256 of every playlist and segment, returned in `checksums` and
+        /// written to `checksums.json` next to the master playlist
+        #[cfg(feature = "checksums")]
+        pub fn with_checksums(mut self, checksums: bool) -> Self {
+            self.context = self.context.with_checksums(checksums);
+            self
+        }
+
+        /// Deletes each segment from the work directory once `process_to_sink` stored it, so
+        /// only the segments being encoded take disk space. Can't be combined with output
+        /// validation, quality metrics, checksums or encryption verification
+        pub fn with_streamed_segment_removal(mut self, remove: bool) -> Self {
+            self.context = self.context.with_streamed_segment_removal(remove);
+            self
+        }
+
+        /// Decrypts the first and last segment of every encrypted rendition right after
+        /// encoding, failing the profile on a key or IV mismatch instead of at playback
+        #[cfg(feature = "encryption")]
+        pub fn with_encryption_verification(mut self, verify: bool) -> Self {
+            self.context = self.context.with_encryption_verification(verify);
+            self
+        }
+
+        /// Picks the renditions: fitted to the probed source (`Ladder::Auto`), a device target's
+        /// ladder or a fixed list. Generated ladders are only known once processing starts, so
+        /// `plan` doesn't list them.
+        pub fn with_ladder(mut self, ladder: Ladder) -> Self {
+            match ladder {
+                Ladder::Auto => {
+                    self.context = self
+                        .context
+                        .with_ladder_generator(LadderGenerator::default());
+                }
+                Ladder::Generated(generator) => {
+                    self.context = self.context.with_ladder_generator(generator);
+                }
+                Ladder::Target(target) => {
+                    self.output_profiles = target.profiles();
+                    self.context = self.context.without_ladder_generator();
+                }
+                Ladder::Fixed(profiles) => {
+                    self.output_profiles = profiles;
+                    self.context = self.context.without_ladder_generator();
+                }
+            }
+            self
+        }
+
+        /// Keeps the renditions that encoded when other profiles fail: the master playlist only
+        /// lists them and the failures are reported in `failed_profiles`
+        pub fn with_partial_results(mut self, partial_results: bool) -> Self {
+            self.context = self.context.with_partial_results(partial_results);
+            self
+        }
+
+        /// Spawns every encoder through `sandbox`, e.g. a `BubblewrapSandbox`, limiting it to the
+        /// job's input and output paths
+        pub fn with_sandbox<P: SandboxPolicy + 'static>(mut self, sandbox: P) -> Self {
+            self.context = self.context.with_sandbox(sandbox);
+            self
+        }
+
+        /// Encrypts the segments with a key fetched from `provider` when the job starts, unless
+        /// `with_encryption` set a key file
+        pub fn with_key_provider<P: KeyProvider + 'static>(mut self, provider: P) -> Self {
+            self.context = self.context.with_key_provider(provider);
+            self
+        }
+
+        /// Rejects the job when its input path or key locations break `policy`, e.g. when
+        /// they come from untrusted user input
+        pub fn with_path_policy(mut self, policy: PathPolicy) -> Self {
+            self.context = self.context.with_path_policy(policy);
+            self
+        }
+
+        /// Encodes at most `limit` profiles at the same time
+        pub fn with_max_concurrent_profiles(mut self, limit: usize) -> Self {
+            self.context = self.context.with_max_concurrent_profiles(limit);
+            self
+        }
+
+        /// Kills any single profile encode running longer than `timeout`
+        pub fn with_profile_timeout(mut self, timeout: Duration) -> Self {
+            self.context = self.context.with_profile_timeout(timeout);
+            self
+        }
+
+        /// Aborts the whole job once it runs longer than `timeout`
+        pub fn with_job_timeout(mut self, timeout: Duration) -> Self {
+            self.context = self.context.with_job_timeout(timeout);
+            self
+        }
+
+        /// Receives per-profile progress while the backend encodes
+        pub fn with_progress_callback<F>(mut self, callback: F) -> Self
+        where
+            F: Fn(ProfileProgress) + Send + Sync + 'static,
+        {
+            self.context = self.context.with_progress_callback(callback);
+            self
+        }
+
+        #[cfg(feature = "job-queue")]
+        pub(crate) fn context(&self) -> &ProcessingContext {
+            &self.context
+        }
+
+        #[cfg(feature = "job-queue")]
+        pub(crate) fn profile_count(&self) -> usize {
+            self.output_profiles.len()
+        }
+
+        fn job(&self) -> ProcessingJob<'_, B, S> {
+            self.job_with_context(&self.context)
+        }
+
+        /// Builds a job for this processor that runs under `context` instead of its own
+        pub(crate) fn job_with_context<'a>(
+            &'a self,
+            context: &'a ProcessingContext,
+        ) -> ProcessingJob<'a, B, S> {
+            self.job_for(&self.input_video_path, context)
+        }
+
+        fn job_for<'a>(
+            &'a self,
+            input: &'a S,
+            context: &'a ProcessingContext,
+        ) -> ProcessingJob<'a, B, S> {
+            ProcessingJob {
+                input,
+                output_profiles: &self.output_profiles,
+                encryption: self.encryption_string.as_ref(),
+                backend: &self.backend,
+                context,
+            }
+        }
+
         pub async fn process_video(&self) -> Result<HlsVideo, HlsKitError> {
-            let input_guard = self.input_video_path.validate()?;
-
-            let temp_file_guard = input_guard.temp_file.as_ref();
-
-            let input_path = match temp_file_guard {
-                Some(temp_file) => temp_file.path().to_string_lossy().to_string(),
-                None => input_guard.path.clone(),
-            };
-
-            let output_dir = TempDir::new()?;
-            let output_dir_path = output_dir.path();
-
-            let tasks: Vec<_> = self
-                .output_profiles
-                .iter()
-                .enumerate()
-                .map(|(index, profile)| {
-                    self.backend.process_profile(
-                        input_path.clone(),
-                        profile,
-                        output_dir_path,
-                        index as i32,
-                        self.encryption_string.as_ref(),
-                    )
-                })
-                .collect();
-
-            let resolution_results: Vec<HlsVideoResolution> = try_join_all(tasks).await?;
-
-            let master_m3u8_data = generate_master_playlist(
-                output_dir_path,
-                resolution_results
-                    .iter()
-                    .map(|result| result.resolution)
-                    .collect(),
-                resolution_results
-                    .iter()
-                    .map(|result| result.playlist_name.as_str())
-                    .collect(),
-            )
-            .await?;
-
-            let hls_video = HlsVideo {
-                master_m3u8_data,
-                resolutions: resolution_results,
-            };
-
-            fs::remove_dir_all(output_dir_path)?;
-            Ok(hls_video)
+            self.job().process().await
+        }
+
+        /// Validates the input and returns the backend commands and output layout without running
+        /// anything, with paths relative to the work directory
+        ///
+        /// In-memory inputs are referenced through a temporary file that no longer exists once
+        /// this returns.
+        pub fn plan(&self) -> Result<ProcessingPlan, HlsKitError> {
+            self.job().plan(Path::new("."))
+        }
+
+        /// Like `plan`, for an encode into `output_dir`
+        pub fn plan_to_dir<P: AsRef<Path>>(
+            &self,
+            output_dir: P,
+        ) -> Result<ProcessingPlan, HlsKitError> {
+            self.job().plan(output_dir.as_ref())
+        }
+
+        /// Splits the job into work units to encode on other machines with
+        /// `process_work_unit`, then assemble with `work_units::merge_results`. Long sources are
+        /// cut into chunks when `chunked_encoding` is given. The input must be a path or URL
+        /// the workers can read
+        pub async fn work_units(
+            &self,
+            chunked_encoding: Option<ChunkedEncoding>,
+        ) -> Result<Vec<WorkUnit>, HlsKitError> {
+            self.job().work_units(chunked_encoding).await
+        }
+
+        /// Encodes a unit planned by `work_units`, with this processor's backend and options
+        pub async fn process_work_unit(
+            &self,
+            unit: &WorkUnit,
+        ) -> Result<WorkUnitResult, HlsKitError> {
+            self.job().process_work_unit(unit).await
+        }
+
+        /// Like `process_video`, also returning the work directory kept by the retention policy
+        pub async fn process_video_with_workdir(
+            &self,
+        ) -> Result<(HlsVideo, Option<PathBuf>), HlsKitError> {
+            self.job().process_with_workdir().await
+        }
+
+        /// Processes the video straight into `output_dir`, returning a manifest of the written files
+        pub async fn process_video_to_dir<P: AsRef<Path>>(
+            &self,
+            output_dir: P,
+        ) -> Result<HlsVideoManifest, HlsKitError> {
+            let output_dir = output_dir.as_ref();
+            fs::create_dir_all(output_dir)?;
+
+            self.job().process_to_dir(output_dir, None::<&NoSink>).await
+        }
+
+        /// Processes the video and forwards every playlist and segment to `sink`
+        pub async fn process_video_to_sink<K: OutputSink>(
+            &self,
+            sink: &K,
+        ) -> Result<(), HlsKitError> {
+            self.job().process_to_sink(sink).await
+        }
+
+        /// Processes `input` with this processor's profiles, backend and options, leaving the
+        /// processor untouched so one configured instance can serve many inputs
+        pub async fn process(&self, input: &S) -> Result<HlsVideo, HlsKitError> {
+            self.job_for(input, &self.context).process().await
+        }
+
+        /// Like `process`, writing the output into `output_dir`
+        pub async fn process_to_dir<P: AsRef<Path>>(
+            &self,
+            input: &S,
+            output_dir: P,
+        ) -> Result<HlsVideoManifest, HlsKitError> {
+            let output_dir = output_dir.as_ref();
+            fs::create_dir_all(output_dir)?;
+
+            self.job_for(input, &self.context)
+                .process_to_dir(output_dir, None::<&NoSink>)
+                .await
         }
+
+        /// Like `process`, forwarding every playlist and segment to `sink`
+        pub async fn process_to_sink<K: OutputSink>(
+            &self,
+            input: &S,
+            sink: &K,
+        ) -> Result<(), HlsKitError> {
+            self.job_for(input, &self.context)
+                .process_to_sink(sink)
+                .await
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ASF_GUID: [u8; 16] = [
+        0x30, 0x26, 0xB2, 0x75, 0x8E, 0x66, 0xCF, 0x11, 0xA6, 0xD9, 0x00, 0xAA, 0x00, 0x62, 0xCE,
+        0x6C,
+    ];
+
+    fn ftyp(brand: &[u8; 4]) -> Vec<u8> {
+        let mut buf = vec![0x00, 0x00, 0x00, 0x18];
+        buf.extend_from_slice(b"ftyp");
+        buf.extend_from_slice(brand);
+        buf.extend_from_slice(&[0; 12]);
+        buf
+    }
+
+    fn transport_stream(timestamp_len: usize) -> Vec<u8> {
+        let packet_len = timestamp_len + 188;
+        let mut buf = vec![0; packet_len * 2];
+        buf[timestamp_len] = 0x47;
+        buf[timestamp_len + packet_len] = 0x47;
+        buf
+    }
+
+    #[test]
+    fn ftyp_brands_match_iso_extensions() {
+        for (brand, ext) in [
+            (b"isom", "mp4"),
+            (b"qt  ", "mov"),
+            (b"M4V ", "m4v"),
+            (b"3gp5", "3gp"),
+            (b"3g2a", "3g2"),
+        ] {
+            assert!(is_valid_magic_bytes(&ftyp(brand), ext), "{ext}");
+        }
+        assert!(!is_valid_magic_bytes(&ftyp(b"M4V "), "webm"));
+        assert!(!is_valid_magic_bytes(&ftyp(b"3gp5"), "ts"));
+        assert!(!is_valid_magic_bytes(b"\0\0\0\x18ft", "m4v"));
+    }
+
+    #[test]
+    fn ebml_header_matches_matroska_extensions() {
+        let ebml = b"\x1A\x45\xDF\xA3\x9F\x42\x86\x81";
+
+        assert!(is_valid_magic_bytes(ebml, "webm"));
+        assert!(is_valid_magic_bytes(ebml, "mkv"));
+        assert!(!is_valid_magic_bytes(ebml, "mp4"));
+        assert!(!is_valid_magic_bytes(&ftyp(b"isom"), "webm"));
+    }
+
+    #[test]
+    fn sync_bytes_match_transport_stream_extensions() {
+        assert!(is_valid_magic_bytes(&transport_stream(0), "ts"));
+        assert!(is_valid_magic_bytes(&transport_stream(4), "m2ts"));
+        assert!(is_valid_magic_bytes(&transport_stream(4), "mts"));
+        assert!(!is_valid_magic_bytes(&transport_stream(0), "m2ts"));
+        assert!(!is_valid_magic_bytes(&transport_stream(4), "ts"));
+        assert!(!is_valid_magic_bytes(&transport_stream(0), "mpg"));
+
+        // A single 0x47 isn't enough, the next packet has to line up too
+        assert!(!is_valid_magic_bytes(&transport_stream(0)[..188], "ts"));
+    }
+
+    #[test]
+    fn flv_header_matches_flv_extension() {
+        let flv = b"FLV\x01\x05\x00\x00\x00\x09";
+
+        assert!(is_valid_magic_bytes(flv, "flv"));
+        assert!(!is_valid_magic_bytes(b"FLV\x02\x05", "flv"));
+        assert!(!is_valid_magic_bytes(flv, "avi"));
+    }
+
+    #[test]
+    fn asf_guid_matches_windows_media_extensions() {
+        assert!(is_valid_magic_bytes(&ASF_GUID, "asf"));
+        assert!(is_valid_magic_bytes(&ASF_GUID, "wmv"));
+        assert!(!is_valid_magic_bytes(&ASF_GUID[..15], "wmv"));
+        assert!(!is_valid_magic_bytes(&ASF_GUID, "flv"));
+        assert!(!is_valid_magic_bytes(b"RIFF\0\0\0\0AVI ", "asf"));
+    }
+
+    #[test]
+    fn pack_and_sequence_headers_match_program_stream_extensions() {
+        let pack = b"\x00\x00\x01\xBA\x44\x00";
+        let sequence = b"\x00\x00\x01\xB3\x14\x00";
+
+        assert!(is_valid_magic_bytes(pack, "mpg"));
+        assert!(is_valid_magic_bytes(sequence, "mpeg"));
+        assert!(!is_valid_magic_bytes(b"\x00\x00\x01\xE0\x00\x00", "mpg"));
+        assert!(!is_valid_magic_bytes(pack, "ts"));
+        assert!(!is_valid_magic_bytes(pack, "mp4"));
+    }
+
+    #[test]
+    fn in_memory_input_accepts_any_known_signature() {
+        for data in [
+            ftyp(b"3gp5"),
+            b"\x1A\x45\xDF\xA3\x9F".to_vec(),
+            transport_stream(0),
+            b"FLV\x01\x05".to_vec(),
+            ASF_GUID.to_vec(),
+            b"\x00\x00\x01\xBA\x44".to_vec(),
+        ] {
+            let guard = VideoInputType::InMemoryFile(data.clone())
+                .validate()
+                .expect("known signature");
+            assert_eq!(fs::read(&guard.path).unwrap(), data);
+        }
+
+        assert!(matches!(
+            VideoInputType::InMemoryFile(b"not a video".to_vec()).validate(),
+            Err(VideoValidatableErrors::InvalidFormat)
+        ));
+    }
+
+    #[test]
+    fn file_input_checks_content_against_its_extension() {
+        let dir = tempfile::tempdir().unwrap();
+        let webm = dir.path().join("clip.webm");
+        let mislabeled = dir.path().join("clip.mp4");
+        fs::write(&webm, b"\x1A\x45\xDF\xA3\x9F\x42\x86\x81").unwrap();
+        fs::write(&mislabeled, b"\x1A\x45\xDF\xA3\x9F\x42\x86\x81").unwrap();
+
+        let path = |path: &Path| VideoInputType::FilePath(path.to_str().unwrap().to_string());
+        assert!(path(&webm).validate().is_ok());
+        assert!(matches!(
+            path(&mislabeled).validate(),
+            Err(VideoValidatableErrors::InvalidFormat)
+        ));
     }
 }
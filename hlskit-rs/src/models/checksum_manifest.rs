@@ -0,0 +1,145 @@
+// SPDX-License-Identifier: LGPL-3.0-only
+/*
+ * Copyright © 2025 The HlsKit Project
+ *
+ * This software is licensed under the GNU Lesser General Public License v3.0 (LGPLv3).
+ * All contributions adhere to the LGPLv3 and the HlsKit Contributor License Agreement (CLA).
+ * A copy of the LGPLv3 can be found at https://www.gnu.org/licenses/lgpl-3.0.html
+ *
+ * HlsKit Contributor License Agreement
+ *
+ * By contributing to or modifying HlsKit, you agree to the following terms:
+ *
+ * 1. Collective Ownership:
+ * The HlsKit project incorporates original code and all contributions as a collective work,
+ * licensed under LGPLv3. Once submitted, contributions become part of the shared HlsKit
+ * ecosystem and cannot be reclaimed, reassigned, or withdrawn. Contributions to your own
+ * forks remain yours unless submitted here, at which point they join this collective whole under LGPLv3.
+ *
+ * 2. Definition of Contribution:
+ * You are considered a contributor if you modify the library in any form (including forks,
+ * wrappers, libraries, or extensions that alter its behavior), whether or not you submit
+ * your changes directly to this repository. All such modifications are part of the broader
+ * HlsKit ecosystem and are subject to this CLA.
+ *
+ * 3. Distribution of Modifications:
+ * If you distribute a modified version of HlsKit, you must license your modifications under
+ * LGPLv3 (with source code available as required by the license) and ensure they are
+ * adoptable by the HlsKit ecosystem (publicly available and compatible).
+ *
+ * 4. Networked Use of Modifications:
+ * If you use a modified version of HlsKit in a networked application, you must provide the
+ * source code of your modifications under LGPLv3 and notify the HlsKit project
+ * (e.g., via email to [higashikataengels@icloud.com]). This does not apply to the use of
+ * the unmodified library in proprietary software, which remains permissible under LGPLv3.
+ *
+ * 5. Scope:
+ * These terms apply to all contributions and modifications derived from the HlsKit project.
+ * The use of the unmodified library in proprietary software is governed solely by the LGPLv3.
+ */
+
+use serde_json::{json, Value};
+
+use crate::tools::hlskit_error::HlsKitError;
+
+/// Name of the sidecar written next to the master playlist
+pub const CHECKSUM_MANIFEST_NAME: &str = "checksums.json";
+
+/// SHA-256 of a playlist or segment of the asset
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FileChecksum {
+    /// Path relative to the master playlist, as listed by `HlsVideo::files`
+    pub name: String,
+    pub size: u64,
+    /// Lowercase hex digest
+    pub sha256: String,
+}
+
+/// Checksums of every file of an asset, for storage and CDN layers to verify it end-to-end
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ChecksumManifest {
+    pub files: Vec<FileChecksum>,
+}
+
+impl ChecksumManifest {
+    /// Hashes every `(name, contents)` pair
+    #[cfg(feature = "checksums")]
+    pub fn from_files<'a, I>(files: I) -> Self
+    where
+        I: IntoIterator<Item = (&'a str, &'a [u8])>,
+    {
+        Self {
+            files: files
+                .into_iter()
+                .map(|(name, data)| FileChecksum {
+                    name: name.to_string(),
+                    size: data.len() as u64,
+                    sha256: sha256_hex(data),
+                })
+                .collect(),
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&FileChecksum> {
+        self.files.iter().find(|file| file.name == name)
+    }
+
+    /// Whether `data` matches the checksum recorded for `name`, false when none is
+    #[cfg(feature = "checksums")]
+    pub fn verify(&self, name: &str, data: &[u8]) -> bool {
+        self.get(name).is_some_and(|file| {
+            file.size == data.len() as u64 && file.sha256.eq_ignore_ascii_case(&sha256_hex(data))
+        })
+    }
+
+    /// The sidecar JSON: `{"algorithm": "sha256", "files": [{"name", "size", "sha256"}]}`
+    pub fn to_json(&self) -> String {
+        let files: Vec<Value> = self
+            .files
+            .iter()
+            .map(|file| json!({"name": file.name, "size": file.size, "sha256": file.sha256}))
+            .collect();
+        let manifest = json!({"algorithm": "sha256", "files": files});
+
+        serde_json::to_string_pretty(&manifest).unwrap_or_default()
+    }
+
+    /// Parses a sidecar written by [`ChecksumManifest::to_json`]
+    pub fn from_json(json: &str) -> Result<Self, HlsKitError> {
+        let invalid = |error: String| HlsKitError::ConfigError {
+            error: format!("Invalid checksum manifest: {error}"),
+        };
+        let manifest: Value = serde_json::from_str(json).map_err(|e| invalid(e.to_string()))?;
+        if manifest["algorithm"] != "sha256" {
+            return Err(invalid(format!(
+                "unsupported algorithm {}",
+                manifest["algorithm"]
+            )));
+        }
+
+        let files = manifest["files"]
+            .as_array()
+            .ok_or_else(|| invalid("no files".to_string()))?
+            .iter()
+            .map(|file| {
+                Some(FileChecksum {
+                    name: file["name"].as_str()?.to_string(),
+                    size: file["size"].as_u64()?,
+                    sha256: file["sha256"].as_str()?.to_string(),
+                })
+            })
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(|| invalid("incomplete file entry".to_string()))?;
+
+        Ok(Self { files })
+    }
+}
+
+#[cfg(feature = "checksums")]
+fn sha256_hex(data: &[u8]) -> String {
+    use sha2::{Digest, Sha256};
+
+    hex::encode(Sha256::digest(data))
+}
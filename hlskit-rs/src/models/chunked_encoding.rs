@@ -0,0 +1,126 @@
+// SPDX-License-Identifier: LGPL-3.0-only
+/*
+ * Copyright © 2025 The HlsKit Project
+ *
+ * This software is licensed under the GNU Lesser General Public License v3.0 (LGPLv3).
+ * All contributions adhere to the LGPLv3 and the HlsKit Contributor License Agreement (CLA).
+ * A copy of the LGPLv3 can be found at https://www.gnu.org/licenses/lgpl-3.0.html
+ *
+ * HlsKit Contributor License Agreement
+ *
+ * By contributing to or modifying HlsKit, you agree to the following terms:
+ *
+ * 1. Collective Ownership:
+ * The HlsKit project incorporates original code and all contributions as a collective work,
+ * licensed under LGPLv3. Once submitted, contributions become part of the shared HlsKit
+ * ecosystem and cannot be reclaimed, reassigned, or withdrawn. Contributions to your own
+ * forks remain yours unless submitted here, at which point they join this collective whole under LGPLv3.
+ *
+ * 2. Definition of Contribution:
+ * You are considered a contributor if you modify the library in any form (including forks,
+ * wrappers, libraries, or extensions that alter its behavior), whether or not you submit
+ * your changes directly to this repository. All such modifications are part of the broader
+ * HlsKit ecosystem and are subject to this CLA.
+ *
+ * 3. Distribution of Modifications:
+ * If you distribute a modified version of HlsKit, you must license your modifications under
+ * LGPLv3 (with source code available as required by the license) and ensure they are
+ * adoptable by the HlsKit ecosystem (publicly available and compatible).
+ *
+ * 4. Networked Use of Modifications:
+ * If you use a modified version of HlsKit in a networked application, you must provide the
+ * source code of your modifications under LGPLv3 and notify the HlsKit project
+ * (e.g., via email to [higashikataengels@icloud.com]). This does not apply to the use of
+ * the unmodified library in proprietary software, which remains permissible under LGPLv3.
+ *
+ * 5. Scope:
+ * These terms apply to all contributions and modifications derived from the HlsKit project.
+ * The use of the unmodified library in proprietary software is governed solely by the LGPLv3.
+ */
+
+use std::time::Duration;
+
+/// Splits long sources into chunks starting on keyframes, encoded side by side and joined into
+/// one rendition afterwards, trading some compression at the chunk boundaries for wall time
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ChunkedEncoding {
+    /// Shortest chunk, each one ending on the first keyframe past it
+    pub chunk_duration: Duration,
+    /// Chunks of a profile encoded at once
+    pub max_parallel_chunks: usize,
+    /// Shorter sources are encoded in one run
+    pub min_source_duration: Duration,
+}
+
+impl Default for ChunkedEncoding {
+    fn default() -> Self {
+        Self {
+            chunk_duration: Duration::from_secs(120),
+            max_parallel_chunks: 4,
+            min_source_duration: Duration::from_secs(600),
+        }
+    }
+}
+
+/// A stretch of the source, from a keyframe to the next chunk or the end of the source
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SourceChunk {
+    pub start: Duration,
+    /// `None` for the last chunk, read until the end of the source
+    pub duration: Option<Duration>,
+}
+
+impl ChunkedEncoding {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// At least a second
+    pub fn with_chunk_duration(mut self, duration: Duration) -> Self {
+        self.chunk_duration = duration.max(Duration::from_secs(1));
+        self
+    }
+
+    /// At least one
+    pub fn with_max_parallel_chunks(mut self, chunks: usize) -> Self {
+        self.max_parallel_chunks = chunks.max(1);
+        self
+    }
+
+    pub fn with_min_source_duration(mut self, duration: Duration) -> Self {
+        self.min_source_duration = duration;
+        self
+    }
+
+    /// Whether a source of `duration` is worth splitting
+    pub fn applies_to(&self, duration: Duration) -> bool {
+        duration >= self.min_source_duration && duration >= self.chunk_duration * 2
+    }
+
+    /// The chunks of a source of `duration` with keyframes at `keyframes`, in order. The last
+    /// chunk is never shorter than half a chunk, and a single chunk means the source has too
+    /// few keyframes to split
+    pub fn plan_chunks(&self, keyframes: &[Duration], duration: Duration) -> Vec<SourceChunk> {
+        let mut starts = vec![Duration::ZERO];
+        for &keyframe in keyframes {
+            let last = starts[starts.len() - 1];
+            if keyframe >= last + self.chunk_duration
+                && keyframe + self.chunk_duration / 2 <= duration
+            {
+                starts.push(keyframe);
+            }
+        }
+
+        let mut chunks = Vec::with_capacity(starts.len());
+        for (index, &start) in starts.iter().enumerate() {
+            chunks.push(SourceChunk {
+                start,
+                duration: starts.get(index + 1).map(|&next| next - start),
+            });
+        }
+
+        chunks
+    }
+}
@@ -0,0 +1,100 @@
+// SPDX-License-Identifier: LGPL-3.0-only
+/*
+ * Copyright © 2025 The HlsKit Project
+ *
+ * This software is licensed under the GNU Lesser General Public License v3.0 (LGPLv3).
+ * All contributions adhere to the LGPLv3 and the HlsKit Contributor License Agreement (CLA).
+ * A copy of the LGPLv3 can be found at https://www.gnu.org/licenses/lgpl-3.0.html
+ *
+ * HlsKit Contributor License Agreement
+ *
+ * By contributing to or modifying HlsKit, you agree to the following terms:
+ *
+ * 1. Collective Ownership:
+ * The HlsKit project incorporates original code and all contributions as a collective work,
+ * licensed under LGPLv3. Once submitted, contributions become part of the shared HlsKit
+ * ecosystem and cannot be reclaimed, reassigned, or withdrawn. Contributions to your own
+ * forks remain yours unless submitted here, at which point they join this collective whole under LGPLv3.
+ *
+ * 2. Definition of Contribution:
+ * You are considered a contributor if you modify the library in any form (including forks,
+ * wrappers, libraries, or extensions that alter its behavior), whether or not you submit
+ * your changes directly to this repository. All such modifications are part of the broader
+ * HlsKit ecosystem and are subject to this CLA.
+ *
+ * 3. Distribution of Modifications:
+ * If you distribute a modified version of HlsKit, you must license your modifications under
+ * LGPLv3 (with source code available as required by the license) and ensure they are
+ * adoptable by the HlsKit ecosystem (publicly available and compatible).
+ *
+ * 4. Networked Use of Modifications:
+ * If you use a modified version of HlsKit in a networked application, you must provide the
+ * source code of your modifications under LGPLv3 and notify the HlsKit project
+ * (e.g., via email to [higashikataengels@icloud.com]). This does not apply to the use of
+ * the unmodified library in proprietary software, which remains permissible under LGPLv3.
+ *
+ * 5. Scope:
+ * These terms apply to all contributions and modifications derived from the HlsKit project.
+ * The use of the unmodified library in proprietary software is governed solely by the LGPLv3.
+ */
+
+use std::fmt;
+
+/// How a backend fared on one conformance case
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum ConformanceOutcome {
+    Passed,
+    /// The output broke the listed expectations, or the job failed
+    Failed(Vec<String>),
+    /// The backend reported the case as unsupported, e.g. encryption without the feature
+    Unsupported(String),
+}
+
+/// A single case of the battery, e.g. `encrypted`
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ConformanceCase {
+    pub name: String,
+    pub outcome: ConformanceOutcome,
+}
+
+/// Outcome of a conformance run, see `BackendConformance`
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ConformanceReport {
+    /// Name of the backend under test
+    pub backend: String,
+    pub cases: Vec<ConformanceCase>,
+}
+
+impl ConformanceReport {
+    /// Whether no case failed, unsupported cases aside
+    pub fn is_conformant(&self) -> bool {
+        self.failures().next().is_none()
+    }
+
+    pub fn failures(&self) -> impl Iterator<Item = &ConformanceCase> {
+        self.cases
+            .iter()
+            .filter(|case| matches!(case.outcome, ConformanceOutcome::Failed(_)))
+    }
+}
+
+impl fmt::Display for ConformanceReport {
+    /// One line per case, e.g. `gstreamer/encrypted: failed: playlist_0.m3u8: no segments`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for case in &self.cases {
+            write!(f, "{}/{}: ", self.backend, case.name)?;
+            match &case.outcome {
+                ConformanceOutcome::Passed => writeln!(f, "passed")?,
+                ConformanceOutcome::Failed(problems) => {
+                    writeln!(f, "failed: {}", problems.join("; "))?
+                }
+                ConformanceOutcome::Unsupported(reason) => writeln!(f, "unsupported: {reason}")?,
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,145 @@
+// SPDX-License-Identifier: LGPL-3.0-only
+/*
+ * Copyright © 2025 The HlsKit Project
+ *
+ * This software is licensed under the GNU Lesser General Public License v3.0 (LGPLv3).
+ * All contributions adhere to the LGPLv3 and the HlsKit Contributor License Agreement (CLA).
+ * A copy of the LGPLv3 can be found at https://www.gnu.org/licenses/lgpl-3.0.html
+ *
+ * HlsKit Contributor License Agreement
+ *
+ * By contributing to or modifying HlsKit, you agree to the following terms:
+ *
+ * 1. Collective Ownership:
+ * The HlsKit project incorporates original code and all contributions as a collective work,
+ * licensed under LGPLv3. Once submitted, contributions become part of the shared HlsKit
+ * ecosystem and cannot be reclaimed, reassigned, or withdrawn. Contributions to your own
+ * forks remain yours unless submitted here, at which point they join this collective whole under LGPLv3.
+ *
+ * 2. Definition of Contribution:
+ * You are considered a contributor if you modify the library in any form (including forks,
+ * wrappers, libraries, or extensions that alter its behavior), whether or not you submit
+ * your changes directly to this repository. All such modifications are part of the broader
+ * HlsKit ecosystem and are subject to this CLA.
+ *
+ * 3. Distribution of Modifications:
+ * If you distribute a modified version of HlsKit, you must license your modifications under
+ * LGPLv3 (with source code available as required by the license) and ensure they are
+ * adoptable by the HlsKit ecosystem (publicly available and compatible).
+ *
+ * 4. Networked Use of Modifications:
+ * If you use a modified version of HlsKit in a networked application, you must provide the
+ * source code of your modifications under LGPLv3 and notify the HlsKit project
+ * (e.g., via email to [higashikataengels@icloud.com]). This does not apply to the use of
+ * the unmodified library in proprietary software, which remains permissible under LGPLv3.
+ *
+ * 5. Scope:
+ * These terms apply to all contributions and modifications derived from the HlsKit project.
+ * The use of the unmodified library in proprietary software is governed solely by the LGPLv3.
+ */
+
+use std::{fmt, time::Duration};
+
+/// How much of a source is black and silent, found by the content preflight
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ContentCheck {
+    pub source_duration: Duration,
+    /// Total length of the black stretches of the video
+    pub black_duration: Duration,
+    /// Total length of the silent stretches of the audio, `None` without an audio stream
+    pub silent_duration: Option<Duration>,
+}
+
+impl ContentCheck {
+    /// Share of the source that is black, from 0 to 1
+    pub fn black_ratio(&self) -> f64 {
+        ratio(self.black_duration, self.source_duration)
+    }
+
+    /// Share of the source that is silent, `None` without an audio stream
+    pub fn silent_ratio(&self) -> Option<f64> {
+        self.silent_duration
+            .map(|silent| ratio(silent, self.source_duration))
+    }
+}
+
+impl fmt::Display for ContentCheck {
+    /// e.g. `12.0s of 12.5s black, 12.5s silent`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:.1}s of {:.1}s black",
+            self.black_duration.as_secs_f64(),
+            self.source_duration.as_secs_f64()
+        )?;
+        match self.silent_duration {
+            Some(silent) => write!(f, ", {:.1}s silent", silent.as_secs_f64()),
+            None => write!(f, ", no audio"),
+        }
+    }
+}
+
+fn ratio(part: Duration, whole: Duration) -> f64 {
+    if whole.is_zero() {
+        return 0.0;
+    }
+    (part.as_secs_f64() / whole.as_secs_f64()).min(1.0)
+}
+
+/// Rejects sources that are (almost) entirely black or silent before anything is encoded
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ContentQc {
+    /// Share of the source, from 0 to 1, from which it counts as entirely black or silent
+    pub coverage: f64,
+    pub reject_black: bool,
+    pub reject_silent: bool,
+}
+
+impl Default for ContentQc {
+    fn default() -> Self {
+        Self {
+            coverage: 0.98,
+            reject_black: true,
+            reject_silent: true,
+        }
+    }
+}
+
+impl ContentQc {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_coverage(mut self, coverage: f64) -> Self {
+        self.coverage = coverage.clamp(0.0, 1.0);
+        self
+    }
+
+    pub fn with_reject_black(mut self, reject_black: bool) -> Self {
+        self.reject_black = reject_black;
+        self
+    }
+
+    /// Silent sources are accepted when false, e.g. for screen recordings without narration
+    pub fn with_reject_silent(mut self, reject_silent: bool) -> Self {
+        self.reject_silent = reject_silent;
+        self
+    }
+
+    pub fn is_black(&self, check: &ContentCheck) -> bool {
+        check.black_ratio() >= self.coverage
+    }
+
+    pub fn is_silent(&self, check: &ContentCheck) -> bool {
+        check
+            .silent_ratio()
+            .is_some_and(|ratio| ratio >= self.coverage)
+    }
+
+    /// Whether `check` fails this QC
+    pub fn rejects(&self, check: &ContentCheck) -> bool {
+        (self.reject_black && self.is_black(check)) || (self.reject_silent && self.is_silent(check))
+    }
+}
@@ -0,0 +1,94 @@
+// SPDX-License-Identifier: LGPL-3.0-only
+/*
+ * Copyright © 2025 The HlsKit Project
+ *
+ * This software is licensed under the GNU Lesser General Public License v3.0 (LGPLv3).
+ * All contributions adhere to the LGPLv3 and the HlsKit Contributor License Agreement (CLA).
+ * A copy of the LGPLv3 can be found at https://www.gnu.org/licenses/lgpl-3.0.html
+ *
+ * HlsKit Contributor License Agreement
+ *
+ * By contributing to or modifying HlsKit, you agree to the following terms:
+ *
+ * 1. Collective Ownership:
+ * The HlsKit project incorporates original code and all contributions as a collective work,
+ * licensed under LGPLv3. Once submitted, contributions become part of the shared HlsKit
+ * ecosystem and cannot be reclaimed, reassigned, or withdrawn. Contributions to your own
+ * forks remain yours unless submitted here, at which point they join this collective whole under LGPLv3.
+ *
+ * 2. Definition of Contribution:
+ * You are considered a contributor if you modify the library in any form (including forks,
+ * wrappers, libraries, or extensions that alter its behavior), whether or not you submit
+ * your changes directly to this repository. All such modifications are part of the broader
+ * HlsKit ecosystem and are subject to this CLA.
+ *
+ * 3. Distribution of Modifications:
+ * If you distribute a modified version of HlsKit, you must license your modifications under
+ * LGPLv3 (with source code available as required by the license) and ensure they are
+ * adoptable by the HlsKit ecosystem (publicly available and compatible).
+ *
+ * 4. Networked Use of Modifications:
+ * If you use a modified version of HlsKit in a networked application, you must provide the
+ * source code of your modifications under LGPLv3 and notify the HlsKit project
+ * (e.g., via email to [higashikataengels@icloud.com]). This does not apply to the use of
+ * the unmodified library in proprietary software, which remains permissible under LGPLv3.
+ *
+ * 5. Scope:
+ * These terms apply to all contributions and modifications derived from the HlsKit project.
+ * The use of the unmodified library in proprietary software is governed solely by the LGPLv3.
+ */
+
+use crate::models::source_info::HdrTransfer;
+
+/// Dynamic range a profile is encoded in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum DynamicRange {
+    /// 8-bit BT.709 H.264, HDR sources are tone-mapped
+    #[default]
+    Sdr,
+    /// 10-bit BT.2020 HEVC keeping the PQ or HLG transfer of HDR sources. SDR sources are
+    /// encoded as `Sdr`, there is nothing to keep
+    Hdr,
+}
+
+/// The `VIDEO-RANGE` a rendition ends up in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum VideoRange {
+    #[default]
+    Sdr,
+    Pq,
+    Hlg,
+}
+
+impl VideoRange {
+    /// ffmpeg's name for the HDR transfer, `None` for SDR
+    pub fn hdr_transfer_name(&self) -> Option<&'static str> {
+        match self {
+            VideoRange::Sdr => None,
+            VideoRange::Pq => Some("smpte2084"),
+            VideoRange::Hlg => Some("arib-std-b67"),
+        }
+    }
+
+    /// The value of the `VIDEO-RANGE` playlist attribute
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            VideoRange::Sdr => "SDR",
+            VideoRange::Pq => "PQ",
+            VideoRange::Hlg => "HLG",
+        }
+    }
+}
+
+impl From<HdrTransfer> for VideoRange {
+    fn from(transfer: HdrTransfer) -> Self {
+        match transfer {
+            HdrTransfer::Pq => VideoRange::Pq,
+            HdrTransfer::Hlg => VideoRange::Hlg,
+        }
+    }
+}
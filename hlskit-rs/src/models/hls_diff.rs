@@ -0,0 +1,181 @@
+// SPDX-License-Identifier: LGPL-3.0-only
+/*
+ * Copyright © 2025 The HlsKit Project
+ *
+ * This software is licensed under the GNU Lesser General Public License v3.0 (LGPLv3).
+ * All contributions adhere to the LGPLv3 and the HlsKit Contributor License Agreement (CLA).
+ * A copy of the LGPLv3 can be found at https://www.gnu.org/licenses/lgpl-3.0.html
+ *
+ * HlsKit Contributor License Agreement
+ *
+ * By contributing to or modifying HlsKit, you agree to the following terms:
+ *
+ * 1. Collective Ownership:
+ * The HlsKit project incorporates original code and all contributions as a collective work,
+ * licensed under LGPLv3. Once submitted, contributions become part of the shared HlsKit
+ * ecosystem and cannot be reclaimed, reassigned, or withdrawn. Contributions to your own
+ * forks remain yours unless submitted here, at which point they join this collective whole under LGPLv3.
+ *
+ * 2. Definition of Contribution:
+ * You are considered a contributor if you modify the library in any form (including forks,
+ * wrappers, libraries, or extensions that alter its behavior), whether or not you submit
+ * your changes directly to this repository. All such modifications are part of the broader
+ * HlsKit ecosystem and are subject to this CLA.
+ *
+ * 3. Distribution of Modifications:
+ * If you distribute a modified version of HlsKit, you must license your modifications under
+ * LGPLv3 (with source code available as required by the license) and ensure they are
+ * adoptable by the HlsKit ecosystem (publicly available and compatible).
+ *
+ * 4. Networked Use of Modifications:
+ * If you use a modified version of HlsKit in a networked application, you must provide the
+ * source code of your modifications under LGPLv3 and notify the HlsKit project
+ * (e.g., via email to [higashikataengels@icloud.com]). This does not apply to the use of
+ * the unmodified library in proprietary software, which remains permissible under LGPLv3.
+ *
+ * 5. Scope:
+ * These terms apply to all contributions and modifications derived from the HlsKit project.
+ * The use of the unmodified library in proprietary software is governed solely by the LGPLv3.
+ */
+
+use std::fmt;
+
+/// What changed between two HLS assets, see `HlsVideo::diff`
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HlsDiff {
+    pub differences: Vec<HlsDifference>,
+}
+
+impl HlsDiff {
+    /// Whether both assets have the same variants, attributes, segments and durations
+    pub fn is_empty(&self) -> bool {
+        self.differences.is_empty()
+    }
+}
+
+impl fmt::Display for HlsDiff {
+    /// One difference per line
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for difference in &self.differences {
+            writeln!(f, "{difference}")?;
+        }
+        Ok(())
+    }
+}
+
+/// A single difference, renditions being matched by resolution
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "kind", rename_all = "snake_case"))]
+pub enum HlsDifference {
+    /// Only the other asset has this variant
+    VariantAdded { resolution: (i32, i32), uri: String },
+    /// Only this asset has this variant
+    VariantRemoved { resolution: (i32, i32), uri: String },
+    /// A `EXT-X-STREAM-INF` attribute, `None` when absent
+    VariantAttributeChanged {
+        resolution: (i32, i32),
+        attribute: String,
+        before: Option<String>,
+        after: Option<String>,
+    },
+    TargetDurationChanged {
+        resolution: (i32, i32),
+        before: u64,
+        after: u64,
+    },
+    SegmentCountChanged {
+        resolution: (i32, i32),
+        before: usize,
+        after: usize,
+    },
+    /// Sum of the `#EXTINF` durations, in seconds
+    DurationChanged {
+        resolution: (i32, i32),
+        before: f64,
+        after: f64,
+    },
+    /// The first segment whose duration differs, in seconds
+    SegmentDurationChanged {
+        resolution: (i32, i32),
+        segment: usize,
+        before: f64,
+        after: f64,
+    },
+    /// Total bytes of the segments
+    SizeChanged {
+        resolution: (i32, i32),
+        before: u64,
+        after: u64,
+    },
+    /// A playlist that could not be compared
+    Unparsable { playlist: String, error: String },
+}
+
+impl fmt::Display for HlsDifference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = |(width, height): &(i32, i32)| format!("{width}x{height}");
+        match self {
+            HlsDifference::VariantAdded { resolution, uri } => {
+                write!(f, "+ {} variant {uri}", name(resolution))
+            }
+            HlsDifference::VariantRemoved { resolution, uri } => {
+                write!(f, "- {} variant {uri}", name(resolution))
+            }
+            HlsDifference::VariantAttributeChanged {
+                resolution,
+                attribute,
+                before,
+                after,
+            } => write!(
+                f,
+                "~ {} {attribute}: {} -> {}",
+                name(resolution),
+                before.as_deref().unwrap_or("(none)"),
+                after.as_deref().unwrap_or("(none)")
+            ),
+            HlsDifference::TargetDurationChanged {
+                resolution,
+                before,
+                after,
+            } => write!(
+                f,
+                "~ {} target duration: {before}s -> {after}s",
+                name(resolution)
+            ),
+            HlsDifference::SegmentCountChanged {
+                resolution,
+                before,
+                after,
+            } => write!(f, "~ {} segments: {before} -> {after}", name(resolution)),
+            HlsDifference::DurationChanged {
+                resolution,
+                before,
+                after,
+            } => write!(
+                f,
+                "~ {} duration: {before:.3}s -> {after:.3}s",
+                name(resolution)
+            ),
+            HlsDifference::SegmentDurationChanged {
+                resolution,
+                segment,
+                before,
+                after,
+            } => write!(
+                f,
+                "~ {} segment {segment} duration: {before:.3}s -> {after:.3}s",
+                name(resolution)
+            ),
+            HlsDifference::SizeChanged {
+                resolution,
+                before,
+                after,
+            } => write!(f, "~ {} size: {before} -> {after} bytes", name(resolution)),
+            HlsDifference::Unparsable { playlist, error } => {
+                write!(f, "! {playlist}: {error}")
+            }
+        }
+    }
+}
@@ -38,25 +38,167 @@
  * The use of the unmodified library in proprietary software is governed solely by the LGPLv3.
  */
 
+use std::{path::Path, time::SystemTime};
+
+use bytes::Bytes;
+
+use crate::{
+    models::{
+        checksum_manifest::ChecksumManifest, hls_diff::HlsDiff,
+        hls_video_processing_settings::HlsVideoProcessingSettings,
+        hls_video_report::HlsVideoReport, lint_finding::LintFinding, profile_error::ProfileError,
+        quality_scores::QualityScores, resource_usage::ResourceUsage,
+        source_adjustments::SourceAdjustments,
+    },
+    tools::{
+        diff_tools, hls_lint::lint, hlskit_error::HlsKitError, rendition_tools, rewrite_tools,
+        segment_tools::load_hls_video_from_dir,
+    },
+    traits::url_signer::UrlSigner,
+    VideoInputType,
+};
+
+pub const MASTER_PLAYLIST_NAME: &str = "master.m3u8";
+
 /// Represents an HLS video segment
 #[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct HlsVideoSegment {
     pub segment_name: String,
-    pub segment_data: Vec<u8>,
+    pub segment_data: Bytes,
 }
 
 /// Represents a video resolution and its corresponding playlist
 #[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct HlsVideoResolution {
     pub resolution: (i32, i32),
     pub playlist_name: String,
-    pub playlist_data: Vec<u8>,
+    pub playlist_data: Bytes,
     pub segments: Vec<HlsVideoSegment>,
+    /// Scores against the source, when quality measurement is enabled
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub quality: Option<QualityScores>,
 }
 
-/// Represents an HLS video with multiple resolutions
+/// Represents an HLS video with multiple resolutions. Playlist and segment data are
+/// reference-counted, clones share them instead of copying
 #[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct HlsVideo {
-    pub master_m3u8_data: Vec<u8>,
+    pub master_m3u8_data: Bytes,
     pub resolutions: Vec<HlsVideoResolution>,
+    /// Profiles left out of the output, only ever filled in partial-results mode
+    pub failed_profiles: Vec<ProfileError>,
+    /// Corrections applied to the source, such as deinterlacing
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub source_adjustments: SourceAdjustments,
+    /// Resources consumed producing this video, empty when loaded from disk
+    pub resource_usage: ResourceUsage,
+    /// SHA-256 of every file, when checksums are enabled or a sidecar was loaded. Cleared
+    /// when URIs are rewritten, the playlists no longer matching
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub checksums: Option<ChecksumManifest>,
+    /// Per-rendition statistics of the job, empty when loaded from disk
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub report: HlsVideoReport,
+}
+
+impl HlsVideo {
+    /// Loads an existing HLS asset from a directory containing `master.m3u8`, its media
+    /// playlists and their segments
+    pub fn load_from_dir<P: AsRef<Path>>(dir: P) -> Result<Self, HlsKitError> {
+        load_hls_video_from_dir(dir.as_ref())
+    }
+
+    /// Checks the asset against Apple's HLS authoring specification, see `hls_lint::lint`
+    pub fn lint(&self) -> Vec<LintFinding> {
+        lint(self)
+    }
+
+    /// Lists what changed from this asset to `other`, e.g. across encoder or config upgrades
+    pub fn diff(&self, other: &HlsVideo) -> HlsDiff {
+        diff_tools::diff(self, other)
+    }
+
+    /// Encodes `profile` from `input` into a new rendition, without touching the existing ones
+    pub async fn add_rendition(
+        &mut self,
+        input: VideoInputType,
+        profile: HlsVideoProcessingSettings,
+    ) -> Result<(), HlsKitError> {
+        rendition_tools::add_rendition(self, input, profile).await
+    }
+
+    /// Encodes `profile` from `input` in place of the rendition at `resolution`
+    pub async fn replace_rendition(
+        &mut self,
+        resolution: (i32, i32),
+        input: VideoInputType,
+        profile: HlsVideoProcessingSettings,
+    ) -> Result<(), HlsKitError> {
+        rendition_tools::replace_rendition(self, resolution, input, profile).await
+    }
+
+    /// Prefixes the segment URIs of the media playlists with `base_url`, e.g. a CDN host
+    pub fn prefix_segment_uris(&mut self, base_url: &str) {
+        rewrite_tools::prefix_segment_uris(self, base_url);
+    }
+
+    /// Replaces every relative URI with a signed URL under `base_url`, see
+    /// `rewrite_tools::sign_uris`
+    pub fn sign_uris(
+        &mut self,
+        base_url: &str,
+        signer: &dyn UrlSigner,
+        expires_at: SystemTime,
+    ) -> Result<(), HlsKitError> {
+        rewrite_tools::sign_uris(self, base_url, signer, expires_at)
+    }
+
+    /// Renames the segments, updating the media playlists, see `rewrite_tools::rename_segments`
+    pub fn rename_segments(&mut self, rename: impl FnMut(&str) -> String) {
+        rewrite_tools::rename_segments(self, rename);
+    }
+
+    /// Renames the media playlists, updating the master playlist
+    pub fn rename_playlists(&mut self, rename: impl FnMut(&str) -> String) {
+        rewrite_tools::rename_playlists(self, rename);
+    }
+
+    /// Moves each rendition into its own subdirectory, updating the master playlist
+    pub fn move_to_subdirectories(&mut self, dir_for: impl FnMut(&HlsVideoResolution) -> String) {
+        rewrite_tools::move_to_subdirectories(self, dir_for);
+    }
+
+    /// Writes every file of the asset under `dir`, creating subdirectories as needed
+    pub fn write_to_dir<P: AsRef<Path>>(&self, dir: P) -> Result<(), HlsKitError> {
+        for (name, data) in self.files() {
+            let path = dir.as_ref().join(name);
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(path, data)?;
+        }
+
+        Ok(())
+    }
+
+    /// Lists every file of the asset as `(relative path, contents)`, master playlist first
+    pub fn files(&self) -> Vec<(&str, &[u8])> {
+        let mut files = vec![(MASTER_PLAYLIST_NAME, self.master_m3u8_data.as_ref())];
+
+        for resolution in &self.resolutions {
+            files.push((
+                resolution.playlist_name.as_str(),
+                resolution.playlist_data.as_ref(),
+            ));
+
+            for segment in &resolution.segments {
+                files.push((segment.segment_name.as_str(), segment.segment_data.as_ref()));
+            }
+        }
+
+        files
+    }
 }
@@ -0,0 +1,113 @@
+// SPDX-License-Identifier: LGPL-3.0-only
+/*
+ * Copyright © 2025 The HlsKit Project
+ *
+ * This software is licensed under the GNU Lesser General Public License v3.0 (LGPLv3).
+ * All contributions adhere to the LGPLv3 and the HlsKit Contributor License Agreement (CLA).
+ * A copy of the LGPLv3 can be found at https://www.gnu.org/licenses/lgpl-3.0.html
+ *
+ * HlsKit Contributor License Agreement
+ *
+ * By contributing to or modifying HlsKit, you agree to the following terms:
+ *
+ * 1. Collective Ownership:
+ * The HlsKit project incorporates original code and all contributions as a collective work,
+ * licensed under LGPLv3. Once submitted, contributions become part of the shared HlsKit
+ * ecosystem and cannot be reclaimed, reassigned, or withdrawn. Contributions to your own
+ * forks remain yours unless submitted here, at which point they join this collective whole under LGPLv3.
+ *
+ * 2. Definition of Contribution:
+ * You are considered a contributor if you modify the library in any form (including forks,
+ * wrappers, libraries, or extensions that alter its behavior), whether or not you submit
+ * your changes directly to this repository. All such modifications are part of the broader
+ * HlsKit ecosystem and are subject to this CLA.
+ *
+ * 3. Distribution of Modifications:
+ * If you distribute a modified version of HlsKit, you must license your modifications under
+ * LGPLv3 (with source code available as required by the license) and ensure they are
+ * adoptable by the HlsKit ecosystem (publicly available and compatible).
+ *
+ * 4. Networked Use of Modifications:
+ * If you use a modified version of HlsKit in a networked application, you must provide the
+ * source code of your modifications under LGPLv3 and notify the HlsKit project
+ * (e.g., via email to [higashikataengels@icloud.com]). This does not apply to the use of
+ * the unmodified library in proprietary software, which remains permissible under LGPLv3.
+ *
+ * 5. Scope:
+ * These terms apply to all contributions and modifications derived from the HlsKit project.
+ * The use of the unmodified library in proprietary software is governed solely by the LGPLv3.
+ */
+
+use std::path::PathBuf;
+
+use bytes::Bytes;
+
+use crate::models::{
+    checksum_manifest::ChecksumManifest, content_check::ContentCheck, dynamic_range::VideoRange,
+    frame_rate::FrameRate, hls_video_report::HlsVideoReport, profile_error::ProfileError,
+    quality_scores::QualityScores, resource_usage::ResourceUsage,
+    source_adjustments::SourceAdjustments, validation_report::ValidationReport,
+};
+
+/// Represents a rendition whose playlist and segments were written to disk
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HlsVideoResolutionManifest {
+    pub resolution: (i32, i32),
+    pub playlist_name: String,
+    pub playlist_path: PathBuf,
+    pub segment_paths: Vec<PathBuf>,
+    /// SDR, or PQ/HLG for HDR renditions
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub video_range: VideoRange,
+    /// Constant frame rate of the rendition, `None` when it follows the source
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub frame_rate: Option<FrameRate>,
+    /// Scores against the source, when quality measurement is enabled
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub quality: Option<QualityScores>,
+    /// Video encoder used, e.g. `libx264`
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub encoder: String,
+    /// RFC 6381 codecs advertised in the master playlist, e.g. `avc1.640028,mp4a.40.2`
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub codecs: Option<String>,
+    /// What the encoder processes of this rendition consumed
+    pub resource_usage: ResourceUsage,
+    /// Hex IV the segments were encrypted with, `None` when unencrypted
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub encryption_iv: Option<String>,
+}
+
+/// Represents an HLS video written to a directory, listing every file produced
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HlsVideoManifest {
+    pub output_dir: PathBuf,
+    /// Where the master playlist is written, only when the job outputs to a directory
+    pub master_playlist_path: PathBuf,
+    /// Contents of the master playlist, built in memory
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub master_playlist: Bytes,
+    pub resolutions: Vec<HlsVideoResolutionManifest>,
+    /// Profiles left out of the output, only ever filled in partial-results mode
+    pub failed_profiles: Vec<ProfileError>,
+    /// Corrections applied to the source, such as deinterlacing
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub source_adjustments: SourceAdjustments,
+    /// Black and silent share of the source, when content QC is enabled
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub content_check: Option<ContentCheck>,
+    /// SHA-256 of every file, also written to `checksums.json`, when enabled
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub checksums: Option<ChecksumManifest>,
+    /// Per-rendition statistics of the job
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub report: HlsVideoReport,
+    /// Findings of output validation, when enabled
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub validation: Option<ValidationReport>,
+    /// Job totals: wall time of the whole job, CPU time of every encoder and the largest
+    /// peak RSS among them
+    pub resource_usage: ResourceUsage,
+}
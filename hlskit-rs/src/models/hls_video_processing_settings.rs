@@ -38,8 +38,18 @@
  * The use of the unmodified library in proprietary software is governed solely by the LGPLv3.
  */
 
+use std::time::Duration;
+
+#[cfg(feature = "config")]
+use crate::{models::processing_config::ProcessingConfig, tools::hlskit_error::HlsKitError};
+use crate::{
+    models::{dynamic_range::DynamicRange, frame_rate::FrameRate, target::Target},
+    VideoProcessorEncryptionSettings,
+};
+
 /// Preset options for FFmpeg video processing
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum FfmpegVideoProcessingPreset {
     VerySlow,
     Slower,
@@ -66,10 +76,64 @@ impl FfmpegVideoProcessingPreset {
             FfmpegVideoProcessingPreset::UltraFast => "ultrafast",
         }
     }
+
+    /// The preset named `value`, e.g. `veryfast`
+    pub fn from_value(value: &str) -> Option<Self> {
+        match value {
+            "veryslow" => Some(FfmpegVideoProcessingPreset::VerySlow),
+            "slower" => Some(FfmpegVideoProcessingPreset::Slower),
+            "slow" => Some(FfmpegVideoProcessingPreset::Slow),
+            "medium" => Some(FfmpegVideoProcessingPreset::Medium),
+            "fast" => Some(FfmpegVideoProcessingPreset::Fast),
+            "faster" => Some(FfmpegVideoProcessingPreset::Faster),
+            "veryfast" => Some(FfmpegVideoProcessingPreset::VeryFast),
+            "superfast" => Some(FfmpegVideoProcessingPreset::SuperFast),
+            "ultrafast" => Some(FfmpegVideoProcessingPreset::UltraFast),
+            _ => None,
+        }
+    }
+
+    /// SVT-AV1 preset of a similar speed, from 0 (slowest) to 13
+    pub fn svt_av1_value(&self) -> u8 {
+        match self {
+            FfmpegVideoProcessingPreset::VerySlow => 3,
+            FfmpegVideoProcessingPreset::Slower => 4,
+            FfmpegVideoProcessingPreset::Slow => 5,
+            FfmpegVideoProcessingPreset::Medium => 6,
+            FfmpegVideoProcessingPreset::Fast => 8,
+            FfmpegVideoProcessingPreset::Faster => 9,
+            FfmpegVideoProcessingPreset::VeryFast => 10,
+            FfmpegVideoProcessingPreset::SuperFast => 11,
+            FfmpegVideoProcessingPreset::UltraFast => 12,
+        }
+    }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Video codec of a transcoded rendition
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum HlsVideoCodec {
+    #[default]
+    H264,
+    Hevc,
+    /// AV1, which HLS only carries in fMP4 segments
+    Av1,
+}
+
+impl HlsVideoCodec {
+    pub fn value(&self) -> &str {
+        match self {
+            HlsVideoCodec::H264 => "h264",
+            HlsVideoCodec::Hevc => "hevc",
+            HlsVideoCodec::Av1 => "av1",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum HlsVideoAudioCodec {
+    #[default]
     Aac,
     Mp3,
     Vorbis,
@@ -85,9 +149,11 @@ impl HlsVideoAudioCodec {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum HlsVideoAudioBitrate {
     Low,
+    #[default]
     Medium,
     High,
 }
@@ -100,16 +166,106 @@ impl HlsVideoAudioBitrate {
             HlsVideoAudioBitrate::High => "320k",
         }
     }
+
+    pub fn bits_per_second(&self) -> u64 {
+        match self {
+            HlsVideoAudioBitrate::Low => 128_000,
+            HlsVideoAudioBitrate::Medium => 256_000,
+            HlsVideoAudioBitrate::High => 320_000,
+        }
+    }
+}
+
+/// Whether a profile encodes the video or only re-segments the source's streams
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum EncodeMode {
+    #[default]
+    Transcode,
+    /// Copies the source streams into segments (`-c copy`) at the source resolution, failing
+    /// when their codecs can't be carried in HLS
+    Passthrough,
+    /// Passthrough when the source codecs can be carried in HLS, transcoding otherwise
+    Auto,
+}
+
+/// Container of a profile's segments
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SegmentType {
+    #[default]
+    MpegTs,
+    /// Fragmented MP4 (CMAF) segments sharing an initialization section, referenced by
+    /// `#EXT-X-MAP`
+    Fmp4,
+}
+
+impl SegmentType {
+    /// Extension of the media segment files
+    pub fn extension(&self) -> &str {
+        match self {
+            SegmentType::MpegTs => "ts",
+            SegmentType::Fmp4 => "m4s",
+        }
+    }
+}
+
+/// How a profile's segments are encrypted
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ProfileEncryption {
+    /// The job's encryption settings, if any
+    #[default]
+    Inherit,
+    /// Left unencrypted, even when the job encrypts
+    Disabled,
+    /// Encrypted with its own key instead of the job's
+    Custom(VideoProcessorEncryptionSettings),
 }
 
+/// The 16:9 renditions of Apple's HLS authoring specification as `(resolution, CRF, audio
+/// bitrate)`, largest first
+pub const APPLE_RECOMMENDED_LADDER: [((i32, i32), i32, HlsVideoAudioBitrate); 7] = [
+    ((1920, 1080), 22, HlsVideoAudioBitrate::High),
+    ((1280, 720), 23, HlsVideoAudioBitrate::Medium),
+    ((960, 540), 24, HlsVideoAudioBitrate::Medium),
+    ((768, 432), 25, HlsVideoAudioBitrate::Medium),
+    ((640, 360), 26, HlsVideoAudioBitrate::Low),
+    ((480, 270), 27, HlsVideoAudioBitrate::Low),
+    ((416, 234), 28, HlsVideoAudioBitrate::Low),
+];
+
 /// Represents the settings for HLS video processing
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct HlsVideoProcessingSettings {
     pub resolution: (i32, i32),
     pub constant_rate_factor: i32,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub video_codec: HlsVideoCodec,
+    #[cfg_attr(feature = "serde", serde(default))]
     pub audio_codec: HlsVideoAudioCodec,
+    #[cfg_attr(feature = "serde", serde(default))]
     pub audio_bitrate: HlsVideoAudioBitrate,
     pub preset: FfmpegVideoProcessingPreset,
+    /// Raw encoder arguments appended before the output, for flags HlsKit doesn't model
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub extra_args: Vec<String>,
+    /// Whether HDR sources stay HDR in this rendition or get tone-mapped to SDR
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub dynamic_range: DynamicRange,
+    /// Constant output frame rate, the source rate when unset
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub frame_rate: Option<FrameRate>,
+    /// Time between forced keyframes, the encoder's own GOP when unset
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub keyframe_interval: Option<Duration>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub encode_mode: EncodeMode,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub encryption: ProfileEncryption,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub segment_type: SegmentType,
 }
 
 impl HlsVideoProcessingSettings {
@@ -123,9 +279,167 @@ impl HlsVideoProcessingSettings {
         Self {
             resolution,
             constant_rate_factor,
-            audio_codec: audio_codec.unwrap_or(HlsVideoAudioCodec::Aac),
-            audio_bitrate: audio_bitrate.unwrap_or(HlsVideoAudioBitrate::Medium),
+            video_codec: HlsVideoCodec::default(),
+            audio_codec: audio_codec.unwrap_or_default(),
+            audio_bitrate: audio_bitrate.unwrap_or_default(),
             preset,
+            extra_args: Vec::new(),
+            dynamic_range: DynamicRange::default(),
+            frame_rate: None,
+            keyframe_interval: None,
+            encode_mode: EncodeMode::default(),
+            encryption: ProfileEncryption::default(),
+            segment_type: SegmentType::default(),
         }
     }
+
+    /// Loads the default ladder of a TOML, YAML or JSON config file, see `ProcessingConfig`
+    #[cfg(feature = "config")]
+    pub fn from_config_file<P: AsRef<std::path::Path>>(path: P) -> Result<Vec<Self>, HlsKitError> {
+        Ok(ProcessingConfig::from_file(path)?.profiles)
+    }
+
+    /// 3840x2160 at CRF 20
+    pub fn p2160() -> Self {
+        Self::standard((3840, 2160), 20, HlsVideoAudioBitrate::High)
+    }
+
+    /// 1920x1080 at CRF 22
+    pub fn p1080() -> Self {
+        Self::standard((1920, 1080), 22, HlsVideoAudioBitrate::High)
+    }
+
+    /// 1280x720 at CRF 23
+    pub fn p720() -> Self {
+        Self::standard((1280, 720), 23, HlsVideoAudioBitrate::Medium)
+    }
+
+    /// 854x480 at CRF 24
+    pub fn p480() -> Self {
+        Self::standard((854, 480), 24, HlsVideoAudioBitrate::Medium)
+    }
+
+    /// 640x360 at CRF 26
+    pub fn p360() -> Self {
+        Self::standard((640, 360), 26, HlsVideoAudioBitrate::Low)
+    }
+
+    /// 426x240 at CRF 28
+    pub fn p240() -> Self {
+        Self::standard((426, 240), 28, HlsVideoAudioBitrate::Low)
+    }
+
+    /// Segments the source as it is, without transcoding, see `EncodeMode::Passthrough`. The
+    /// resolution is the source's, filled in once probed
+    pub fn passthrough() -> Self {
+        Self::standard((0, 0), 23, HlsVideoAudioBitrate::Medium)
+            .with_encode_mode(EncodeMode::Passthrough)
+    }
+
+    /// 1080p, 720p, 480p and 360p renditions
+    pub fn ladder_1080p() -> Vec<Self> {
+        vec![Self::p1080(), Self::p720(), Self::p480(), Self::p360()]
+    }
+
+    /// 720p, 480p and 360p renditions
+    pub fn ladder_720p() -> Vec<Self> {
+        vec![Self::p720(), Self::p480(), Self::p360()]
+    }
+
+    /// The ladder tuned for a class of devices, with codec profile and GOP settings
+    pub fn for_target(target: Target) -> Vec<Self> {
+        target.profiles()
+    }
+
+    /// Every rendition of `APPLE_RECOMMENDED_LADDER`
+    pub fn apple_ladder() -> Vec<Self> {
+        APPLE_RECOMMENDED_LADDER
+            .iter()
+            .map(|&(resolution, crf, audio_bitrate)| Self::standard(resolution, crf, audio_bitrate))
+            .collect()
+    }
+
+    fn standard(
+        resolution: (i32, i32),
+        constant_rate_factor: i32,
+        audio_bitrate: HlsVideoAudioBitrate,
+    ) -> Self {
+        Self::new(
+            resolution,
+            constant_rate_factor,
+            Some(HlsVideoAudioCodec::Aac),
+            Some(audio_bitrate),
+            FfmpegVideoProcessingPreset::Medium,
+        )
+    }
+
+    /// Keeps HDR sources HDR (`DynamicRange::Hdr`) instead of tone-mapping them
+    pub fn with_dynamic_range(mut self, dynamic_range: DynamicRange) -> Self {
+        self.dynamic_range = dynamic_range;
+        self
+    }
+
+    /// Encodes at this constant frame rate, e.g. `FrameRate::from_fps(30)` for a 60 fps source
+    pub fn with_frame_rate(mut self, frame_rate: FrameRate) -> Self {
+        self.frame_rate = Some(frame_rate);
+        self
+    }
+
+    /// Forces a keyframe every `interval`, e.g. 2 seconds so any segment duration that is a
+    /// multiple of it cuts on a keyframe
+    pub fn with_keyframe_interval(mut self, interval: Duration) -> Self {
+        self.keyframe_interval = Some(interval);
+        self
+    }
+
+    pub fn with_encode_mode(mut self, encode_mode: EncodeMode) -> Self {
+        self.encode_mode = encode_mode;
+        self
+    }
+
+    /// Encodes the video as HEVC or AV1 instead of H.264. AV1 needs `SegmentType::Fmp4`
+    pub fn with_video_codec(mut self, video_codec: HlsVideoCodec) -> Self {
+        self.video_codec = video_codec;
+        self
+    }
+
+    /// Writes fMP4 (`SegmentType::Fmp4`) instead of MPEG-TS segments
+    pub fn with_segment_type(mut self, segment_type: SegmentType) -> Self {
+        self.segment_type = segment_type;
+        self
+    }
+
+    /// Encrypts this rendition with its own key instead of the job's
+    pub fn with_encryption(mut self, encryption: VideoProcessorEncryptionSettings) -> Self {
+        self.encryption = ProfileEncryption::Custom(encryption);
+        self
+    }
+
+    /// Leaves this rendition unencrypted, e.g. to only encrypt 1080p and above
+    pub fn without_encryption(mut self) -> Self {
+        self.encryption = ProfileEncryption::Disabled;
+        self
+    }
+
+    /// The encryption of this rendition given the job's `encryption`
+    pub fn effective_encryption<'a>(
+        &'a self,
+        encryption: Option<&'a VideoProcessorEncryptionSettings>,
+    ) -> Option<&'a VideoProcessorEncryptionSettings> {
+        match &self.encryption {
+            ProfileEncryption::Inherit => encryption,
+            ProfileEncryption::Disabled => None,
+            ProfileEncryption::Custom(encryption) => Some(encryption),
+        }
+    }
+
+    /// Adds raw ffmpeg arguments, e.g. `["-tune", "film"]`
+    pub fn with_extra_args<I, A>(mut self, args: I) -> Self
+    where
+        I: IntoIterator<Item = A>,
+        A: Into<String>,
+    {
+        self.extra_args.extend(args.into_iter().map(Into::into));
+        self
+    }
 }
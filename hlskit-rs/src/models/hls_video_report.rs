@@ -0,0 +1,82 @@
+// SPDX-License-Identifier: LGPL-3.0-only
+/*
+ * Copyright © 2025 The HlsKit Project
+ *
+ * This software is licensed under the GNU Lesser General Public License v3.0 (LGPLv3).
+ * All contributions adhere to the LGPLv3 and the HlsKit Contributor License Agreement (CLA).
+ * A copy of the LGPLv3 can be found at https://www.gnu.org/licenses/lgpl-3.0.html
+ *
+ * HlsKit Contributor License Agreement
+ *
+ * By contributing to or modifying HlsKit, you agree to the following terms:
+ *
+ * 1. Collective Ownership:
+ * The HlsKit project incorporates original code and all contributions as a collective work,
+ * licensed under LGPLv3. Once submitted, contributions become part of the shared HlsKit
+ * ecosystem and cannot be reclaimed, reassigned, or withdrawn. Contributions to your own
+ * forks remain yours unless submitted here, at which point they join this collective whole under LGPLv3.
+ *
+ * 2. Definition of Contribution:
+ * You are considered a contributor if you modify the library in any form (including forks,
+ * wrappers, libraries, or extensions that alter its behavior), whether or not you submit
+ * your changes directly to this repository. All such modifications are part of the broader
+ * HlsKit ecosystem and are subject to this CLA.
+ *
+ * 3. Distribution of Modifications:
+ * If you distribute a modified version of HlsKit, you must license your modifications under
+ * LGPLv3 (with source code available as required by the license) and ensure they are
+ * adoptable by the HlsKit ecosystem (publicly available and compatible).
+ *
+ * 4. Networked Use of Modifications:
+ * If you use a modified version of HlsKit in a networked application, you must provide the
+ * source code of your modifications under LGPLv3 and notify the HlsKit project
+ * (e.g., via email to [higashikataengels@icloud.com]). This does not apply to the use of
+ * the unmodified library in proprietary software, which remains permissible under LGPLv3.
+ *
+ * 5. Scope:
+ * These terms apply to all contributions and modifications derived from the HlsKit project.
+ * The use of the unmodified library in proprietary software is governed solely by the LGPLv3.
+ */
+
+use std::time::Duration;
+
+use crate::models::job_id::JobId;
+
+/// Statistics of one encoded rendition
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RenditionReport {
+    pub resolution: (i32, i32),
+    pub playlist_name: String,
+    /// Video encoder, e.g. `libx264` or `x264enc`
+    pub encoder: String,
+    pub encode_wall_time: Duration,
+    pub segment_count: usize,
+    /// Bytes of all segments
+    pub total_bytes: u64,
+    /// Bits per second over the whole rendition
+    pub average_bitrate: u64,
+    /// Bits per second of the largest segment relative to its duration
+    pub peak_bitrate: u64,
+    /// Hex IV the segments were encrypted with, generated by HlsKit unless configured
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub encryption_iv: Option<String>,
+}
+
+/// Statistics of a finished job, ready to be stored with its records
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HlsVideoReport {
+    pub job_id: Option<JobId>,
+    /// Backend that encoded the job, e.g. `ffmpeg`
+    pub backend: String,
+    pub wall_time: Duration,
+    pub renditions: Vec<RenditionReport>,
+    /// Profiles left out in partial-results mode
+    pub failed_profiles: usize,
+    /// Bytes of every segment of every rendition
+    pub total_bytes: u64,
+    /// Encrypted content key from the job's `KeyProvider`, e.g. a KMS ciphertext blob
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub encrypted_key: Option<Vec<u8>>,
+}
@@ -0,0 +1,109 @@
+// SPDX-License-Identifier: LGPL-3.0-only
+/*
+ * Copyright © 2025 The HlsKit Project
+ *
+ * This software is licensed under the GNU Lesser General Public License v3.0 (LGPLv3).
+ * All contributions adhere to the LGPLv3 and the HlsKit Contributor License Agreement (CLA).
+ * A copy of the LGPLv3 can be found at https://www.gnu.org/licenses/lgpl-3.0.html
+ *
+ * HlsKit Contributor License Agreement
+ *
+ * By contributing to or modifying HlsKit, you agree to the following terms:
+ *
+ * 1. Collective Ownership:
+ * The HlsKit project incorporates original code and all contributions as a collective work,
+ * licensed under LGPLv3. Once submitted, contributions become part of the shared HlsKit
+ * ecosystem and cannot be reclaimed, reassigned, or withdrawn. Contributions to your own
+ * forks remain yours unless submitted here, at which point they join this collective whole under LGPLv3.
+ *
+ * 2. Definition of Contribution:
+ * You are considered a contributor if you modify the library in any form (including forks,
+ * wrappers, libraries, or extensions that alter its behavior), whether or not you submit
+ * your changes directly to this repository. All such modifications are part of the broader
+ * HlsKit ecosystem and are subject to this CLA.
+ *
+ * 3. Distribution of Modifications:
+ * If you distribute a modified version of HlsKit, you must license your modifications under
+ * LGPLv3 (with source code available as required by the license) and ensure they are
+ * adoptable by the HlsKit ecosystem (publicly available and compatible).
+ *
+ * 4. Networked Use of Modifications:
+ * If you use a modified version of HlsKit in a networked application, you must provide the
+ * source code of your modifications under LGPLv3 and notify the HlsKit project
+ * (e.g., via email to [higashikataengels@icloud.com]). This does not apply to the use of
+ * the unmodified library in proprietary software, which remains permissible under LGPLv3.
+ *
+ * 5. Scope:
+ * These terms apply to all contributions and modifications derived from the HlsKit project.
+ * The use of the unmodified library in proprietary software is governed solely by the LGPLv3.
+ */
+
+use std::fmt;
+
+use crate::models::validation_report::FindingSeverity;
+
+/// The authoring rule a lint finding is about
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum LintRule {
+    /// Playlists start with `#EXTM3U` and carry the required tags
+    PlaylistSyntax,
+    /// `EXT-X-TARGETDURATION` is present, bounds every segment and is 6 seconds
+    SegmentDuration,
+    /// Every video segment starts with an IDR frame
+    IdrStart,
+    /// Segment boundaries line up across variants
+    VariantAlignment,
+    /// Variants declare `CODECS`
+    Codecs,
+    /// Variants declare `BANDWIDTH`, `AVERAGE-BANDWIDTH`, `RESOLUTION` and `FRAME-RATE`
+    VariantAttributes,
+    /// An audio-only variant is offered
+    AudioOnlyVariant,
+    /// I-frame playlists are offered for trick play
+    IFramePlaylist,
+}
+
+impl LintRule {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LintRule::PlaylistSyntax => "playlist_syntax",
+            LintRule::SegmentDuration => "segment_duration",
+            LintRule::IdrStart => "idr_start",
+            LintRule::VariantAlignment => "variant_alignment",
+            LintRule::Codecs => "codecs",
+            LintRule::VariantAttributes => "variant_attributes",
+            LintRule::AudioOnlyVariant => "audio_only_variant",
+            LintRule::IFramePlaylist => "i_frame_playlist",
+        }
+    }
+}
+
+/// A deviation from Apple's HLS authoring specification
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LintFinding {
+    pub rule: LintRule,
+    pub severity: FindingSeverity,
+    /// The playlist or segment concerned
+    pub file: Option<String>,
+    pub message: String,
+}
+
+impl fmt::Display for LintFinding {
+    /// e.g. `error [codecs] master.m3u8: variant playlist_0.m3u8 has no CODECS`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.severity {
+            FindingSeverity::Warning => "warning",
+            FindingSeverity::Error => "error",
+        };
+        write!(
+            f,
+            "{severity} [{}] {}: {}",
+            self.rule.as_str(),
+            self.file.as_deref().unwrap_or("-"),
+            self.message
+        )
+    }
+}
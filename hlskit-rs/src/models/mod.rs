@@ -38,5 +38,51 @@
  * The use of the unmodified library in proprietary software is governed solely by the LGPLv3.
  */
 
+pub mod audio_sync;
+#[cfg(feature = "cache")]
+pub mod cache_key;
+pub mod checksum_manifest;
+pub mod chunked_encoding;
+#[cfg(feature = "encryption")]
+pub mod clear_key;
+#[cfg(feature = "test-support")]
+pub mod conformance_report;
+pub mod content_check;
+pub mod deinterlace_policy;
+pub mod dynamic_range;
+pub mod encoder_version;
+pub mod frame_rate;
+pub mod frame_rate_policy;
+pub mod hls_artifact;
+pub mod hls_diff;
 pub mod hls_video;
+pub mod hls_video_manifest;
 pub mod hls_video_processing_settings;
+pub mod hls_video_report;
+pub mod job_event;
+pub mod job_id;
+pub mod key_material;
+pub mod ladder;
+pub mod lint_finding;
+pub mod orientation_policy;
+pub mod path_policy;
+pub mod playlist;
+#[cfg(feature = "config")]
+pub mod processing_config;
+pub mod processing_plan;
+pub mod profile_error;
+pub mod profile_progress;
+pub mod quality_scores;
+pub mod queued_job;
+pub mod resource_limits;
+pub mod resource_usage;
+pub mod retention_policy;
+pub mod scene_segmentation;
+pub mod secret;
+pub mod size_estimate;
+pub mod source_adjustments;
+pub mod source_info;
+pub mod target;
+pub mod upscale_policy;
+pub mod validation_report;
+pub mod work_unit;
@@ -0,0 +1,108 @@
+// SPDX-License-Identifier: LGPL-3.0-only
+/*
+ * Copyright © 2025 The HlsKit Project
+ *
+ * This software is licensed under the GNU Lesser General Public License v3.0 (LGPLv3).
+ * All contributions adhere to the LGPLv3 and the HlsKit Contributor License Agreement (CLA).
+ * A copy of the LGPLv3 can be found at https://www.gnu.org/licenses/lgpl-3.0.html
+ *
+ * HlsKit Contributor License Agreement
+ *
+ * By contributing to or modifying HlsKit, you agree to the following terms:
+ *
+ * 1. Collective Ownership:
+ * The HlsKit project incorporates original code and all contributions as a collective work,
+ * licensed under LGPLv3. Once submitted, contributions become part of the shared HlsKit
+ * ecosystem and cannot be reclaimed, reassigned, or withdrawn. Contributions to your own
+ * forks remain yours unless submitted here, at which point they join this collective whole under LGPLv3.
+ *
+ * 2. Definition of Contribution:
+ * You are considered a contributor if you modify the library in any form (including forks,
+ * wrappers, libraries, or extensions that alter its behavior), whether or not you submit
+ * your changes directly to this repository. All such modifications are part of the broader
+ * HlsKit ecosystem and are subject to this CLA.
+ *
+ * 3. Distribution of Modifications:
+ * If you distribute a modified version of HlsKit, you must license your modifications under
+ * LGPLv3 (with source code available as required by the license) and ensure they are
+ * adoptable by the HlsKit ecosystem (publicly available and compatible).
+ *
+ * 4. Networked Use of Modifications:
+ * If you use a modified version of HlsKit in a networked application, you must provide the
+ * source code of your modifications under LGPLv3 and notify the HlsKit project
+ * (e.g., via email to [higashikataengels@icloud.com]). This does not apply to the use of
+ * the unmodified library in proprietary software, which remains permissible under LGPLv3.
+ *
+ * 5. Scope:
+ * These terms apply to all contributions and modifications derived from the HlsKit project.
+ * The use of the unmodified library in proprietary software is governed solely by the LGPLv3.
+ */
+
+use crate::{
+    models::hls_video_processing_settings::HlsVideoProcessingSettings,
+    tools::ladder_generator::even,
+};
+
+/// How profiles are adapted to the orientation of the probed source
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum OrientationPolicy {
+    /// Encode the configured dimensions even when that squashes the picture
+    AsConfigured,
+    /// Swap the width and height of landscape profiles for portrait sources, and the other
+    /// way around, so a 1920x1080 profile becomes 1080x1920 for a phone video
+    #[default]
+    Swap,
+    /// Keep the short side of every profile and derive the long side from the source aspect
+    /// ratio, e.g. 1080x1440 for a 3:4 portrait source
+    MatchAspect,
+}
+
+impl OrientationPolicy {
+    /// The profiles to encode for a source of `source_resolution` (as displayed)
+    pub fn apply(
+        &self,
+        profiles: &[HlsVideoProcessingSettings],
+        source_resolution: (i32, i32),
+    ) -> Vec<HlsVideoProcessingSettings> {
+        let (source_width, source_height) = source_resolution;
+        if source_width <= 0 || source_height <= 0 {
+            return profiles.to_vec();
+        }
+        let portrait_source = source_height > source_width;
+
+        profiles
+            .iter()
+            .map(|profile| {
+                let (width, height) = profile.resolution;
+                let mut adapted = profile.clone();
+
+                adapted.resolution = match self {
+                    OrientationPolicy::AsConfigured => (width, height),
+                    OrientationPolicy::Swap
+                        if (height > width) != portrait_source && width != height =>
+                    {
+                        (height, width)
+                    }
+                    OrientationPolicy::Swap => (width, height),
+                    OrientationPolicy::MatchAspect => {
+                        let short_side = width.min(height);
+                        let long_side = even(
+                            (short_side as f64 * source_width.max(source_height) as f64
+                                / source_width.min(source_height) as f64)
+                                .round() as i32,
+                        );
+                        if portrait_source {
+                            (short_side, long_side)
+                        } else {
+                            (long_side, short_side)
+                        }
+                    }
+                };
+
+                adapted
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,196 @@
+// SPDX-License-Identifier: LGPL-3.0-only
+/*
+ * Copyright © 2025 The HlsKit Project
+ *
+ * This software is licensed under the GNU Lesser General Public License v3.0 (LGPLv3).
+ * All contributions adhere to the LGPLv3 and the HlsKit Contributor License Agreement (CLA).
+ * A copy of the LGPLv3 can be found at https://www.gnu.org/licenses/lgpl-3.0.html
+ *
+ * HlsKit Contributor License Agreement
+ *
+ * By contributing to or modifying HlsKit, you agree to the following terms:
+ *
+ * 1. Collective Ownership:
+ * The HlsKit project incorporates original code and all contributions as a collective work,
+ * licensed under LGPLv3. Once submitted, contributions become part of the shared HlsKit
+ * ecosystem and cannot be reclaimed, reassigned, or withdrawn. Contributions to your own
+ * forks remain yours unless submitted here, at which point they join this collective whole under LGPLv3.
+ *
+ * 2. Definition of Contribution:
+ * You are considered a contributor if you modify the library in any form (including forks,
+ * wrappers, libraries, or extensions that alter its behavior), whether or not you submit
+ * your changes directly to this repository. All such modifications are part of the broader
+ * HlsKit ecosystem and are subject to this CLA.
+ *
+ * 3. Distribution of Modifications:
+ * If you distribute a modified version of HlsKit, you must license your modifications under
+ * LGPLv3 (with source code available as required by the license) and ensure they are
+ * adoptable by the HlsKit ecosystem (publicly available and compatible).
+ *
+ * 4. Networked Use of Modifications:
+ * If you use a modified version of HlsKit in a networked application, you must provide the
+ * source code of your modifications under LGPLv3 and notify the HlsKit project
+ * (e.g., via email to [higashikataengels@icloud.com]). This does not apply to the use of
+ * the unmodified library in proprietary software, which remains permissible under LGPLv3.
+ *
+ * 5. Scope:
+ * These terms apply to all contributions and modifications derived from the HlsKit project.
+ * The use of the unmodified library in proprietary software is governed solely by the LGPLv3.
+ */
+
+use std::path::{Component, Path, PathBuf};
+
+use crate::tools::hlskit_error::HlsKitError;
+
+/// Characters a shell or gst-launch would interpret, rejected in every checked value
+const UNSAFE_CHARACTERS: &[char] = &[
+    ';', '|', '&', '$', '`', '<', '>', '(', ')', '{', '}', '[', ']', '*', '?', '!', '\\', '\'',
+    '"', '\n', '\r', '\0',
+];
+
+/// Rules for user-influenced paths, file names and URLs, e.g. segment names, key paths and base
+/// URLs accepted by a multi-tenant service, checked before they reach an encoder command
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PathPolicy {
+    /// Directories absolute paths must stay within, any directory when empty
+    pub allowed_roots: Vec<PathBuf>,
+    /// Accepts `..` components
+    pub allow_parent_components: bool,
+    /// URL schemes accepted for base and key URLs, add `data` for embedded keys
+    pub allowed_url_schemes: Vec<String>,
+}
+
+impl Default for PathPolicy {
+    fn default() -> Self {
+        Self {
+            allowed_roots: Vec::new(),
+            allow_parent_components: false,
+            allowed_url_schemes: vec!["https".to_string(), "http".to_string()],
+        }
+    }
+}
+
+impl PathPolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Confines absolute paths to `root`, may be called for several roots
+    pub fn with_allowed_root<P: AsRef<Path>>(mut self, root: P) -> Self {
+        self.allowed_roots.push(root.as_ref().to_path_buf());
+        self
+    }
+
+    pub fn with_parent_components(mut self, allow: bool) -> Self {
+        self.allow_parent_components = allow;
+        self
+    }
+
+    pub fn with_url_schemes<I, S>(mut self, schemes: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.allowed_url_schemes = schemes.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Checks a file or directory path, e.g. a key path or output directory
+    pub fn check_path<P: AsRef<Path>>(&self, path: P) -> Result<(), HlsKitError> {
+        let path = path.as_ref();
+        let display = path.to_string_lossy();
+        check_characters(&display)?;
+
+        if !self.allow_parent_components
+            && path
+                .components()
+                .any(|component| component == Component::ParentDir)
+        {
+            return Err(rejected(&display, "contains `..`"));
+        }
+
+        if path.is_absolute()
+            && !self.allowed_roots.is_empty()
+            && !self
+                .allowed_roots
+                .iter()
+                .any(|root| normalize(path).starts_with(normalize(root)))
+        {
+            return Err(rejected(&display, "is outside the allowed directories"));
+        }
+
+        Ok(())
+    }
+
+    /// Checks a relative file name or template, e.g. `data_%03d.ts`, which may name a
+    /// subdirectory but can't leave the output directory
+    pub fn check_file_name(&self, name: &str) -> Result<(), HlsKitError> {
+        check_characters(name)?;
+
+        let path = Path::new(name);
+        if name.is_empty() || path.is_absolute() || name.starts_with('/') {
+            return Err(rejected(name, "is not a relative file name"));
+        }
+        if path
+            .components()
+            .any(|component| !matches!(component, Component::Normal(_)))
+        {
+            return Err(rejected(name, "leaves the output directory"));
+        }
+
+        Ok(())
+    }
+
+    /// Checks a base or key URL written into playlists and commands
+    pub fn check_url(&self, url: &str) -> Result<(), HlsKitError> {
+        if url.chars().any(|c| c.is_whitespace() || c.is_control()) || url.contains(['"', '`']) {
+            return Err(rejected(
+                url,
+                "contains whitespace, quotes or control characters",
+            ));
+        }
+
+        let scheme = url.split_once(':').map(|(scheme, _)| scheme);
+        match scheme {
+            Some(scheme)
+                if self
+                    .allowed_url_schemes
+                    .iter()
+                    .any(|allowed| allowed.eq_ignore_ascii_case(scheme)) =>
+            {
+                Ok(())
+            }
+            _ => Err(rejected(url, "does not use an allowed URL scheme")),
+        }
+    }
+}
+
+fn check_characters(value: &str) -> Result<(), HlsKitError> {
+    match value.chars().find(|c| UNSAFE_CHARACTERS.contains(c)) {
+        Some(c) => Err(rejected(value, &format!("contains {c:?}"))),
+        None => Ok(()),
+    }
+}
+
+/// `path` with `.` components dropped and `..` applied, without touching the file system
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+fn rejected(value: &str, reason: &str) -> HlsKitError {
+    HlsKitError::PathRejected {
+        path: value.to_string(),
+        reason: reason.to_string(),
+    }
+}
@@ -0,0 +1,168 @@
+// SPDX-License-Identifier: LGPL-3.0-only
+/*
+ * Copyright © 2025 The HlsKit Project
+ *
+ * This software is licensed under the GNU Lesser General Public License v3.0 (LGPLv3).
+ * All contributions adhere to the LGPLv3 and the HlsKit Contributor License Agreement (CLA).
+ * A copy of the LGPLv3 can be found at https://www.gnu.org/licenses/lgpl-3.0.html
+ *
+ * HlsKit Contributor License Agreement
+ *
+ * By contributing to or modifying HlsKit, you agree to the following terms:
+ *
+ * 1. Collective Ownership:
+ * The HlsKit project incorporates original code and all contributions as a collective work,
+ * licensed under LGPLv3. Once submitted, contributions become part of the shared HlsKit
+ * ecosystem and cannot be reclaimed, reassigned, or withdrawn. Contributions to your own
+ * forks remain yours unless submitted here, at which point they join this collective whole under LGPLv3.
+ *
+ * 2. Definition of Contribution:
+ * You are considered a contributor if you modify the library in any form (including forks,
+ * wrappers, libraries, or extensions that alter its behavior), whether or not you submit
+ * your changes directly to this repository. All such modifications are part of the broader
+ * HlsKit ecosystem and are subject to this CLA.
+ *
+ * 3. Distribution of Modifications:
+ * If you distribute a modified version of HlsKit, you must license your modifications under
+ * LGPLv3 (with source code available as required by the license) and ensure they are
+ * adoptable by the HlsKit ecosystem (publicly available and compatible).
+ *
+ * 4. Networked Use of Modifications:
+ * If you use a modified version of HlsKit in a networked application, you must provide the
+ * source code of your modifications under LGPLv3 and notify the HlsKit project
+ * (e.g., via email to [higashikataengels@icloud.com]). This does not apply to the use of
+ * the unmodified library in proprietary software, which remains permissible under LGPLv3.
+ *
+ * 5. Scope:
+ * These terms apply to all contributions and modifications derived from the HlsKit project.
+ * The use of the unmodified library in proprietary software is governed solely by the LGPLv3.
+ */
+
+/// A parsed master (multivariant) playlist
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MasterPlaylist {
+    pub version: Option<u8>,
+    pub independent_segments: bool,
+    pub variants: Vec<VariantStream>,
+    /// `EXT-X-I-FRAME-STREAM-INF` playlists, for trick play
+    pub i_frame_variants: Vec<VariantStream>,
+    /// `EXT-X-MEDIA` alternative renditions (audio, subtitles, ...)
+    pub media: Vec<MediaRendition>,
+    /// Tags this parser doesn't model, kept verbatim
+    pub other_tags: Vec<String>,
+}
+
+/// An `EXT-X-STREAM-INF` (or `EXT-X-I-FRAME-STREAM-INF`) variant
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct VariantStream {
+    pub uri: String,
+    pub bandwidth: u64,
+    pub average_bandwidth: Option<u64>,
+    pub resolution: Option<(i32, i32)>,
+    pub codecs: Option<String>,
+    pub frame_rate: Option<f64>,
+    /// `SDR`, `PQ` or `HLG`
+    pub video_range: Option<String>,
+    /// Group id of the `EXT-X-MEDIA` audio renditions
+    pub audio: Option<String>,
+    pub subtitles: Option<String>,
+    /// Every attribute as written, modelled or not
+    pub attributes: Vec<(String, String)>,
+}
+
+impl VariantStream {
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// An `EXT-X-MEDIA` rendition
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MediaRendition {
+    /// `AUDIO`, `VIDEO`, `SUBTITLES` or `CLOSED-CAPTIONS`
+    pub media_type: String,
+    pub group_id: String,
+    pub name: String,
+    pub language: Option<String>,
+    /// Absent for renditions muxed into the variant streams
+    pub uri: Option<String>,
+    pub default: bool,
+    pub autoselect: bool,
+    pub channels: Option<String>,
+    pub attributes: Vec<(String, String)>,
+}
+
+/// A parsed media playlist
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MediaPlaylist {
+    pub version: Option<u8>,
+    /// Seconds no segment exceeds
+    pub target_duration: u64,
+    pub media_sequence: u64,
+    /// `VOD` or `EVENT`, `None` for live playlists
+    pub playlist_type: Option<String>,
+    pub independent_segments: bool,
+    /// Whether `EXT-X-ENDLIST` closes the playlist
+    pub end_list: bool,
+    /// `EXT-X-MAP` initialization section of fMP4 segments
+    pub map: Option<InitSection>,
+    pub segments: Vec<MediaSegment>,
+    /// Tags this parser doesn't model, kept verbatim
+    pub other_tags: Vec<String>,
+}
+
+impl MediaPlaylist {
+    /// Sum of the segment durations, in seconds
+    pub fn duration(&self) -> f64 {
+        self.segments.iter().map(|segment| segment.duration).sum()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MediaSegment {
+    pub uri: String,
+    /// `EXTINF` duration, in seconds
+    pub duration: f64,
+    pub title: Option<String>,
+    pub byte_range: Option<ByteRange>,
+    /// The `EXT-X-KEY` in effect, `None` when clear
+    pub key: Option<SegmentKey>,
+    /// Preceded by `EXT-X-DISCONTINUITY`
+    pub discontinuity: bool,
+    pub program_date_time: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ByteRange {
+    pub length: u64,
+    /// Start of the range, right after the previous one when `None`
+    pub offset: Option<u64>,
+}
+
+/// An `EXT-X-KEY` with a method other than `NONE`
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SegmentKey {
+    /// `AES-128`, `SAMPLE-AES`, ...
+    pub method: String,
+    pub uri: Option<String>,
+    /// Hex IV, `0x` prefix included
+    pub iv: Option<String>,
+    pub key_format: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct InitSection {
+    pub uri: String,
+    pub byte_range: Option<ByteRange>,
+}
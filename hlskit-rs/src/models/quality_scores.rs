@@ -0,0 +1,89 @@
+// SPDX-License-Identifier: LGPL-3.0-only
+/*
+ * Copyright © 2025 The HlsKit Project
+ *
+ * This software is licensed under the GNU Lesser General Public License v3.0 (LGPLv3).
+ * All contributions adhere to the LGPLv3 and the HlsKit Contributor License Agreement (CLA).
+ * A copy of the LGPLv3 can be found at https://www.gnu.org/licenses/lgpl-3.0.html
+ *
+ * HlsKit Contributor License Agreement
+ *
+ * By contributing to or modifying HlsKit, you agree to the following terms:
+ *
+ * 1. Collective Ownership:
+ * The HlsKit project incorporates original code and all contributions as a collective work,
+ * licensed under LGPLv3. Once submitted, contributions become part of the shared HlsKit
+ * ecosystem and cannot be reclaimed, reassigned, or withdrawn. Contributions to your own
+ * forks remain yours unless submitted here, at which point they join this collective whole under LGPLv3.
+ *
+ * 2. Definition of Contribution:
+ * You are considered a contributor if you modify the library in any form (including forks,
+ * wrappers, libraries, or extensions that alter its behavior), whether or not you submit
+ * your changes directly to this repository. All such modifications are part of the broader
+ * HlsKit ecosystem and are subject to this CLA.
+ *
+ * 3. Distribution of Modifications:
+ * If you distribute a modified version of HlsKit, you must license your modifications under
+ * LGPLv3 (with source code available as required by the license) and ensure they are
+ * adoptable by the HlsKit ecosystem (publicly available and compatible).
+ *
+ * 4. Networked Use of Modifications:
+ * If you use a modified version of HlsKit in a networked application, you must provide the
+ * source code of your modifications under LGPLv3 and notify the HlsKit project
+ * (e.g., via email to [higashikataengels@icloud.com]). This does not apply to the use of
+ * the unmodified library in proprietary software, which remains permissible under LGPLv3.
+ *
+ * 5. Scope:
+ * These terms apply to all contributions and modifications derived from the HlsKit project.
+ * The use of the unmodified library in proprietary software is governed solely by the LGPLv3.
+ */
+
+/// A full-reference quality metric computed by ffmpeg
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum QualityMetric {
+    /// Netflix VMAF (0-100), needs ffmpeg built with libvmaf
+    Vmaf,
+    /// Average PSNR in dB over all planes
+    Psnr,
+    /// SSIM over all planes (0-1)
+    Ssim,
+}
+
+impl QualityMetric {
+    /// The ffmpeg filter computing the metric
+    pub fn filter_name(&self) -> &'static str {
+        match self {
+            QualityMetric::Vmaf => "libvmaf",
+            QualityMetric::Psnr => "psnr",
+            QualityMetric::Ssim => "ssim",
+        }
+    }
+}
+
+/// Quality of a rendition compared to its source, one score per measured metric
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct QualityScores {
+    pub vmaf: Option<f64>,
+    pub psnr: Option<f64>,
+    pub ssim: Option<f64>,
+}
+
+// Scores are parsed from ffmpeg's reports and never NaN
+impl Eq for QualityScores {}
+
+impl QualityScores {
+    pub fn get(&self, metric: QualityMetric) -> Option<f64> {
+        match metric {
+            QualityMetric::Vmaf => self.vmaf,
+            QualityMetric::Psnr => self.psnr,
+            QualityMetric::Ssim => self.ssim,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.vmaf.is_none() && self.psnr.is_none() && self.ssim.is_none()
+    }
+}
@@ -0,0 +1,81 @@
+// SPDX-License-Identifier: LGPL-3.0-only
+/*
+ * Copyright © 2025 The HlsKit Project
+ *
+ * This software is licensed under the GNU Lesser General Public License v3.0 (LGPLv3).
+ * All contributions adhere to the LGPLv3 and the HlsKit Contributor License Agreement (CLA).
+ * A copy of the LGPLv3 can be found at https://www.gnu.org/licenses/lgpl-3.0.html
+ *
+ * HlsKit Contributor License Agreement
+ *
+ * By contributing to or modifying HlsKit, you agree to the following terms:
+ *
+ * 1. Collective Ownership:
+ * The HlsKit project incorporates original code and all contributions as a collective work,
+ * licensed under LGPLv3. Once submitted, contributions become part of the shared HlsKit
+ * ecosystem and cannot be reclaimed, reassigned, or withdrawn. Contributions to your own
+ * forks remain yours unless submitted here, at which point they join this collective whole under LGPLv3.
+ *
+ * 2. Definition of Contribution:
+ * You are considered a contributor if you modify the library in any form (including forks,
+ * wrappers, libraries, or extensions that alter its behavior), whether or not you submit
+ * your changes directly to this repository. All such modifications are part of the broader
+ * HlsKit ecosystem and are subject to this CLA.
+ *
+ * 3. Distribution of Modifications:
+ * If you distribute a modified version of HlsKit, you must license your modifications under
+ * LGPLv3 (with source code available as required by the license) and ensure they are
+ * adoptable by the HlsKit ecosystem (publicly available and compatible).
+ *
+ * 4. Networked Use of Modifications:
+ * If you use a modified version of HlsKit in a networked application, you must provide the
+ * source code of your modifications under LGPLv3 and notify the HlsKit project
+ * (e.g., via email to [higashikataengels@icloud.com]). This does not apply to the use of
+ * the unmodified library in proprietary software, which remains permissible under LGPLv3.
+ *
+ * 5. Scope:
+ * These terms apply to all contributions and modifications derived from the HlsKit project.
+ * The use of the unmodified library in proprietary software is governed solely by the LGPLv3.
+ */
+
+use std::path::{Path, PathBuf};
+
+/// OS-level constraints applied to every encoder process of a job
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ResourceLimits {
+    /// Encoder worker threads, passed as `-threads` to ffmpeg and `threads` to x264enc
+    pub threads: Option<u32>,
+    /// Process niceness from -20 (highest priority) to 19 (lowest), unix only
+    pub niceness: Option<i32>,
+    /// CPU cores the encoder may run on, Linux only
+    pub cpu_affinity: Option<Vec<usize>>,
+    /// cgroup v2 directory the encoder is moved into after spawning, Linux only
+    pub cgroup: Option<PathBuf>,
+}
+
+impl ResourceLimits {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_threads(mut self, threads: u32) -> Self {
+        self.threads = Some(threads.max(1));
+        self
+    }
+
+    pub fn with_niceness(mut self, niceness: i32) -> Self {
+        self.niceness = Some(niceness.clamp(-20, 19));
+        self
+    }
+
+    pub fn with_cpu_affinity(mut self, cores: Vec<usize>) -> Self {
+        self.cpu_affinity = Some(cores);
+        self
+    }
+
+    pub fn with_cgroup<P: AsRef<Path>>(mut self, cgroup: P) -> Self {
+        self.cgroup = Some(cgroup.as_ref().to_path_buf());
+        self
+    }
+}
@@ -0,0 +1,109 @@
+// SPDX-License-Identifier: LGPL-3.0-only
+/*
+ * Copyright © 2025 The HlsKit Project
+ *
+ * This software is licensed under the GNU Lesser General Public License v3.0 (LGPLv3).
+ * All contributions adhere to the LGPLv3 and the HlsKit Contributor License Agreement (CLA).
+ * A copy of the LGPLv3 can be found at https://www.gnu.org/licenses/lgpl-3.0.html
+ *
+ * HlsKit Contributor License Agreement
+ *
+ * By contributing to or modifying HlsKit, you agree to the following terms:
+ *
+ * 1. Collective Ownership:
+ * The HlsKit project incorporates original code and all contributions as a collective work,
+ * licensed under LGPLv3. Once submitted, contributions become part of the shared HlsKit
+ * ecosystem and cannot be reclaimed, reassigned, or withdrawn. Contributions to your own
+ * forks remain yours unless submitted here, at which point they join this collective whole under LGPLv3.
+ *
+ * 2. Definition of Contribution:
+ * You are considered a contributor if you modify the library in any form (including forks,
+ * wrappers, libraries, or extensions that alter its behavior), whether or not you submit
+ * your changes directly to this repository. All such modifications are part of the broader
+ * HlsKit ecosystem and are subject to this CLA.
+ *
+ * 3. Distribution of Modifications:
+ * If you distribute a modified version of HlsKit, you must license your modifications under
+ * LGPLv3 (with source code available as required by the license) and ensure they are
+ * adoptable by the HlsKit ecosystem (publicly available and compatible).
+ *
+ * 4. Networked Use of Modifications:
+ * If you use a modified version of HlsKit in a networked application, you must provide the
+ * source code of your modifications under LGPLv3 and notify the HlsKit project
+ * (e.g., via email to [higashikataengels@icloud.com]). This does not apply to the use of
+ * the unmodified library in proprietary software, which remains permissible under LGPLv3.
+ *
+ * 5. Scope:
+ * These terms apply to all contributions and modifications derived from the HlsKit project.
+ * The use of the unmodified library in proprietary software is governed solely by the LGPLv3.
+ */
+
+use std::time::Duration;
+
+/// Places keyframes, and so segment boundaries, on scene cuts instead of every fixed
+/// duration: each segment ends on the last cut found between the minimum and maximum segment
+/// duration, or at the maximum when there is none.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SceneSegmentation {
+    /// Scene change score (0 to 1) from which a frame counts as a cut
+    pub threshold: f64,
+    pub min_segment_duration: Duration,
+    pub max_segment_duration: Duration,
+}
+
+impl Default for SceneSegmentation {
+    fn default() -> Self {
+        Self {
+            threshold: 0.4,
+            min_segment_duration: Duration::from_secs(4),
+            max_segment_duration: Duration::from_secs(10),
+        }
+    }
+}
+
+impl SceneSegmentation {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_threshold(mut self, threshold: f64) -> Self {
+        self.threshold = threshold.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Bounds of the segment durations, at least a second apart
+    pub fn with_segment_duration(mut self, min: Duration, max: Duration) -> Self {
+        self.min_segment_duration = min.max(Duration::from_secs(1));
+        self.max_segment_duration = max.max(self.min_segment_duration + Duration::from_secs(1));
+        self
+    }
+
+    /// Whole seconds ffmpeg's HLS muxer must wait before cutting at the next keyframe, so it
+    /// cuts at every planned one
+    pub fn hls_time(&self) -> i32 {
+        self.min_segment_duration.as_secs().max(1) as i32
+    }
+
+    /// The keyframe times for a source of `duration` with cuts at `scene_changes`, in order
+    pub fn plan_keyframes(&self, scene_changes: &[Duration], duration: Duration) -> Vec<Duration> {
+        let mut keyframes = Vec::new();
+        let mut last = Duration::ZERO;
+
+        while last + self.max_segment_duration < duration {
+            let earliest = last + self.min_segment_duration;
+            let latest = last + self.max_segment_duration;
+            let next = scene_changes
+                .iter()
+                .rev()
+                .find(|&&cut| cut >= earliest && cut <= latest)
+                .copied()
+                .unwrap_or(latest);
+
+            keyframes.push(next);
+            last = next;
+        }
+
+        keyframes
+    }
+}
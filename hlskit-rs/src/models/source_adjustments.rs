@@ -0,0 +1,91 @@
+// SPDX-License-Identifier: LGPL-3.0-only
+/*
+ * Copyright © 2025 The HlsKit Project
+ *
+ * This software is licensed under the GNU Lesser General Public License v3.0 (LGPLv3).
+ * All contributions adhere to the LGPLv3 and the HlsKit Contributor License Agreement (CLA).
+ * A copy of the LGPLv3 can be found at https://www.gnu.org/licenses/lgpl-3.0.html
+ *
+ * HlsKit Contributor License Agreement
+ *
+ * By contributing to or modifying HlsKit, you agree to the following terms:
+ *
+ * 1. Collective Ownership:
+ * The HlsKit project incorporates original code and all contributions as a collective work,
+ * licensed under LGPLv3. Once submitted, contributions become part of the shared HlsKit
+ * ecosystem and cannot be reclaimed, reassigned, or withdrawn. Contributions to your own
+ * forks remain yours unless submitted here, at which point they join this collective whole under LGPLv3.
+ *
+ * 2. Definition of Contribution:
+ * You are considered a contributor if you modify the library in any form (including forks,
+ * wrappers, libraries, or extensions that alter its behavior), whether or not you submit
+ * your changes directly to this repository. All such modifications are part of the broader
+ * HlsKit ecosystem and are subject to this CLA.
+ *
+ * 3. Distribution of Modifications:
+ * If you distribute a modified version of HlsKit, you must license your modifications under
+ * LGPLv3 (with source code available as required by the license) and ensure they are
+ * adoptable by the HlsKit ecosystem (publicly available and compatible).
+ *
+ * 4. Networked Use of Modifications:
+ * If you use a modified version of HlsKit in a networked application, you must provide the
+ * source code of your modifications under LGPLv3 and notify the HlsKit project
+ * (e.g., via email to [higashikataengels@icloud.com]). This does not apply to the use of
+ * the unmodified library in proprietary software, which remains permissible under LGPLv3.
+ *
+ * 5. Scope:
+ * These terms apply to all contributions and modifications derived from the HlsKit project.
+ * The use of the unmodified library in proprietary software is governed solely by the LGPLv3.
+ */
+
+use std::time::Duration;
+
+use crate::models::{
+    dynamic_range::{DynamicRange, VideoRange},
+    frame_rate::FrameRate,
+    hls_video_processing_settings::HlsVideoProcessingSettings,
+    source_info::HdrTransfer,
+};
+
+/// Corrections applied to the source before encoding, decided during preflight
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SourceAdjustments {
+    /// The source was detected (or declared) interlaced and deinterlaced
+    pub deinterlaced: bool,
+    /// Transfer of an HDR source, kept by `DynamicRange::Hdr` profiles
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub hdr_source: Option<HdrTransfer>,
+    /// The source is HDR and SDR renditions were tone-mapped from it
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub tone_mapped: bool,
+    /// Rate a variable frame rate source was normalized to
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub constant_frame_rate: Option<FrameRate>,
+    /// Keyframes forced on scene cuts, where segments start
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub scene_keyframes: Vec<Duration>,
+}
+
+impl SourceAdjustments {
+    /// Whether `profile` has to be tone-mapped down from the HDR source
+    pub fn tone_maps(&self, profile: &HlsVideoProcessingSettings) -> bool {
+        self.hdr_source.is_some() && profile.dynamic_range == DynamicRange::Sdr
+    }
+
+    /// The constant rate `profile` is encoded at, `None` to keep source timestamps
+    pub fn frame_rate_for(&self, profile: &HlsVideoProcessingSettings) -> Option<FrameRate> {
+        profile.frame_rate.or(self.constant_frame_rate)
+    }
+
+    /// The range `profile` is encoded in
+    pub fn video_range_for(&self, profile: &HlsVideoProcessingSettings) -> VideoRange {
+        match (self.hdr_source, profile.dynamic_range) {
+            (Some(transfer), DynamicRange::Hdr) => transfer.into(),
+            _ => VideoRange::Sdr,
+        }
+    }
+}
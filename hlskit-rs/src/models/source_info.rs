@@ -0,0 +1,199 @@
+// SPDX-License-Identifier: LGPL-3.0-only
+/*
+ * Copyright © 2025 The HlsKit Project
+ *
+ * This software is licensed under the GNU Lesser General Public License v3.0 (LGPLv3).
+ * All contributions adhere to the LGPLv3 and the HlsKit Contributor License Agreement (CLA).
+ * A copy of the LGPLv3 can be found at https://www.gnu.org/licenses/lgpl-3.0.html
+ *
+ * HlsKit Contributor License Agreement
+ *
+ * By contributing to or modifying HlsKit, you agree to the following terms:
+ *
+ * 1. Collective Ownership:
+ * The HlsKit project incorporates original code and all contributions as a collective work,
+ * licensed under LGPLv3. Once submitted, contributions become part of the shared HlsKit
+ * ecosystem and cannot be reclaimed, reassigned, or withdrawn. Contributions to your own
+ * forks remain yours unless submitted here, at which point they join this collective whole under LGPLv3.
+ *
+ * 2. Definition of Contribution:
+ * You are considered a contributor if you modify the library in any form (including forks,
+ * wrappers, libraries, or extensions that alter its behavior), whether or not you submit
+ * your changes directly to this repository. All such modifications are part of the broader
+ * HlsKit ecosystem and are subject to this CLA.
+ *
+ * 3. Distribution of Modifications:
+ * If you distribute a modified version of HlsKit, you must license your modifications under
+ * LGPLv3 (with source code available as required by the license) and ensure they are
+ * adoptable by the HlsKit ecosystem (publicly available and compatible).
+ *
+ * 4. Networked Use of Modifications:
+ * If you use a modified version of HlsKit in a networked application, you must provide the
+ * source code of your modifications under LGPLv3 and notify the HlsKit project
+ * (e.g., via email to [higashikataengels@icloud.com]). This does not apply to the use of
+ * the unmodified library in proprietary software, which remains permissible under LGPLv3.
+ *
+ * 5. Scope:
+ * These terms apply to all contributions and modifications derived from the HlsKit project.
+ * The use of the unmodified library in proprietary software is governed solely by the LGPLv3.
+ */
+
+use std::time::Duration;
+
+/// What ffprobe reports about a source video
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SourceInfo {
+    /// Width and height of the first video stream, as stored (before rotation)
+    pub resolution: (i32, i32),
+    /// Average frames per second
+    pub frame_rate: Option<f64>,
+    /// The container's base rate differs from the average, as with screen recordings and
+    /// phone clips
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub variable_frame_rate: bool,
+    /// Bits per second of the video stream, or of the whole container when unknown
+    pub bitrate: Option<u64>,
+    pub duration: Option<Duration>,
+    /// Container names as ffprobe lists them, e.g. `mov,mp4,m4a,3gp,3g2,mj2`
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub format_name: Option<String>,
+    /// Codec of the first video stream, e.g. `h264` or `hevc`
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub video_codec: Option<String>,
+    /// Bits per color component of the first video stream
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub bit_depth: Option<u8>,
+    /// Clockwise rotation players apply on display, one of 0, 90, 180 or 270
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub rotation: u16,
+    /// Whether the first video stream's field order marks it interlaced, `None` when unknown
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub interlaced: Option<bool>,
+    /// Set when the first video stream uses a PQ or HLG transfer
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub hdr: Option<HdrMetadata>,
+    /// Every stream of the container, in file order
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub streams: Vec<StreamInfo>,
+}
+
+impl SourceInfo {
+    /// The resolution viewers see, with width and height swapped for portrait rotations
+    pub fn display_resolution(&self) -> (i32, i32) {
+        let (width, height) = self.resolution;
+        match self.rotation {
+            90 | 270 => (height, width),
+            _ => (width, height),
+        }
+    }
+
+    pub fn is_hdr(&self) -> bool {
+        self.hdr.is_some()
+    }
+
+    pub fn streams_of(&self, kind: StreamKind) -> impl Iterator<Item = &StreamInfo> {
+        self.streams
+            .iter()
+            .filter(move |stream| stream.kind == kind)
+    }
+
+    pub fn has_audio(&self) -> bool {
+        self.streams_of(StreamKind::Audio).next().is_some()
+    }
+
+    /// Whether the video and audio streams can be copied into MPEG-TS segments as they are:
+    /// H.264 video and AAC, MP3, AC-3 or E-AC-3 audio
+    pub fn is_hls_compatible(&self) -> bool {
+        let codec_in = |stream: &StreamInfo, codecs: &[&str]| {
+            stream
+                .codec
+                .as_deref()
+                .is_some_and(|codec| codecs.contains(&codec))
+        };
+
+        self.video_codec.as_deref() == Some("h264")
+            && self
+                .streams_of(StreamKind::Audio)
+                .all(|stream| codec_in(stream, &["aac", "mp3", "ac3", "eac3"]))
+    }
+}
+
+/// A single stream of the source container
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StreamInfo {
+    pub index: u32,
+    pub kind: StreamKind,
+    pub codec: Option<String>,
+    pub bitrate: Option<u64>,
+    /// ISO 639 language tag, when the container has one
+    pub language: Option<String>,
+    /// Audio channel count
+    pub channels: Option<u32>,
+    /// Audio sample rate in Hz
+    pub sample_rate: Option<u32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum StreamKind {
+    Video,
+    Audio,
+    Subtitle,
+    Data,
+    Attachment,
+    #[default]
+    Other,
+}
+
+impl StreamKind {
+    /// Maps ffprobe's `codec_type`
+    pub fn from_codec_type(codec_type: &str) -> Self {
+        match codec_type {
+            "video" => StreamKind::Video,
+            "audio" => StreamKind::Audio,
+            "subtitle" => StreamKind::Subtitle,
+            "data" => StreamKind::Data,
+            "attachment" => StreamKind::Attachment,
+            _ => StreamKind::Other,
+        }
+    }
+}
+
+/// High dynamic range signalling of a video stream
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HdrMetadata {
+    pub transfer: HdrTransfer,
+    /// e.g. `bt2020`
+    pub color_primaries: Option<String>,
+    /// e.g. `bt2020nc`
+    pub color_space: Option<String>,
+    /// MaxCLL in nits, from the content light level side data
+    pub max_content_light_level: Option<u32>,
+    /// MaxFALL in nits, from the content light level side data
+    pub max_frame_average_light_level: Option<u32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum HdrTransfer {
+    /// SMPTE ST 2084, used by HDR10 and Dolby Vision
+    Pq,
+    /// ARIB STD-B67 hybrid log-gamma
+    Hlg,
+}
+
+impl HdrTransfer {
+    /// Maps ffprobe's `color_transfer`, returning `None` for SDR transfers
+    pub fn from_color_transfer(color_transfer: &str) -> Option<Self> {
+        match color_transfer {
+            "smpte2084" => Some(HdrTransfer::Pq),
+            "arib-std-b67" => Some(HdrTransfer::Hlg),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,126 @@
+// SPDX-License-Identifier: LGPL-3.0-only
+/*
+ * Copyright © 2025 The HlsKit Project
+ *
+ * This software is licensed under the GNU Lesser General Public License v3.0 (LGPLv3).
+ * All contributions adhere to the LGPLv3 and the HlsKit Contributor License Agreement (CLA).
+ * A copy of the LGPLv3 can be found at https://www.gnu.org/licenses/lgpl-3.0.html
+ *
+ * HlsKit Contributor License Agreement
+ *
+ * By contributing to or modifying HlsKit, you agree to the following terms:
+ *
+ * 1. Collective Ownership:
+ * The HlsKit project incorporates original code and all contributions as a collective work,
+ * licensed under LGPLv3. Once submitted, contributions become part of the shared HlsKit
+ * ecosystem and cannot be reclaimed, reassigned, or withdrawn. Contributions to your own
+ * forks remain yours unless submitted here, at which point they join this collective whole under LGPLv3.
+ *
+ * 2. Definition of Contribution:
+ * You are considered a contributor if you modify the library in any form (including forks,
+ * wrappers, libraries, or extensions that alter its behavior), whether or not you submit
+ * your changes directly to this repository. All such modifications are part of the broader
+ * HlsKit ecosystem and are subject to this CLA.
+ *
+ * 3. Distribution of Modifications:
+ * If you distribute a modified version of HlsKit, you must license your modifications under
+ * LGPLv3 (with source code available as required by the license) and ensure they are
+ * adoptable by the HlsKit ecosystem (publicly available and compatible).
+ *
+ * 4. Networked Use of Modifications:
+ * If you use a modified version of HlsKit in a networked application, you must provide the
+ * source code of your modifications under LGPLv3 and notify the HlsKit project
+ * (e.g., via email to [higashikataengels@icloud.com]). This does not apply to the use of
+ * the unmodified library in proprietary software, which remains permissible under LGPLv3.
+ *
+ * 5. Scope:
+ * These terms apply to all contributions and modifications derived from the HlsKit project.
+ * The use of the unmodified library in proprietary software is governed solely by the LGPLv3.
+ */
+
+use std::time::Duration;
+
+use crate::models::hls_video_processing_settings::{
+    FfmpegVideoProcessingPreset, HlsVideoAudioBitrate, HlsVideoAudioCodec, HlsVideoCodec,
+    HlsVideoProcessingSettings, SegmentType,
+};
+
+/// Time between forced keyframes, so every segment boundary can start on one
+const KEYFRAME_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Class of playback devices an encoding ladder is tuned for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Target {
+    /// Phones on cellular networks: small renditions, baseline-friendly H.264, lean audio
+    MobileCellular,
+    /// Desktop browsers: 1080p down to 360p
+    WebDesktop,
+    /// Living-room TVs: 2160p down to 720p HEVC at higher quality
+    SmartTv4k,
+}
+
+impl Target {
+    /// The renditions for this target, largest first. The H.264 profile and level are passed
+    /// as ffmpeg arguments, which the GStreamer backend ignores. HEVC renditions use fMP4
+    /// segments, the only ones HLS carries it in
+    pub fn profiles(&self) -> Vec<HlsVideoProcessingSettings> {
+        let (renditions, preset, h264_profile_level): (&[_], _, _) = match self {
+            Target::MobileCellular => (
+                &[
+                    ((640, 360), 26, HlsVideoAudioBitrate::Low),
+                    ((480, 270), 27, HlsVideoAudioBitrate::Low),
+                    ((416, 234), 28, HlsVideoAudioBitrate::Low),
+                ],
+                FfmpegVideoProcessingPreset::Faster,
+                Some(("main", "3.1")),
+            ),
+            Target::WebDesktop => (
+                &[
+                    ((1920, 1080), 22, HlsVideoAudioBitrate::High),
+                    ((1280, 720), 23, HlsVideoAudioBitrate::Medium),
+                    ((854, 480), 24, HlsVideoAudioBitrate::Medium),
+                    ((640, 360), 26, HlsVideoAudioBitrate::Low),
+                ],
+                FfmpegVideoProcessingPreset::Medium,
+                Some(("high", "4.1")),
+            ),
+            Target::SmartTv4k => (
+                &[
+                    ((3840, 2160), 20, HlsVideoAudioBitrate::High),
+                    ((2560, 1440), 21, HlsVideoAudioBitrate::High),
+                    ((1920, 1080), 22, HlsVideoAudioBitrate::High),
+                    ((1280, 720), 23, HlsVideoAudioBitrate::Medium),
+                ],
+                FfmpegVideoProcessingPreset::Slow,
+                None,
+            ),
+        };
+
+        renditions
+            .iter()
+            .map(|&(resolution, crf, audio_bitrate)| {
+                let profile = HlsVideoProcessingSettings::new(
+                    resolution,
+                    crf,
+                    Some(HlsVideoAudioCodec::Aac),
+                    Some(audio_bitrate),
+                    preset,
+                )
+                .with_keyframe_interval(KEYFRAME_INTERVAL);
+
+                match h264_profile_level {
+                    Some((h264_profile, h264_level)) => profile.with_extra_args([
+                        "-profile:v",
+                        h264_profile,
+                        "-level:v",
+                        h264_level,
+                    ]),
+                    None => profile
+                        .with_video_codec(HlsVideoCodec::Hevc)
+                        .with_segment_type(SegmentType::Fmp4),
+                }
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,125 @@
+// SPDX-License-Identifier: LGPL-3.0-only
+/*
+ * Copyright © 2025 The HlsKit Project
+ *
+ * This software is licensed under the GNU Lesser General Public License v3.0 (LGPLv3).
+ * All contributions adhere to the LGPLv3 and the HlsKit Contributor License Agreement (CLA).
+ * A copy of the LGPLv3 can be found at https://www.gnu.org/licenses/lgpl-3.0.html
+ *
+ * HlsKit Contributor License Agreement
+ *
+ * By contributing to or modifying HlsKit, you agree to the following terms:
+ *
+ * 1. Collective Ownership:
+ * The HlsKit project incorporates original code and all contributions as a collective work,
+ * licensed under LGPLv3. Once submitted, contributions become part of the shared HlsKit
+ * ecosystem and cannot be reclaimed, reassigned, or withdrawn. Contributions to your own
+ * forks remain yours unless submitted here, at which point they join this collective whole under LGPLv3.
+ *
+ * 2. Definition of Contribution:
+ * You are considered a contributor if you modify the library in any form (including forks,
+ * wrappers, libraries, or extensions that alter its behavior), whether or not you submit
+ * your changes directly to this repository. All such modifications are part of the broader
+ * HlsKit ecosystem and are subject to this CLA.
+ *
+ * 3. Distribution of Modifications:
+ * If you distribute a modified version of HlsKit, you must license your modifications under
+ * LGPLv3 (with source code available as required by the license) and ensure they are
+ * adoptable by the HlsKit ecosystem (publicly available and compatible).
+ *
+ * 4. Networked Use of Modifications:
+ * If you use a modified version of HlsKit in a networked application, you must provide the
+ * source code of your modifications under LGPLv3 and notify the HlsKit project
+ * (e.g., via email to [higashikataengels@icloud.com]). This does not apply to the use of
+ * the unmodified library in proprietary software, which remains permissible under LGPLv3.
+ *
+ * 5. Scope:
+ * These terms apply to all contributions and modifications derived from the HlsKit project.
+ * The use of the unmodified library in proprietary software is governed solely by the LGPLv3.
+ */
+
+use crate::{
+    models::hls_video_processing_settings::HlsVideoProcessingSettings,
+    tools::ladder_generator::even,
+};
+
+/// What to do with profiles larger than the source video
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum UpscalePolicy {
+    /// Encode every profile as configured, upscaling when needed
+    #[default]
+    Allow,
+    /// Drop profiles larger than the source. When none fit, the smallest profile is kept and
+    /// clamped so the job still produces a rendition
+    Skip,
+    /// Scale profiles larger than the source down to fit it, keeping their aspect ratio and
+    /// dropping the duplicates this creates
+    Clamp,
+}
+
+impl UpscalePolicy {
+    /// The profiles to encode for a source of `source_resolution` (as displayed)
+    pub fn apply(
+        &self,
+        profiles: &[HlsVideoProcessingSettings],
+        source_resolution: (i32, i32),
+    ) -> Vec<HlsVideoProcessingSettings> {
+        let (source_width, source_height) = source_resolution;
+        if *self == UpscalePolicy::Allow || source_width <= 0 || source_height <= 0 {
+            return profiles.to_vec();
+        }
+
+        let fits = |profile: &&HlsVideoProcessingSettings| {
+            profile.resolution.0 <= source_width && profile.resolution.1 <= source_height
+        };
+
+        match self {
+            UpscalePolicy::Skip => {
+                let fitting: Vec<_> = profiles.iter().filter(fits).cloned().collect();
+                if !fitting.is_empty() {
+                    return fitting;
+                }
+
+                profiles
+                    .iter()
+                    .min_by_key(|profile| profile.resolution.0 as i64 * profile.resolution.1 as i64)
+                    .map(|smallest| vec![clamp(smallest, source_resolution)])
+                    .unwrap_or_default()
+            }
+            _ => {
+                let mut clamped: Vec<HlsVideoProcessingSettings> = Vec::new();
+                for profile in profiles {
+                    let profile = clamp(profile, source_resolution);
+                    if !clamped
+                        .iter()
+                        .any(|kept| kept.resolution == profile.resolution)
+                    {
+                        clamped.push(profile);
+                    }
+                }
+                clamped
+            }
+        }
+    }
+}
+
+fn clamp(
+    profile: &HlsVideoProcessingSettings,
+    (source_width, source_height): (i32, i32),
+) -> HlsVideoProcessingSettings {
+    let (width, height) = profile.resolution;
+    let scale = (source_width as f64 / width as f64)
+        .min(source_height as f64 / height as f64)
+        .min(1.0);
+
+    let mut clamped = profile.clone();
+    if scale < 1.0 {
+        clamped.resolution = (
+            even((width as f64 * scale).round() as i32),
+            even((height as f64 * scale).round() as i32),
+        );
+    }
+    clamped
+}
@@ -0,0 +1,130 @@
+// SPDX-License-Identifier: LGPL-3.0-only
+/*
+ * Copyright © 2025 The HlsKit Project
+ *
+ * This software is licensed under the GNU Lesser General Public License v3.0 (LGPLv3).
+ * All contributions adhere to the LGPLv3 and the HlsKit Contributor License Agreement (CLA).
+ * A copy of the LGPLv3 can be found at https://www.gnu.org/licenses/lgpl-3.0.html
+ *
+ * HlsKit Contributor License Agreement
+ *
+ * By contributing to or modifying HlsKit, you agree to the following terms:
+ *
+ * 1. Collective Ownership:
+ * The HlsKit project incorporates original code and all contributions as a collective work,
+ * licensed under LGPLv3. Once submitted, contributions become part of the shared HlsKit
+ * ecosystem and cannot be reclaimed, reassigned, or withdrawn. Contributions to your own
+ * forks remain yours unless submitted here, at which point they join this collective whole under LGPLv3.
+ *
+ * 2. Definition of Contribution:
+ * You are considered a contributor if you modify the library in any form (including forks,
+ * wrappers, libraries, or extensions that alter its behavior), whether or not you submit
+ * your changes directly to this repository. All such modifications are part of the broader
+ * HlsKit ecosystem and are subject to this CLA.
+ *
+ * 3. Distribution of Modifications:
+ * If you distribute a modified version of HlsKit, you must license your modifications under
+ * LGPLv3 (with source code available as required by the license) and ensure they are
+ * adoptable by the HlsKit ecosystem (publicly available and compatible).
+ *
+ * 4. Networked Use of Modifications:
+ * If you use a modified version of HlsKit in a networked application, you must provide the
+ * source code of your modifications under LGPLv3 and notify the HlsKit project
+ * (e.g., via email to [higashikataengels@icloud.com]). This does not apply to the use of
+ * the unmodified library in proprietary software, which remains permissible under LGPLv3.
+ *
+ * 5. Scope:
+ * These terms apply to all contributions and modifications derived from the HlsKit project.
+ * The use of the unmodified library in proprietary software is governed solely by the LGPLv3.
+ */
+
+use std::{fmt, path::PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum FindingSeverity {
+    /// Worth a look, but players will cope
+    Warning,
+    /// The asset is broken and should not be published
+    Error,
+}
+
+/// A problem found in the produced asset
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ValidationFinding {
+    pub severity: FindingSeverity,
+    /// The playlist or segment concerned
+    pub path: Option<PathBuf>,
+    pub message: String,
+}
+
+impl fmt::Display for ValidationFinding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.path {
+            Some(path) => write!(f, "{}: {}", path.display(), self.message),
+            None => f.write_str(&self.message),
+        }
+    }
+}
+
+/// What output validation checked and found, see `OutputValidation`
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ValidationReport {
+    pub findings: Vec<ValidationFinding>,
+    pub playlists_checked: usize,
+    pub segments_checked: usize,
+    pub segments_decoded: usize,
+}
+
+impl ValidationReport {
+    /// Whether nothing worse than a warning was found
+    pub fn is_valid(&self) -> bool {
+        self.errors().next().is_none()
+    }
+
+    pub fn errors(&self) -> impl Iterator<Item = &ValidationFinding> {
+        self.findings
+            .iter()
+            .filter(|finding| finding.severity == FindingSeverity::Error)
+    }
+
+    pub fn warnings(&self) -> impl Iterator<Item = &ValidationFinding> {
+        self.findings
+            .iter()
+            .filter(|finding| finding.severity == FindingSeverity::Warning)
+    }
+
+    pub(crate) fn error(&mut self, path: Option<PathBuf>, message: impl Into<String>) {
+        self.findings.push(ValidationFinding {
+            severity: FindingSeverity::Error,
+            path,
+            message: message.into(),
+        });
+    }
+
+    pub(crate) fn warning(&mut self, path: Option<PathBuf>, message: impl Into<String>) {
+        self.findings.push(ValidationFinding {
+            severity: FindingSeverity::Warning,
+            path,
+            message: message.into(),
+        });
+    }
+}
+
+impl fmt::Display for ValidationReport {
+    /// Summarizes the errors, e.g. `2 error(s): a.ts: empty segment; ...`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let errors: Vec<String> = self.errors().map(ToString::to_string).collect();
+        if errors.is_empty() {
+            return write!(
+                f,
+                "valid ({} playlists, {} segments)",
+                self.playlists_checked, self.segments_checked
+            );
+        }
+        write!(f, "{} error(s): {}", errors.len(), errors.join("; "))
+    }
+}
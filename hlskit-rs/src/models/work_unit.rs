@@ -0,0 +1,85 @@
+// SPDX-License-Identifier: LGPL-3.0-only
+/*
+ * Copyright © 2025 The HlsKit Project
+ *
+ * This software is licensed under the GNU Lesser General Public License v3.0 (LGPLv3).
+ * All contributions adhere to the LGPLv3 and the HlsKit Contributor License Agreement (CLA).
+ * A copy of the LGPLv3 can be found at https://www.gnu.org/licenses/lgpl-3.0.html
+ *
+ * HlsKit Contributor License Agreement
+ *
+ * By contributing to or modifying HlsKit, you agree to the following terms:
+ *
+ * 1. Collective Ownership:
+ * The HlsKit project incorporates original code and all contributions as a collective work,
+ * licensed under LGPLv3. Once submitted, contributions become part of the shared HlsKit
+ * ecosystem and cannot be reclaimed, reassigned, or withdrawn. Contributions to your own
+ * forks remain yours unless submitted here, at which point they join this collective whole under LGPLv3.
+ *
+ * 2. Definition of Contribution:
+ * You are considered a contributor if you modify the library in any form (including forks,
+ * wrappers, libraries, or extensions that alter its behavior), whether or not you submit
+ * your changes directly to this repository. All such modifications are part of the broader
+ * HlsKit ecosystem and are subject to this CLA.
+ *
+ * 3. Distribution of Modifications:
+ * If you distribute a modified version of HlsKit, you must license your modifications under
+ * LGPLv3 (with source code available as required by the license) and ensure they are
+ * adoptable by the HlsKit ecosystem (publicly available and compatible).
+ *
+ * 4. Networked Use of Modifications:
+ * If you use a modified version of HlsKit in a networked application, you must provide the
+ * source code of your modifications under LGPLv3 and notify the HlsKit project
+ * (e.g., via email to [higashikataengels@icloud.com]). This does not apply to the use of
+ * the unmodified library in proprietary software, which remains permissible under LGPLv3.
+ *
+ * 5. Scope:
+ * These terms apply to all contributions and modifications derived from the HlsKit project.
+ * The use of the unmodified library in proprietary software is governed solely by the LGPLv3.
+ */
+
+use std::time::Duration;
+
+use crate::{
+    models::{
+        chunked_encoding::SourceChunk, dynamic_range::VideoRange, frame_rate::FrameRate,
+        hls_video::HlsVideoResolution, hls_video_processing_settings::HlsVideoProcessingSettings,
+        resource_usage::ResourceUsage, source_adjustments::SourceAdjustments,
+    },
+    VideoProcessorEncryptionSettings,
+};
+
+/// A profile, or a chunk of one, planned by `VideoProcessor::work_units` with everything needed
+/// to encode it on another machine through `VideoProcessor::process_work_unit`. The input and
+/// key info file must be reachable there under the same path or URL
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WorkUnit {
+    pub input: String,
+    pub profile: HlsVideoProcessingSettings,
+    pub stream_index: i32,
+    /// Position of `chunk` among the chunks of the profile, 0 for a whole profile
+    pub chunk_index: usize,
+    /// The stretch of the source to encode, `None` for all of it
+    pub chunk: Option<SourceChunk>,
+    pub encryption: Option<VideoProcessorEncryptionSettings>,
+    /// Corrections decided while planning, applied by the worker's backend
+    pub source_adjustments: SourceAdjustments,
+    pub source_duration: Option<Duration>,
+    pub source_has_audio: Option<bool>,
+}
+
+/// The rendition, or the part of one, encoded from a `WorkUnit`, for `merge_results`
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WorkUnitResult {
+    pub stream_index: i32,
+    pub chunk_index: usize,
+    pub resolution: HlsVideoResolution,
+    pub frame_rate: Option<FrameRate>,
+    pub video_range: VideoRange,
+    /// RFC 6381 codecs of the rendition, for the master playlist
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub codecs: Option<String>,
+    pub resource_usage: ResourceUsage,
+}
@@ -0,0 +1,543 @@
+// SPDX-License-Identifier: LGPL-3.0-only
+/*
+ * Copyright © 2025 The HlsKit Project
+ *
+ * This software is licensed under the GNU Lesser General Public License v3.0 (LGPLv3).
+ * All contributions adhere to the LGPLv3 and the HlsKit Contributor License Agreement (CLA).
+ * A copy of the LGPLv3 can be found at https://www.gnu.org/licenses/lgpl-3.0.html
+ *
+ * HlsKit Contributor License Agreement
+ *
+ * By contributing to or modifying HlsKit, you agree to the following terms:
+ *
+ * 1. Collective Ownership:
+ * The HlsKit project incorporates original code and all contributions as a collective work,
+ * licensed under LGPLv3. Once submitted, contributions become part of the shared HlsKit
+ * ecosystem and cannot be reclaimed, reassigned, or withdrawn. Contributions to your own
+ * forks remain yours unless submitted here, at which point they join this collective whole under LGPLv3.
+ *
+ * 2. Definition of Contribution:
+ * You are considered a contributor if you modify the library in any form (including forks,
+ * wrappers, libraries, or extensions that alter its behavior), whether or not you submit
+ * your changes directly to this repository. All such modifications are part of the broader
+ * HlsKit ecosystem and are subject to this CLA.
+ *
+ * 3. Distribution of Modifications:
+ * If you distribute a modified version of HlsKit, you must license your modifications under
+ * LGPLv3 (with source code available as required by the license) and ensure they are
+ * adoptable by the HlsKit ecosystem (publicly available and compatible).
+ *
+ * 4. Networked Use of Modifications:
+ * If you use a modified version of HlsKit in a networked application, you must provide the
+ * source code of your modifications under LGPLv3 and notify the HlsKit project
+ * (e.g., via email to [higashikataengels@icloud.com]). This does not apply to the use of
+ * the unmodified library in proprietary software, which remains permissible under LGPLv3.
+ *
+ * 5. Scope:
+ * These terms apply to all contributions and modifications derived from the HlsKit project.
+ * The use of the unmodified library in proprietary software is governed solely by the LGPLv3.
+ */
+
+use std::{
+    cmp::Ordering,
+    collections::{BinaryHeap, HashMap},
+    fs,
+    future::Future,
+    path::PathBuf,
+    pin::Pin,
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+use tokio::sync::oneshot;
+
+use crate::{
+    models::{
+        hls_video::HlsVideo,
+        hls_video_manifest::HlsVideoManifest,
+        job_id::JobId,
+        queued_job::{JobPriority, JobStatus, PreemptionPolicy},
+    },
+    prelude::VideoProcessor,
+    tools::{
+        hlskit_error::HlsKitError, internals::pipeline::NoSink,
+        processing_context::CancellationToken,
+    },
+    traits::{
+        output_sink::OutputSink, video_processing_backend::VideoProcessingBackend,
+        video_validatable::VideoValidatable,
+    },
+};
+
+type AttemptFuture = Pin<Box<dyn Future<Output = Attempt> + Send>>;
+type StartAttempt = Arc<dyn Fn(CancellationToken) -> AttemptFuture + Send + Sync>;
+
+/// Runs many `VideoProcessor` jobs on the tokio runtime under global limits
+///
+/// Jobs start in priority order (FIFO within a priority) while fewer than
+/// `max_concurrent_jobs` are running and, when an encode budget is set, while
+/// the profiles they would encode in parallel fit in it.
+#[derive(Clone)]
+pub struct JobQueue {
+    state: Arc<Mutex<QueueState>>,
+}
+
+/// Awaits the result of a job submitted to a `JobQueue`
+#[derive(Debug)]
+pub struct JobHandle<T> {
+    id: JobId,
+    receiver: oneshot::Receiver<Result<T, HlsKitError>>,
+}
+
+struct QueueState {
+    max_concurrent_jobs: usize,
+    max_concurrent_encodes: Option<usize>,
+    preemption_policy: PreemptionPolicy,
+    running_jobs: usize,
+    running_encodes: usize,
+    pending: BinaryHeap<PendingJob>,
+    jobs: HashMap<JobId, JobRecord>,
+}
+
+struct JobRecord {
+    status: JobStatus,
+    priority: JobPriority,
+    token: CancellationToken,
+    attempt: Option<CancellationToken>,
+    preempted: bool,
+}
+
+struct PendingJob {
+    id: JobId,
+    priority: JobPriority,
+    encodes: usize,
+    start: StartAttempt,
+}
+
+/// How a single run of a job ended
+enum Attempt {
+    Finished(JobStatus),
+    Preempted,
+}
+
+impl PartialEq for PendingJob {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for PendingJob {}
+
+impl PartialOrd for PendingJob {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for PendingJob {
+    // Highest priority first, then the oldest job
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.priority
+            .cmp(&other.priority)
+            .then_with(|| other.id.cmp(&self.id))
+    }
+}
+
+impl<T> JobHandle<T> {
+    pub fn id(&self) -> JobId {
+        self.id
+    }
+
+    /// Waits for the job to finish, failing with `Cancelled` if it never ran
+    pub async fn wait(self) -> Result<T, HlsKitError> {
+        self.receiver.await.unwrap_or(Err(HlsKitError::Cancelled))
+    }
+}
+
+impl JobQueue {
+    pub fn new(max_concurrent_jobs: usize) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(QueueState {
+                max_concurrent_jobs: max_concurrent_jobs.max(1),
+                max_concurrent_encodes: None,
+                preemption_policy: PreemptionPolicy::default(),
+                running_jobs: 0,
+                running_encodes: 0,
+                pending: BinaryHeap::new(),
+                jobs: HashMap::new(),
+            })),
+        }
+    }
+
+    /// Caps the encoder processes running across all jobs, e.g. to the CPU count
+    pub fn with_max_concurrent_encodes(self, limit: usize) -> Self {
+        self.lock().max_concurrent_encodes = Some(limit.max(1));
+        self
+    }
+
+    /// Lets higher-priority jobs preempt running lower-priority ones when the queue is full
+    pub fn with_preemption_policy(self, policy: PreemptionPolicy) -> Self {
+        self.lock().preemption_policy = policy;
+        self
+    }
+
+    /// Queues `processor` and resolves to the in-memory `HlsVideo`
+    pub fn submit<B, S>(
+        &self,
+        processor: VideoProcessor<B, S>,
+        priority: JobPriority,
+    ) -> JobHandle<HlsVideo>
+    where
+        B: VideoProcessingBackend + Default + 'static,
+        S: VideoValidatable + Default + Send + Sync + 'static,
+    {
+        let encodes = encodes_of(&processor);
+        let parent = processor.context().cancellation_token().cloned();
+        let processor = Arc::new(processor);
+
+        self.enqueue(priority, encodes, parent, move |id, token| {
+            let processor = processor.clone();
+            async move {
+                let context = processor
+                    .context()
+                    .clone()
+                    .with_job_id(id)
+                    .with_cancellation_token(token);
+                processor.job_with_context(&context).process().await
+            }
+        })
+    }
+
+    /// Queues `processor` and writes its output to `output_dir`
+    pub fn submit_to_dir<B, S>(
+        &self,
+        processor: VideoProcessor<B, S>,
+        output_dir: impl Into<PathBuf>,
+        priority: JobPriority,
+    ) -> JobHandle<HlsVideoManifest>
+    where
+        B: VideoProcessingBackend + Default + 'static,
+        S: VideoValidatable + Default + Send + Sync + 'static,
+    {
+        let output_dir = Arc::new(output_dir.into());
+        let encodes = encodes_of(&processor);
+        let parent = processor.context().cancellation_token().cloned();
+        let processor = Arc::new(processor);
+
+        self.enqueue(priority, encodes, parent, move |id, token| {
+            let processor = processor.clone();
+            let output_dir = output_dir.clone();
+            async move {
+                fs::create_dir_all(output_dir.as_path())?;
+
+                let context = processor
+                    .context()
+                    .clone()
+                    .with_job_id(id)
+                    .with_cancellation_token(token);
+                processor
+                    .job_with_context(&context)
+                    .process_to_dir(&output_dir, None::<&NoSink>)
+                    .await
+            }
+        })
+    }
+
+    /// Queues `processor` and hands its artifacts to `sink`
+    pub fn submit_to_sink<B, S, K>(
+        &self,
+        processor: VideoProcessor<B, S>,
+        sink: K,
+        priority: JobPriority,
+    ) -> JobHandle<()>
+    where
+        B: VideoProcessingBackend + Default + 'static,
+        S: VideoValidatable + Default + Send + Sync + 'static,
+        K: OutputSink + 'static,
+    {
+        let sink = Arc::new(sink);
+        let encodes = encodes_of(&processor);
+        let parent = processor.context().cancellation_token().cloned();
+        let processor = Arc::new(processor);
+
+        self.enqueue(priority, encodes, parent, move |id, token| {
+            let processor = processor.clone();
+            let sink = sink.clone();
+            async move {
+                let context = processor
+                    .context()
+                    .clone()
+                    .with_job_id(id)
+                    .with_cancellation_token(token);
+                processor
+                    .job_with_context(&context)
+                    .process_to_sink(sink.as_ref())
+                    .await
+            }
+        })
+    }
+
+    pub fn status(&self, id: JobId) -> Option<JobStatus> {
+        self.lock()
+            .jobs
+            .get(&id)
+            .map(|record| record.status.clone())
+    }
+
+    pub fn priority(&self, id: JobId) -> Option<JobPriority> {
+        self.lock().jobs.get(&id).map(|record| record.priority)
+    }
+
+    /// Lists every known job with its status, ordered by submission
+    pub fn jobs(&self) -> Vec<(JobId, JobStatus)> {
+        let state = self.lock();
+        let mut jobs: Vec<_> = state
+            .jobs
+            .iter()
+            .map(|(id, record)| (*id, record.status.clone()))
+            .collect();
+        jobs.sort_by_key(|(id, _)| *id);
+        jobs
+    }
+
+    pub fn queued_count(&self) -> usize {
+        self.lock().pending.len()
+    }
+
+    pub fn running_count(&self) -> usize {
+        self.lock().running_jobs
+    }
+
+    /// Drops a queued job or cancels a running one, returns false if it already finished
+    pub fn cancel(&self, id: JobId) -> bool {
+        let mut state = self.lock();
+        let Some(record) = state.jobs.get_mut(&id) else {
+            return false;
+        };
+
+        match record.status {
+            JobStatus::Queued => {
+                record.status = JobStatus::Cancelled;
+                record.token.cancel();
+                state.pending.retain(|job| job.id != id);
+                true
+            }
+            JobStatus::Running => {
+                record.token.cancel();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Forgets finished jobs so their status no longer takes up memory
+    pub fn clear_finished(&self) {
+        self.lock()
+            .jobs
+            .retain(|_, record| !record.status.is_finished());
+    }
+
+    fn enqueue<T, F, Fut>(
+        &self,
+        priority: JobPriority,
+        encodes: usize,
+        parent: Option<CancellationToken>,
+        job: F,
+    ) -> JobHandle<T>
+    where
+        T: Send + 'static,
+        F: Fn(JobId, CancellationToken) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<T, HlsKitError>> + Send + 'static,
+    {
+        let id = JobId::next();
+        let (sender, receiver) = oneshot::channel();
+        let sender = Arc::new(Mutex::new(Some(sender)));
+        let token = parent
+            .map(|parent| parent.child_token())
+            .unwrap_or_default();
+        let job_token = token.clone();
+
+        let start: StartAttempt = Arc::new(move |attempt_token| {
+            let attempt = job(id, attempt_token);
+            let sender = sender.clone();
+            let job_token = job_token.clone();
+            Box::pin(async move {
+                let result = attempt.await;
+
+                // Only the queue cancels an attempt without cancelling the job
+                if matches!(result, Err(HlsKitError::Cancelled)) && !job_token.is_cancelled() {
+                    return Attempt::Preempted;
+                }
+
+                let status = match &result {
+                    Ok(_) => JobStatus::Completed,
+                    Err(HlsKitError::Cancelled) => JobStatus::Cancelled,
+                    Err(e) => JobStatus::Failed {
+                        error: e.to_string(),
+                    },
+                };
+                if let Some(sender) = sender.lock().ok().and_then(|mut sender| sender.take()) {
+                    let _ = sender.send(result);
+                }
+                Attempt::Finished(status)
+            })
+        });
+
+        {
+            let mut state = self.lock();
+            state.jobs.insert(
+                id,
+                JobRecord {
+                    status: JobStatus::Queued,
+                    priority,
+                    token,
+                    attempt: None,
+                    preempted: false,
+                },
+            );
+            state.pending.push(PendingJob {
+                id,
+                priority,
+                encodes,
+                start,
+            });
+        }
+
+        tracing::debug!("Queued {} with {:?} priority", id, priority);
+        self.dispatch();
+
+        JobHandle { id, receiver }
+    }
+
+    fn dispatch(&self) {
+        let mut state = self.lock();
+
+        while let Some(next) = state.pending.peek() {
+            // A job larger than the whole budget still runs once the queue is idle
+            let encodes = match state.max_concurrent_encodes {
+                Some(limit) => next.encodes.min(limit),
+                None => next.encodes,
+            };
+            let fits = state.running_jobs < state.max_concurrent_jobs
+                && state.max_concurrent_encodes.is_none_or(|limit| {
+                    state.running_jobs == 0 || state.running_encodes + encodes <= limit
+                });
+
+            if !fits {
+                let priority = next.priority;
+                state.preempt_for(priority);
+                break;
+            }
+
+            let Some(job) = state.pending.pop() else {
+                break;
+            };
+            let Some(record) = state.jobs.get_mut(&job.id) else {
+                continue;
+            };
+            let attempt = record.token.child_token();
+            record.status = JobStatus::Running;
+            record.attempt = Some(attempt.clone());
+            record.preempted = false;
+            state.running_jobs += 1;
+            state.running_encodes += encodes;
+
+            tracing::debug!("Starting {}", job.id);
+            let queue = self.clone();
+            let task = (job.start)(attempt);
+            tokio::spawn(async move {
+                let attempt = task.await;
+                queue.finish(job, encodes, attempt);
+            });
+        }
+    }
+
+    fn finish(&self, job: PendingJob, encodes: usize, attempt: Attempt) {
+        {
+            let mut state = self.lock();
+            state.running_jobs -= 1;
+            state.running_encodes -= encodes;
+
+            if let Some(record) = state.jobs.get_mut(&job.id) {
+                record.attempt = None;
+                record.preempted = false;
+                match attempt {
+                    Attempt::Finished(status) => {
+                        tracing::debug!("{} finished as {:?}", job.id, status);
+                        record.status = status;
+                    }
+                    Attempt::Preempted => {
+                        tracing::info!("{} was preempted and queued again", job.id);
+                        record.status = JobStatus::Queued;
+                        state.pending.push(job);
+                    }
+                }
+            }
+        }
+
+        self.dispatch();
+    }
+
+    fn lock(&self) -> MutexGuard<'_, QueueState> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl QueueState {
+    /// Kills the lowest-priority running job below `priority`, unless one is already being preempted
+    fn preempt_for(&mut self, priority: JobPriority) {
+        if self.preemption_policy == PreemptionPolicy::Never
+            || self.jobs.values().any(|record| record.preempted)
+        {
+            return;
+        }
+
+        let victim = self
+            .jobs
+            .iter_mut()
+            .filter(|(_, record)| record.status == JobStatus::Running && record.priority < priority)
+            .min_by_key(|(id, record)| (record.priority, std::cmp::Reverse(**id)));
+
+        let Some((id, record)) = victim else {
+            return;
+        };
+
+        tracing::info!("Preempting {} for a {:?} priority job", id, priority);
+        record.preempted = true;
+        match self.preemption_policy {
+            PreemptionPolicy::Cancel => record.token.cancel(),
+            _ => {
+                if let Some(attempt) = &record.attempt {
+                    attempt.cancel();
+                }
+            }
+        }
+    }
+}
+
+impl std::fmt::Debug for JobQueue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let state = self.lock();
+        f.debug_struct("JobQueue")
+            .field("max_concurrent_jobs", &state.max_concurrent_jobs)
+            .field("max_concurrent_encodes", &state.max_concurrent_encodes)
+            .field("preemption_policy", &state.preemption_policy)
+            .field("running_jobs", &state.running_jobs)
+            .field("queued_jobs", &state.pending.len())
+            .finish()
+    }
+}
+
+/// Number of encoder processes a job runs at once
+fn encodes_of<B, S>(processor: &VideoProcessor<B, S>) -> usize
+where
+    B: VideoProcessingBackend + Default,
+    S: VideoValidatable + Default,
+{
+    let profiles = processor.profile_count().max(1);
+    match processor.context().max_concurrent_profiles() {
+        Some(limit) => profiles.min(limit),
+        None => profiles,
+    }
+}
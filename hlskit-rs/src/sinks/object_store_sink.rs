@@ -0,0 +1,178 @@
+// SPDX-License-Identifier: LGPL-3.0-only
+/*
+ * Copyright © 2025 The HlsKit Project
+ *
+ * This software is licensed under the GNU Lesser General Public License v3.0 (LGPLv3).
+ * All contributions adhere to the LGPLv3 and the HlsKit Contributor License Agreement (CLA).
+ * A copy of the LGPLv3 can be found at https://www.gnu.org/licenses/lgpl-3.0.html
+ *
+ * HlsKit Contributor License Agreement
+ *
+ * By contributing to or modifying HlsKit, you agree to the following terms:
+ *
+ * 1. Collective Ownership:
+ * The HlsKit project incorporates original code and all contributions as a collective work,
+ * licensed under LGPLv3. Once submitted, contributions become part of the shared HlsKit
+ * ecosystem and cannot be reclaimed, reassigned, or withdrawn. Contributions to your own
+ * forks remain yours unless submitted here, at which point they join this collective whole under LGPLv3.
+ *
+ * 2. Definition of Contribution:
+ * You are considered a contributor if you modify the library in any form (including forks,
+ * wrappers, libraries, or extensions that alter its behavior), whether or not you submit
+ * your changes directly to this repository. All such modifications are part of the broader
+ * HlsKit ecosystem and are subject to this CLA.
+ *
+ * 3. Distribution of Modifications:
+ * If you distribute a modified version of HlsKit, you must license your modifications under
+ * LGPLv3 (with source code available as required by the license) and ensure they are
+ * adoptable by the HlsKit ecosystem (publicly available and compatible).
+ *
+ * 4. Networked Use of Modifications:
+ * If you use a modified version of HlsKit in a networked application, you must provide the
+ * source code of your modifications under LGPLv3 and notify the HlsKit project
+ * (e.g., via email to [higashikataengels@icloud.com]). This does not apply to the use of
+ * the unmodified library in proprietary software, which remains permissible under LGPLv3.
+ *
+ * 5. Scope:
+ * These terms apply to all contributions and modifications derived from the HlsKit project.
+ * The use of the unmodified library in proprietary software is governed solely by the LGPLv3.
+ */
+
+use std::sync::Arc;
+
+use object_store::{
+    path::Path as ObjectPath, Attribute, Attributes, ObjectStore, PutMultipartOptions, PutOptions,
+    PutPayload, WriteMultipart,
+};
+
+use crate::{
+    models::hls_artifact::{HlsArtifact, HlsArtifactKind},
+    tools::hlskit_error::HlsKitError,
+    traits::output_sink::OutputSink,
+};
+
+/// Writes playlists and segments to any `object_store` backend (GCS, Azure Blob, S3-compatible, local)
+#[derive(Debug, Clone)]
+pub struct ObjectStoreSink {
+    store: Arc<dyn ObjectStore>,
+    prefix: String,
+    playlist_cache_control: Option<String>,
+    segment_cache_control: Option<String>,
+    multipart_threshold: usize,
+    multipart_part_size: usize,
+}
+
+impl ObjectStoreSink {
+    pub fn new(store: Arc<dyn ObjectStore>) -> Self {
+        Self {
+            store,
+            prefix: String::new(),
+            playlist_cache_control: None,
+            segment_cache_control: None,
+            multipart_threshold: 16 * 1024 * 1024,
+            multipart_part_size: 8 * 1024 * 1024,
+        }
+    }
+
+    pub fn with_prefix(mut self, prefix: &str) -> Self {
+        self.prefix = prefix.trim_matches('/').to_string();
+        self
+    }
+
+    pub fn with_playlist_cache_control(mut self, cache_control: &str) -> Self {
+        self.playlist_cache_control = Some(cache_control.to_string());
+        self
+    }
+
+    pub fn with_segment_cache_control(mut self, cache_control: &str) -> Self {
+        self.segment_cache_control = Some(cache_control.to_string());
+        self
+    }
+
+    /// Objects larger than `bytes` are written with a multipart upload
+    pub fn with_multipart_threshold(mut self, bytes: usize) -> Self {
+        self.multipart_threshold = bytes;
+        self
+    }
+
+    pub fn with_multipart_part_size(mut self, bytes: usize) -> Self {
+        self.multipart_part_size = bytes.max(1);
+        self
+    }
+
+    fn object_path(&self, name: &str) -> ObjectPath {
+        if self.prefix.is_empty() {
+            ObjectPath::from(name)
+        } else {
+            ObjectPath::from(format!("{}/{}", self.prefix, name))
+        }
+    }
+
+    fn attributes(&self, artifact: &HlsArtifact) -> Attributes {
+        let mut attributes = Attributes::new();
+        attributes.insert(
+            Attribute::ContentType,
+            artifact.content_type().to_string().into(),
+        );
+
+        let cache_control = match artifact.kind {
+            HlsArtifactKind::Segment => &self.segment_cache_control,
+            HlsArtifactKind::MasterPlaylist | HlsArtifactKind::MediaPlaylist => {
+                &self.playlist_cache_control
+            }
+        };
+
+        if let Some(cache_control) = cache_control {
+            attributes.insert(Attribute::CacheControl, cache_control.clone().into());
+        }
+
+        attributes
+    }
+}
+
+impl OutputSink for ObjectStoreSink {
+    async fn write_artifact(&self, artifact: HlsArtifact) -> Result<(), HlsKitError> {
+        let path = self.object_path(&artifact.name);
+        let attributes = self.attributes(&artifact);
+
+        if artifact.data.len() > self.multipart_threshold {
+            let upload = self
+                .store
+                .put_multipart_opts(
+                    &path,
+                    PutMultipartOptions {
+                        attributes,
+                        ..Default::default()
+                    },
+                )
+                .await
+                .map_err(|e| sink_error(&path, e))?;
+
+            let mut writer = WriteMultipart::new_with_chunk_size(upload, self.multipart_part_size);
+            writer.put(artifact.data);
+            writer.finish().await.map_err(|e| sink_error(&path, e))?;
+        } else {
+            self.store
+                .put_opts(
+                    &path,
+                    PutPayload::from(artifact.data),
+                    PutOptions {
+                        attributes,
+                        ..Default::default()
+                    },
+                )
+                .await
+                .map_err(|e| sink_error(&path, e))?;
+        }
+
+        Ok(())
+    }
+}
+
+fn sink_error(path: &ObjectPath, error: object_store::Error) -> HlsKitError {
+    tracing::error!("Failed to write '{}' to object store: {}", path, error);
+
+    HlsKitError::SinkError {
+        error: format!("Object store write of '{path}' failed: {error}"),
+    }
+}
@@ -0,0 +1,260 @@
+// SPDX-License-Identifier: LGPL-3.0-only
+/*
+ * Copyright © 2025 The HlsKit Project
+ *
+ * This software is licensed under the GNU Lesser General Public License v3.0 (LGPLv3).
+ * All contributions adhere to the LGPLv3 and the HlsKit Contributor License Agreement (CLA).
+ * A copy of the LGPLv3 can be found at https://www.gnu.org/licenses/lgpl-3.0.html
+ *
+ * HlsKit Contributor License Agreement
+ *
+ * By contributing to or modifying HlsKit, you agree to the following terms:
+ *
+ * 1. Collective Ownership:
+ * The HlsKit project incorporates original code and all contributions as a collective work,
+ * licensed under LGPLv3. Once submitted, contributions become part of the shared HlsKit
+ * ecosystem and cannot be reclaimed, reassigned, or withdrawn. Contributions to your own
+ * forks remain yours unless submitted here, at which point they join this collective whole under LGPLv3.
+ *
+ * 2. Definition of Contribution:
+ * You are considered a contributor if you modify the library in any form (including forks,
+ * wrappers, libraries, or extensions that alter its behavior), whether or not you submit
+ * your changes directly to this repository. All such modifications are part of the broader
+ * HlsKit ecosystem and are subject to this CLA.
+ *
+ * 3. Distribution of Modifications:
+ * If you distribute a modified version of HlsKit, you must license your modifications under
+ * LGPLv3 (with source code available as required by the license) and ensure they are
+ * adoptable by the HlsKit ecosystem (publicly available and compatible).
+ *
+ * 4. Networked Use of Modifications:
+ * If you use a modified version of HlsKit in a networked application, you must provide the
+ * source code of your modifications under LGPLv3 and notify the HlsKit project
+ * (e.g., via email to [higashikataengels@icloud.com]). This does not apply to the use of
+ * the unmodified library in proprietary software, which remains permissible under LGPLv3.
+ *
+ * 5. Scope:
+ * These terms apply to all contributions and modifications derived from the HlsKit project.
+ * The use of the unmodified library in proprietary software is governed solely by the LGPLv3.
+ */
+
+use aws_sdk_s3::{
+    primitives::ByteStream,
+    types::{CompletedMultipartUpload, CompletedPart},
+    Client,
+};
+use futures::{stream, StreamExt, TryStreamExt};
+
+use crate::{
+    models::hls_artifact::{HlsArtifact, HlsArtifactKind},
+    tools::hlskit_error::HlsKitError,
+    traits::output_sink::OutputSink,
+};
+
+const MIN_MULTIPART_PART_SIZE: usize = 5 * 1024 * 1024;
+
+/// Uploads playlists and segments to an S3 bucket under an optional key prefix
+#[derive(Debug, Clone)]
+pub struct S3Sink {
+    client: Client,
+    bucket: String,
+    prefix: String,
+    playlist_cache_control: Option<String>,
+    segment_cache_control: Option<String>,
+    multipart_threshold: usize,
+    multipart_part_size: usize,
+    multipart_concurrency: usize,
+}
+
+impl S3Sink {
+    pub fn new(client: Client, bucket: &str) -> Self {
+        Self {
+            client,
+            bucket: bucket.to_string(),
+            prefix: String::new(),
+            playlist_cache_control: None,
+            segment_cache_control: None,
+            multipart_threshold: 16 * 1024 * 1024,
+            multipart_part_size: 8 * 1024 * 1024,
+            multipart_concurrency: 4,
+        }
+    }
+
+    /// Builds the client from `config`, e.g. one `aws_config::load_from_env` shared with a
+    /// `KmsKeyProvider`
+    pub fn from_config(config: &aws_config::SdkConfig, bucket: &str) -> Self {
+        Self::new(Client::new(config), bucket)
+    }
+
+    pub fn with_prefix(mut self, prefix: &str) -> Self {
+        self.prefix = prefix.trim_matches('/').to_string();
+        self
+    }
+
+    pub fn with_playlist_cache_control(mut self, cache_control: &str) -> Self {
+        self.playlist_cache_control = Some(cache_control.to_string());
+        self
+    }
+
+    pub fn with_segment_cache_control(mut self, cache_control: &str) -> Self {
+        self.segment_cache_control = Some(cache_control.to_string());
+        self
+    }
+
+    /// Objects larger than `bytes` are uploaded with a parallel multipart upload
+    pub fn with_multipart_threshold(mut self, bytes: usize) -> Self {
+        self.multipart_threshold = bytes;
+        self
+    }
+
+    /// Part size for multipart uploads, S3 requires at least 5 MiB
+    pub fn with_multipart_part_size(mut self, bytes: usize) -> Self {
+        self.multipart_part_size = bytes.max(MIN_MULTIPART_PART_SIZE);
+        self
+    }
+
+    pub fn with_multipart_concurrency(mut self, concurrency: usize) -> Self {
+        self.multipart_concurrency = concurrency.max(1);
+        self
+    }
+
+    fn object_key(&self, name: &str) -> String {
+        if self.prefix.is_empty() {
+            name.to_string()
+        } else {
+            format!("{}/{}", self.prefix, name)
+        }
+    }
+
+    fn cache_control(&self, kind: HlsArtifactKind) -> Option<String> {
+        match kind {
+            HlsArtifactKind::Segment => self.segment_cache_control.clone(),
+            HlsArtifactKind::MasterPlaylist | HlsArtifactKind::MediaPlaylist => {
+                self.playlist_cache_control.clone()
+            }
+        }
+    }
+
+    async fn put_object(&self, key: &str, artifact: HlsArtifact) -> Result<(), HlsKitError> {
+        self.client
+            .put_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .content_type(artifact.content_type())
+            .set_cache_control(self.cache_control(artifact.kind))
+            .body(ByteStream::from(artifact.data))
+            .send()
+            .await
+            .map_err(|e| sink_error(key, e))?;
+
+        Ok(())
+    }
+
+    async fn put_multipart_object(
+        &self,
+        key: &str,
+        artifact: HlsArtifact,
+    ) -> Result<(), HlsKitError> {
+        let upload = self
+            .client
+            .create_multipart_upload()
+            .bucket(&self.bucket)
+            .key(key)
+            .content_type(artifact.content_type())
+            .set_cache_control(self.cache_control(artifact.kind))
+            .send()
+            .await
+            .map_err(|e| sink_error(key, e))?;
+
+        let upload_id = upload.upload_id().unwrap_or_default().to_string();
+
+        let chunks = (0..artifact.data.len())
+            .step_by(self.multipart_part_size)
+            .map(|start| {
+                let end = (start + self.multipart_part_size).min(artifact.data.len());
+                artifact.data.slice(start..end)
+            });
+
+        let parts = stream::iter(chunks.enumerate())
+            .map(|(index, chunk)| {
+                let part_number = index as i32 + 1;
+                let upload_id = upload_id.as_str();
+                async move {
+                    let part = self
+                        .client
+                        .upload_part()
+                        .bucket(&self.bucket)
+                        .key(key)
+                        .upload_id(upload_id)
+                        .part_number(part_number)
+                        .body(ByteStream::from(chunk))
+                        .send()
+                        .await
+                        .map_err(|e| sink_error(key, e))?;
+
+                    Ok::<_, HlsKitError>(
+                        CompletedPart::builder()
+                            .part_number(part_number)
+                            .set_e_tag(part.e_tag().map(str::to_string))
+                            .build(),
+                    )
+                }
+            })
+            .buffer_unordered(self.multipart_concurrency)
+            .try_collect::<Vec<_>>()
+            .await;
+
+        let mut parts = match parts {
+            Ok(parts) => parts,
+            Err(e) => {
+                let _ = self
+                    .client
+                    .abort_multipart_upload()
+                    .bucket(&self.bucket)
+                    .key(key)
+                    .upload_id(&upload_id)
+                    .send()
+                    .await;
+                return Err(e);
+            }
+        };
+
+        parts.sort_by_key(|part| part.part_number());
+
+        self.client
+            .complete_multipart_upload()
+            .bucket(&self.bucket)
+            .key(key)
+            .upload_id(&upload_id)
+            .multipart_upload(
+                CompletedMultipartUpload::builder()
+                    .set_parts(Some(parts))
+                    .build(),
+            )
+            .send()
+            .await
+            .map_err(|e| sink_error(key, e))?;
+
+        Ok(())
+    }
+}
+
+impl OutputSink for S3Sink {
+    async fn write_artifact(&self, artifact: HlsArtifact) -> Result<(), HlsKitError> {
+        let key = self.object_key(&artifact.name);
+
+        if artifact.data.len() > self.multipart_threshold {
+            self.put_multipart_object(&key, artifact).await
+        } else {
+            self.put_object(&key, artifact).await
+        }
+    }
+}
+
+fn sink_error<E: std::fmt::Debug>(key: &str, error: E) -> HlsKitError {
+    tracing::error!("Failed to upload '{}' to S3: {:?}", key, error);
+
+    HlsKitError::SinkError {
+        error: format!("S3 upload of '{key}' failed: {error:?}"),
+    }
+}
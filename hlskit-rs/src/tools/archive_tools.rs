@@ -0,0 +1,102 @@
+// SPDX-License-Identifier: LGPL-3.0-only
+/*
+ * Copyright © 2025 The HlsKit Project
+ *
+ * This software is licensed under the GNU Lesser General Public License v3.0 (LGPLv3).
+ * All contributions adhere to the LGPLv3 and the HlsKit Contributor License Agreement (CLA).
+ * A copy of the LGPLv3 can be found at https://www.gnu.org/licenses/lgpl-3.0.html
+ *
+ * HlsKit Contributor License Agreement
+ *
+ * By contributing to or modifying HlsKit, you agree to the following terms:
+ *
+ * 1. Collective Ownership:
+ * The HlsKit project incorporates original code and all contributions as a collective work,
+ * licensed under LGPLv3. Once submitted, contributions become part of the shared HlsKit
+ * ecosystem and cannot be reclaimed, reassigned, or withdrawn. Contributions to your own
+ * forks remain yours unless submitted here, at which point they join this collective whole under LGPLv3.
+ *
+ * 2. Definition of Contribution:
+ * You are considered a contributor if you modify the library in any form (including forks,
+ * wrappers, libraries, or extensions that alter its behavior), whether or not you submit
+ * your changes directly to this repository. All such modifications are part of the broader
+ * HlsKit ecosystem and are subject to this CLA.
+ *
+ * 3. Distribution of Modifications:
+ * If you distribute a modified version of HlsKit, you must license your modifications under
+ * LGPLv3 (with source code available as required by the license) and ensure they are
+ * adoptable by the HlsKit ecosystem (publicly available and compatible).
+ *
+ * 4. Networked Use of Modifications:
+ * If you use a modified version of HlsKit in a networked application, you must provide the
+ * source code of your modifications under LGPLv3 and notify the HlsKit project
+ * (e.g., via email to [higashikataengels@icloud.com]). This does not apply to the use of
+ * the unmodified library in proprietary software, which remains permissible under LGPLv3.
+ *
+ * 5. Scope:
+ * These terms apply to all contributions and modifications derived from the HlsKit project.
+ * The use of the unmodified library in proprietary software is governed solely by the LGPLv3.
+ */
+
+use std::io::{Cursor, Seek, Write};
+
+use zip::{write::SimpleFileOptions, CompressionMethod, ZipWriter};
+
+use crate::{models::hls_video::HlsVideo, tools::hlskit_error::HlsKitError};
+
+impl HlsVideo {
+    /// Writes the whole asset as a tar archive into `writer`
+    pub fn write_tar<W: Write>(&self, writer: W) -> Result<W, HlsKitError> {
+        let mut builder = tar::Builder::new(writer);
+
+        for (path, data) in self.files() {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(data.len() as u64);
+            header.set_mode(0o644);
+            header.set_mtime(0);
+            header.set_cksum();
+            builder.append_data(&mut header, path, data)?;
+        }
+
+        Ok(builder.into_inner()?)
+    }
+
+    /// Writes the whole asset as a zip archive into `writer`. Segments are stored as-is since
+    /// they are already compressed, playlists are deflated.
+    pub fn write_zip<W: Write + Seek>(&self, writer: W) -> Result<W, HlsKitError> {
+        let mut zip = ZipWriter::new(writer);
+
+        for (path, data) in self.files() {
+            let method = if path.ends_with(".m3u8") {
+                CompressionMethod::Deflated
+            } else {
+                CompressionMethod::Stored
+            };
+
+            zip.start_file(
+                path,
+                SimpleFileOptions::default().compression_method(method),
+            )
+            .map_err(archive_error)?;
+            zip.write_all(data)?;
+        }
+
+        zip.finish().map_err(archive_error)
+    }
+
+    /// Packs the asset into an in-memory tar archive
+    pub fn into_tar(self) -> Result<Vec<u8>, HlsKitError> {
+        self.write_tar(Vec::new())
+    }
+
+    /// Packs the asset into an in-memory zip archive
+    pub fn into_zip(self) -> Result<Vec<u8>, HlsKitError> {
+        Ok(self.write_zip(Cursor::new(Vec::new()))?.into_inner())
+    }
+}
+
+fn archive_error(error: zip::result::ZipError) -> HlsKitError {
+    HlsKitError::ArchiveError {
+        error: error.to_string(),
+    }
+}
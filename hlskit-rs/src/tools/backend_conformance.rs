@@ -0,0 +1,379 @@
+// SPDX-License-Identifier: LGPL-3.0-only
+/*
+ * Copyright © 2025 The HlsKit Project
+ *
+ * This software is licensed under the GNU Lesser General Public License v3.0 (LGPLv3).
+ * All contributions adhere to the LGPLv3 and the HlsKit Contributor License Agreement (CLA).
+ * A copy of the LGPLv3 can be found at https://www.gnu.org/licenses/lgpl-3.0.html
+ *
+ * HlsKit Contributor License Agreement
+ *
+ * By contributing to or modifying HlsKit, you agree to the following terms:
+ *
+ * 1. Collective Ownership:
+ * The HlsKit project incorporates original code and all contributions as a collective work,
+ * licensed under LGPLv3. Once submitted, contributions become part of the shared HlsKit
+ * ecosystem and cannot be reclaimed, reassigned, or withdrawn. Contributions to your own
+ * forks remain yours unless submitted here, at which point they join this collective whole under LGPLv3.
+ *
+ * 2. Definition of Contribution:
+ * You are considered a contributor if you modify the library in any form (including forks,
+ * wrappers, libraries, or extensions that alter its behavior), whether or not you submit
+ * your changes directly to this repository. All such modifications are part of the broader
+ * HlsKit ecosystem and are subject to this CLA.
+ *
+ * 3. Distribution of Modifications:
+ * If you distribute a modified version of HlsKit, you must license your modifications under
+ * LGPLv3 (with source code available as required by the license) and ensure they are
+ * adoptable by the HlsKit ecosystem (publicly available and compatible).
+ *
+ * 4. Networked Use of Modifications:
+ * If you use a modified version of HlsKit in a networked application, you must provide the
+ * source code of your modifications under LGPLv3 and notify the HlsKit project
+ * (e.g., via email to [higashikataengels@icloud.com]). This does not apply to the use of
+ * the unmodified library in proprietary software, which remains permissible under LGPLv3.
+ *
+ * 5. Scope:
+ * These terms apply to all contributions and modifications derived from the HlsKit project.
+ * The use of the unmodified library in proprietary software is governed solely by the LGPLv3.
+ */
+
+use std::path::Path;
+
+use crate::{
+    models::{
+        conformance_report::{ConformanceCase, ConformanceOutcome, ConformanceReport},
+        hls_video::HlsVideo,
+        hls_video_processing_settings::{EncodeMode, HlsVideoProcessingSettings},
+        playlist::MediaPlaylist,
+    },
+    tools::{
+        hlskit_error::HlsKitError,
+        internals::pipeline::ProcessingJob,
+        m3u8_tools::{parse_master, parse_media},
+        processing_context::ProcessingContext,
+    },
+    traits::video_processing_backend::VideoProcessingBackend,
+    VideoInputType, VideoProcessorEncryptionSettings,
+};
+
+/// Key URI written into the playlists of the `encrypted` case
+const CONFORMANCE_KEY_URL: &str = "https://conformance.hlskit.invalid/key";
+
+/// Largest gap between the durations of two backends' renditions, as a share of the longest
+const DURATION_TOLERANCE: f64 = 0.05;
+
+/// A standard battery for `VideoProcessingBackend` implementations: the sample input is
+/// encoded through a fixed ladder in the clear and with AES-128, and every output is checked
+/// for the structure HlsKit relies on. `compare` also checks the outputs match those of a
+/// reference backend, so third-party backends can verify they are drop-in replacements
+#[derive(Debug, Clone)]
+pub struct BackendConformance {
+    input: VideoInputType,
+    ladder: Vec<HlsVideoProcessingSettings>,
+}
+
+impl BackendConformance {
+    /// Runs the battery on `input`, a short clip with audio, through 360p and 240p renditions
+    pub fn new(input: VideoInputType) -> Self {
+        Self {
+            input,
+            ladder: vec![
+                HlsVideoProcessingSettings::p360(),
+                HlsVideoProcessingSettings::p240(),
+            ],
+        }
+    }
+
+    pub fn with_ladder(mut self, ladder: Vec<HlsVideoProcessingSettings>) -> Self {
+        self.ladder = ladder;
+        self
+    }
+
+    /// Checks the structure of every output of `backend`
+    pub async fn run<B: VideoProcessingBackend>(&self, backend: &B) -> ConformanceReport {
+        let mut cases = Vec::new();
+        for encrypted in [false, true] {
+            let outcome = match self.encode(backend, encrypted).await {
+                Ok(video) => outcome_of(self.check_structure(&video, encrypted)),
+                Err(e) => failure_outcome(e),
+            };
+            cases.push(case(encrypted, outcome));
+        }
+
+        ConformanceReport {
+            backend: backend.name().to_string(),
+            cases,
+        }
+    }
+
+    /// Like `run`, also checking `candidate` produces the same renditions as `reference`
+    pub async fn compare<R, C>(&self, reference: &R, candidate: &C) -> ConformanceReport
+    where
+        R: VideoProcessingBackend,
+        C: VideoProcessingBackend,
+    {
+        let mut cases = Vec::new();
+        for encrypted in [false, true] {
+            let outcome = match (
+                self.encode(reference, encrypted).await,
+                self.encode(candidate, encrypted).await,
+            ) {
+                (_, Err(e)) => failure_outcome(e),
+                (Err(e), Ok(_)) => ConformanceOutcome::Unsupported(format!(
+                    "reference backend {} failed: {e}",
+                    reference.name()
+                )),
+                (Ok(expected), Ok(video)) => {
+                    let mut problems = self.check_structure(&video, encrypted);
+                    problems.extend(check_equivalence(&expected, &video));
+                    outcome_of(problems)
+                }
+            };
+            cases.push(case(encrypted, outcome));
+        }
+
+        ConformanceReport {
+            backend: candidate.name().to_string(),
+            cases,
+        }
+    }
+
+    async fn encode<B: VideoProcessingBackend>(
+        &self,
+        backend: &B,
+        encrypted: bool,
+    ) -> Result<HlsVideo, HlsKitError> {
+        // The staged key must outlive the job
+        let key_dir = tempfile::tempdir()?;
+        let encryption = match encrypted {
+            true => Some(stage_key(key_dir.path())?),
+            false => None,
+        };
+
+        ProcessingJob {
+            input: &self.input,
+            output_profiles: &self.ladder,
+            encryption: encryption.as_ref(),
+            backend,
+            context: &ProcessingContext::default(),
+        }
+        .process()
+        .await
+    }
+
+    fn check_structure(&self, video: &HlsVideo, encrypted: bool) -> Vec<String> {
+        let mut problems = Vec::new();
+
+        if video.resolutions.len() != self.ladder.len() {
+            problems.push(format!(
+                "{} renditions for {} profiles",
+                video.resolutions.len(),
+                self.ladder.len()
+            ));
+        }
+        for (resolution, profile) in video.resolutions.iter().zip(&self.ladder) {
+            if profile.encode_mode != EncodeMode::Passthrough
+                && resolution.resolution != profile.resolution
+            {
+                problems.push(format!(
+                    "{} is {:?} instead of {:?}",
+                    resolution.playlist_name, resolution.resolution, profile.resolution
+                ));
+            }
+        }
+
+        match parse_master(&String::from_utf8_lossy(&video.master_m3u8_data)) {
+            Ok(master) => {
+                if master.variants.len() != video.resolutions.len() {
+                    problems.push(format!(
+                        "master playlist lists {} variants for {} renditions",
+                        master.variants.len(),
+                        video.resolutions.len()
+                    ));
+                }
+                for variant in &master.variants {
+                    if !video
+                        .resolutions
+                        .iter()
+                        .any(|resolution| resolution.playlist_name == variant.uri)
+                    {
+                        problems.push(format!("master playlist lists unknown {}", variant.uri));
+                    }
+                }
+            }
+            Err(e) => problems.push(format!("master playlist: {e}")),
+        }
+
+        for resolution in &video.resolutions {
+            let name = &resolution.playlist_name;
+            let media = match media_of(video, name) {
+                Ok(media) => media,
+                Err(e) => {
+                    problems.push(format!("{name}: {e}"));
+                    continue;
+                }
+            };
+
+            if !media.end_list {
+                problems.push(format!("{name}: no #EXT-X-ENDLIST"));
+            }
+            if media.segments.is_empty() {
+                problems.push(format!("{name}: no segments"));
+            }
+
+            let uris = media
+                .map
+                .iter()
+                .map(|map| &map.uri)
+                .chain(media.segments.iter().map(|segment| &segment.uri));
+            for uri in uris {
+                if !resolution
+                    .segments
+                    .iter()
+                    .any(|segment| &segment.segment_name == uri)
+                {
+                    problems.push(format!("{name}: {uri} is missing"));
+                }
+            }
+
+            for segment in &media.segments {
+                let uri = &segment.uri;
+                match (&segment.key, encrypted) {
+                    (None, false) => {}
+                    (Some(key), true)
+                        if key.method == "AES-128"
+                            && key.uri.as_deref() == Some(CONFORMANCE_KEY_URL) => {}
+                    (None, true) => problems.push(format!("{name}: {uri} is not encrypted")),
+                    (Some(key), _) => problems.push(format!(
+                        "{name}: {uri} has an unexpected {} key at {:?}",
+                        key.method, key.uri
+                    )),
+                }
+            }
+        }
+
+        problems
+    }
+}
+
+/// Compares the renditions of two outputs of the same case: resolutions, segment container
+/// and duration, leaving encoder-specific segment boundaries aside
+fn check_equivalence(expected: &HlsVideo, video: &HlsVideo) -> Vec<String> {
+    let mut problems = Vec::new();
+
+    let resolutions = |video: &HlsVideo| -> Vec<(i32, i32)> {
+        video.resolutions.iter().map(|r| r.resolution).collect()
+    };
+    if resolutions(expected) != resolutions(video) {
+        problems.push(format!(
+            "renditions {:?} instead of the reference's {:?}",
+            resolutions(video),
+            resolutions(expected)
+        ));
+    }
+
+    for (reference, resolution) in expected.resolutions.iter().zip(&video.resolutions) {
+        let name = &resolution.playlist_name;
+        let (Ok(reference_media), Ok(media)) = (
+            media_of(expected, &reference.playlist_name),
+            media_of(video, name),
+        ) else {
+            continue;
+        };
+
+        if reference_media.map.is_some() != media.map.is_some() {
+            problems.push(format!(
+                "{name}: fMP4 initialization section differs from the reference"
+            ));
+        }
+        let extension = |media: &MediaPlaylist| {
+            media
+                .segments
+                .first()
+                .and_then(|segment| Path::new(&segment.uri).extension())
+                .map(|extension| extension.to_string_lossy().into_owned())
+        };
+        if extension(&reference_media) != extension(&media) {
+            problems.push(format!(
+                "{name}: {:?} segments instead of the reference's {:?}",
+                extension(&media),
+                extension(&reference_media)
+            ));
+        }
+
+        let duration = |media: &MediaPlaylist| -> f64 {
+            media.segments.iter().map(|segment| segment.duration).sum()
+        };
+        let (reference_duration, rendition_duration) =
+            (duration(&reference_media), duration(&media));
+        if (reference_duration - rendition_duration).abs()
+            > reference_duration.max(rendition_duration) * DURATION_TOLERANCE
+        {
+            problems.push(format!(
+                "{name}: lasts {rendition_duration:.3}s, the reference {reference_duration:.3}s"
+            ));
+        }
+    }
+
+    problems
+}
+
+fn media_of(video: &HlsVideo, playlist_name: &str) -> Result<MediaPlaylist, HlsKitError> {
+    let resolution = video
+        .resolutions
+        .iter()
+        .find(|resolution| resolution.playlist_name == playlist_name)
+        .ok_or_else(|| HlsKitError::FileNotFound {
+            file_path: playlist_name.to_string(),
+        })?;
+
+    parse_media(&String::from_utf8_lossy(&resolution.playlist_data))
+}
+
+/// Writes a random key and the ffmpeg key info file pointing to it into `dir`
+fn stage_key(dir: &Path) -> Result<VideoProcessorEncryptionSettings, HlsKitError> {
+    let mut key = [0; 16];
+    getrandom::fill(&mut key).map_err(|e| HlsKitError::EncryptionError {
+        error: format!("Failed to generate the conformance key: {e}"),
+    })?;
+
+    let key_path = dir.join("conformance.key");
+    let key_info_path = dir.join("conformance.keyinfo");
+    std::fs::write(&key_path, key)?;
+    std::fs::write(
+        &key_info_path,
+        format!("{CONFORMANCE_KEY_URL}\n{}\n", key_path.display()),
+    )?;
+
+    Ok(VideoProcessorEncryptionSettings {
+        encryption_key_url: CONFORMANCE_KEY_URL.to_string(),
+        encryption_key_path: key_info_path.to_string_lossy().into_owned(),
+        iv: None,
+    })
+}
+
+fn case(encrypted: bool, outcome: ConformanceOutcome) -> ConformanceCase {
+    ConformanceCase {
+        name: match encrypted {
+            true => "encrypted",
+            false => "clear",
+        }
+        .to_string(),
+        outcome,
+    }
+}
+
+fn outcome_of(problems: Vec<String>) -> ConformanceOutcome {
+    match problems.is_empty() {
+        true => ConformanceOutcome::Passed,
+        false => ConformanceOutcome::Failed(problems),
+    }
+}
+
+fn failure_outcome(error: HlsKitError) -> ConformanceOutcome {
+    match error {
+        HlsKitError::UnsupportedOperation { error } => ConformanceOutcome::Unsupported(error),
+        e => ConformanceOutcome::Failed(vec![e.to_string()]),
+    }
+}
@@ -0,0 +1,144 @@
+// SPDX-License-Identifier: LGPL-3.0-only
+/*
+ * Copyright © 2025 The HlsKit Project
+ *
+ * This software is licensed under the GNU Lesser General Public License v3.0 (LGPLv3).
+ * All contributions adhere to the LGPLv3 and the HlsKit Contributor License Agreement (CLA).
+ * A copy of the LGPLv3 can be found at https://www.gnu.org/licenses/lgpl-3.0.html
+ *
+ * HlsKit Contributor License Agreement
+ *
+ * By contributing to or modifying HlsKit, you agree to the following terms:
+ *
+ * 1. Collective Ownership:
+ * The HlsKit project incorporates original code and all contributions as a collective work,
+ * licensed under LGPLv3. Once submitted, contributions become part of the shared HlsKit
+ * ecosystem and cannot be reclaimed, reassigned, or withdrawn. Contributions to your own
+ * forks remain yours unless submitted here, at which point they join this collective whole under LGPLv3.
+ *
+ * 2. Definition of Contribution:
+ * You are considered a contributor if you modify the library in any form (including forks,
+ * wrappers, libraries, or extensions that alter its behavior), whether or not you submit
+ * your changes directly to this repository. All such modifications are part of the broader
+ * HlsKit ecosystem and are subject to this CLA.
+ *
+ * 3. Distribution of Modifications:
+ * If you distribute a modified version of HlsKit, you must license your modifications under
+ * LGPLv3 (with source code available as required by the license) and ensure they are
+ * adoptable by the HlsKit ecosystem (publicly available and compatible).
+ *
+ * 4. Networked Use of Modifications:
+ * If you use a modified version of HlsKit in a networked application, you must provide the
+ * source code of your modifications under LGPLv3 and notify the HlsKit project
+ * (e.g., via email to [higashikataengels@icloud.com]). This does not apply to the use of
+ * the unmodified library in proprietary software, which remains permissible under LGPLv3.
+ *
+ * 5. Scope:
+ * These terms apply to all contributions and modifications derived from the HlsKit project.
+ * The use of the unmodified library in proprietary software is governed solely by the LGPLv3.
+ */
+
+use std::{
+    ffi::OsStr,
+    path::{Path, PathBuf},
+};
+
+use tokio::process::Command;
+
+use crate::{models::encoder_version::EncoderVersion, tools::hlskit_error::HlsKitError};
+
+/// Environment variable overriding the ffmpeg binary
+pub const FFMPEG_PATH_ENV: &str = "HLSKIT_FFMPEG_PATH";
+/// Environment variable overriding the ffprobe binary
+pub const FFPROBE_PATH_ENV: &str = "HLSKIT_FFPROBE_PATH";
+/// Environment variable overriding the gst-launch binary
+pub const GST_LAUNCH_PATH_ENV: &str = "HLSKIT_GST_LAUNCH_PATH";
+
+/// Oldest ffmpeg release whose HLS muxer supports everything the backend asks of it
+pub const MIN_FFMPEG_VERSION: (u32, u32, u32) = (4, 0, 0);
+/// Oldest GStreamer release shipping `hlssink2`
+pub const MIN_GSTREAMER_VERSION: (u32, u32, u32) = (1, 14, 0);
+
+/// The binary named by `env_var` when it is set, `default` (looked up in PATH) otherwise
+pub fn resolve_binary(env_var: &str, default: &str) -> PathBuf {
+    std::env::var_os(env_var)
+        .filter(|path| !path.is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(default))
+}
+
+/// Runs `binary` with `args` and parses the version it prints, failing when the binary is
+/// missing or older than `minimum`
+pub async fn detect_version<I, S>(
+    binary: &Path,
+    args: I,
+    parse: fn(&str) -> Option<EncoderVersion>,
+    minimum: (u32, u32, u32),
+) -> Result<EncoderVersion, HlsKitError>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<OsStr>,
+{
+    let binary_name = binary.display().to_string();
+
+    let output = Command::new(binary)
+        .args(args)
+        .kill_on_drop(true)
+        .output()
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to run '{}': {}", binary_name, e);
+            HlsKitError::BinaryNotFound {
+                binary: binary_name.clone(),
+            }
+        })?;
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let version = parse(&stdout).ok_or_else(|| HlsKitError::CommandExecutionError {
+        error: format!("Could not read the version of {binary_name}"),
+    })?;
+
+    if !version.is_at_least(minimum) {
+        return Err(HlsKitError::UnsupportedBinaryVersion {
+            binary: binary_name,
+            found: version.raw,
+            required: format!("{}.{}.{}", minimum.0, minimum.1, minimum.2),
+        });
+    }
+
+    Ok(version)
+}
+
+/// Parses `ffmpeg -version`, e.g. `ffmpeg version 6.1.1-3ubuntu5 Copyright ...`
+pub fn parse_ffmpeg_version(output: &str) -> Option<EncoderVersion> {
+    let line = output.lines().find(|line| line.contains(" version "))?;
+    let version = line.split(" version ").nth(1)?.split_whitespace().next()?;
+
+    Some(EncoderVersion {
+        raw: line.trim().to_string(),
+        number: parse_version_number(version.trim_start_matches('n')),
+    })
+}
+
+/// Parses `gst-launch-1.0 --version`, whose second line reads `GStreamer 1.22.0`
+pub fn parse_gstreamer_version(output: &str) -> Option<EncoderVersion> {
+    let line = output
+        .lines()
+        .find(|line| line.trim_start().starts_with("GStreamer "))?;
+    let version = line.split_whitespace().nth(1)?;
+
+    Some(EncoderVersion {
+        raw: line.trim().to_string(),
+        number: parse_version_number(version),
+    })
+}
+
+/// Reads the leading `major.minor[.patch]` of a version string
+fn parse_version_number(version: &str) -> Option<(u32, u32, u32)> {
+    let mut parts = version.split(['.', '-', '+', '~']).map(str::parse::<u32>);
+    let major = parts.next()?.ok()?;
+    let minor = parts.next()?.ok()?;
+    let patch = parts.next().and_then(Result::ok).unwrap_or(0);
+
+    Some((major, minor, patch))
+}
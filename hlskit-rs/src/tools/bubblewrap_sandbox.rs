@@ -0,0 +1,138 @@
+// SPDX-License-Identifier: LGPL-3.0-only
+/*
+ * Copyright © 2025 The HlsKit Project
+ *
+ * This software is licensed under the GNU Lesser General Public License v3.0 (LGPLv3).
+ * All contributions adhere to the LGPLv3 and the HlsKit Contributor License Agreement (CLA).
+ * A copy of the LGPLv3 can be found at https://www.gnu.org/licenses/lgpl-3.0.html
+ *
+ * HlsKit Contributor License Agreement
+ *
+ * By contributing to or modifying HlsKit, you agree to the following terms:
+ *
+ * 1. Collective Ownership:
+ * The HlsKit project incorporates original code and all contributions as a collective work,
+ * licensed under LGPLv3. Once submitted, contributions become part of the shared HlsKit
+ * ecosystem and cannot be reclaimed, reassigned, or withdrawn. Contributions to your own
+ * forks remain yours unless submitted here, at which point they join this collective whole under LGPLv3.
+ *
+ * 2. Definition of Contribution:
+ * You are considered a contributor if you modify the library in any form (including forks,
+ * wrappers, libraries, or extensions that alter its behavior), whether or not you submit
+ * your changes directly to this repository. All such modifications are part of the broader
+ * HlsKit ecosystem and are subject to this CLA.
+ *
+ * 3. Distribution of Modifications:
+ * If you distribute a modified version of HlsKit, you must license your modifications under
+ * LGPLv3 (with source code available as required by the license) and ensure they are
+ * adoptable by the HlsKit ecosystem (publicly available and compatible).
+ *
+ * 4. Networked Use of Modifications:
+ * If you use a modified version of HlsKit in a networked application, you must provide the
+ * source code of your modifications under LGPLv3 and notify the HlsKit project
+ * (e.g., via email to [higashikataengels@icloud.com]). This does not apply to the use of
+ * the unmodified library in proprietary software, which remains permissible under LGPLv3.
+ *
+ * 5. Scope:
+ * These terms apply to all contributions and modifications derived from the HlsKit project.
+ * The use of the unmodified library in proprietary software is governed solely by the LGPLv3.
+ */
+
+use std::path::{Path, PathBuf};
+
+use crate::{
+    tools::hlskit_error::HlsKitError,
+    traits::sandbox_policy::{SandboxPolicy, SandboxScope},
+};
+
+/// System directories encoders need to load their binaries and shared libraries
+const SYSTEM_DIRS: [&str; 6] = ["/usr", "/bin", "/sbin", "/lib", "/lib64", "/etc"];
+
+/// Runs encoders under bubblewrap (`bwrap`) with no network and only the job's files visible
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BubblewrapSandbox {
+    binary: PathBuf,
+    share_network: bool,
+    read_only_binds: Vec<PathBuf>,
+}
+
+impl Default for BubblewrapSandbox {
+    fn default() -> Self {
+        Self {
+            binary: PathBuf::from("bwrap"),
+            share_network: false,
+            read_only_binds: Vec::new(),
+        }
+    }
+}
+
+impl BubblewrapSandbox {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_binary<P: AsRef<Path>>(mut self, binary: P) -> Self {
+        self.binary = binary.as_ref().to_path_buf();
+        self
+    }
+
+    /// Keeps network access, needed when inputs or keys are fetched over HTTP
+    pub fn with_network(mut self, share_network: bool) -> Self {
+        self.share_network = share_network;
+        self
+    }
+
+    /// Exposes an extra path read-only, e.g. a custom ffmpeg install
+    pub fn with_read_only_bind<P: AsRef<Path>>(mut self, path: P) -> Self {
+        self.read_only_binds.push(path.as_ref().to_path_buf());
+        self
+    }
+}
+
+impl SandboxPolicy for BubblewrapSandbox {
+    fn wrap(&self, command: Vec<String>, scope: &SandboxScope) -> Result<Vec<String>, HlsKitError> {
+        let mut args = vec![
+            self.binary.to_string_lossy().to_string(),
+            "--die-with-parent".to_string(),
+            "--new-session".to_string(),
+            "--unshare-all".to_string(),
+        ];
+
+        if self.share_network {
+            args.push("--share-net".to_string());
+        }
+
+        for dir in SYSTEM_DIRS {
+            args.extend([
+                "--ro-bind-try".to_string(),
+                dir.to_string(),
+                dir.to_string(),
+            ]);
+        }
+
+        args.extend(
+            ["--proc", "/proc", "--dev", "/dev", "--tmpfs", "/tmp"]
+                .iter()
+                .map(|arg| arg.to_string()),
+        );
+
+        for path in self.read_only_binds.iter().chain(&scope.readable) {
+            let path = absolute(path)?;
+            args.extend(["--ro-bind".to_string(), path.clone(), path]);
+        }
+
+        for path in &scope.writable {
+            let path = absolute(path)?;
+            args.extend(["--bind".to_string(), path.clone(), path]);
+        }
+
+        args.push("--".to_string());
+        args.extend(command);
+
+        Ok(args)
+    }
+}
+
+fn absolute(path: &Path) -> Result<String, HlsKitError> {
+    Ok(std::path::absolute(path)?.to_string_lossy().to_string())
+}
@@ -0,0 +1,86 @@
+// SPDX-License-Identifier: LGPL-3.0-only
+/*
+ * Copyright © 2025 The HlsKit Project
+ *
+ * This software is licensed under the GNU Lesser General Public License v3.0 (LGPLv3).
+ * All contributions adhere to the LGPLv3 and the HlsKit Contributor License Agreement (CLA).
+ * A copy of the LGPLv3 can be found at https://www.gnu.org/licenses/lgpl-3.0.html
+ *
+ * HlsKit Contributor License Agreement
+ *
+ * By contributing to or modifying HlsKit, you agree to the following terms:
+ *
+ * 1. Collective Ownership:
+ * The HlsKit project incorporates original code and all contributions as a collective work,
+ * licensed under LGPLv3. Once submitted, contributions become part of the shared HlsKit
+ * ecosystem and cannot be reclaimed, reassigned, or withdrawn. Contributions to your own
+ * forks remain yours unless submitted here, at which point they join this collective whole under LGPLv3.
+ *
+ * 2. Definition of Contribution:
+ * You are considered a contributor if you modify the library in any form (including forks,
+ * wrappers, libraries, or extensions that alter its behavior), whether or not you submit
+ * your changes directly to this repository. All such modifications are part of the broader
+ * HlsKit ecosystem and are subject to this CLA.
+ *
+ * 3. Distribution of Modifications:
+ * If you distribute a modified version of HlsKit, you must license your modifications under
+ * LGPLv3 (with source code available as required by the license) and ensure they are
+ * adoptable by the HlsKit ecosystem (publicly available and compatible).
+ *
+ * 4. Networked Use of Modifications:
+ * If you use a modified version of HlsKit in a networked application, you must provide the
+ * source code of your modifications under LGPLv3 and notify the HlsKit project
+ * (e.g., via email to [higashikataengels@icloud.com]). This does not apply to the use of
+ * the unmodified library in proprietary software, which remains permissible under LGPLv3.
+ *
+ * 5. Scope:
+ * These terms apply to all contributions and modifications derived from the HlsKit project.
+ * The use of the unmodified library in proprietary software is governed solely by the LGPLv3.
+ */
+
+use std::path::{Path, PathBuf};
+
+use crate::tools::{
+    concat_tools::join_media_playlists,
+    hlskit_error::HlsKitError,
+    m3u8_tools::parse_media,
+    rewrite_tools::rewrite_playlist_uris,
+    segment_tools::{file_name_of, segment_path_for},
+};
+
+/// Moves the segments of the chunk renditions at `chunk_playlists` next to `playlist_path`,
+/// numbered in order with `segment_pattern` as if a single run had written them, and writes
+/// the joined playlist to `playlist_path`. Chunks are separated by `#EXT-X-DISCONTINUITY`,
+/// each one starting its timestamps over
+pub(crate) async fn join_chunk_renditions(
+    chunk_playlists: &[PathBuf],
+    segment_pattern: &str,
+    playlist_path: &Path,
+) -> Result<(), HlsKitError> {
+    let mut playlists = Vec::with_capacity(chunk_playlists.len());
+    let mut segment_index = 0;
+
+    for chunk_playlist in chunk_playlists {
+        let playlist = tokio::fs::read_to_string(chunk_playlist).await?;
+        let chunk_dir = chunk_playlist.parent().unwrap_or(Path::new(""));
+
+        let mut renames = Vec::new();
+        for segment in parse_media(&playlist)?.segments {
+            let segment_path = segment_path_for(segment_pattern, segment_index);
+            tokio::fs::rename(chunk_dir.join(&segment.uri), &segment_path).await?;
+            renames.push((segment.uri, file_name_of(&segment_path)));
+            segment_index += 1;
+        }
+
+        playlists.push(rewrite_playlist_uris(&playlist, |uri| {
+            renames
+                .iter()
+                .find(|(old, _)| old == uri)
+                .map_or_else(|| uri.to_string(), |(_, new)| new.clone())
+        }));
+    }
+
+    tokio::fs::write(playlist_path, join_media_playlists(&playlists, true)?).await?;
+
+    Ok(())
+}
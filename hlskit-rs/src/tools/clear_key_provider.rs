@@ -0,0 +1,147 @@
+// SPDX-License-Identifier: LGPL-3.0-only
+/*
+ * Copyright © 2025 The HlsKit Project
+ *
+ * This software is licensed under the GNU Lesser General Public License v3.0 (LGPLv3).
+ * All contributions adhere to the LGPLv3 and the HlsKit Contributor License Agreement (CLA).
+ * A copy of the LGPLv3 can be found at https://www.gnu.org/licenses/lgpl-3.0.html
+ *
+ * HlsKit Contributor License Agreement
+ *
+ * By contributing to or modifying HlsKit, you agree to the following terms:
+ *
+ * 1. Collective Ownership:
+ * The HlsKit project incorporates original code and all contributions as a collective work,
+ * licensed under LGPLv3. Once submitted, contributions become part of the shared HlsKit
+ * ecosystem and cannot be reclaimed, reassigned, or withdrawn. Contributions to your own
+ * forks remain yours unless submitted here, at which point they join this collective whole under LGPLv3.
+ *
+ * 2. Definition of Contribution:
+ * You are considered a contributor if you modify the library in any form (including forks,
+ * wrappers, libraries, or extensions that alter its behavior), whether or not you submit
+ * your changes directly to this repository. All such modifications are part of the broader
+ * HlsKit ecosystem and are subject to this CLA.
+ *
+ * 3. Distribution of Modifications:
+ * If you distribute a modified version of HlsKit, you must license your modifications under
+ * LGPLv3 (with source code available as required by the license) and ensure they are
+ * adoptable by the HlsKit ecosystem (publicly available and compatible).
+ *
+ * 4. Networked Use of Modifications:
+ * If you use a modified version of HlsKit in a networked application, you must provide the
+ * source code of your modifications under LGPLv3 and notify the HlsKit project
+ * (e.g., via email to [higashikataengels@icloud.com]). This does not apply to the use of
+ * the unmodified library in proprietary software, which remains permissible under LGPLv3.
+ *
+ * 5. Scope:
+ * These terms apply to all contributions and modifications derived from the HlsKit project.
+ * The use of the unmodified library in proprietary software is governed solely by the LGPLv3.
+ */
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+
+use crate::{
+    models::{
+        clear_key::{ClearKey, ClearKeyLicense},
+        job_id::JobId,
+        key_material::KeyMaterial,
+        secret::SecretKey,
+    },
+    tools::hlskit_error::HlsKitError,
+    traits::key_provider::{KeyFuture, KeyProvider},
+};
+
+/// Clear key mode for testing encrypted pipelines without a DRM vendor. Generates a random key
+/// per job and embeds it in the playlists as a `data:` URI, so players need no key server, and
+/// keeps every key for [`ClearKeyProvider::license`].
+///
+/// Clear keys offer no protection, never use them for real content.
+#[derive(Debug, Clone, Default)]
+pub struct ClearKeyProvider {
+    key_uri: Option<String>,
+    fixed_key: Option<SecretKey>,
+    keys: Arc<Mutex<HashMap<JobId, ClearKey>>>,
+}
+
+impl ClearKeyProvider {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Points the playlists to a test key server instead of embedding the key, `{job_id}` is
+    /// replaced with the job id
+    pub fn with_key_uri(mut self, key_uri: &str) -> Self {
+        self.key_uri = Some(key_uri.to_string());
+        self
+    }
+
+    /// Uses `key` for every job, for reproducible outputs
+    pub fn with_key(mut self, key: impl Into<SecretKey>) -> Self {
+        self.fixed_key = Some(key.into());
+        self
+    }
+
+    /// The key of `job_id`, once the job has fetched it
+    pub fn key(&self, job_id: JobId) -> Option<ClearKey> {
+        self.lock_keys().get(&job_id).cloned()
+    }
+
+    /// The clear key license of `job_id`, once the job has fetched its key
+    pub fn license(&self, job_id: JobId) -> Option<ClearKeyLicense> {
+        self.key(job_id)
+            .map(|key| ClearKeyLicense { keys: vec![key] })
+    }
+
+    fn lock_keys(&self) -> std::sync::MutexGuard<'_, HashMap<JobId, ClearKey>> {
+        self.keys.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn generate_key(&self, job_id: JobId) -> Result<KeyMaterial, HlsKitError> {
+        let key = match &self.fixed_key {
+            Some(key) => key.clone(),
+            None => random_key()?,
+        };
+        let mut kid = [0; 16];
+        fill_random(&mut kid)?;
+
+        let key_uri = match &self.key_uri {
+            Some(key_uri) => key_uri.replace("{job_id}", &job_id.0.to_string()),
+            None => format!("data:text/plain;base64,{}", STANDARD.encode(key.expose())),
+        };
+
+        self.lock_keys().insert(
+            job_id,
+            ClearKey {
+                kid,
+                key: key.clone(),
+            },
+        );
+
+        Ok(KeyMaterial::new(key, key_uri))
+    }
+}
+
+impl KeyProvider for ClearKeyProvider {
+    fn fetch_key(&self, job_id: JobId, _key_index: u32) -> KeyFuture<'_> {
+        let key = self.generate_key(job_id);
+        Box::pin(async move { key })
+    }
+}
+
+fn random_key() -> Result<SecretKey, HlsKitError> {
+    let mut key = SecretKey::default();
+    fill_random(key.expose_mut())?;
+
+    Ok(key)
+}
+
+fn fill_random(bytes: &mut [u8]) -> Result<(), HlsKitError> {
+    getrandom::fill(bytes).map_err(|e| HlsKitError::EncryptionError {
+        error: format!("Failed to generate a clear key: {e}"),
+    })
+}
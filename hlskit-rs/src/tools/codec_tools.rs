@@ -0,0 +1,230 @@
+// SPDX-License-Identifier: LGPL-3.0-only
+/*
+ * Copyright © 2025 The HlsKit Project
+ *
+ * This software is licensed under the GNU Lesser General Public License v3.0 (LGPLv3).
+ * All contributions adhere to the LGPLv3 and the HlsKit Contributor License Agreement (CLA).
+ * A copy of the LGPLv3 can be found at https://www.gnu.org/licenses/lgpl-3.0.html
+ *
+ * HlsKit Contributor License Agreement
+ *
+ * By contributing to or modifying HlsKit, you agree to the following terms:
+ *
+ * 1. Collective Ownership:
+ * The HlsKit project incorporates original code and all contributions as a collective work,
+ * licensed under LGPLv3. Once submitted, contributions become part of the shared HlsKit
+ * ecosystem and cannot be reclaimed, reassigned, or withdrawn. Contributions to your own
+ * forks remain yours unless submitted here, at which point they join this collective whole under LGPLv3.
+ *
+ * 2. Definition of Contribution:
+ * You are considered a contributor if you modify the library in any form (including forks,
+ * wrappers, libraries, or extensions that alter its behavior), whether or not you submit
+ * your changes directly to this repository. All such modifications are part of the broader
+ * HlsKit ecosystem and are subject to this CLA.
+ *
+ * 3. Distribution of Modifications:
+ * If you distribute a modified version of HlsKit, you must license your modifications under
+ * LGPLv3 (with source code available as required by the license) and ensure they are
+ * adoptable by the HlsKit ecosystem (publicly available and compatible).
+ *
+ * 4. Networked Use of Modifications:
+ * If you use a modified version of HlsKit in a networked application, you must provide the
+ * source code of your modifications under LGPLv3 and notify the HlsKit project
+ * (e.g., via email to [higashikataengels@icloud.com]). This does not apply to the use of
+ * the unmodified library in proprietary software, which remains permissible under LGPLv3.
+ *
+ * 5. Scope:
+ * These terms apply to all contributions and modifications derived from the HlsKit project.
+ * The use of the unmodified library in proprietary software is governed solely by the LGPLv3.
+ */
+
+use crate::{
+    models::{
+        dynamic_range::VideoRange,
+        hls_video_processing_settings::{
+            FfmpegVideoProcessingPreset, HlsVideoAudioCodec, HlsVideoProcessingSettings,
+        },
+    },
+    tools::processing_context::ProcessingContext,
+};
+
+/// Frame rate assumed when neither the profile nor the probed source gives one
+const DEFAULT_FRAME_RATE: f64 = 30.0;
+
+/// H.264 `level_idc` with the largest frame, in macroblocks, and macroblock rate it allows
+const H264_LEVELS: [(u8, u64, u64); 16] = [
+    (10, 99, 1_485),
+    (11, 396, 3_000),
+    (12, 396, 6_000),
+    (13, 396, 11_880),
+    (21, 792, 19_800),
+    (22, 1_620, 20_250),
+    (30, 1_620, 40_500),
+    (31, 3_600, 108_000),
+    (32, 5_120, 216_000),
+    (40, 8_192, 245_760),
+    (42, 8_704, 522_240),
+    (50, 22_080, 589_824),
+    (51, 36_864, 983_040),
+    (52, 36_864, 2_073_600),
+    (60, 139_264, 4_177_920),
+    (62, 139_264, 16_711_680),
+];
+
+/// HEVC `general_level_idc` with the largest picture, in luma samples, and luma sample rate it
+/// allows
+const HEVC_LEVELS: [(u8, u64, u64); 12] = [
+    (60, 122_880, 3_686_400),
+    (63, 245_760, 7_372_800),
+    (90, 552_960, 16_588_800),
+    (93, 983_040, 33_177_600),
+    (120, 2_228_224, 66_846_720),
+    (123, 2_228_224, 133_693_440),
+    (150, 8_912_896, 267_386_880),
+    (153, 8_912_896, 534_773_760),
+    (156, 8_912_896, 1_069_547_520),
+    (180, 35_651_584, 1_069_547_520),
+    (183, 35_651_584, 2_139_095_040),
+    (186, 35_651_584, 4_278_190_080),
+];
+
+/// AV1 `seq_level_idx` with the largest picture and display sample rate it allows
+const AV1_LEVELS: [(u8, u64, u64); 12] = [
+    (0, 147_456, 4_423_680),
+    (1, 278_784, 8_363_520),
+    (4, 665_856, 19_975_680),
+    (5, 1_065_024, 31_950_720),
+    (8, 2_359_296, 70_778_880),
+    (9, 2_359_296, 141_557_760),
+    (12, 8_912_896, 267_386_880),
+    (13, 8_912_896, 534_773_760),
+    (14, 8_912_896, 1_069_547_520),
+    (16, 35_651_584, 1_069_547_520),
+    (17, 35_651_584, 2_139_095_040),
+    (18, 35_651_584, 4_278_190_080),
+];
+
+/// The `CODECS` attribute of a rendition `encoder` wrote for `profile`, listing the audio codec
+/// when `has_audio`
+pub fn rendition_codecs(
+    encoder: &str,
+    profile: &HlsVideoProcessingSettings,
+    resolution: (i32, i32),
+    video_range: VideoRange,
+    has_audio: bool,
+    context: &ProcessingContext,
+) -> Option<String> {
+    let frame_rate = context
+        .source_adjustments()
+        .frame_rate_for(profile)
+        .map(|frame_rate| frame_rate.as_f64())
+        .or(context.source_frame_rate());
+    let video = video_codec_name(
+        encoder,
+        &profile.preset,
+        &profile.extra_args,
+        resolution,
+        frame_rate,
+        video_range,
+    )?;
+
+    match has_audio {
+        true => Some(format!("{video},{}", audio_codec_name(profile.audio_codec))),
+        false => Some(video),
+    }
+}
+
+/// RFC 6381 name of what `encoder` writes at `resolution` and `frame_rate`: H.264 High
+/// (Constrained Baseline for `ultrafast`), HEVC Main or Main 10, or AV1 Main, at the lowest
+/// level fitting the picture size and sample rate. The H.264 profile and level set through
+/// ffmpeg's `-profile:v` and `-level:v` in `extra_args` win. `None` for encoders HlsKit
+/// doesn't drive
+pub fn video_codec_name(
+    encoder: &str,
+    preset: &FfmpegVideoProcessingPreset,
+    extra_args: &[String],
+    resolution: (i32, i32),
+    frame_rate: Option<f64>,
+    video_range: VideoRange,
+) -> Option<String> {
+    let (width, height) = (resolution.0.max(1) as u64, resolution.1.max(1) as u64);
+    let frame_rate = frame_rate
+        .filter(|frame_rate| frame_rate.is_finite() && *frame_rate > 0.0)
+        .unwrap_or(DEFAULT_FRAME_RATE);
+    let samples = width * height;
+    let sample_rate = (samples as f64 * frame_rate).ceil() as u64;
+    let hdr = video_range != VideoRange::Sdr;
+
+    match encoder {
+        "libx264" | "x264enc" => {
+            let macroblocks = width.div_ceil(16) * height.div_ceil(16);
+            let macroblock_rate = (macroblocks as f64 * frame_rate).ceil() as u64;
+            // Only ffmpeg reads the extra arguments
+            let extra_arg = |name| {
+                (encoder == "libx264")
+                    .then(|| extra_arg(extra_args, name))
+                    .flatten()
+            };
+            let level = extra_arg("-level:v")
+                .and_then(parse_h264_level)
+                .unwrap_or_else(|| level_for(&H264_LEVELS, macroblocks, macroblock_rate));
+            // ultrafast turns off CABAC and 8x8 transforms, leaving x264 at Constrained Baseline
+            let profile = match (extra_arg("-profile:v"), preset) {
+                (Some("baseline"), _) | (None, FfmpegVideoProcessingPreset::UltraFast) => "42c0",
+                (Some("main"), _) => "4d40",
+                _ => "6400",
+            };
+            Some(format!("avc1.{profile}{level:02x}"))
+        }
+        "libx265" | "x265enc" => {
+            let level = level_for(&HEVC_LEVELS, samples, sample_rate);
+            let profile = if hdr { "2.4" } else { "1.6" };
+            Some(format!("hvc1.{profile}.L{level}.B0"))
+        }
+        "libsvtav1" | "svtav1enc" => {
+            let level = level_for(&AV1_LEVELS, samples, sample_rate);
+            let bit_depth = if hdr { 10 } else { 8 };
+            Some(format!("av01.0.{level:02}M.{bit_depth:02}"))
+        }
+        _ => None,
+    }
+}
+
+/// RFC 6381 name of `codec`, AAC being AAC-LC
+pub fn audio_codec_name(codec: HlsVideoAudioCodec) -> &'static str {
+    match codec {
+        HlsVideoAudioCodec::Aac => "mp4a.40.2",
+        HlsVideoAudioCodec::Mp3 => "mp4a.40.34",
+        HlsVideoAudioCodec::Vorbis => "vorbis",
+    }
+}
+
+/// The value following `name` in `extra_args`
+fn extra_arg<'a>(extra_args: &'a [String], name: &str) -> Option<&'a str> {
+    extra_args
+        .iter()
+        .position(|arg| arg == name)
+        .and_then(|index| extra_args.get(index + 1))
+        .map(String::as_str)
+}
+
+/// `level_idc` of an ffmpeg H.264 level, written `4.1` or `41`
+fn parse_h264_level(level: &str) -> Option<u8> {
+    match level.split_once('.') {
+        Some((major, minor)) => major
+            .parse::<u8>()
+            .ok()?
+            .checked_mul(10)?
+            .checked_add(minor.parse().ok()?),
+        None => level.parse().ok(),
+    }
+}
+
+/// The first level of `levels` allowing `size` and `rate`, the highest one otherwise
+fn level_for(levels: &[(u8, u64, u64)], size: u64, rate: u64) -> u8 {
+    levels
+        .iter()
+        .find(|(_, max_size, max_rate)| size <= *max_size && rate <= *max_rate)
+        .or(levels.last())
+        .map_or(0, |(level, _, _)| *level)
+}
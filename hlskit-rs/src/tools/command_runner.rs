@@ -40,42 +40,267 @@
 
 use std::process::Stdio;
 
-use tokio::process::Command;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+    process::Command,
+};
 
-use crate::tools::hlskit_error::HlsKitError;
+use crate::{
+    models::{resource_limits::ResourceLimits, resource_usage::ResourceUsage},
+    tools::{
+        hlskit_error::{CommandFailure, HlsKitError},
+        internals::usage_sampler::UsageSampler,
+        processing_context::ProcessingContext,
+    },
+};
+
+/// How much of a failed command's stderr is kept in the returned error
+const MAX_STDERR_LEN: usize = 4096;
 
 #[tracing::instrument]
 pub async fn run_command(command: &[String]) -> Result<(), HlsKitError> {
+    run_command_with_output_handler(command, |_| {}).await
+}
+
+/// Runs the command, handing every stdout line to `on_stdout_line` while the process executes
+pub async fn run_command_with_output_handler<F: FnMut(&str)>(
+    command: &[String],
+    on_stdout_line: F,
+) -> Result<(), HlsKitError> {
+    run_command_with_context(command, &ProcessingContext::default(), on_stdout_line).await?;
+    Ok(())
+}
+
+/// Runs the command under the constraints of the job `context`, killing the process as soon as
+/// the job is cancelled, and returns the resources it consumed
+#[tracing::instrument(skip(context, on_stdout_line))]
+pub async fn run_command_with_context<F: FnMut(&str)>(
+    command: &[String],
+    context: &ProcessingContext,
+    mut on_stdout_line: F,
+) -> Result<ResourceUsage, HlsKitError> {
+    if context.is_cancelled() {
+        return Err(HlsKitError::Cancelled);
+    }
+
+    let command = context.intercept_command(command).inspect_err(|e| {
+        tracing::error!("Command '{}' was rejected: {}", command.join(" "), e);
+    })?;
+    let command = context.sandbox_command(command)?;
+
     tracing::debug!("[DEBUG] Running command: {}", command.join(" "));
 
-    let process = Command::new(&command[0])
+    let mut process_command = Command::new(&command[0]);
+    process_command
         .args(&command[1..])
         .stdin(Stdio::piped())
         .stdout(Stdio::piped())
         .stderr(Stdio::piped())
-        .spawn()
-        .map_err(|e| {
-            tracing::error!("Failed to spawn command '{}': {}", command[0], e);
-
-            HlsKitError::CommandExecutionError {
-                error: e.to_string(),
-            }
-        })?;
+        .kill_on_drop(true);
+    apply_process_limits(&mut process_command, context.resource_limits());
 
-    let output = process.wait_with_output().await.map_err(|e| {
+    let mut process = process_command.spawn().map_err(|e| {
         tracing::error!("Failed to spawn command '{}': {}", command[0], e);
 
         HlsKitError::CommandExecutionError {
-            error: format!("Failed to capture GStreamer output: {e}"),
+            error: e.to_string(),
         }
     })?;
 
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        tracing::error!("Failed to spawn command '{}': {}", command[0], stderr);
+    if let Err(e) = join_cgroup(process.id(), context.resource_limits()) {
+        tracing::error!("Failed to move '{}' into its cgroup: {}", command[0], e);
+        let _ = process.start_kill();
+        let _ = process.wait().await;
         return Err(HlsKitError::CommandExecutionError {
-            error: format!("GStreamer failed: {stderr}"),
+            error: format!("Failed to move {} into its cgroup: {e}", command[0]),
         });
     }
-    Ok(())
+
+    let mut sampler = UsageSampler::new(process.id());
+
+    // Only inputs piped to the encoder are written to stdin, which is closed afterwards so
+    // the encoder doesn't wait for more
+    let stdin = process.stdin.take();
+    let stdin_input = context.stdin_input();
+    let write_stdin = async move {
+        if let (Some(mut stdin), Some(input)) = (stdin, stdin_input) {
+            match stdin.write_all(input).await {
+                // The encoder may exit before reading all of it, its status tells why
+                Err(e) if e.kind() != std::io::ErrorKind::BrokenPipe => return Err(e),
+                _ => {}
+            }
+        }
+        Ok::<_, std::io::Error>(())
+    };
+
+    let stdout = process.stdout.take();
+    let stderr = process.stderr.take();
+
+    let read_stdout = async {
+        if let Some(stdout) = stdout {
+            let mut lines = BufReader::new(stdout).lines();
+            while let Some(line) = lines.next_line().await? {
+                on_stdout_line(&line);
+            }
+        }
+        Ok::<_, std::io::Error>(())
+    };
+
+    // Drain stderr concurrently, otherwise a chatty encoder blocks on a full pipe
+    let read_stderr = async {
+        let mut buffer = Vec::new();
+        if let Some(mut stderr) = stderr {
+            stderr.read_to_end(&mut buffer).await?;
+        }
+        Ok::<_, std::io::Error>(buffer)
+    };
+
+    let outcome = tokio::select! {
+        outcome = async {
+            futures::join!(write_stdin, read_stdout, read_stderr, process.wait())
+        } => Some(outcome),
+        // Never finishes, it only samples while the process runs
+        _ = sampler.run() => None,
+        _ = context.cancelled() => None,
+        _ = context.disk_quota_exceeded() => None,
+    };
+
+    let Some((stdin_result, stdout_result, stderr_result, status)) = outcome else {
+        tracing::warn!("Killing command '{}'", command[0]);
+        let _ = process.start_kill();
+        let _ = process.wait().await;
+        context.check_disk_quota()?;
+        return Err(HlsKitError::Cancelled);
+    };
+
+    let capture_error = |e: std::io::Error| {
+        tracing::error!("Failed to capture output of '{}': {}", command[0], e);
+
+        HlsKitError::CommandExecutionError {
+            error: format!("Failed to capture {} output: {e}", command[0]),
+        }
+    };
+
+    stdin_result.map_err(|e| {
+        tracing::error!("Failed to write the input of '{}': {}", command[0], e);
+
+        HlsKitError::CommandExecutionError {
+            error: format!("Failed to write {} input: {e}", command[0]),
+        }
+    })?;
+    stdout_result.map_err(capture_error)?;
+    let stderr = stderr_result.map_err(capture_error)?;
+    let status = status.map_err(capture_error)?;
+
+    if !status.success() {
+        let stderr = String::from_utf8_lossy(&stderr);
+        tracing::error!("Command '{}' failed: {}", command[0], stderr);
+        return Err(HlsKitError::CommandFailed(Box::new(CommandFailure {
+            command,
+            exit_code: status.code(),
+            stderr: truncate_stderr(&stderr),
+            ..Default::default()
+        })));
+    }
+
+    let usage = sampler.finish();
+    tracing::debug!(
+        "'{}' used {:?} of CPU in {:?}, peak RSS {:?} bytes",
+        command[0],
+        usage.cpu_time(),
+        usage.wall_time,
+        usage.peak_rss_bytes
+    );
+    Ok(usage)
+}
+
+/// Keeps the end of `stderr`, where encoders print the error that made them exit
+fn truncate_stderr(stderr: &str) -> String {
+    if stderr.len() <= MAX_STDERR_LEN {
+        return stderr.to_string();
+    }
+
+    let mut start = stderr.len() - MAX_STDERR_LEN;
+    while !stderr.is_char_boundary(start) {
+        start += 1;
+    }
+    format!("...{}", &stderr[start..])
+}
+
+/// Sets the niceness and CPU affinity of the child right before it execs the encoder
+#[cfg(unix)]
+fn apply_process_limits(command: &mut Command, limits: &ResourceLimits) {
+    let niceness = limits.niceness;
+
+    #[cfg(target_os = "linux")]
+    let cpu_set = limits.cpu_affinity.as_ref().map(|cores| {
+        // SAFETY: cpu_set_t is a plain bitmask for which all zeroes is the empty set
+        let mut cpu_set: libc::cpu_set_t = unsafe { std::mem::zeroed() };
+        for &core in cores
+            .iter()
+            .filter(|&&core| core < libc::CPU_SETSIZE as usize)
+        {
+            // SAFETY: `core` is within the bounds of the set
+            unsafe { libc::CPU_SET(core, &mut cpu_set) };
+        }
+        cpu_set
+    });
+    #[cfg(not(target_os = "linux"))]
+    if limits.cpu_affinity.is_some() {
+        tracing::warn!("CPU affinity is only supported on Linux, ignoring it");
+    }
+
+    #[cfg(target_os = "linux")]
+    let has_cpu_set = cpu_set.is_some();
+    #[cfg(not(target_os = "linux"))]
+    let has_cpu_set = false;
+
+    if niceness.is_none() && !has_cpu_set {
+        return;
+    }
+
+    // SAFETY: the hook only calls async-signal-safe libc functions and allocates nothing
+    unsafe {
+        command.pre_exec(move || {
+            if let Some(niceness) = niceness {
+                if libc::setpriority(libc::PRIO_PROCESS, 0, niceness) != 0 {
+                    return Err(std::io::Error::last_os_error());
+                }
+            }
+
+            #[cfg(target_os = "linux")]
+            if let Some(cpu_set) = &cpu_set {
+                if libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), cpu_set) != 0
+                {
+                    return Err(std::io::Error::last_os_error());
+                }
+            }
+
+            Ok(())
+        });
+    }
+}
+
+#[cfg(not(unix))]
+fn apply_process_limits(_command: &mut Command, limits: &ResourceLimits) {
+    if limits.niceness.is_some() || limits.cpu_affinity.is_some() {
+        tracing::warn!("Niceness and CPU affinity are only supported on unix, ignoring them");
+    }
+}
+
+/// Moves the spawned encoder into the configured cgroup v2 directory
+fn join_cgroup(pid: Option<u32>, limits: &ResourceLimits) -> std::io::Result<()> {
+    let (Some(cgroup), Some(pid)) = (&limits.cgroup, pid) else {
+        return Ok(());
+    };
+
+    if !cfg!(target_os = "linux") {
+        tracing::warn!(
+            "cgroups are only supported on Linux, ignoring {}",
+            cgroup.display()
+        );
+        return Ok(());
+    }
+
+    std::fs::write(cgroup.join("cgroup.procs"), pid.to_string())
 }
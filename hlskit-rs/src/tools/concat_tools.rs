@@ -0,0 +1,226 @@
+// SPDX-License-Identifier: LGPL-3.0-only
+/*
+ * Copyright © 2025 The HlsKit Project
+ *
+ * This software is licensed under the GNU Lesser General Public License v3.0 (LGPLv3).
+ * All contributions adhere to the LGPLv3 and the HlsKit Contributor License Agreement (CLA).
+ * A copy of the LGPLv3 can be found at https://www.gnu.org/licenses/lgpl-3.0.html
+ *
+ * HlsKit Contributor License Agreement
+ *
+ * By contributing to or modifying HlsKit, you agree to the following terms:
+ *
+ * 1. Collective Ownership:
+ * The HlsKit project incorporates original code and all contributions as a collective work,
+ * licensed under LGPLv3. Once submitted, contributions become part of the shared HlsKit
+ * ecosystem and cannot be reclaimed, reassigned, or withdrawn. Contributions to your own
+ * forks remain yours unless submitted here, at which point they join this collective whole under LGPLv3.
+ *
+ * 2. Definition of Contribution:
+ * You are considered a contributor if you modify the library in any form (including forks,
+ * wrappers, libraries, or extensions that alter its behavior), whether or not you submit
+ * your changes directly to this repository. All such modifications are part of the broader
+ * HlsKit ecosystem and are subject to this CLA.
+ *
+ * 3. Distribution of Modifications:
+ * If you distribute a modified version of HlsKit, you must license your modifications under
+ * LGPLv3 (with source code available as required by the license) and ensure they are
+ * adoptable by the HlsKit ecosystem (publicly available and compatible).
+ *
+ * 4. Networked Use of Modifications:
+ * If you use a modified version of HlsKit in a networked application, you must provide the
+ * source code of your modifications under LGPLv3 and notify the HlsKit project
+ * (e.g., via email to [higashikataengels@icloud.com]). This does not apply to the use of
+ * the unmodified library in proprietary software, which remains permissible under LGPLv3.
+ *
+ * 5. Scope:
+ * These terms apply to all contributions and modifications derived from the HlsKit project.
+ * The use of the unmodified library in proprietary software is governed solely by the LGPLv3.
+ */
+
+use bytes::Bytes;
+
+use crate::{
+    models::hls_video::{HlsVideo, HlsVideoResolution, HlsVideoSegment},
+    tools::{
+        hlskit_error::{HlsKitError, VideoValidatableErrors},
+        m3u8_tools::parse_media,
+        rewrite_tools::{relative_uri, rewrite_playlist_uris},
+    },
+};
+
+/// Tags describing a media playlist as a whole, written once at the top of the joined one
+const PLAYLIST_HEADER_TAGS: [&str; 8] = [
+    "#EXTM3U",
+    "#EXT-X-VERSION:",
+    "#EXT-X-TARGETDURATION:",
+    "#EXT-X-MEDIA-SEQUENCE:",
+    "#EXT-X-DISCONTINUITY-SEQUENCE:",
+    "#EXT-X-PLAYLIST-TYPE:",
+    "#EXT-X-INDEPENDENT-SEGMENTS",
+    "#EXT-X-ENDLIST",
+];
+
+/// Joins VOD assets produced with the same ladder into one, without re-encoding. Each
+/// rendition plays the matching renditions of `videos` back to back, separated by
+/// `#EXT-X-DISCONTINUITY`, and the segments of the n-th asset are prefixed with `{n:03}_`
+/// to keep their names apart. The master playlist is the first asset's
+pub fn concatenate_hls_videos(videos: &[HlsVideo]) -> Result<HlsVideo, HlsKitError> {
+    let Some((first, others)) = videos.split_first() else {
+        return Err(ladder_mismatch("no assets to concatenate".to_string()));
+    };
+
+    for (index, video) in others.iter().enumerate() {
+        let ladder = |video: &HlsVideo| {
+            video
+                .resolutions
+                .iter()
+                .map(|resolution| (resolution.resolution, resolution.playlist_name.clone()))
+                .collect::<Vec<_>>()
+        };
+        if ladder(video) != ladder(first) {
+            return Err(ladder_mismatch(format!(
+                "asset {} does not have the ladder of asset 0",
+                index + 1
+            )));
+        }
+    }
+
+    let mut resolutions = Vec::with_capacity(first.resolutions.len());
+    for rendition in 0..first.resolutions.len() {
+        let parts: Vec<&HlsVideoResolution> = videos
+            .iter()
+            .map(|video| &video.resolutions[rendition])
+            .collect();
+        resolutions.push(concatenate_renditions(&parts)?);
+    }
+
+    Ok(HlsVideo {
+        master_m3u8_data: first.master_m3u8_data.clone(),
+        resolutions,
+        ..Default::default()
+    })
+}
+
+pub(crate) fn concatenate_renditions(
+    parts: &[&HlsVideoResolution],
+) -> Result<HlsVideoResolution, HlsKitError> {
+    let first = parts[0];
+    let playlist_dir = first
+        .playlist_name
+        .rsplit_once('/')
+        .map_or("", |(dir, _)| dir);
+
+    let mut playlists = Vec::with_capacity(parts.len());
+    let mut segments = Vec::new();
+
+    for (index, part) in parts.iter().enumerate() {
+        let playlist = String::from_utf8_lossy(&part.playlist_data);
+        let renames: Vec<(String, String)> = part
+            .segments
+            .iter()
+            .map(|segment| {
+                let renamed = prefixed_name(&segment.segment_name, index);
+                segments.push(HlsVideoSegment {
+                    segment_name: renamed.clone(),
+                    segment_data: segment.segment_data.clone(),
+                });
+                (
+                    relative_uri(playlist_dir, &segment.segment_name),
+                    relative_uri(playlist_dir, &renamed),
+                )
+            })
+            .collect();
+        playlists.push(rewrite_playlist_uris(&playlist, |uri| {
+            renames
+                .iter()
+                .find(|(old, _)| old == uri)
+                .map_or_else(|| uri.to_string(), |(_, new)| new.clone())
+        }));
+    }
+
+    Ok(HlsVideoResolution {
+        resolution: first.resolution,
+        playlist_name: first.playlist_name.clone(),
+        playlist_data: Bytes::from(join_media_playlists(&playlists, true)?),
+        segments,
+        quality: None,
+    })
+}
+
+/// Joins media playlists, whose segment URIs don't collide, into one VOD playlist playing them
+/// back to back, separated by `#EXT-X-DISCONTINUITY` when `discontinuous`
+pub(crate) fn join_media_playlists(
+    playlists: &[String],
+    discontinuous: bool,
+) -> Result<String, HlsKitError> {
+    let mut version = None;
+    let mut target_duration = 0;
+    let mut independent_segments = true;
+    let mut body = String::new();
+    let mut encrypted = false;
+
+    for (index, playlist) in playlists.iter().enumerate() {
+        let media = parse_media(playlist)?;
+        version = version.max(media.version);
+        target_duration = target_duration.max(media.target_duration);
+        independent_segments &= media.independent_segments;
+
+        if index > 0 {
+            if discontinuous {
+                body.push_str("#EXT-X-DISCONTINUITY\n");
+            }
+            // Keys carry over to the following segments, which this part may not expect
+            let first_segment_key = media
+                .segments
+                .first()
+                .and_then(|segment| segment.key.as_ref());
+            if encrypted && first_segment_key.is_none() {
+                body.push_str("#EXT-X-KEY:METHOD=NONE\n");
+            }
+        }
+        encrypted = media
+            .segments
+            .last()
+            .is_some_and(|segment| segment.key.is_some());
+
+        for line in playlist.lines() {
+            let trimmed = line.trim();
+            if trimmed.is_empty()
+                || PLAYLIST_HEADER_TAGS
+                    .iter()
+                    .any(|tag| trimmed.starts_with(tag))
+            {
+                continue;
+            }
+            body.push_str(line);
+            body.push('\n');
+        }
+    }
+
+    let mut playlist = String::from("#EXTM3U\n");
+    if let Some(version) = version {
+        playlist.push_str(&format!("#EXT-X-VERSION:{version}\n"));
+    }
+    playlist.push_str(&format!("#EXT-X-TARGETDURATION:{target_duration}\n"));
+    playlist.push_str("#EXT-X-MEDIA-SEQUENCE:0\n#EXT-X-PLAYLIST-TYPE:VOD\n");
+    if independent_segments {
+        playlist.push_str("#EXT-X-INDEPENDENT-SEGMENTS\n");
+    }
+    playlist.push_str(&body);
+    playlist.push_str("#EXT-X-ENDLIST\n");
+
+    Ok(playlist)
+}
+
+/// `name` with its file name prefixed by the index of the asset it comes from
+fn prefixed_name(name: &str, index: usize) -> String {
+    match name.rsplit_once('/') {
+        Some((dir, file_name)) => format!("{dir}/{index:03}_{file_name}"),
+        None => format!("{index:03}_{name}"),
+    }
+}
+
+fn ladder_mismatch(error: String) -> HlsKitError {
+    VideoValidatableErrors::InvalidVideoInput { error }.into()
+}
@@ -0,0 +1,242 @@
+// SPDX-License-Identifier: LGPL-3.0-only
+/*
+ * Copyright © 2025 The HlsKit Project
+ *
+ * This software is licensed under the GNU Lesser General Public License v3.0 (LGPLv3).
+ * All contributions adhere to the LGPLv3 and the HlsKit Contributor License Agreement (CLA).
+ * A copy of the LGPLv3 can be found at https://www.gnu.org/licenses/lgpl-3.0.html
+ *
+ * HlsKit Contributor License Agreement
+ *
+ * By contributing to or modifying HlsKit, you agree to the following terms:
+ *
+ * 1. Collective Ownership:
+ * The HlsKit project incorporates original code and all contributions as a collective work,
+ * licensed under LGPLv3. Once submitted, contributions become part of the shared HlsKit
+ * ecosystem and cannot be reclaimed, reassigned, or withdrawn. Contributions to your own
+ * forks remain yours unless submitted here, at which point they join this collective whole under LGPLv3.
+ *
+ * 2. Definition of Contribution:
+ * You are considered a contributor if you modify the library in any form (including forks,
+ * wrappers, libraries, or extensions that alter its behavior), whether or not you submit
+ * your changes directly to this repository. All such modifications are part of the broader
+ * HlsKit ecosystem and are subject to this CLA.
+ *
+ * 3. Distribution of Modifications:
+ * If you distribute a modified version of HlsKit, you must license your modifications under
+ * LGPLv3 (with source code available as required by the license) and ensure they are
+ * adoptable by the HlsKit ecosystem (publicly available and compatible).
+ *
+ * 4. Networked Use of Modifications:
+ * If you use a modified version of HlsKit in a networked application, you must provide the
+ * source code of your modifications under LGPLv3 and notify the HlsKit project
+ * (e.g., via email to [higashikataengels@icloud.com]). This does not apply to the use of
+ * the unmodified library in proprietary software, which remains permissible under LGPLv3.
+ *
+ * 5. Scope:
+ * These terms apply to all contributions and modifications derived from the HlsKit project.
+ * The use of the unmodified library in proprietary software is governed solely by the LGPLv3.
+ */
+
+use crate::{
+    models::{
+        hls_diff::{HlsDiff, HlsDifference},
+        hls_video::{HlsVideo, HlsVideoResolution, MASTER_PLAYLIST_NAME},
+        playlist::{MasterPlaylist, MediaPlaylist, VariantStream},
+    },
+    tools::m3u8_tools::{parse_master, parse_media},
+};
+
+/// Durations closer than this, in seconds, are considered equal
+const DURATION_TOLERANCE: f64 = 0.001;
+
+/// Compares `before` with `after`: the variants of the master playlists, their attributes,
+/// and the target duration, segments, durations and size of each rendition
+pub fn diff(before: &HlsVideo, after: &HlsVideo) -> HlsDiff {
+    let mut differences = Vec::new();
+
+    let (Some(before_master), Some(after_master)) = (
+        parse_master_of(before, &mut differences),
+        parse_master_of(after, &mut differences),
+    ) else {
+        return HlsDiff { differences };
+    };
+
+    for variant in &before_master.variants {
+        let resolution = variant.resolution.unwrap_or_default();
+        match find_variant(&after_master, resolution) {
+            Some(other) => {
+                diff_attributes(resolution, variant, other, &mut differences);
+                if let (Some(before), Some(after)) = (
+                    rendition_of(before, resolution),
+                    rendition_of(after, resolution),
+                ) {
+                    diff_renditions(resolution, before, after, &mut differences);
+                }
+            }
+            None => differences.push(HlsDifference::VariantRemoved {
+                resolution,
+                uri: variant.uri.clone(),
+            }),
+        }
+    }
+
+    for variant in &after_master.variants {
+        let resolution = variant.resolution.unwrap_or_default();
+        if find_variant(&before_master, resolution).is_none() {
+            differences.push(HlsDifference::VariantAdded {
+                resolution,
+                uri: variant.uri.clone(),
+            });
+        }
+    }
+
+    HlsDiff { differences }
+}
+
+fn parse_master_of(
+    video: &HlsVideo,
+    differences: &mut Vec<HlsDifference>,
+) -> Option<MasterPlaylist> {
+    parse_master(&String::from_utf8_lossy(&video.master_m3u8_data))
+        .map_err(|e| {
+            differences.push(HlsDifference::Unparsable {
+                playlist: MASTER_PLAYLIST_NAME.to_string(),
+                error: e.to_string(),
+            })
+        })
+        .ok()
+}
+
+fn find_variant(master: &MasterPlaylist, resolution: (i32, i32)) -> Option<&VariantStream> {
+    master
+        .variants
+        .iter()
+        .find(|variant| variant.resolution.unwrap_or_default() == resolution)
+}
+
+fn rendition_of(video: &HlsVideo, resolution: (i32, i32)) -> Option<&HlsVideoResolution> {
+    video
+        .resolutions
+        .iter()
+        .find(|rendition| rendition.resolution == resolution)
+}
+
+/// Compares every attribute either variant declares
+fn diff_attributes(
+    resolution: (i32, i32),
+    before: &VariantStream,
+    after: &VariantStream,
+    differences: &mut Vec<HlsDifference>,
+) {
+    let names = before
+        .attributes
+        .iter()
+        .chain(&after.attributes)
+        .map(|(name, _)| name.as_str());
+    let mut seen = Vec::new();
+
+    for name in names {
+        if seen.contains(&name) {
+            continue;
+        }
+        seen.push(name);
+
+        let (before, after) = (before.attribute(name), after.attribute(name));
+        if before != after {
+            differences.push(HlsDifference::VariantAttributeChanged {
+                resolution,
+                attribute: name.to_string(),
+                before: before.map(str::to_string),
+                after: after.map(str::to_string),
+            });
+        }
+    }
+}
+
+fn diff_renditions(
+    resolution: (i32, i32),
+    before: &HlsVideoResolution,
+    after: &HlsVideoResolution,
+    differences: &mut Vec<HlsDifference>,
+) {
+    let parse = |rendition: &HlsVideoResolution, differences: &mut Vec<HlsDifference>| {
+        parse_media(&String::from_utf8_lossy(&rendition.playlist_data))
+            .map_err(|e| {
+                differences.push(HlsDifference::Unparsable {
+                    playlist: rendition.playlist_name.clone(),
+                    error: e.to_string(),
+                })
+            })
+            .ok()
+    };
+    let (Some(before_media), Some(after_media)) =
+        (parse(before, differences), parse(after, differences))
+    else {
+        return;
+    };
+
+    if before_media.target_duration != after_media.target_duration {
+        differences.push(HlsDifference::TargetDurationChanged {
+            resolution,
+            before: before_media.target_duration,
+            after: after_media.target_duration,
+        });
+    }
+    diff_segments(resolution, &before_media, &after_media, differences);
+
+    let size = |rendition: &HlsVideoResolution| {
+        rendition
+            .segments
+            .iter()
+            .map(|segment| segment.segment_data.len() as u64)
+            .sum::<u64>()
+    };
+    let (before_size, after_size) = (size(before), size(after));
+    if before_size != after_size {
+        differences.push(HlsDifference::SizeChanged {
+            resolution,
+            before: before_size,
+            after: after_size,
+        });
+    }
+}
+
+fn diff_segments(
+    resolution: (i32, i32),
+    before: &MediaPlaylist,
+    after: &MediaPlaylist,
+    differences: &mut Vec<HlsDifference>,
+) {
+    if before.segments.len() != after.segments.len() {
+        differences.push(HlsDifference::SegmentCountChanged {
+            resolution,
+            before: before.segments.len(),
+            after: after.segments.len(),
+        });
+    }
+
+    if let Some((segment, (before, after))) = before
+        .segments
+        .iter()
+        .zip(&after.segments)
+        .enumerate()
+        .find(|(_, (before, after))| (before.duration - after.duration).abs() > DURATION_TOLERANCE)
+    {
+        differences.push(HlsDifference::SegmentDurationChanged {
+            resolution,
+            segment,
+            before: before.duration,
+            after: after.duration,
+        });
+    }
+
+    let (before, after) = (before.duration(), after.duration());
+    if (before - after).abs() > DURATION_TOLERANCE {
+        differences.push(HlsDifference::DurationChanged {
+            resolution,
+            before,
+            after,
+        });
+    }
+}
@@ -0,0 +1,108 @@
+// SPDX-License-Identifier: LGPL-3.0-only
+/*
+ * Copyright © 2025 The HlsKit Project
+ *
+ * This software is licensed under the GNU Lesser General Public License v3.0 (LGPLv3).
+ * All contributions adhere to the LGPLv3 and the HlsKit Contributor License Agreement (CLA).
+ * A copy of the LGPLv3 can be found at https://www.gnu.org/licenses/lgpl-3.0.html
+ *
+ * HlsKit Contributor License Agreement
+ *
+ * By contributing to or modifying HlsKit, you agree to the following terms:
+ *
+ * 1. Collective Ownership:
+ * The HlsKit project incorporates original code and all contributions as a collective work,
+ * licensed under LGPLv3. Once submitted, contributions become part of the shared HlsKit
+ * ecosystem and cannot be reclaimed, reassigned, or withdrawn. Contributions to your own
+ * forks remain yours unless submitted here, at which point they join this collective whole under LGPLv3.
+ *
+ * 2. Definition of Contribution:
+ * You are considered a contributor if you modify the library in any form (including forks,
+ * wrappers, libraries, or extensions that alter its behavior), whether or not you submit
+ * your changes directly to this repository. All such modifications are part of the broader
+ * HlsKit ecosystem and are subject to this CLA.
+ *
+ * 3. Distribution of Modifications:
+ * If you distribute a modified version of HlsKit, you must license your modifications under
+ * LGPLv3 (with source code available as required by the license) and ensure they are
+ * adoptable by the HlsKit ecosystem (publicly available and compatible).
+ *
+ * 4. Networked Use of Modifications:
+ * If you use a modified version of HlsKit in a networked application, you must provide the
+ * source code of your modifications under LGPLv3 and notify the HlsKit project
+ * (e.g., via email to [higashikataengels@icloud.com]). This does not apply to the use of
+ * the unmodified library in proprietary software, which remains permissible under LGPLv3.
+ *
+ * 5. Scope:
+ * These terms apply to all contributions and modifications derived from the HlsKit project.
+ * The use of the unmodified library in proprietary software is governed solely by the LGPLv3.
+ */
+
+use std::path::{Path, PathBuf};
+
+use crate::{
+    models::{
+        cache_key::CacheKey,
+        hls_video::{HlsVideo, MASTER_PLAYLIST_NAME},
+    },
+    tools::hlskit_error::HlsKitError,
+    traits::result_cache::{CacheFuture, ResultCache},
+};
+
+/// Keeps every cached video as a regular HLS directory under a root, named after its key, so
+/// entries can also be served as they are. Only the playlists and segments are stored: cached
+/// videos come back without their report or resource usage
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DirResultCache {
+    root: PathBuf,
+}
+
+impl DirResultCache {
+    pub fn new<P: AsRef<Path>>(root: P) -> Self {
+        Self {
+            root: root.as_ref().to_path_buf(),
+        }
+    }
+
+    /// The directory holding the video stored under `key`, whether or not it exists yet
+    pub fn entry_dir(&self, key: &CacheKey) -> PathBuf {
+        self.root.join(key.to_string())
+    }
+
+    fn load(&self, key: &CacheKey) -> Result<Option<HlsVideo>, HlsKitError> {
+        let entry_dir = self.entry_dir(key);
+        if !entry_dir.join(MASTER_PLAYLIST_NAME).is_file() {
+            return Ok(None);
+        }
+
+        HlsVideo::load_from_dir(entry_dir).map(Some)
+    }
+
+    fn store(&self, key: &CacheKey, hls_video: &HlsVideo) -> Result<(), HlsKitError> {
+        std::fs::create_dir_all(&self.root)?;
+
+        // Written aside then renamed, so readers never see a half-written entry
+        let staging_dir = tempfile::Builder::new()
+            .prefix(".staging-")
+            .tempdir_in(&self.root)?;
+        hls_video.write_to_dir(staging_dir.path())?;
+
+        let entry_dir = self.entry_dir(key);
+        if entry_dir.exists() {
+            return Ok(());
+        }
+        std::fs::rename(staging_dir.path(), entry_dir)?;
+
+        Ok(())
+    }
+}
+
+impl ResultCache for DirResultCache {
+    fn get<'a>(&'a self, key: &'a CacheKey) -> CacheFuture<'a, Option<HlsVideo>> {
+        Box::pin(async move { self.load(key) })
+    }
+
+    fn put<'a>(&'a self, key: &'a CacheKey, hls_video: &'a HlsVideo) -> CacheFuture<'a, ()> {
+        Box::pin(async move { self.store(key, hls_video) })
+    }
+}
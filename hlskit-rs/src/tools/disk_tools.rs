@@ -0,0 +1,203 @@
+// SPDX-License-Identifier: LGPL-3.0-only
+/*
+ * Copyright © 2025 The HlsKit Project
+ *
+ * This software is licensed under the GNU Lesser General Public License v3.0 (LGPLv3).
+ * All contributions adhere to the LGPLv3 and the HlsKit Contributor License Agreement (CLA).
+ * A copy of the LGPLv3 can be found at https://www.gnu.org/licenses/lgpl-3.0.html
+ *
+ * HlsKit Contributor License Agreement
+ *
+ * By contributing to or modifying HlsKit, you agree to the following terms:
+ *
+ * 1. Collective Ownership:
+ * The HlsKit project incorporates original code and all contributions as a collective work,
+ * licensed under LGPLv3. Once submitted, contributions become part of the shared HlsKit
+ * ecosystem and cannot be reclaimed, reassigned, or withdrawn. Contributions to your own
+ * forks remain yours unless submitted here, at which point they join this collective whole under LGPLv3.
+ *
+ * 2. Definition of Contribution:
+ * You are considered a contributor if you modify the library in any form (including forks,
+ * wrappers, libraries, or extensions that alter its behavior), whether or not you submit
+ * your changes directly to this repository. All such modifications are part of the broader
+ * HlsKit ecosystem and are subject to this CLA.
+ *
+ * 3. Distribution of Modifications:
+ * If you distribute a modified version of HlsKit, you must license your modifications under
+ * LGPLv3 (with source code available as required by the license) and ensure they are
+ * adoptable by the HlsKit ecosystem (publicly available and compatible).
+ *
+ * 4. Networked Use of Modifications:
+ * If you use a modified version of HlsKit in a networked application, you must provide the
+ * source code of your modifications under LGPLv3 and notify the HlsKit project
+ * (e.g., via email to [higashikataengels@icloud.com]). This does not apply to the use of
+ * the unmodified library in proprietary software, which remains permissible under LGPLv3.
+ *
+ * 5. Scope:
+ * These terms apply to all contributions and modifications derived from the HlsKit project.
+ * The use of the unmodified library in proprietary software is governed solely by the LGPLv3.
+ */
+
+use std::{fs, path::Path};
+
+use crate::{
+    models::{
+        dynamic_range::DynamicRange,
+        hls_video_processing_settings::HlsVideoProcessingSettings,
+        size_estimate::{RenditionEstimate, SizeEstimate},
+        source_info::SourceInfo,
+    },
+    tools::hlskit_error::HlsKitError,
+};
+
+/// Extra room kept on top of the estimate for playlists, key files and encoder overshoot
+const HEADROOM_FACTOR: f64 = 1.2;
+
+/// Bits per pixel per frame of 1080p H.264 at CRF 23, typical of live-action content
+const REFERENCE_BITS_PER_PIXEL: f64 = 0.07;
+
+const REFERENCE_PIXELS: f64 = 1920.0 * 1080.0;
+
+/// What MPEG-TS packetization adds to the elementary streams
+const TS_OVERHEAD_FACTOR: f64 = 1.08;
+
+/// Predicts the bitrate and size of every rendition of `profiles` for `source`, so storage
+/// quotas and costs can be checked before a job is enqueued
+///
+/// Bitrates follow the pixel rate, halving every 6 CRF steps, with larger frames needing
+/// fewer bits per pixel and HEVC (HDR renditions) about 40% fewer bits than H.264. No
+/// rendition is expected to exceed the source bitrate. Actual sizes vary with the content,
+/// by up to a factor of two for very static or very noisy sources.
+pub fn estimate_output(
+    source: &SourceInfo,
+    profiles: &[HlsVideoProcessingSettings],
+) -> SizeEstimate {
+    let source_fps = source.frame_rate.unwrap_or(30.0);
+    let seconds = source
+        .duration
+        .map_or(0.0, |duration| duration.as_secs_f64());
+
+    let renditions: Vec<RenditionEstimate> = profiles
+        .iter()
+        .map(|profile| {
+            let pixels =
+                (profile.resolution.0.max(1) as f64) * (profile.resolution.1.max(1) as f64);
+            let fps = profile.frame_rate.map_or(source_fps, |rate| rate.as_f64());
+            let bits_per_pixel = REFERENCE_BITS_PER_PIXEL
+                * (pixels / REFERENCE_PIXELS).powf(-0.25)
+                * 2f64.powf((23 - profile.constant_rate_factor) as f64 / 6.0)
+                * match profile.dynamic_range {
+                    DynamicRange::Sdr => 1.0,
+                    DynamicRange::Hdr => 0.6,
+                };
+
+            let mut video_bitrate = (pixels * fps * bits_per_pixel) as u64;
+            if let Some(source_bitrate) = source.bitrate.filter(|bitrate| *bitrate > 0) {
+                video_bitrate = video_bitrate.min(source_bitrate);
+            }
+            let audio_bitrate = match source.has_audio() {
+                true => profile.audio_bitrate.bits_per_second(),
+                false => 0,
+            };
+
+            RenditionEstimate {
+                resolution: profile.resolution,
+                video_bitrate,
+                audio_bitrate,
+                bytes: ((video_bitrate + audio_bitrate) as f64 / 8.0 * seconds * TS_OVERHEAD_FACTOR)
+                    as u64,
+            }
+        })
+        .collect();
+
+    SizeEstimate {
+        duration: source.duration,
+        total_bytes: renditions.iter().map(|rendition| rendition.bytes).sum(),
+        renditions,
+    }
+}
+
+/// Roughly estimates the bytes the encoded profiles will take on disk
+///
+/// The largest profile is assumed to be about as large as the source and the others to
+/// shrink with their pixel count.
+pub fn estimate_required_space(source_size: u64, profiles: &[HlsVideoProcessingSettings]) -> u64 {
+    let pixels = |profile: &HlsVideoProcessingSettings| {
+        (profile.resolution.0.max(1) as f64) * (profile.resolution.1.max(1) as f64)
+    };
+    let largest = profiles.iter().map(pixels).fold(1.0, f64::max);
+
+    let ratio: f64 = profiles
+        .iter()
+        .map(|profile| (pixels(profile) / largest).max(0.1))
+        .sum();
+
+    (source_size as f64 * ratio * HEADROOM_FACTOR) as u64
+}
+
+/// Free bytes available to unprivileged users on the volume holding `path`
+#[cfg(unix)]
+pub fn available_space(path: &Path) -> std::io::Result<u64> {
+    use std::{ffi::CString, os::unix::ffi::OsStrExt};
+
+    let path = CString::new(path.as_os_str().as_bytes())
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?;
+
+    // SAFETY: statvfs only writes into the zeroed struct we hand it
+    let mut stats: libc::statvfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statvfs(path.as_ptr(), &mut stats) } != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    #[allow(clippy::unnecessary_cast)]
+    Ok(stats.f_bavail as u64 * stats.f_frsize as u64)
+}
+
+#[cfg(not(unix))]
+pub fn available_space(_path: &Path) -> std::io::Result<u64> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "free space lookup is only supported on unix",
+    ))
+}
+
+/// Fails with `InsufficientDiskSpace` when `output_dir` can't hold the estimated output
+pub fn ensure_disk_space(
+    input_path: &str,
+    output_dir: &Path,
+    profiles: &[HlsVideoProcessingSettings],
+) -> Result<(), HlsKitError> {
+    ensure_disk_space_for(fs::metadata(input_path)?.len(), output_dir, profiles)
+}
+
+/// Like `ensure_disk_space`, for a source of `source_size` bytes
+pub fn ensure_disk_space_for(
+    source_size: u64,
+    output_dir: &Path,
+    profiles: &[HlsVideoProcessingSettings],
+) -> Result<(), HlsKitError> {
+    let required = estimate_required_space(source_size, profiles);
+
+    let available = match available_space(output_dir) {
+        Ok(available) => available,
+        Err(e) => {
+            tracing::warn!("Skipping disk space preflight: {}", e);
+            return Ok(());
+        }
+    };
+
+    if available < required {
+        tracing::error!(
+            "{} needs about {} bytes but only {} are free",
+            output_dir.display(),
+            required,
+            available
+        );
+        return Err(HlsKitError::InsufficientDiskSpace {
+            required,
+            available,
+        });
+    }
+
+    Ok(())
+}
@@ -0,0 +1,536 @@
+// SPDX-License-Identifier: LGPL-3.0-only
+/*
+ * Copyright © 2025 The HlsKit Project
+ *
+ * This software is licensed under the GNU Lesser General Public License v3.0 (LGPLv3).
+ * All contributions adhere to the LGPLv3 and the HlsKit Contributor License Agreement (CLA).
+ * A copy of the LGPLv3 can be found at https://www.gnu.org/licenses/lgpl-3.0.html
+ *
+ * HlsKit Contributor License Agreement
+ *
+ * By contributing to or modifying HlsKit, you agree to the following terms:
+ *
+ * 1. Collective Ownership:
+ * The HlsKit project incorporates original code and all contributions as a collective work,
+ * licensed under LGPLv3. Once submitted, contributions become part of the shared HlsKit
+ * ecosystem and cannot be reclaimed, reassigned, or withdrawn. Contributions to your own
+ * forks remain yours unless submitted here, at which point they join this collective whole under LGPLv3.
+ *
+ * 2. Definition of Contribution:
+ * You are considered a contributor if you modify the library in any form (including forks,
+ * wrappers, libraries, or extensions that alter its behavior), whether or not you submit
+ * your changes directly to this repository. All such modifications are part of the broader
+ * HlsKit ecosystem and are subject to this CLA.
+ *
+ * 3. Distribution of Modifications:
+ * If you distribute a modified version of HlsKit, you must license your modifications under
+ * LGPLv3 (with source code available as required by the license) and ensure they are
+ * adoptable by the HlsKit ecosystem (publicly available and compatible).
+ *
+ * 4. Networked Use of Modifications:
+ * If you use a modified version of HlsKit in a networked application, you must provide the
+ * source code of your modifications under LGPLv3 and notify the HlsKit project
+ * (e.g., via email to [higashikataengels@icloud.com]). This does not apply to the use of
+ * the unmodified library in proprietary software, which remains permissible under LGPLv3.
+ *
+ * 5. Scope:
+ * These terms apply to all contributions and modifications derived from the HlsKit project.
+ * The use of the unmodified library in proprietary software is governed solely by the LGPLv3.
+ */
+
+use std::path::{Path, PathBuf};
+
+use aes::{
+    cipher::{block_padding::Pkcs7, BlockDecryptMut, BlockEncryptMut, KeyIvInit},
+    Aes128,
+};
+use bytes::Bytes;
+use zeroize::Zeroizing;
+
+use crate::{
+    models::{
+        hls_video::{HlsVideo, HlsVideoResolution},
+        playlist::MediaPlaylist,
+        secret::SecretKey,
+    },
+    tools::{hlskit_error::HlsKitError, m3u8_tools::parse_media, rewrite_tools::relative_uri},
+    VideoProcessorEncryptionSettings,
+};
+
+type Aes128CbcEnc = cbc::Encryptor<Aes128>;
+type Aes128CbcDec = cbc::Decryptor<Aes128>;
+
+/// Reads a 16-byte AES-128 key file, as referenced by `encryption_key_path`
+pub fn read_key_file(path: &Path) -> Result<SecretKey, HlsKitError> {
+    let key = Zeroizing::new(std::fs::read(path)?);
+    <[u8; 16]>::try_from(key.as_slice())
+        .map(SecretKey::new)
+        .map_err(|_| HlsKitError::EncryptionError {
+            error: format!(
+                "{} holds {} bytes, an AES-128 key is 16",
+                path.display(),
+                key.len()
+            ),
+        })
+}
+
+/// The lines of an ffmpeg key info file, as referenced by `encryption_key_path`
+pub(crate) struct KeyInfo {
+    pub key_uri: String,
+    pub key_path: PathBuf,
+    pub iv: Option<String>,
+}
+
+pub(crate) fn read_key_info_file(path: &Path) -> Result<KeyInfo, HlsKitError> {
+    let key_info = std::fs::read_to_string(path)?;
+    let mut lines = key_info.lines().map(str::trim);
+    match (lines.next(), lines.next()) {
+        (Some(key_uri), Some(key_path)) if !key_uri.is_empty() && !key_path.is_empty() => {
+            Ok(KeyInfo {
+                key_uri: key_uri.to_string(),
+                key_path: PathBuf::from(key_path),
+                iv: lines.next().filter(|iv| !iv.is_empty()).map(str::to_string),
+            })
+        }
+        _ => Err(HlsKitError::EncryptionError {
+            error: format!("{} is not an ffmpeg key info file", path.display()),
+        }),
+    }
+}
+
+/// Encrypts a rendition written in the clear, in place, exactly like ffmpeg's
+/// `-hls_key_info_file`: AES-128-CBC with PKCS#7 padding, the IV of the key info file or each
+/// segment's media sequence number, and an `#EXT-X-KEY` tag before the first segment
+pub(crate) fn encrypt_rendition_files(
+    playlist_path: &Path,
+    encryption: &VideoProcessorEncryptionSettings,
+) -> Result<(), HlsKitError> {
+    let key_info = read_key_info_file(Path::new(&encryption.encryption_key_path))?;
+    let key = read_key_file(&key_info.key_path)?;
+    let iv = key_info.iv.as_deref().map(parse_iv).transpose()?;
+
+    let playlist = std::fs::read_to_string(playlist_path)?;
+    let media = parse_media(&playlist)?;
+    let playlist_dir = playlist_path.parent().unwrap_or(Path::new("."));
+
+    for (index, entry) in media.segments.iter().enumerate() {
+        let segment_path = playlist_dir.join(&entry.uri);
+        let iv = iv.unwrap_or_else(|| sequence_iv(media.media_sequence + index as u64));
+        let encrypted = encrypt_segment(&std::fs::read(&segment_path)?, key.expose(), &iv);
+        std::fs::write(&segment_path, encrypted)?;
+    }
+
+    let mut key_tag = format!("#EXT-X-KEY:METHOD=AES-128,URI=\"{}\"", key_info.key_uri);
+    if let Some(iv) = &iv {
+        key_tag.push_str(&format!(",IV=0x{}", hex::encode(iv)));
+    }
+    std::fs::write(playlist_path, replace_key_tags(&playlist, Some(&key_tag)))?;
+
+    Ok(())
+}
+
+/// Decrypts the first and last segment of an encrypted rendition with the key of
+/// `encryption`, failing when either doesn't decrypt into MPEG-TS, fMP4 or packed audio. Catches
+/// key and IV mismatches while packaging rather than at playback
+pub(crate) fn verify_encrypted_rendition(
+    playlist_path: &Path,
+    encryption: &VideoProcessorEncryptionSettings,
+) -> Result<(), HlsKitError> {
+    let key_info = read_key_info_file(Path::new(&encryption.encryption_key_path))?;
+    let key = read_key_file(&key_info.key_path)?;
+
+    let media = parse_media(&std::fs::read_to_string(playlist_path)?)?;
+    let playlist_dir = playlist_path.parent().unwrap_or(Path::new("."));
+    let last = media.segments.len().saturating_sub(1);
+
+    for index in [0, last] {
+        let Some(entry) = media.segments.get(index) else {
+            break;
+        };
+        let segment_path = playlist_dir.join(&entry.uri);
+        let fail = |reason: &str| HlsKitError::EncryptionError {
+            error: format!("{} fails verification: {reason}", segment_path.display()),
+        };
+
+        let iv = match &entry.key {
+            Some(key) if key.method == "AES-128" => match &key.iv {
+                Some(iv) => parse_iv(iv)?,
+                None => sequence_iv(media.media_sequence + index as u64),
+            },
+            _ => return Err(fail("not encrypted with AES-128")),
+        };
+        let clear = decrypt_segment(&std::fs::read(&segment_path)?, key.expose(), &iv)
+            .map_err(|_| fail("wrong key or IV"))?;
+        if !is_media_segment(&clear) {
+            return Err(fail("decrypts into garbage, wrong key or IV"));
+        }
+    }
+
+    Ok(())
+}
+
+/// Whether `data` starts like an MPEG-TS, fMP4, ADTS or ID3-tagged packed audio segment
+fn is_media_segment(data: &[u8]) -> bool {
+    let ts = data.first() == Some(&0x47) && data.get(188).is_none_or(|&byte| byte == 0x47);
+    let fmp4 = data.len() >= 8
+        && matches!(
+            &data[4..8],
+            b"styp" | b"ftyp" | b"moof" | b"sidx" | b"emsg" | b"prft"
+        );
+    let adts = data.len() >= 2 && data[0] == 0xFF && data[1] & 0xF0 == 0xF0;
+
+    ts || fmp4 || adts || data.starts_with(b"ID3")
+}
+
+/// Parses a 128-bit IV written in hex, with or without the `0x` prefix of `EXT-X-KEY`
+pub fn parse_iv(iv: &str) -> Result<[u8; 16], HlsKitError> {
+    let digits = iv
+        .strip_prefix("0x")
+        .or_else(|| iv.strip_prefix("0X"))
+        .unwrap_or(iv);
+    let mut parsed = [0; 16];
+    hex::decode_to_slice(digits, &mut parsed).map_err(|e| HlsKitError::EncryptionError {
+        error: format!("invalid IV {iv:?}: {e}"),
+    })?;
+
+    Ok(parsed)
+}
+
+/// The IV players use for a segment when `EXT-X-KEY` has none: its media sequence number
+pub fn sequence_iv(media_sequence: u64) -> [u8; 16] {
+    let mut iv = [0; 16];
+    iv[8..].copy_from_slice(&media_sequence.to_be_bytes());
+    iv
+}
+
+/// Encrypts a whole segment with AES-128-CBC and PKCS#7 padding, as `METHOD=AES-128` expects
+pub fn encrypt_segment(data: &[u8], key: &[u8; 16], iv: &[u8; 16]) -> Vec<u8> {
+    Aes128CbcEnc::new(key.into(), iv.into()).encrypt_padded_vec_mut::<Pkcs7>(data)
+}
+
+/// Reverses [`encrypt_segment`]
+pub fn decrypt_segment(data: &[u8], key: &[u8; 16], iv: &[u8; 16]) -> Result<Vec<u8>, HlsKitError> {
+    Aes128CbcDec::new(key.into(), iv.into())
+        .decrypt_padded_vec_mut::<Pkcs7>(data)
+        .map_err(|_| HlsKitError::EncryptionError {
+            error: "segment does not decrypt, wrong key or IV".to_string(),
+        })
+}
+
+/// Produces an AES-128 encrypted copy of `video` without re-encoding, re-wrapping every
+/// segment with the key at `encryption.encryption_key_path` and pointing the media playlists
+/// to `encryption.encryption_key_url`. Segments already encrypted with AES-128 are first
+/// decrypted with `source_key`. Without `encryption.iv`, each segment's IV is its media
+/// sequence number
+pub fn reencrypt_hls_video(
+    video: &HlsVideo,
+    encryption: &VideoProcessorEncryptionSettings,
+    source_key: Option<&[u8; 16]>,
+) -> Result<HlsVideo, HlsKitError> {
+    let key = read_key_file(Path::new(&encryption.encryption_key_path))?;
+    let iv = encryption.iv.as_deref().map(parse_iv).transpose()?;
+
+    let mut key_tag = format!(
+        "#EXT-X-KEY:METHOD=AES-128,URI=\"{}\"",
+        encryption.encryption_key_url
+    );
+    if let Some(iv) = &iv {
+        key_tag.push_str(&format!(",IV=0x{}", hex::encode(iv)));
+    }
+
+    let mut reencrypted = video.clone();
+    reencrypted.checksums = None;
+
+    for resolution in &mut reencrypted.resolutions {
+        let playlist = String::from_utf8_lossy(&resolution.playlist_data).into_owned();
+        let media = parse_media(&playlist)?;
+        for_each_segment(resolution, &media, source_key, |data, sequence| {
+            let iv = iv.unwrap_or_else(|| sequence_iv(sequence));
+            Ok(Bytes::from(encrypt_segment(data, key.expose(), &iv)))
+        })?;
+        resolution.playlist_data = Bytes::from(replace_key_tags(&playlist, Some(&key_tag)));
+    }
+
+    Ok(reencrypted)
+}
+
+/// [`reencrypt_hls_video`] on the asset in `input_dir`, written to `output_dir`
+pub fn reencrypt_hls_dir(
+    input_dir: &Path,
+    output_dir: &Path,
+    encryption: &VideoProcessorEncryptionSettings,
+    source_key: Option<&[u8; 16]>,
+) -> Result<HlsVideo, HlsKitError> {
+    let video = HlsVideo::load_from_dir(input_dir)?;
+    let reencrypted = reencrypt_hls_video(&video, encryption, source_key)?;
+    reencrypted.write_to_dir(output_dir)?;
+
+    Ok(reencrypted)
+}
+
+/// Decrypts an AES-128 encrypted `video` with `key`, dropping the `#EXT-X-KEY` tags of its
+/// media playlists. Segments left in the clear keep sharing their data with `video`
+pub fn decrypt_hls_video(video: &HlsVideo, key: &[u8; 16]) -> Result<HlsVideo, HlsKitError> {
+    let mut decrypted = video.clone();
+    decrypted.checksums = None;
+
+    for resolution in &mut decrypted.resolutions {
+        let playlist = String::from_utf8_lossy(&resolution.playlist_data).into_owned();
+        let media = parse_media(&playlist)?;
+        for_each_segment(resolution, &media, Some(key), |data, _| Ok(data.clone()))?;
+        resolution.playlist_data = Bytes::from(replace_key_tags(&playlist, None));
+    }
+
+    Ok(decrypted)
+}
+
+/// [`decrypt_hls_video`] on the asset in `input_dir`, written to `output_dir`
+pub fn decrypt_hls_dir(
+    input_dir: &Path,
+    output_dir: &Path,
+    key: &[u8; 16],
+) -> Result<HlsVideo, HlsKitError> {
+    let video = HlsVideo::load_from_dir(input_dir)?;
+    let decrypted = decrypt_hls_video(&video, key)?;
+    decrypted.write_to_dir(output_dir)?;
+
+    Ok(decrypted)
+}
+
+/// Replaces the segment data of `resolution` with `transform(clear data, media sequence)`,
+/// decrypting segments encrypted with AES-128 using `source_key` first
+pub(crate) fn for_each_segment(
+    resolution: &mut HlsVideoResolution,
+    media: &MediaPlaylist,
+    source_key: Option<&[u8; 16]>,
+    mut transform: impl FnMut(&Bytes, u64) -> Result<Bytes, HlsKitError>,
+) -> Result<(), HlsKitError> {
+    let playlist_dir = resolution
+        .playlist_name
+        .rsplit_once('/')
+        .map_or("", |(dir, _)| dir)
+        .to_string();
+
+    for (index, entry) in media.segments.iter().enumerate() {
+        let sequence = media.media_sequence + index as u64;
+        let segment = resolution
+            .segments
+            .iter_mut()
+            .find(|segment| {
+                let name = relative_uri(&playlist_dir, &segment.segment_name);
+                entry.uri == name
+                    || (entry.uri.contains("://") && entry.uri.ends_with(&format!("/{name}")))
+            })
+            .ok_or_else(|| HlsKitError::FileNotFound {
+                file_path: entry.uri.clone(),
+            })?;
+
+        let clear = match &entry.key {
+            None => transform(&segment.segment_data, sequence)?,
+            Some(key) if key.method == "AES-128" => {
+                let source_key = source_key.ok_or_else(|| HlsKitError::EncryptionError {
+                    error: format!("{} is encrypted and no source key was given", entry.uri),
+                })?;
+                let iv = match &key.iv {
+                    Some(iv) => parse_iv(iv)?,
+                    None => sequence_iv(sequence),
+                };
+                transform(
+                    &Bytes::from(decrypt_segment(&segment.segment_data, source_key, &iv)?),
+                    sequence,
+                )?
+            }
+            Some(key) => {
+                return Err(HlsKitError::UnsupportedOperation {
+                    error: format!("{} segments can't be re-wrapped", key.method),
+                })
+            }
+        };
+        segment.segment_data = clear;
+    }
+
+    Ok(())
+}
+
+/// Drops the `#EXT-X-KEY` tags of a media playlist, writing `key_tag` before the first
+/// segment instead
+pub(crate) fn replace_key_tags(playlist: &str, key_tag: Option<&str>) -> String {
+    let mut rewritten = String::with_capacity(playlist.len());
+    let mut key_tag = key_tag;
+
+    for line in playlist.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with("#EXT-X-KEY:") {
+            continue;
+        }
+        if trimmed.starts_with("#EXTINF:") {
+            if let Some(key_tag) = key_tag.take() {
+                rewritten.push_str(key_tag);
+                rewritten.push('\n');
+            }
+        }
+        rewritten.push_str(line);
+        rewritten.push('\n');
+    }
+
+    rewritten
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::hls_video::HlsVideoSegment;
+
+    const KEY: [u8; 16] = *b"0123456789abcdef";
+    const KEY_URI: &str = "https://keys.example.com/rendition.key";
+    const MEDIA_SEQUENCE: u64 = 5;
+
+    /// A two-segment rendition in the clear, its segments shaped like MPEG-TS
+    fn clear_video() -> HlsVideo {
+        let segments: Vec<HlsVideoSegment> = (0..2u8)
+            .map(|index| {
+                let mut data = vec![index; 376];
+                data[0] = 0x47;
+                data[188] = 0x47;
+                HlsVideoSegment {
+                    segment_name: format!("data_0_00{index}.ts"),
+                    segment_data: Bytes::from(data),
+                }
+            })
+            .collect();
+        let playlist = format!(
+            "#EXTM3U\n#EXT-X-VERSION:3\n#EXT-X-TARGETDURATION:4\n#EXT-X-MEDIA-SEQUENCE:{MEDIA_SEQUENCE}\n#EXTINF:4.000000,\n{}\n#EXTINF:3.500000,\n{}\n#EXT-X-ENDLIST\n",
+            segments[0].segment_name, segments[1].segment_name,
+        );
+
+        HlsVideo {
+            resolutions: vec![HlsVideoResolution {
+                resolution: (1280, 720),
+                playlist_name: "playlist_0.m3u8".to_string(),
+                playlist_data: Bytes::from(playlist),
+                segments,
+                quality: None,
+            }],
+            ..Default::default()
+        }
+    }
+
+    /// Encrypts `clear` on disk through the key info file read by the GStreamer backend, the
+    /// file ffmpeg gets as `-hls_key_info_file`, and loads the result back
+    fn encrypt_with_key_info(clear: &HlsVideo, iv: Option<&str>) -> HlsVideo {
+        let dir = tempfile::tempdir().unwrap();
+        clear.write_to_dir(dir.path()).unwrap();
+
+        let key_path = dir.path().join("rendition.key");
+        let key_info_path = dir.path().join("rendition.keyinfo");
+        std::fs::write(&key_path, KEY).unwrap();
+        std::fs::write(
+            &key_info_path,
+            format!("{KEY_URI}\n{}\n{}\n", key_path.display(), iv.unwrap_or("")),
+        )
+        .unwrap();
+
+        let resolution = &clear.resolutions[0];
+        encrypt_rendition_files(
+            &dir.path().join(&resolution.playlist_name),
+            &VideoProcessorEncryptionSettings {
+                encryption_key_url: KEY_URI.to_string(),
+                encryption_key_path: key_info_path.to_string_lossy().to_string(),
+                iv: None,
+            },
+        )
+        .unwrap();
+
+        let mut encrypted = clear.clone();
+        let encrypted_resolution = &mut encrypted.resolutions[0];
+        encrypted_resolution.playlist_data =
+            Bytes::from(std::fs::read(dir.path().join(&resolution.playlist_name)).unwrap());
+        for segment in &mut encrypted_resolution.segments {
+            segment.segment_data =
+                Bytes::from(std::fs::read(dir.path().join(&segment.segment_name)).unwrap());
+        }
+        encrypted
+    }
+
+    /// Encrypts `clear` in memory through encryption settings pointing at the key itself
+    fn encrypt_with_settings(clear: &HlsVideo, iv: Option<&str>) -> HlsVideo {
+        let dir = tempfile::tempdir().unwrap();
+        let key_path = dir.path().join("rendition.key");
+        std::fs::write(&key_path, KEY).unwrap();
+
+        reencrypt_hls_video(
+            clear,
+            &VideoProcessorEncryptionSettings {
+                encryption_key_url: KEY_URI.to_string(),
+                encryption_key_path: key_path.to_string_lossy().to_string(),
+                iv: iv.map(str::to_string),
+            },
+            None,
+        )
+        .unwrap()
+    }
+
+    fn key_tags(video: &HlsVideo) -> Vec<String> {
+        String::from_utf8_lossy(&video.resolutions[0].playlist_data)
+            .lines()
+            .filter(|line| line.starts_with("#EXT-X-KEY:"))
+            .map(str::to_string)
+            .collect()
+    }
+
+    fn assert_encrypts_identically(iv: Option<&str>, expected_key_tag: &str) {
+        let clear = clear_video();
+        let with_key_info = encrypt_with_key_info(&clear, iv);
+        let with_settings = encrypt_with_settings(&clear, iv);
+
+        assert_eq!(with_key_info.resolutions, with_settings.resolutions);
+        assert_eq!(key_tags(&with_key_info), [expected_key_tag]);
+
+        // What ffmpeg writes for the same key info file
+        for (index, (encrypted, clear)) in with_key_info.resolutions[0]
+            .segments
+            .iter()
+            .zip(&clear.resolutions[0].segments)
+            .enumerate()
+        {
+            let segment_iv = match iv {
+                Some(iv) => parse_iv(iv).unwrap(),
+                None => sequence_iv(MEDIA_SEQUENCE + index as u64),
+            };
+            assert_ne!(encrypted.segment_data, clear.segment_data);
+            assert_eq!(
+                encrypted.segment_data,
+                encrypt_segment(&clear.segment_data, &KEY, &segment_iv)
+            );
+        }
+
+        let decrypted = decrypt_hls_video(&with_key_info, &KEY).unwrap();
+        assert_eq!(decrypted.resolutions, clear.resolutions);
+    }
+
+    #[test]
+    fn key_info_and_settings_encrypt_identically_with_an_iv() {
+        assert_encrypts_identically(
+            Some("0x000102030405060708090a0b0c0d0e0f"),
+            &format!(
+                "#EXT-X-KEY:METHOD=AES-128,URI=\"{KEY_URI}\",IV=0x000102030405060708090a0b0c0d0e0f"
+            ),
+        );
+    }
+
+    #[test]
+    fn key_info_and_settings_encrypt_identically_with_sequence_ivs() {
+        assert_encrypts_identically(
+            None,
+            &format!("#EXT-X-KEY:METHOD=AES-128,URI=\"{KEY_URI}\""),
+        );
+    }
+
+    #[test]
+    fn wrong_key_fails_the_round_trip() {
+        let encrypted = encrypt_with_key_info(&clear_video(), None);
+
+        let decrypted = decrypt_hls_video(&encrypted, b"fedcba9876543210");
+        assert!(decrypted.map_or(true, |video| video.resolutions != clear_video().resolutions));
+    }
+}
@@ -38,41 +38,315 @@
  * The use of the unmodified library in proprietary software is governed solely by the LGPLv3.
  */
 
-use std::path::{Path, PathBuf};
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
 
-use crate::tools::{
-    hlskit_error::FfmpegCommandBuilderError,
-    internals::hls_output_config::{HlsOutputConfig, HlsOutputEncryptionConfig},
+use crate::{
+    models::{
+        audio_sync::AudioSync,
+        chunked_encoding::SourceChunk,
+        dynamic_range::VideoRange,
+        frame_rate::FrameRate,
+        hls_video_processing_settings::{
+            FfmpegVideoProcessingPreset, HlsVideoAudioBitrate, HlsVideoAudioCodec, HlsVideoCodec,
+        },
+    },
+    tools::{
+        hlskit_error::FfmpegCommandBuilderError,
+        internals::hls_output_config::{HlsOutputConfig, HlsOutputEncryptionConfig},
+    },
 };
 
+/// Linearizes the input, tone-maps it with Hable and converts it to BT.709
+const TONE_MAP_FILTER: &str = "zscale=t=linear:npl=100,format=gbrpf32le,zscale=p=bt709,\
+tonemap=tonemap=hable:desat=0,zscale=t=bt709:m=bt709:r=tv,format=yuv420p";
+
+/// The ffmpeg encoder of `codec`. HDR output needs a 10-bit codec, so H.264 is replaced by HEVC
+pub fn ffmpeg_video_encoder(codec: HlsVideoCodec, video_range: VideoRange) -> &'static str {
+    match (codec, video_range.hdr_transfer_name()) {
+        (HlsVideoCodec::Av1, _) => "libsvtav1",
+        (HlsVideoCodec::Hevc, _) | (HlsVideoCodec::H264, Some(_)) => "libx265",
+        (HlsVideoCodec::H264, None) => "libx264",
+    }
+}
+
+/// The ffmpeg encoder of `codec`
+pub fn ffmpeg_audio_encoder(codec: HlsVideoAudioCodec) -> &'static str {
+    match codec {
+        HlsVideoAudioCodec::Aac => "aac",
+        HlsVideoAudioCodec::Mp3 => "libmp3lame",
+        HlsVideoAudioCodec::Vorbis => "libvorbis",
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct FfmpegCommand {
+    binary: Option<PathBuf>,
     input_path: PathBuf,
     output_path: PathBuf,
     width: i32,
     height: i32,
     crf: i32,
     preset: String,
+    video_codec: HlsVideoCodec,
     hls_config: Option<HlsOutputConfig>,
+    progress: bool,
+    deinterlace: bool,
+    tone_map: bool,
+    video_range: VideoRange,
+    frame_rate: Option<FrameRate>,
+    keyframes: Vec<Duration>,
+    keyframe_interval: Option<Duration>,
+    audio: Option<(HlsVideoAudioCodec, HlsVideoAudioBitrate)>,
+    audio_sync: AudioSync,
+    source_chunk: Option<SourceChunk>,
+    threads: Option<u32>,
+    copy_streams: bool,
+    fmp4_init_filename: Option<String>,
+    extra_args: Vec<String>,
 }
 
 impl FfmpegCommand {
     pub fn to_args(&self) -> Vec<String> {
-        let mut args = vec!["ffmpeg".to_string()];
+        let mut args = self.input_args();
+        // The offset audio is read from the second input, copied as is in passthrough
+        let maps = match (self.audio_sync.offset_ms != 0, self.copy_streams) {
+            (true, _) => &["-map", "0:v:0", "-map", "1:a:0?"][..],
+            (false, true) => &["-map", "0:v:0", "-map", "0:a?"][..],
+            (false, false) => &[][..],
+        };
+        args.extend(maps.iter().map(|arg| arg.to_string()));
+
+        if self.copy_streams {
+            args.extend(["-c", "copy"].iter().map(|arg| arg.to_string()));
+            self.push_output_args(&mut args);
+            return args;
+        }
+
+        if self.audio_sync.resample {
+            args.push("-af".to_string());
+            args.push("aresample=async=1".to_string());
+        }
+
+        let mut filters = Vec::new();
+        if self.deinterlace {
+            filters.push("yadif".to_string());
+        }
+        filters.extend(self.output_filters());
+        args.push("-vf".to_string());
+        args.push(filters.join(","));
+
+        args.extend(self.encoder_args());
+        self.push_output_args(&mut args);
+
+        args
+    }
+
+    /// One invocation decoding the input of `commands` once and splitting the decoded video
+    /// into an encode and HLS output per command. The input, progress, deinterlacing and audio
+    /// sync settings are taken from the first command
+    pub fn split_args(commands: &[FfmpegCommand]) -> Vec<String> {
+        let Some(first) = commands.first() else {
+            return Vec::new();
+        };
+
+        let mut args = first.input_args();
+
+        let mut graph = String::from("[0:v:0]");
+        if first.deinterlace {
+            graph.push_str("yadif,");
+        }
+        graph.push_str(&format!("split={}", commands.len()));
+        for index in 0..commands.len() {
+            graph.push_str(&format!("[s{index}]"));
+        }
+        for (index, command) in commands.iter().enumerate() {
+            graph.push_str(&format!(
+                ";[s{index}]{}[v{index}]",
+                command.output_filters().join(",")
+            ));
+        }
+        args.push("-filter_complex".to_string());
+        args.push(graph);
 
+        let audio = if first.audio_sync.offset_ms != 0 {
+            "1:a:0?"
+        } else {
+            "0:a:0?"
+        };
+        for (index, command) in commands.iter().enumerate() {
+            args.push("-map".to_string());
+            args.push(format!("[v{index}]"));
+            args.push("-map".to_string());
+            args.push(audio.to_string());
+            if first.audio_sync.resample {
+                args.push("-af".to_string());
+                args.push("aresample=async=1".to_string());
+            }
+            args.extend(command.encoder_args());
+            command.push_output_args(&mut args);
+        }
+
+        args
+    }
+
+    /// The binary, progress reporting and inputs
+    fn input_args(&self) -> Vec<String> {
+        let mut args = vec![self.binary.as_ref().map_or_else(
+            || "ffmpeg".to_string(),
+            |binary| binary.display().to_string(),
+        )];
+
+        if self.progress {
+            args.push("-progress".to_string());
+            args.push("pipe:1".to_string());
+            args.push("-nostats".to_string());
+        }
+
+        args.extend(self.chunk_input_args());
         args.push("-i".to_string());
         args.push(self.input_path.to_str().unwrap_or_default().to_string());
 
-        args.push("-vf".to_string());
-        args.push(format!("scale={}x{}", self.width, self.height));
+        // Offsetting audio means reading the input a second time with shifted timestamps
+        if self.audio_sync.offset_ms != 0 {
+            args.push("-itsoffset".to_string());
+            args.push(format!("{:.3}", self.audio_sync.offset_ms as f64 / 1000.0));
+            args.extend(self.chunk_input_args());
+            args.push("-i".to_string());
+            args.push(self.input_path.to_str().unwrap_or_default().to_string());
+        }
+
+        args
+    }
+
+    /// Seeks to the start of the chunk and stops reading at its end
+    fn chunk_input_args(&self) -> Vec<String> {
+        let Some(chunk) = &self.source_chunk else {
+            return Vec::new();
+        };
+
+        let mut args = vec![
+            "-ss".to_string(),
+            format!("{:.6}", chunk.start.as_secs_f64()),
+        ];
+        if let Some(duration) = chunk.duration {
+            args.push("-t".to_string());
+            args.push(format!("{:.6}", duration.as_secs_f64()));
+        }
+
+        args
+    }
+
+    /// Filters turning the decoded, deinterlaced video into this output's
+    fn output_filters(&self) -> Vec<String> {
+        let mut filters = Vec::new();
+        if self.tone_map {
+            filters.push(TONE_MAP_FILTER.to_string());
+        }
+        filters.push(format!("scale={}x{}", self.width, self.height));
+
+        filters
+    }
 
+    /// Video encoder settings of this output
+    fn encoder_args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        let encoder = ffmpeg_video_encoder(self.video_codec, self.video_range);
         args.push("-c:v".to_string());
-        args.push("libx264".to_string());
+        args.push(encoder.to_string());
+        // Apple players only accept HEVC tagged hvc1
+        if encoder == "libx265" {
+            args.push("-tag:v".to_string());
+            args.push("hvc1".to_string());
+        }
+        if let Some(transfer) = self.video_range.hdr_transfer_name() {
+            args.extend(
+                [
+                    "-pix_fmt",
+                    "yuv420p10le",
+                    "-color_primaries",
+                    "bt2020",
+                    "-color_trc",
+                    transfer,
+                    "-colorspace",
+                    "bt2020nc",
+                ]
+                .iter()
+                .map(|arg| arg.to_string()),
+            );
+            if encoder == "libx265" {
+                args.push("-x265-params".to_string());
+                args.push(format!(
+                    "repeat-headers=1:colorprim=bt2020:transfer={transfer}:colormatrix=bt2020nc"
+                ));
+            }
+        }
         args.push("-crf".to_string());
         args.push(self.crf.to_string());
         args.push("-preset".to_string());
-        args.push(self.preset.to_string());
+        // SVT-AV1 presets are numbers, map the x264 name onto one of a similar speed
+        args.push(
+            match (
+                self.video_codec,
+                FfmpegVideoProcessingPreset::from_value(&self.preset),
+            ) {
+                (HlsVideoCodec::Av1, Some(preset)) => preset.svt_av1_value().to_string(),
+                _ => self.preset.to_string(),
+            },
+        );
+
+        // -vsync rather than -fps_mode, which needs ffmpeg 5.1
+        if let Some(frame_rate) = self.frame_rate {
+            args.push("-vsync".to_string());
+            args.push("cfr".to_string());
+            args.push("-r".to_string());
+            args.push(frame_rate.to_string());
+        }
+
+        // Only the forced keyframes may start a segment, so periodic and scene-cut keyframes
+        // of the encoder are disabled
+        if !self.keyframes.is_empty() {
+            let times: Vec<String> = self
+                .keyframes
+                .iter()
+                .map(|time| format!("{:.3}", time.as_secs_f64()))
+                .collect();
+            args.push("-force_key_frames".to_string());
+            args.push(times.join(","));
+            args.extend(
+                ["-sc_threshold", "0", "-g", "100000"]
+                    .iter()
+                    .map(|arg| arg.to_string()),
+            );
+        } else if let Some(interval) = self.keyframe_interval {
+            args.push("-force_key_frames".to_string());
+            args.push(format!(
+                "expr:gte(t,n_forced*{:.3})",
+                interval.as_secs_f64()
+            ));
+            args.push("-sc_threshold".to_string());
+            args.push("0".to_string());
+        }
+
+        if let Some((codec, bitrate)) = self.audio {
+            args.push("-c:a".to_string());
+            args.push(ffmpeg_audio_encoder(codec).to_string());
+            args.push("-b:a".to_string());
+            args.push(bitrate.value().to_string());
+        }
+
+        if let Some(threads) = self.threads {
+            args.push("-threads".to_string());
+            args.push(threads.to_string());
+        }
+
+        args
+    }
 
+    /// The HLS muxer settings, extra arguments and output path
+    fn push_output_args(&self, args: &mut Vec<String>) {
         if let Some(hls_conf) = &self.hls_config {
             args.push("-hls_time".to_string());
             args.push(hls_conf.hls_time.to_string());
@@ -87,24 +361,28 @@ impl FfmpegCommand {
             args.push("-hls_segment_filename".to_string());
             args.push(hls_conf.segment_filename_pattern.to_string());
 
+            if let Some(init_filename) = &self.fmp4_init_filename {
+                args.push("-hls_segment_type".to_string());
+                args.push("fmp4".to_string());
+                args.push("-hls_fmp4_init_filename".to_string());
+                args.push(init_filename.to_string());
+            }
+
             if let Some(base_url) = &hls_conf.base_url {
                 args.push("-hls_base_url".to_string());
                 args.push(base_url.to_string());
             }
 
+            // ffmpeg reads the IV from the third line of the key info file
             if let Some(encryption_config) = &hls_conf.encryption_config {
                 args.push("-hls_key_info_file".to_string());
                 args.push(encryption_config.encryption_key_path.to_string());
-                if let Some(iv) = &encryption_config.iv {
-                    args.push("-hls_iv".to_string());
-                    args.push(iv.to_string());
-                }
             }
         }
 
-        args.push(self.output_path.to_str().unwrap_or_default().to_string());
+        args.extend(self.extra_args.iter().cloned());
 
-        args
+        args.push(self.output_path.to_str().unwrap_or_default().to_string());
     }
 }
 
@@ -162,17 +440,7 @@ impl FfmpegCommandBuilder {
     }
 
     pub fn preset(mut self, name: &str) -> Self {
-        let valid_presets = [
-            "ultrafast",
-            "superfast",
-            "fast",
-            "medium",
-            "slow",
-            "slower",
-            "veryslow",
-            "none",
-        ];
-        if !valid_presets.contains(&name) {
+        if name != "none" && FfmpegVideoProcessingPreset::from_value(name).is_none() {
             self.build_errors
                 .push(FfmpegCommandBuilderError::FfmpegSettingError(format!(
                     "Preset '{name}' is not a recognized FFmpeg preset.",
@@ -213,6 +481,109 @@ impl FfmpegCommandBuilder {
         self
     }
 
+    /// Makes ffmpeg report machine-readable progress on stdout
+    pub fn enable_progress(mut self) -> Self {
+        self.command.progress = true;
+        self
+    }
+
+    /// Appends raw arguments right before the output path, e.g. `&["-tune", "film"]`
+    pub fn extra_args(mut self, args: &[&str]) -> Self {
+        self.command
+            .extra_args
+            .extend(args.iter().map(|arg| arg.to_string()));
+        self
+    }
+
+    /// Runs this ffmpeg binary instead of the one found in PATH
+    pub fn binary<P: AsRef<Path>>(mut self, binary: P) -> Self {
+        self.command.binary = Some(binary.as_ref().to_path_buf());
+        self
+    }
+
+    /// Deinterlaces the input (`yadif`) before scaling
+    pub fn deinterlace(mut self) -> Self {
+        self.command.deinterlace = true;
+        self
+    }
+
+    /// Tone-maps an HDR input to BT.709 SDR before scaling (needs ffmpeg built with zimg)
+    pub fn tone_map_to_sdr(mut self) -> Self {
+        self.command.tone_map = true;
+        self
+    }
+
+    /// Encodes 10-bit HEVC tagged with the BT.2020 primaries and the PQ or HLG transfer of
+    /// `video_range`, instead of 8-bit H.264
+    pub fn video_range(mut self, video_range: VideoRange) -> Self {
+        self.command.video_range = video_range;
+        self
+    }
+
+    /// Outputs a constant frame rate, duplicating or dropping frames of variable frame rate
+    /// inputs so audio stays in sync
+    pub fn constant_frame_rate(mut self, frame_rate: FrameRate) -> Self {
+        self.command.frame_rate = Some(frame_rate);
+        self
+    }
+
+    /// Forces keyframes at exactly these times, e.g. on scene cuts, and nowhere else
+    pub fn force_keyframes(mut self, times: &[Duration]) -> Self {
+        self.command.keyframes = times.to_vec();
+        self
+    }
+
+    /// Forces a keyframe every `interval` and disables scene-cut keyframes, so segments can cut
+    /// on a fixed grid. Ignored when `force_keyframes` sets the keyframes
+    pub fn keyframe_interval(mut self, interval: Duration) -> Self {
+        self.command.keyframe_interval = Some(interval);
+        self
+    }
+
+    /// Encodes the audio as `codec` at `bitrate`, ffmpeg's default encoder otherwise
+    pub fn audio(mut self, codec: HlsVideoAudioCodec, bitrate: HlsVideoAudioBitrate) -> Self {
+        self.command.audio = Some((codec, bitrate));
+        self
+    }
+
+    /// Resamples drifting audio and/or shifts it against the video
+    pub fn audio_sync(mut self, audio_sync: AudioSync) -> Self {
+        self.command.audio_sync = audio_sync;
+        self
+    }
+
+    /// Copies the first video stream and every audio stream instead of encoding, ignoring the
+    /// filter, encoder and keyframe settings
+    pub fn copy_streams(mut self) -> Self {
+        self.command.copy_streams = true;
+        self
+    }
+
+    /// Caps the number of encoder threads
+    pub fn threads(mut self, threads: u32) -> Self {
+        self.command.threads = Some(threads);
+        self
+    }
+
+    /// Encodes the video as `codec`, H.264 by default. AV1 needs `fmp4` HLS output
+    pub fn video_codec(mut self, codec: HlsVideoCodec) -> Self {
+        self.command.video_codec = codec;
+        self
+    }
+
+    /// Encodes only `chunk` of the input, its timestamps starting over from zero
+    pub fn source_chunk(mut self, chunk: SourceChunk) -> Self {
+        self.command.source_chunk = Some(chunk);
+        self
+    }
+
+    /// Writes fMP4 segments, with the initialization section in `init_filename` next to the
+    /// playlist
+    pub fn fmp4(mut self, init_filename: &str) -> Self {
+        self.command.fmp4_init_filename = Some(init_filename.to_string());
+        self
+    }
+
     pub fn build(&mut self) -> Result<Vec<String>, FfmpegCommandBuilderError> {
         if !self.build_errors.is_empty() {
             let error_messages: Vec<String> =
@@ -256,6 +627,33 @@ impl FfmpegCommandBuilder {
             ));
         }
 
+        if matches!(self.command.audio, Some((HlsVideoAudioCodec::Vorbis, _)))
+            && !self.command.copy_streams
+            && self.command.hls_config.is_some()
+            && self.command.fmp4_init_filename.is_none()
+        {
+            return Err(FfmpegCommandBuilderError::ConfigurationError(
+                "Vorbis audio can't be muxed into MPEG-TS segments.".to_string(),
+            ));
+        }
+
+        if self.command.video_codec == HlsVideoCodec::Av1
+            && !self.command.copy_streams
+            && self.command.hls_config.is_some()
+            && self.command.fmp4_init_filename.is_none()
+        {
+            return Err(FfmpegCommandBuilderError::ConfigurationError(
+                "AV1 can't be muxed into MPEG-TS segments, enable fMP4 using `.fmp4()`."
+                    .to_string(),
+            ));
+        }
+
         Ok(self.command.to_args())
     }
+
+    /// Validates like `build`, returning the command itself, e.g. for `FfmpegCommand::split_args`
+    pub fn build_command(&mut self) -> Result<FfmpegCommand, FfmpegCommandBuilderError> {
+        self.build()?;
+        Ok(std::mem::take(&mut self.command))
+    }
 }
@@ -0,0 +1,88 @@
+// SPDX-License-Identifier: LGPL-3.0-only
+/*
+ * Copyright © 2025 The HlsKit Project
+ *
+ * This software is licensed under the GNU Lesser General Public License v3.0 (LGPLv3).
+ * All contributions adhere to the LGPLv3 and the HlsKit Contributor License Agreement (CLA).
+ * A copy of the LGPLv3 can be found at https://www.gnu.org/licenses/lgpl-3.0.html
+ *
+ * HlsKit Contributor License Agreement
+ *
+ * By contributing to or modifying HlsKit, you agree to the following terms:
+ *
+ * 1. Collective Ownership:
+ * The HlsKit project incorporates original code and all contributions as a collective work,
+ * licensed under LGPLv3. Once submitted, contributions become part of the shared HlsKit
+ * ecosystem and cannot be reclaimed, reassigned, or withdrawn. Contributions to your own
+ * forks remain yours unless submitted here, at which point they join this collective whole under LGPLv3.
+ *
+ * 2. Definition of Contribution:
+ * You are considered a contributor if you modify the library in any form (including forks,
+ * wrappers, libraries, or extensions that alter its behavior), whether or not you submit
+ * your changes directly to this repository. All such modifications are part of the broader
+ * HlsKit ecosystem and are subject to this CLA.
+ *
+ * 3. Distribution of Modifications:
+ * If you distribute a modified version of HlsKit, you must license your modifications under
+ * LGPLv3 (with source code available as required by the license) and ensure they are
+ * adoptable by the HlsKit ecosystem (publicly available and compatible).
+ *
+ * 4. Networked Use of Modifications:
+ * If you use a modified version of HlsKit in a networked application, you must provide the
+ * source code of your modifications under LGPLv3 and notify the HlsKit project
+ * (e.g., via email to [higashikataengels@icloud.com]). This does not apply to the use of
+ * the unmodified library in proprietary software, which remains permissible under LGPLv3.
+ *
+ * 5. Scope:
+ * These terms apply to all contributions and modifications derived from the HlsKit project.
+ * The use of the unmodified library in proprietary software is governed solely by the LGPLv3.
+ */
+
+use std::time::Duration;
+
+/// A single block of key/value pairs emitted by `ffmpeg -progress`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FfmpegProgressUpdate {
+    pub frame: Option<u64>,
+    pub fps: Option<f64>,
+    pub out_time: Option<Duration>,
+    pub speed: Option<f64>,
+    pub finished: bool,
+}
+
+/// Accumulates `ffmpeg -progress pipe:1` lines into complete updates
+#[derive(Debug, Default)]
+pub struct FfmpegProgressParser {
+    current: FfmpegProgressUpdate,
+}
+
+impl FfmpegProgressParser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feeds one output line, returning an update once a `progress=` line closes the block
+    pub fn parse_line(&mut self, line: &str) -> Option<FfmpegProgressUpdate> {
+        let (key, value) = line.trim().split_once('=')?;
+        let value = value.trim();
+
+        match key {
+            "frame" => self.current.frame = value.parse().ok(),
+            "fps" => self.current.fps = value.parse().ok(),
+            // Despite the name, ffmpeg reports out_time_ms in microseconds as well
+            "out_time_us" | "out_time_ms" => {
+                if let Ok(micros) = value.parse::<u64>() {
+                    self.current.out_time = Some(Duration::from_micros(micros));
+                }
+            }
+            "speed" => self.current.speed = value.trim_end_matches('x').trim().parse().ok(),
+            "progress" => {
+                self.current.finished = value == "end";
+                return Some(std::mem::take(&mut self.current));
+            }
+            _ => {}
+        }
+
+        None
+    }
+}
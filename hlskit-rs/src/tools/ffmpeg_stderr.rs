@@ -0,0 +1,105 @@
+// SPDX-License-Identifier: LGPL-3.0-only
+/*
+ * Copyright © 2025 The HlsKit Project
+ *
+ * This software is licensed under the GNU Lesser General Public License v3.0 (LGPLv3).
+ * All contributions adhere to the LGPLv3 and the HlsKit Contributor License Agreement (CLA).
+ * A copy of the LGPLv3 can be found at https://www.gnu.org/licenses/lgpl-3.0.html
+ *
+ * HlsKit Contributor License Agreement
+ *
+ * By contributing to or modifying HlsKit, you agree to the following terms:
+ *
+ * 1. Collective Ownership:
+ * The HlsKit project incorporates original code and all contributions as a collective work,
+ * licensed under LGPLv3. Once submitted, contributions become part of the shared HlsKit
+ * ecosystem and cannot be reclaimed, reassigned, or withdrawn. Contributions to your own
+ * forks remain yours unless submitted here, at which point they join this collective whole under LGPLv3.
+ *
+ * 2. Definition of Contribution:
+ * You are considered a contributor if you modify the library in any form (including forks,
+ * wrappers, libraries, or extensions that alter its behavior), whether or not you submit
+ * your changes directly to this repository. All such modifications are part of the broader
+ * HlsKit ecosystem and are subject to this CLA.
+ *
+ * 3. Distribution of Modifications:
+ * If you distribute a modified version of HlsKit, you must license your modifications under
+ * LGPLv3 (with source code available as required by the license) and ensure they are
+ * adoptable by the HlsKit ecosystem (publicly available and compatible).
+ *
+ * 4. Networked Use of Modifications:
+ * If you use a modified version of HlsKit in a networked application, you must provide the
+ * source code of your modifications under LGPLv3 and notify the HlsKit project
+ * (e.g., via email to [higashikataengels@icloud.com]). This does not apply to the use of
+ * the unmodified library in proprietary software, which remains permissible under LGPLv3.
+ *
+ * 5. Scope:
+ * These terms apply to all contributions and modifications derived from the HlsKit project.
+ * The use of the unmodified library in proprietary software is governed solely by the LGPLv3.
+ */
+
+use crate::tools::hlskit_error::{FfmpegErrorKind, HlsKitError};
+
+/// Substrings of ffmpeg's stderr, lowercased, that identify each failure class. Checked in
+/// order, so environment problems win over the input errors they usually cause.
+const PATTERNS: [(FfmpegErrorKind, &[&str]); 6] = [
+    (
+        FfmpegErrorKind::OutOfDisk,
+        &["no space left on device", "disk quota exceeded"],
+    ),
+    (
+        FfmpegErrorKind::PermissionDenied,
+        &["permission denied", "operation not permitted"],
+    ),
+    (
+        FfmpegErrorKind::EncoderNotFound,
+        &["unknown encoder", "encoder not found"],
+    ),
+    (
+        FfmpegErrorKind::UnsupportedCodec,
+        &[
+            "unsupported codec",
+            "codec not currently supported",
+            "decoder not found",
+            "unknown decoder",
+            "no decoder for",
+        ],
+    ),
+    (
+        FfmpegErrorKind::InputNotFound,
+        &["no such file or directory"],
+    ),
+    (
+        FfmpegErrorKind::CorruptInput,
+        &[
+            "invalid data found when processing input",
+            "moov atom not found",
+            "error while decoding",
+            "could not find codec parameters",
+            "invalid nal unit",
+            "corrupt",
+        ],
+    ),
+];
+
+/// Works out why ffmpeg failed from what it printed on stderr
+pub fn classify_ffmpeg_stderr(stderr: &str) -> FfmpegErrorKind {
+    let stderr = stderr.to_lowercase();
+
+    PATTERNS
+        .iter()
+        .find(|(_, needles)| needles.iter().any(|needle| stderr.contains(needle)))
+        .map_or(FfmpegErrorKind::Other, |(kind, _)| *kind)
+}
+
+/// Turns a failed ffmpeg run into a typed `HlsKitError::FfmpegFailure`, leaving other errors
+/// untouched
+pub fn classify_ffmpeg_error(error: HlsKitError) -> HlsKitError {
+    match error {
+        HlsKitError::CommandFailed(failure) => HlsKitError::FfmpegFailure {
+            kind: classify_ffmpeg_stderr(&failure.stderr),
+            failure,
+        },
+        error => error,
+    }
+}
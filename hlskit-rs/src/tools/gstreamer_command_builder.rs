@@ -40,19 +40,46 @@
 
 use std::path::{Path, PathBuf};
 
-use crate::tools::{
-    hlskit_error::GStreamerCommandBuilderError,
-    internals::hls_output_config::{HlsOutputConfig, HlsOutputEncryptionConfig},
+use crate::{
+    models::{
+        frame_rate::FrameRate,
+        hls_video_processing_settings::{
+            FfmpegVideoProcessingPreset, HlsVideoAudioBitrate, HlsVideoAudioCodec, HlsVideoCodec,
+        },
+    },
+    tools::{
+        hlskit_error::GStreamerCommandBuilderError, internals::hls_output_config::HlsOutputConfig,
+    },
+    traits::video_processing_backend::STDIN_INPUT,
 };
 
+/// The GStreamer encoder element of `codec`
+pub fn gstreamer_video_encoder(codec: HlsVideoCodec) -> &'static str {
+    match codec {
+        HlsVideoCodec::H264 => "x264enc",
+        HlsVideoCodec::Hevc => "x265enc",
+        HlsVideoCodec::Av1 => "svtav1enc",
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct GStreamerCommand {
+    binary: Option<PathBuf>,
     input_path: PathBuf,
     output_path: PathBuf,
     width: i32,
     height: i32,
     bitrate: i32,
+    preset: Option<FfmpegVideoProcessingPreset>,
+    video_codec: HlsVideoCodec,
     hls_config: Option<HlsOutputConfig>,
+    deinterlace: bool,
+    frame_rate: Option<FrameRate>,
+    keyframe_distance: Option<u32>,
+    threads: Option<u32>,
+    audio: Option<(HlsVideoAudioCodec, HlsVideoAudioBitrate)>,
+    init_location: Option<String>,
+    progress: bool,
 }
 
 #[derive(Debug, Default)]
@@ -107,12 +134,18 @@ impl GStreamerCommandBuilder {
         self
     }
 
+    /// Encoder speed preset, `medium` by default. x264enc and x265enc share ffmpeg's preset
+    /// names, svtav1enc gets the numeric preset of a similar speed
+    pub fn preset(mut self, preset: FfmpegVideoProcessingPreset) -> Self {
+        self.command.preset = Some(preset);
+        self
+    }
+
     pub fn enable_hls(
         mut self,
         segment_pattern: &str,
         playlist_type: Option<&str>,
         base_url: Option<&str>,
-        encryption: Option<HlsOutputEncryptionConfig>,
         hls_time: i32,
     ) -> Self {
         if !segment_pattern.contains('%') {
@@ -127,13 +160,71 @@ impl GStreamerCommandBuilder {
             segment_filename_pattern: segment_pattern.to_string(),
             playlist_type: playlist_type.map(String::from),
             base_url: base_url.map(String::from),
-            encryption_config: encryption,
+            encryption_config: None,
             hls_time,
         });
 
         self
     }
 
+    /// Runs this gst-launch binary instead of the one found in PATH
+    pub fn binary<P: AsRef<Path>>(mut self, binary: P) -> Self {
+        self.command.binary = Some(binary.as_ref().to_path_buf());
+        self
+    }
+
+    /// Deinterlaces the decoded video before scaling
+    pub fn deinterlace(mut self) -> Self {
+        self.command.deinterlace = true;
+        self
+    }
+
+    /// Outputs a constant frame rate through `videorate`
+    pub fn constant_frame_rate(mut self, frame_rate: FrameRate) -> Self {
+        self.command.frame_rate = Some(frame_rate);
+        self
+    }
+
+    /// Caps the number of encoder threads
+    pub fn threads(mut self, threads: u32) -> Self {
+        self.command.threads = Some(threads);
+        self
+    }
+
+    /// Starts a new GOP at least every `frames` frames
+    pub fn max_keyframe_distance(mut self, frames: u32) -> Self {
+        self.command.keyframe_distance = Some(frames.max(1));
+        self
+    }
+
+    /// Encodes the first audio stream of the input next to the video. The source must have
+    /// one, or the muxer waits for audio forever. Left out of `fmp4` output
+    pub fn audio(mut self, codec: HlsVideoAudioCodec, bitrate: HlsVideoAudioBitrate) -> Self {
+        self.command.audio = Some((codec, bitrate));
+        self
+    }
+
+    /// Makes gst-launch print the bus messages of a `progressreport` element on stdout, see
+    /// `GStreamerProgressParser`
+    pub fn enable_progress(mut self) -> Self {
+        self.command.progress = true;
+        self
+    }
+
+    /// Encodes the video through x265enc or svtav1enc instead of x264enc. AV1 needs `fmp4`,
+    /// so its renditions are video only
+    pub fn video_codec(mut self, codec: HlsVideoCodec) -> Self {
+        self.command.video_codec = codec;
+        self
+    }
+
+    /// Writes fMP4 segments through `hlscmafsink`, with the initialization section at
+    /// `init_location`
+    pub fn fmp4(mut self, init_location: &str) -> Self {
+        self.command.init_location = Some(init_location.to_string());
+        self
+    }
+
     pub fn build(&mut self) -> Result<Vec<String>, GStreamerCommandBuilderError> {
         if !self.errors.is_empty() {
             return Err(self.errors.remove(0));
@@ -159,27 +250,192 @@ impl GStreamerCommandBuilder {
             ));
         }
 
+        // hlscmafsink carries a single stream
+        if self.command.init_location.is_some() && self.command.audio.take().is_some() {
+            tracing::warn!("fMP4 output of the GStreamer backend leaves the audio out");
+        }
+
+        if matches!(self.command.audio, Some((HlsVideoAudioCodec::Vorbis, _))) {
+            return Err(GStreamerCommandBuilderError::InvalidConfig(
+                "Vorbis audio can't be muxed into MPEG-TS segments.".to_string(),
+            ));
+        }
+
+        if self.command.video_codec == HlsVideoCodec::Av1 && self.command.init_location.is_none() {
+            return Err(GStreamerCommandBuilderError::InvalidConfig(
+                "AV1 can't be muxed into MPEG-TS segments, enable fMP4 output.".to_string(),
+            ));
+        }
+
         Ok(self.command.to_args())
     }
+
+    /// Validates like `build`, returning the command itself, e.g. for `GStreamerCommand::tee_args`
+    pub fn build_command(&mut self) -> Result<GStreamerCommand, GStreamerCommandBuilderError> {
+        self.build()?;
+        Ok(std::mem::take(&mut self.command))
+    }
 }
 
 impl GStreamerCommand {
     pub fn to_args(&self) -> Vec<String> {
-        let mut args = vec!["gst-launch-1.0".to_string()];
+        let mut args = self.source_args();
+        if self.audio.is_some() {
+            args.push("name=dec dec. ! queue".to_string());
+        }
+        args.extend(self.decoded_video_args());
+        args.extend(self.video_encoder_args());
+        self.push_output_args(
+            &mut args,
+            "mux",
+            "dec. ! queue ! audioconvert ! audioresample",
+        );
+
+        args
+    }
+
+    /// One pipeline decoding the input of `commands` once and teeing the decoded video and
+    /// audio into a scale, encode and sink branch per command. The input, progress and
+    /// deinterlacing settings are taken from the first command
+    pub fn tee_args(commands: &[GStreamerCommand]) -> Vec<String> {
+        let Some(first) = commands.first() else {
+            return Vec::new();
+        };
+
+        let mut args = first.source_args();
+        args.push("name=dec dec. ! queue".to_string());
+        args.extend(first.decoded_video_args());
+        args.push("! tee name=vt".to_string());
+        if commands.iter().any(|command| command.audio.is_some()) {
+            args.push("dec. ! queue ! audioconvert ! audioresample ! tee name=at".to_string());
+        }
+
+        for (index, command) in commands.iter().enumerate() {
+            args.push("vt. ! queue".to_string());
+            args.extend(command.video_encoder_args());
+            command.push_output_args(&mut args, &format!("mux{index}"), "at. ! queue");
+        }
+
+        args
+    }
+
+    /// The binary and the decoded input, up to `decodebin`
+    fn source_args(&self) -> Vec<String> {
+        let mut args = vec![self.binary.as_ref().map_or_else(
+            || "gst-launch-1.0".to_string(),
+            |binary| binary.display().to_string(),
+        )];
+
+        if self.progress {
+            args.push("-m".to_string());
+        }
 
-        args.push("filesrc".to_string());
-        args.push(format!("location={}", self.input_path.display()));
+        if self.input_path == Path::new(STDIN_INPUT) {
+            args.push("fdsrc fd=0".to_string());
+        } else {
+            args.push("filesrc".to_string());
+            args.push(format!("location={}", self.input_path.display()));
+        }
         args.push("! decodebin".to_string());
-        args.push("! videoconvert ! videoscale".to_string());
-        args.push(format!(
-            "! video/x-raw,width={},height={}",
-            self.width, self.height
-        ));
-        args.push(format!(
-            "! x264enc bitrate={} speed-preset=medium tune=zerolatency",
-            self.bitrate
-        ));
-        args.push("! mpegtsmux".to_string());
+
+        args
+    }
+
+    /// Elements applied to the decoded video before scaling
+    fn decoded_video_args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        if self.progress {
+            args.push("! progressreport update-freq=1 silent=true".to_string());
+        }
+        if self.deinterlace {
+            args.push("! deinterlace".to_string());
+        }
+
+        args
+    }
+
+    /// Scaling and encoding of the decoded video
+    fn video_encoder_args(&self) -> Vec<String> {
+        let mut args = vec!["! videoconvert ! videoscale".to_string()];
+        match self.frame_rate {
+            Some(frame_rate) => {
+                args.push("! videorate".to_string());
+                args.push(format!(
+                    "! video/x-raw,width={},height={},framerate={}",
+                    self.width, self.height, frame_rate
+                ));
+            }
+            None => args.push(format!(
+                "! video/x-raw,width={},height={}",
+                self.width, self.height
+            )),
+        }
+        // No `tune=zerolatency`, it disables B-frames and lookahead for nothing in VOD output
+        let preset = self.preset.unwrap_or(FfmpegVideoProcessingPreset::Medium);
+        let encoder = gstreamer_video_encoder(self.video_codec);
+        match self.video_codec {
+            HlsVideoCodec::H264 | HlsVideoCodec::Hevc => args.push(format!(
+                "! {encoder} bitrate={} speed-preset={}",
+                self.bitrate,
+                preset.value()
+            )),
+            HlsVideoCodec::Av1 => args.push(format!(
+                "! {encoder} target-bitrate={} preset={}",
+                self.bitrate,
+                preset.svt_av1_value()
+            )),
+        }
+        if let Some(frames) = self.keyframe_distance {
+            args.push(match self.video_codec {
+                HlsVideoCodec::H264 | HlsVideoCodec::Hevc => format!("key-int-max={frames}"),
+                HlsVideoCodec::Av1 => format!("intra-period-length={frames}"),
+            });
+        }
+        if let Some(threads) = self.threads {
+            args.push(match self.video_codec {
+                HlsVideoCodec::H264 => format!("threads={threads}"),
+                // x265enc only takes its thread pool size through x265 options
+                HlsVideoCodec::Hevc => format!("option-string=pools={threads}"),
+                HlsVideoCodec::Av1 => format!("logical-processors={threads}"),
+            });
+        }
+
+        args
+    }
+
+    /// Muxing and sink of the encoded video, encoding the audio read from `audio_source` into
+    /// the muxer named `mux`
+    fn push_output_args(&self, args: &mut Vec<String>, mux: &str, audio_source: &str) {
+        if let (Some(init_location), Some(hls)) = (&self.init_location, &self.hls_config) {
+            let parser = match self.video_codec {
+                HlsVideoCodec::H264 => "h264parse",
+                HlsVideoCodec::Hevc => "h265parse",
+                HlsVideoCodec::Av1 => "av1parse",
+            };
+            args.push(format!("! {parser} ! hlscmafsink"));
+            args.push(format!("playlist-location={}", self.output_path.display()));
+            args.push(format!("location={}", hls.segment_filename_pattern));
+            args.push(format!("init-location={init_location}"));
+            args.push(format!("target-duration={}", hls.hls_time));
+            return;
+        }
+
+        if let Some((codec, bitrate)) = self.audio {
+            args.push(format!("! {mux}. {audio_source}"));
+            args.push(match codec {
+                HlsVideoAudioCodec::Mp3 => format!(
+                    "! lamemp3enc target=bitrate cbr=true bitrate={} ! mpegaudioparse",
+                    bitrate.bits_per_second() / 1000
+                ),
+                _ => format!(
+                    "! voaacenc bitrate={} ! aacparse",
+                    bitrate.bits_per_second()
+                ),
+            });
+            args.push(format!("! {mux}. mpegtsmux name={mux}"));
+        } else {
+            args.push("! mpegtsmux".to_string());
+        }
 
         if let Some(hls) = &self.hls_config {
             args.push("! hlssink".to_string());
@@ -188,37 +444,67 @@ impl GStreamerCommand {
 
             args.push(format!("location={}", hls.segment_filename_pattern));
             args.push(format!("target-duration={}", hls.hls_time));
-
-            if let Some(enc) = &hls.encryption_config {
-                args.push(format!("key-file={}", enc.encryption_key_path));
-
-                // Use base_url as the prefix to form the full key URI
-                let key_filename = std::path::Path::new(&enc.encryption_key_path)
-                    .file_name()
-                    .unwrap_or_default()
-                    .to_string_lossy();
-
-                if let Some(base_url) = &hls.base_url {
-                    let mut key_uri = base_url.clone();
-                    if !key_uri.ends_with('/') {
-                        key_uri.push('/');
-                    }
-                    key_uri.push_str(&key_filename);
-                    args.push(format!("key-uri={key_uri}"));
-                } else {
-                    // Fallback to just using the filename
-                    args.push(format!("key-uri={key_filename}"));
-                }
-
-                if let Some(iv) = &enc.iv {
-                    args.push(format!("iv={iv}"));
-                }
-            }
         } else {
             args.push("! filesink".to_string());
             args.push(format!("location={}", self.output_path.display()));
         }
+    }
+}
 
-        args
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fmp4_builder() -> GStreamerCommandBuilder {
+        GStreamerCommandBuilder::new()
+            .input("input.mp4")
+            .output("out/playlist_0.m3u8")
+            .dimensions(1280, 720)
+            .bitrate(3000)
+            .enable_hls("out/data_0_%03d.m4s", None, None, 6)
+            .fmp4("out/init_0.mp4")
+    }
+
+    #[test]
+    fn fmp4_output_leaves_audio_out() {
+        let args = fmp4_builder()
+            .audio(HlsVideoAudioCodec::Aac, HlsVideoAudioBitrate::Medium)
+            .build()
+            .expect("fMP4 with audio should build");
+        let pipeline = args.join(" ");
+
+        assert!(pipeline.contains("hlscmafsink"));
+        assert!(!pipeline.contains("voaacenc"));
+        assert!(!pipeline.contains("dec."));
+    }
+
+    #[test]
+    fn av1_builds_for_sources_with_audio() {
+        let args = fmp4_builder()
+            .video_codec(HlsVideoCodec::Av1)
+            .audio(HlsVideoAudioCodec::Aac, HlsVideoAudioBitrate::Medium)
+            .build()
+            .expect("AV1 with audio should build");
+        let pipeline = args.join(" ");
+
+        assert!(pipeline.contains("svtav1enc"));
+        assert!(pipeline.contains("av1parse ! hlscmafsink"));
+    }
+
+    #[test]
+    fn mpeg_ts_output_keeps_audio() {
+        let args = GStreamerCommandBuilder::new()
+            .input("input.mp4")
+            .output("out/playlist_0.m3u8")
+            .dimensions(1280, 720)
+            .bitrate(3000)
+            .enable_hls("out/data_0_%03d.ts", None, None, 6)
+            .audio(HlsVideoAudioCodec::Aac, HlsVideoAudioBitrate::Medium)
+            .build()
+            .expect("MPEG-TS with audio should build");
+        let pipeline = args.join(" ");
+
+        assert!(pipeline.contains("voaacenc bitrate=256000"));
+        assert!(pipeline.contains("mpegtsmux name=mux"));
     }
 }
@@ -0,0 +1,98 @@
+// SPDX-License-Identifier: LGPL-3.0-only
+/*
+ * Copyright © 2025 The HlsKit Project
+ *
+ * This software is licensed under the GNU Lesser General Public License v3.0 (LGPLv3).
+ * All contributions adhere to the LGPLv3 and the HlsKit Contributor License Agreement (CLA).
+ * A copy of the LGPLv3 can be found at https://www.gnu.org/licenses/lgpl-3.0.html
+ *
+ * HlsKit Contributor License Agreement
+ *
+ * By contributing to or modifying HlsKit, you agree to the following terms:
+ *
+ * 1. Collective Ownership:
+ * The HlsKit project incorporates original code and all contributions as a collective work,
+ * licensed under LGPLv3. Once submitted, contributions become part of the shared HlsKit
+ * ecosystem and cannot be reclaimed, reassigned, or withdrawn. Contributions to your own
+ * forks remain yours unless submitted here, at which point they join this collective whole under LGPLv3.
+ *
+ * 2. Definition of Contribution:
+ * You are considered a contributor if you modify the library in any form (including forks,
+ * wrappers, libraries, or extensions that alter its behavior), whether or not you submit
+ * your changes directly to this repository. All such modifications are part of the broader
+ * HlsKit ecosystem and are subject to this CLA.
+ *
+ * 3. Distribution of Modifications:
+ * If you distribute a modified version of HlsKit, you must license your modifications under
+ * LGPLv3 (with source code available as required by the license) and ensure they are
+ * adoptable by the HlsKit ecosystem (publicly available and compatible).
+ *
+ * 4. Networked Use of Modifications:
+ * If you use a modified version of HlsKit in a networked application, you must provide the
+ * source code of your modifications under LGPLv3 and notify the HlsKit project
+ * (e.g., via email to [higashikataengels@icloud.com]). This does not apply to the use of
+ * the unmodified library in proprietary software, which remains permissible under LGPLv3.
+ *
+ * 5. Scope:
+ * These terms apply to all contributions and modifications derived from the HlsKit project.
+ * The use of the unmodified library in proprietary software is governed solely by the LGPLv3.
+ */
+
+use std::time::Duration;
+
+/// A `progress` message posted by `progressreport` on the pipeline bus
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GStreamerProgressUpdate {
+    /// Stream time reached, when the element reports in time format
+    pub out_time: Option<Duration>,
+    /// Stream duration, when known
+    pub duration: Option<Duration>,
+    pub percent: Option<f64>,
+}
+
+/// Picks the `progressreport` messages out of `gst-launch-1.0 -m` output
+#[derive(Debug, Default)]
+pub struct GStreamerProgressParser;
+
+impl GStreamerProgressParser {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Feeds one output line, e.g. `Got message #42 from element "progressreport0" (element):
+    /// progress, percent=(int)25, percent-double=(double)25, timestamp=..., current=(gint64)5,
+    /// total=(gint64)20, format=(string)time;`
+    pub fn parse_line(&self, line: &str) -> Option<GStreamerProgressUpdate> {
+        let (_, structure) = line.split_once("(element): progress,")?;
+
+        let field = |name: &str| {
+            structure
+                .split(',')
+                .filter_map(|field| field.trim().split_once('='))
+                .find(|(key, _)| *key == name)
+                .map(|(_, value)| {
+                    // Values are typed, e.g. `(gint64)5`
+                    let value = value.trim().trim_end_matches(';');
+                    value.split_once(')').map_or(value, |(_, value)| value)
+                })
+        };
+
+        let time_format = field("format") == Some("time");
+        let seconds = |name: &str| {
+            field(name)
+                .filter(|_| time_format)
+                .and_then(|value| value.parse::<i64>().ok())
+                .filter(|seconds| *seconds >= 0)
+                .map(|seconds| Duration::from_secs(seconds as u64))
+        };
+
+        Some(GStreamerProgressUpdate {
+            out_time: seconds("current"),
+            duration: seconds("total"),
+            percent: field("percent-double")
+                .or_else(|| field("percent"))
+                .and_then(|value| value.parse::<f64>().ok())
+                .map(|percent| percent.clamp(0.0, 100.0)),
+        })
+    }
+}
@@ -0,0 +1,136 @@
+// SPDX-License-Identifier: LGPL-3.0-only
+/*
+ * Copyright © 2025 The HlsKit Project
+ *
+ * This software is licensed under the GNU Lesser General Public License v3.0 (LGPLv3).
+ * All contributions adhere to the LGPLv3 and the HlsKit Contributor License Agreement (CLA).
+ * A copy of the LGPLv3 can be found at https://www.gnu.org/licenses/lgpl-3.0.html
+ *
+ * HlsKit Contributor License Agreement
+ *
+ * By contributing to or modifying HlsKit, you agree to the following terms:
+ *
+ * 1. Collective Ownership:
+ * The HlsKit project incorporates original code and all contributions as a collective work,
+ * licensed under LGPLv3. Once submitted, contributions become part of the shared HlsKit
+ * ecosystem and cannot be reclaimed, reassigned, or withdrawn. Contributions to your own
+ * forks remain yours unless submitted here, at which point they join this collective whole under LGPLv3.
+ *
+ * 2. Definition of Contribution:
+ * You are considered a contributor if you modify the library in any form (including forks,
+ * wrappers, libraries, or extensions that alter its behavior), whether or not you submit
+ * your changes directly to this repository. All such modifications are part of the broader
+ * HlsKit ecosystem and are subject to this CLA.
+ *
+ * 3. Distribution of Modifications:
+ * If you distribute a modified version of HlsKit, you must license your modifications under
+ * LGPLv3 (with source code available as required by the license) and ensure they are
+ * adoptable by the HlsKit ecosystem (publicly available and compatible).
+ *
+ * 4. Networked Use of Modifications:
+ * If you use a modified version of HlsKit in a networked application, you must provide the
+ * source code of your modifications under LGPLv3 and notify the HlsKit project
+ * (e.g., via email to [higashikataengels@icloud.com]). This does not apply to the use of
+ * the unmodified library in proprietary software, which remains permissible under LGPLv3.
+ *
+ * 5. Scope:
+ * These terms apply to all contributions and modifications derived from the HlsKit project.
+ * The use of the unmodified library in proprietary software is governed solely by the LGPLv3.
+ */
+
+use crate::tools::hlskit_error::{GStreamerErrorKind, HlsKitError};
+
+/// Substrings of gst-launch's error and warning messages, lowercased, that identify each
+/// failure class. Checked in order, a missing decoder also failing caps negotiation downstream.
+const PATTERNS: [(GStreamerErrorKind, &[&str]); 6] = [
+    (
+        GStreamerErrorKind::OutOfDisk,
+        &["no space left on device", "disk quota exceeded"],
+    ),
+    (
+        GStreamerErrorKind::PermissionDenied,
+        &["permission denied", "operation not permitted"],
+    ),
+    (
+        GStreamerErrorKind::MissingPlugin,
+        &[
+            "no element \"",
+            "missing a plug-in",
+            "no such element or plugin",
+        ],
+    ),
+    (
+        GStreamerErrorKind::InputNotFound,
+        &["resource not found", "no such file or directory"],
+    ),
+    (
+        GStreamerErrorKind::CapsNegotiation,
+        &["not-negotiated", "could not link", "delayed linking failed"],
+    ),
+    (
+        GStreamerErrorKind::DecodeError,
+        &[
+            "could not decode stream",
+            "could not determine type of stream",
+            "stream doesn't contain enough data",
+            "decoding error",
+            "corrupt",
+        ],
+    ),
+];
+
+/// Works out why gst-launch failed from what it printed on stderr
+pub fn classify_gstreamer_stderr(stderr: &str) -> GStreamerErrorKind {
+    let stderr = stderr.to_lowercase();
+
+    PATTERNS
+        .iter()
+        .find(|(_, needles)| needles.iter().any(|needle| stderr.contains(needle)))
+        .map_or(GStreamerErrorKind::Other, |(kind, _)| *kind)
+}
+
+/// The first error gst-launch reported, e.g. `filesrc0: Resource not found.` out of
+/// `ERROR: from element /GstPipeline:pipeline0/GstFileSrc:filesrc0: Resource not found.`,
+/// or `no element "x265enc"` out of `WARNING: erroneous pipeline: no element "x265enc"`
+pub fn gstreamer_error_message(stderr: &str) -> Option<String> {
+    stderr.lines().find_map(|line| {
+        let line = line.trim();
+        if let Some(error) = line.strip_prefix("ERROR: ") {
+            return Some(match error.strip_prefix("from element ") {
+                Some(from_element) => match from_element.split_once(": ") {
+                    Some((path, message)) => {
+                        let element = path.rsplit(':').next().unwrap_or(path);
+                        format!("{element}: {message}")
+                    }
+                    None => from_element.to_string(),
+                },
+                None => error.to_string(),
+            });
+        }
+
+        line.strip_prefix("WARNING: erroneous pipeline: ")
+            .map(str::to_string)
+    })
+}
+
+/// Turns a failed gst-launch run into a typed `HlsKitError::GstreamerError`, leaving other
+/// errors untouched
+pub fn classify_gstreamer_error(error: HlsKitError) -> HlsKitError {
+    match error {
+        HlsKitError::CommandFailed(failure) => HlsKitError::GstreamerError {
+            kind: classify_gstreamer_stderr(&failure.stderr),
+            message: gstreamer_error_message(&failure.stderr).unwrap_or_else(|| {
+                failure
+                    .stderr
+                    .lines()
+                    .rev()
+                    .find(|line| !line.trim().is_empty())
+                    .unwrap_or_default()
+                    .trim()
+                    .to_string()
+            }),
+            failure,
+        },
+        error => error,
+    }
+}
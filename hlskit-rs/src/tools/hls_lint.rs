@@ -0,0 +1,482 @@
+// SPDX-License-Identifier: LGPL-3.0-only
+/*
+ * Copyright © 2025 The HlsKit Project
+ *
+ * This software is licensed under the GNU Lesser General Public License v3.0 (LGPLv3).
+ * All contributions adhere to the LGPLv3 and the HlsKit Contributor License Agreement (CLA).
+ * A copy of the LGPLv3 can be found at https://www.gnu.org/licenses/lgpl-3.0.html
+ *
+ * HlsKit Contributor License Agreement
+ *
+ * By contributing to or modifying HlsKit, you agree to the following terms:
+ *
+ * 1. Collective Ownership:
+ * The HlsKit project incorporates original code and all contributions as a collective work,
+ * licensed under LGPLv3. Once submitted, contributions become part of the shared HlsKit
+ * ecosystem and cannot be reclaimed, reassigned, or withdrawn. Contributions to your own
+ * forks remain yours unless submitted here, at which point they join this collective whole under LGPLv3.
+ *
+ * 2. Definition of Contribution:
+ * You are considered a contributor if you modify the library in any form (including forks,
+ * wrappers, libraries, or extensions that alter its behavior), whether or not you submit
+ * your changes directly to this repository. All such modifications are part of the broader
+ * HlsKit ecosystem and are subject to this CLA.
+ *
+ * 3. Distribution of Modifications:
+ * If you distribute a modified version of HlsKit, you must license your modifications under
+ * LGPLv3 (with source code available as required by the license) and ensure they are
+ * adoptable by the HlsKit ecosystem (publicly available and compatible).
+ *
+ * 4. Networked Use of Modifications:
+ * If you use a modified version of HlsKit in a networked application, you must provide the
+ * source code of your modifications under LGPLv3 and notify the HlsKit project
+ * (e.g., via email to [higashikataengels@icloud.com]). This does not apply to the use of
+ * the unmodified library in proprietary software, which remains permissible under LGPLv3.
+ *
+ * 5. Scope:
+ * These terms apply to all contributions and modifications derived from the HlsKit project.
+ * The use of the unmodified library in proprietary software is governed solely by the LGPLv3.
+ */
+
+use crate::{
+    models::{
+        hls_video::{HlsVideo, HlsVideoResolution, MASTER_PLAYLIST_NAME},
+        lint_finding::{LintFinding, LintRule},
+        validation_report::FindingSeverity,
+    },
+    tools::m3u8_tools::parse_attribute_list,
+};
+
+/// Target duration recommended by the authoring specification, in seconds
+const RECOMMENDED_TARGET_DURATION: u64 = 6;
+
+/// Gap between the segment boundaries of two variants, in seconds, above which they are not
+/// considered aligned
+const ALIGNMENT_TOLERANCE: f64 = 0.1;
+
+const TS_PACKET_SIZE: usize = 188;
+
+/// Checks `video` against the key rules of Apple's HLS authoring specification, the way
+/// mediastreamvalidator would: variant attributes, segment durations, IDR frames at the start
+/// of segments, aligned variants, an audio-only variant and I-frame playlists
+pub fn lint(video: &HlsVideo) -> Vec<LintFinding> {
+    let mut findings = Vec::new();
+    let master_playlist = String::from_utf8_lossy(&video.master_m3u8_data);
+    lint_master_playlist(&master_playlist, &mut findings);
+
+    let mut boundaries = Vec::with_capacity(video.resolutions.len());
+    for resolution in &video.resolutions {
+        if let Some(durations) = lint_media_playlist(resolution, &mut findings) {
+            boundaries.push((resolution.playlist_name.as_str(), durations));
+        }
+    }
+    lint_alignment(&boundaries, &mut findings);
+
+    findings
+}
+
+fn push(
+    findings: &mut Vec<LintFinding>,
+    rule: LintRule,
+    severity: FindingSeverity,
+    file: Option<&str>,
+    message: String,
+) {
+    findings.push(LintFinding {
+        rule,
+        severity,
+        file: file.map(str::to_string),
+        message,
+    });
+}
+
+fn lint_master_playlist(master_playlist: &str, findings: &mut Vec<LintFinding>) {
+    use FindingSeverity::{Error, Warning};
+
+    if !master_playlist.trim_start().starts_with("#EXTM3U") {
+        push(
+            findings,
+            LintRule::PlaylistSyntax,
+            Error,
+            Some(MASTER_PLAYLIST_NAME),
+            "missing #EXTM3U header".to_string(),
+        );
+    }
+    if !master_playlist.contains("#EXT-X-INDEPENDENT-SEGMENTS") {
+        push(
+            findings,
+            LintRule::PlaylistSyntax,
+            Warning,
+            Some(MASTER_PLAYLIST_NAME),
+            "#EXT-X-INDEPENDENT-SEGMENTS should be present".to_string(),
+        );
+    }
+
+    let mut has_audio_only = false;
+    let mut variant_count = 0;
+    let mut lines = master_playlist.lines().map(str::trim);
+    while let Some(line) = lines.next() {
+        let Some(attributes) = line.strip_prefix("#EXT-X-STREAM-INF:") else {
+            continue;
+        };
+        variant_count += 1;
+        let attributes = parse_attribute_list(attributes);
+        let uri = lines
+            .by_ref()
+            .find(|line| !line.is_empty() && !line.starts_with('#'))
+            .unwrap_or("?");
+        let attribute = |name: &str| {
+            attributes
+                .iter()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.as_str())
+        };
+
+        if attribute("BANDWIDTH").is_none() {
+            push(
+                findings,
+                LintRule::VariantAttributes,
+                Error,
+                Some(MASTER_PLAYLIST_NAME),
+                format!("variant {uri} has no BANDWIDTH"),
+            );
+        }
+        if attribute("AVERAGE-BANDWIDTH").is_none() {
+            push(
+                findings,
+                LintRule::VariantAttributes,
+                Warning,
+                Some(MASTER_PLAYLIST_NAME),
+                format!("variant {uri} should declare AVERAGE-BANDWIDTH"),
+            );
+        }
+
+        let codecs = attribute("CODECS");
+        match codecs {
+            None => push(
+                findings,
+                LintRule::Codecs,
+                Error,
+                Some(MASTER_PLAYLIST_NAME),
+                format!("variant {uri} has no CODECS"),
+            ),
+            Some(codecs) => {
+                let video_codecs = ["avc1", "avc3", "hvc1", "hev1", "dvh1", "dvhe", "av01"];
+                let audio_only = !codecs
+                    .split(',')
+                    .any(|codec| video_codecs.iter().any(|v| codec.trim().starts_with(v)));
+                has_audio_only |= audio_only;
+                if audio_only {
+                    continue;
+                }
+            }
+        }
+
+        if attribute("RESOLUTION").is_none() {
+            push(
+                findings,
+                LintRule::VariantAttributes,
+                Warning,
+                Some(MASTER_PLAYLIST_NAME),
+                format!("video variant {uri} should declare RESOLUTION"),
+            );
+        }
+        if attribute("FRAME-RATE").is_none() {
+            push(
+                findings,
+                LintRule::VariantAttributes,
+                Warning,
+                Some(MASTER_PLAYLIST_NAME),
+                format!("video variant {uri} should declare FRAME-RATE"),
+            );
+        }
+    }
+
+    if variant_count == 0 {
+        push(
+            findings,
+            LintRule::PlaylistSyntax,
+            Error,
+            Some(MASTER_PLAYLIST_NAME),
+            "no #EXT-X-STREAM-INF variants".to_string(),
+        );
+    }
+    if !has_audio_only {
+        push(
+            findings,
+            LintRule::AudioOnlyVariant,
+            Warning,
+            Some(MASTER_PLAYLIST_NAME),
+            "an audio-only variant should be provided".to_string(),
+        );
+    }
+    if !master_playlist.contains("#EXT-X-I-FRAME-STREAM-INF:") {
+        push(
+            findings,
+            LintRule::IFramePlaylist,
+            Warning,
+            Some(MASTER_PLAYLIST_NAME),
+            "I-frame playlists should be provided for trick play".to_string(),
+        );
+    }
+}
+
+/// Lints a media playlist and its segments, returning its segment durations
+fn lint_media_playlist(
+    resolution: &HlsVideoResolution,
+    findings: &mut Vec<LintFinding>,
+) -> Option<Vec<f64>> {
+    use FindingSeverity::{Error, Warning};
+
+    let name = resolution.playlist_name.as_str();
+    let playlist = String::from_utf8_lossy(&resolution.playlist_data);
+    if !playlist.trim_start().starts_with("#EXTM3U") {
+        push(
+            findings,
+            LintRule::PlaylistSyntax,
+            Error,
+            Some(name),
+            "missing #EXTM3U header".to_string(),
+        );
+        return None;
+    }
+    if !playlist.contains("#EXT-X-VERSION:") {
+        push(
+            findings,
+            LintRule::PlaylistSyntax,
+            Warning,
+            Some(name),
+            "#EXT-X-VERSION should be present".to_string(),
+        );
+    }
+
+    let target_duration = playlist
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("#EXT-X-TARGETDURATION:"))
+        .and_then(|value| value.trim().parse::<u64>().ok());
+    let durations: Vec<f64> = playlist
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix("#EXTINF:"))
+        .filter_map(|value| value.split(',').next()?.trim().parse().ok())
+        .collect();
+
+    match target_duration {
+        None => push(
+            findings,
+            LintRule::SegmentDuration,
+            Error,
+            Some(name),
+            "no #EXT-X-TARGETDURATION".to_string(),
+        ),
+        Some(target_duration) => {
+            for (index, duration) in durations.iter().enumerate() {
+                if duration.round() as u64 > target_duration {
+                    push(
+                        findings,
+                        LintRule::SegmentDuration,
+                        Error,
+                        Some(name),
+                        format!(
+                            "segment {index} lasts {duration:.3}s, over the {target_duration}s target duration"
+                        ),
+                    );
+                }
+            }
+            if target_duration != RECOMMENDED_TARGET_DURATION {
+                push(
+                    findings,
+                    LintRule::SegmentDuration,
+                    Warning,
+                    Some(name),
+                    format!(
+                        "target duration is {target_duration}s, {RECOMMENDED_TARGET_DURATION}s is recommended"
+                    ),
+                );
+            }
+        }
+    }
+
+    // Encrypted segments can't be inspected without the key
+    if !playlist.contains("#EXT-X-KEY:METHOD=AES-128") {
+        for segment in &resolution.segments {
+            if !segment.segment_name.ends_with(".ts") {
+                continue;
+            }
+            match first_video_frame(&segment.segment_data) {
+                Some(VideoFrameStart::Idr) | None => {}
+                Some(VideoFrameStart::RandomAccess) => push(
+                    findings,
+                    LintRule::IdrStart,
+                    Warning,
+                    Some(&segment.segment_name),
+                    "starts with a CRA rather than an IDR frame".to_string(),
+                ),
+                Some(VideoFrameStart::NonKeyframe) => push(
+                    findings,
+                    LintRule::IdrStart,
+                    Error,
+                    Some(&segment.segment_name),
+                    "does not start with an IDR frame".to_string(),
+                ),
+            }
+        }
+    }
+
+    Some(durations)
+}
+
+/// Compares the segment boundaries of every variant with the first one's
+fn lint_alignment(boundaries: &[(&str, Vec<f64>)], findings: &mut Vec<LintFinding>) {
+    let Some(((_, reference), others)) = boundaries.split_first() else {
+        return;
+    };
+
+    for (name, durations) in others {
+        let mut reference_end = 0.0;
+        let mut end = 0.0;
+        let misaligned = reference.len() != durations.len()
+            || reference.iter().zip(durations).any(|(a, b)| {
+                reference_end += a;
+                end += b;
+                (reference_end - end).abs() > ALIGNMENT_TOLERANCE
+            });
+
+        if misaligned {
+            push(
+                findings,
+                LintRule::VariantAlignment,
+                FindingSeverity::Error,
+                Some(name),
+                format!(
+                    "segment boundaries don't match those of {}",
+                    boundaries[0].0
+                ),
+            );
+        }
+    }
+}
+
+enum VideoFrameStart {
+    Idr,
+    /// An HEVC CRA/BLA picture, decodable but not an IDR
+    RandomAccess,
+    NonKeyframe,
+}
+
+/// Finds the first H.264 or HEVC picture of a transport stream segment, `None` when there is
+/// no such video stream or the segment can't be parsed
+fn first_video_frame(segment: &[u8]) -> Option<VideoFrameStart> {
+    let packets = || {
+        segment
+            .chunks_exact(TS_PACKET_SIZE)
+            .filter(|packet| packet[0] == 0x47)
+    };
+
+    let pmt_pid = packets()
+        .filter(|packet| pid(packet) == 0)
+        .find_map(|packet| parse_pat(section(packet)?))?;
+    let (video_pid, hevc) = packets()
+        .filter(|packet| pid(packet) == pmt_pid)
+        .find_map(|packet| parse_pmt(section(packet)?))?;
+
+    // The first PES of the video stream, up to the start of the next one
+    let mut pes = Vec::new();
+    for packet in packets().filter(|packet| pid(packet) == video_pid) {
+        let starts_unit = packet[1] & 0x40 != 0;
+        if starts_unit && !pes.is_empty() {
+            break;
+        }
+        if starts_unit || !pes.is_empty() {
+            pes.extend_from_slice(payload(packet)?);
+        }
+    }
+
+    if pes.len() < 9 || pes[..3] != [0, 0, 1] {
+        return None;
+    }
+    let header_length = 9 + pes[8] as usize;
+    first_picture(pes.get(header_length..)?, hevc)
+}
+
+fn pid(packet: &[u8]) -> u16 {
+    (u16::from(packet[1] & 0x1f) << 8) | u16::from(packet[2])
+}
+
+fn payload(packet: &[u8]) -> Option<&[u8]> {
+    let adaptation_field = (packet[3] >> 4) & 0x3;
+    match adaptation_field {
+        1 => Some(&packet[4..]),
+        3 => packet.get(5 + packet[4] as usize..),
+        _ => None,
+    }
+}
+
+/// The PSI section of a packet starting one, past its pointer field
+fn section(packet: &[u8]) -> Option<&[u8]> {
+    if packet[1] & 0x40 == 0 {
+        return None;
+    }
+    let payload = payload(packet)?;
+    payload.get(1 + *payload.first()? as usize..)
+}
+
+/// PID of the first program's PMT
+fn parse_pat(section: &[u8]) -> Option<u16> {
+    let section_length = ((usize::from(section.get(1)? & 0x0f)) << 8) | *section.get(2)? as usize;
+    let programs = section.get(8..(3 + section_length).saturating_sub(4))?;
+    programs
+        .chunks_exact(4)
+        .find(|program| program[0] != 0 || program[1] != 0)
+        .map(|program| (u16::from(program[2] & 0x1f) << 8) | u16::from(program[3]))
+}
+
+/// PID of the H.264 or HEVC stream, and whether it is HEVC
+fn parse_pmt(section: &[u8]) -> Option<(u16, bool)> {
+    let section_length = ((usize::from(section.get(1)? & 0x0f)) << 8) | *section.get(2)? as usize;
+    let program_info_length =
+        ((usize::from(section.get(10)? & 0x0f)) << 8) | *section.get(11)? as usize;
+    let end = (3 + section_length).saturating_sub(4).min(section.len());
+
+    let mut offset = 12 + program_info_length;
+    while offset + 5 <= end {
+        let stream_type = section[offset];
+        let pid = (u16::from(section[offset + 1] & 0x1f) << 8) | u16::from(section[offset + 2]);
+        let es_info_length =
+            ((usize::from(section[offset + 3] & 0x0f)) << 8) | section[offset + 4] as usize;
+        match stream_type {
+            0x1b => return Some((pid, false)),
+            0x24 => return Some((pid, true)),
+            _ => offset += 5 + es_info_length,
+        }
+    }
+    None
+}
+
+/// Classifies the first picture (VCL NAL unit) of an Annex B elementary stream
+fn first_picture(stream: &[u8], hevc: bool) -> Option<VideoFrameStart> {
+    let mut index = 0;
+    while index + 3 < stream.len() {
+        if stream[index..index + 3] != [0, 0, 1] {
+            index += 1;
+            continue;
+        }
+        let header = stream[index + 3];
+        index += 3;
+
+        if hevc {
+            match (header >> 1) & 0x3f {
+                19 | 20 => return Some(VideoFrameStart::Idr),
+                16..=21 => return Some(VideoFrameStart::RandomAccess),
+                0..=15 => return Some(VideoFrameStart::NonKeyframe),
+                _ => {}
+            }
+        } else {
+            match header & 0x1f {
+                5 => return Some(VideoFrameStart::Idr),
+                1..=4 => return Some(VideoFrameStart::NonKeyframe),
+                _ => {}
+            }
+        }
+    }
+    None
+}
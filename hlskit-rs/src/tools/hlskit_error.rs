@@ -41,8 +41,12 @@
 #[cfg(feature = "native-bindings")]
 use ffmpeg_next::Error;
 
+use std::{fmt, time::Duration};
+
 use thiserror::Error;
 
+use crate::models::{content_check::ContentCheck, validation_report::ValidationReport};
+
 #[derive(Error, Debug, Clone, PartialEq, Eq)]
 pub enum VideoValidatableErrors {
     #[error("Invalid video format")]
@@ -93,6 +97,145 @@ pub enum GStreamerCommandBuilderError {
     MissingOutput,
 }
 
+/// Why an ffmpeg run failed, as far as its stderr tells
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FfmpegErrorKind {
+    #[error("unsupported codec")]
+    UnsupportedCodec,
+    #[error("no such encoder")]
+    EncoderNotFound,
+    #[error("corrupt input")]
+    CorruptInput,
+    #[error("input not found")]
+    InputNotFound,
+    #[error("permission denied")]
+    PermissionDenied,
+    #[error("out of disk space")]
+    OutOfDisk,
+    #[error("unknown failure")]
+    Other,
+}
+
+/// Why a gst-launch run failed, as far as its error and warning messages tell
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GStreamerErrorKind {
+    /// An element or plugin isn't installed, e.g. `svtav1enc` from gst-plugins-bad
+    #[error("missing plugin")]
+    MissingPlugin,
+    #[error("caps negotiation failed")]
+    CapsNegotiation,
+    /// The input couldn't be typed or decoded
+    #[error("decode error")]
+    DecodeError,
+    #[error("input not found")]
+    InputNotFound,
+    #[error("permission denied")]
+    PermissionDenied,
+    #[error("out of disk space")]
+    OutOfDisk,
+    #[error("unknown failure")]
+    Other,
+}
+
+/// An encoder run that exited unsuccessfully, with everything needed to diagnose it
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CommandFailure {
+    /// The exact argument vector that was executed
+    pub command: Vec<String>,
+    pub exit_code: Option<i32>,
+    /// The end of the process' stderr, truncated to a few kilobytes
+    pub stderr: String,
+    pub profile_index: Option<i32>,
+    pub resolution: Option<(i32, i32)>,
+}
+
+impl fmt::Display for CommandFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "`{}` exited with ", self.command.join(" "))?;
+        match self.exit_code {
+            Some(code) => write!(f, "code {code}")?,
+            None => write!(f, "a signal")?,
+        }
+        if let Some(index) = self.profile_index {
+            write!(f, " (profile {index}")?;
+            if let Some((width, height)) = self.resolution {
+                write!(f, ", {width}x{height}")?;
+            }
+            write!(f, ")")?;
+        }
+        write!(f, ": {}", self.stderr.trim_end())
+    }
+}
+
+/// Stable, machine-readable classification of an `HlsKitError`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+#[non_exhaustive]
+pub enum ErrorCode {
+    Io,
+    InvalidInput,
+    InvalidConfiguration,
+    FileNotFound,
+    PermissionDenied,
+    EncoderFailed,
+    EncoderNotFound,
+    UnsupportedCodec,
+    BinaryNotFound,
+    UnsupportedBinaryVersion,
+    CommandRejected,
+    Unsupported,
+    Cancelled,
+    Timeout,
+    InsufficientDiskSpace,
+    DiskQuotaExceeded,
+    SinkFailed,
+    ArchiveFailed,
+    InvalidOutput,
+    EncryptionFailed,
+    SigningFailed,
+    NativeApi,
+}
+
+impl ErrorCode {
+    /// The snake_case name of the code, e.g. `"disk_quota_exceeded"`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorCode::Io => "io",
+            ErrorCode::InvalidInput => "invalid_input",
+            ErrorCode::InvalidConfiguration => "invalid_configuration",
+            ErrorCode::FileNotFound => "file_not_found",
+            ErrorCode::PermissionDenied => "permission_denied",
+            ErrorCode::EncoderFailed => "encoder_failed",
+            ErrorCode::EncoderNotFound => "encoder_not_found",
+            ErrorCode::UnsupportedCodec => "unsupported_codec",
+            ErrorCode::BinaryNotFound => "binary_not_found",
+            ErrorCode::UnsupportedBinaryVersion => "unsupported_binary_version",
+            ErrorCode::CommandRejected => "command_rejected",
+            ErrorCode::Unsupported => "unsupported",
+            ErrorCode::Cancelled => "cancelled",
+            ErrorCode::Timeout => "timeout",
+            ErrorCode::InsufficientDiskSpace => "insufficient_disk_space",
+            ErrorCode::DiskQuotaExceeded => "disk_quota_exceeded",
+            ErrorCode::SinkFailed => "sink_failed",
+            ErrorCode::ArchiveFailed => "archive_failed",
+            ErrorCode::InvalidOutput => "invalid_output",
+            ErrorCode::EncryptionFailed => "encryption_failed",
+            ErrorCode::SigningFailed => "signing_failed",
+            ErrorCode::NativeApi => "native_api",
+        }
+    }
+}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
 #[derive(Error, Debug)]
 pub enum HlsKitError {
     #[error(transparent)]
@@ -107,14 +250,173 @@ pub enum HlsKitError {
     VideoValidationError(#[from] VideoValidatableErrors),
     #[error("[HlsKit] Failed to spawn Ffmpeg: {error:?}")]
     FfmpegError { error: String },
-    #[error("[HlsKit] Failed to spawn GStreamer: {error:?}")]
-    GstreamerError { error: String },
+    /// A failed gst-launch run, `message` being the error GStreamer reported
+    #[error("[HlsKit] GStreamer failed ({kind}): {message}")]
+    GstreamerError {
+        kind: GStreamerErrorKind,
+        message: String,
+        failure: Box<CommandFailure>,
+    },
     #[error("Something went wrong while executing the command: {error:?}")]
     CommandExecutionError { error: String },
+    #[error("[HlsKit] {0}")]
+    CommandFailed(Box<CommandFailure>),
+    #[error("[HlsKit] ffmpeg failed ({kind}): {failure}")]
+    FfmpegFailure {
+        kind: FfmpegErrorKind,
+        failure: Box<CommandFailure>,
+    },
+    #[error("[HlsKit] Processing was cancelled")]
+    Cancelled,
+    #[error("[HlsKit] Timed out after {elapsed:?} (profile: {profile_index:?})")]
+    Timeout {
+        profile_index: Option<i32>,
+        elapsed: Duration,
+    },
+    #[error(
+        "[HlsKit] Not enough disk space: about {required} bytes needed, {available} available"
+    )]
+    InsufficientDiskSpace { required: u64, available: u64 },
+    #[error("[HlsKit] Disk quota of {quota} bytes exceeded ({used} bytes written)")]
+    DiskQuotaExceeded { quota: u64, used: u64 },
+    #[error("[HlsKit] Command rejected by interceptor: {error:?}")]
+    CommandRejected { error: String },
+    #[error("[HlsKit] Unsupported operation: {error:?}")]
+    UnsupportedOperation { error: String },
+    #[error("[HlsKit] Could not run {binary:?}, is it installed?")]
+    BinaryNotFound { binary: String },
+    #[error("[HlsKit] {binary:?} is too old ({found:?}), {required} or newer is required")]
+    UnsupportedBinaryVersion {
+        binary: String,
+        found: String,
+        required: String,
+    },
+    #[error("[HlsKit] Invalid configuration: {error:?}")]
+    ConfigError { error: String },
     #[error("File {file_path:?} not found")]
     FileNotFound { file_path: String },
+    #[error("[HlsKit] Failed to write output to sink: {error:?}")]
+    SinkError { error: String },
+    #[error("[HlsKit] Failed to build archive: {error:?}")]
+    ArchiveError { error: String },
+    #[error("[HlsKit] Output validation failed: {0}")]
+    OutputValidationFailed(Box<ValidationReport>),
+    #[error("[HlsKit] Invalid playlist at line {line}: {error}")]
+    PlaylistParseError { line: usize, error: String },
+    #[error("[HlsKit] Source rejected by content QC: {check}")]
+    ContentRejected { check: ContentCheck },
+    #[error("[HlsKit] Encryption failed: {error}")]
+    EncryptionError { error: String },
+    #[error("[HlsKit] Path {path:?} rejected: {reason}")]
+    PathRejected { path: String, reason: String },
+    #[error("[HlsKit] URL signing failed: {error}")]
+    SigningError { error: String },
 
     #[cfg(feature = "native-bindings")]
     #[error(transparent)]
     FfmpegAPIError(#[from] Error),
 }
+
+impl HlsKitError {
+    /// The failed encoder run behind this error, if any
+    pub fn command_failure(&self) -> Option<&CommandFailure> {
+        match self {
+            HlsKitError::CommandFailed(failure)
+            | HlsKitError::FfmpegFailure { failure, .. }
+            | HlsKitError::GstreamerError { failure, .. } => Some(failure),
+            _ => None,
+        }
+    }
+
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            HlsKitError::IO(e) => match e.kind() {
+                std::io::ErrorKind::NotFound => ErrorCode::FileNotFound,
+                std::io::ErrorKind::PermissionDenied => ErrorCode::PermissionDenied,
+                std::io::ErrorKind::StorageFull => ErrorCode::InsufficientDiskSpace,
+                _ => ErrorCode::Io,
+            },
+            HlsKitError::FFMPEGBUILDER(_)
+            | HlsKitError::GSTREAMERBUILDER(_)
+            | HlsKitError::VideoProcessingError(_)
+            | HlsKitError::ConfigError { .. } => ErrorCode::InvalidConfiguration,
+            HlsKitError::VideoValidationError(VideoValidatableErrors::FileNotFound) => {
+                ErrorCode::FileNotFound
+            }
+            HlsKitError::VideoValidationError(_) => ErrorCode::InvalidInput,
+            HlsKitError::FfmpegError { .. }
+            | HlsKitError::CommandExecutionError { .. }
+            | HlsKitError::CommandFailed(_) => ErrorCode::EncoderFailed,
+            HlsKitError::FfmpegFailure { kind, .. } => match kind {
+                FfmpegErrorKind::UnsupportedCodec => ErrorCode::UnsupportedCodec,
+                FfmpegErrorKind::EncoderNotFound => ErrorCode::EncoderNotFound,
+                FfmpegErrorKind::CorruptInput => ErrorCode::InvalidInput,
+                FfmpegErrorKind::InputNotFound => ErrorCode::FileNotFound,
+                FfmpegErrorKind::PermissionDenied => ErrorCode::PermissionDenied,
+                FfmpegErrorKind::OutOfDisk => ErrorCode::InsufficientDiskSpace,
+                FfmpegErrorKind::Other => ErrorCode::EncoderFailed,
+            },
+            HlsKitError::GstreamerError { kind, .. } => match kind {
+                GStreamerErrorKind::MissingPlugin => ErrorCode::EncoderNotFound,
+                GStreamerErrorKind::CapsNegotiation => ErrorCode::UnsupportedCodec,
+                GStreamerErrorKind::DecodeError => ErrorCode::InvalidInput,
+                GStreamerErrorKind::InputNotFound => ErrorCode::FileNotFound,
+                GStreamerErrorKind::PermissionDenied => ErrorCode::PermissionDenied,
+                GStreamerErrorKind::OutOfDisk => ErrorCode::InsufficientDiskSpace,
+                GStreamerErrorKind::Other => ErrorCode::EncoderFailed,
+            },
+            HlsKitError::Cancelled => ErrorCode::Cancelled,
+            HlsKitError::Timeout { .. } => ErrorCode::Timeout,
+            HlsKitError::InsufficientDiskSpace { .. } => ErrorCode::InsufficientDiskSpace,
+            HlsKitError::DiskQuotaExceeded { .. } => ErrorCode::DiskQuotaExceeded,
+            HlsKitError::CommandRejected { .. } => ErrorCode::CommandRejected,
+            HlsKitError::UnsupportedOperation { .. } => ErrorCode::Unsupported,
+            HlsKitError::BinaryNotFound { .. } => ErrorCode::BinaryNotFound,
+            HlsKitError::UnsupportedBinaryVersion { .. } => ErrorCode::UnsupportedBinaryVersion,
+            HlsKitError::FileNotFound { .. } => ErrorCode::FileNotFound,
+            HlsKitError::SinkError { .. } => ErrorCode::SinkFailed,
+            HlsKitError::ArchiveError { .. } => ErrorCode::ArchiveFailed,
+            HlsKitError::OutputValidationFailed(_) => ErrorCode::InvalidOutput,
+            HlsKitError::ContentRejected { .. }
+            | HlsKitError::PlaylistParseError { .. }
+            | HlsKitError::PathRejected { .. } => ErrorCode::InvalidInput,
+            HlsKitError::EncryptionError { .. } => ErrorCode::EncryptionFailed,
+            HlsKitError::SigningError { .. } => ErrorCode::SigningFailed,
+            #[cfg(feature = "native-bindings")]
+            HlsKitError::FfmpegAPIError(_) => ErrorCode::NativeApi,
+        }
+    }
+
+    /// Whether running the same job again may succeed, e.g. after a timeout or a failed
+    /// upload, as opposed to bad input or configuration
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            HlsKitError::IO(e) => matches!(
+                e.kind(),
+                std::io::ErrorKind::Interrupted
+                    | std::io::ErrorKind::TimedOut
+                    | std::io::ErrorKind::WouldBlock
+                    | std::io::ErrorKind::ConnectionReset
+                    | std::io::ErrorKind::ConnectionAborted
+                    | std::io::ErrorKind::BrokenPipe
+                    | std::io::ErrorKind::StorageFull
+            ),
+            _ => matches!(
+                self.code(),
+                ErrorCode::Timeout | ErrorCode::InsufficientDiskSpace | ErrorCode::SinkFailed
+            ),
+        }
+    }
+
+    /// Records which profile a failed encoder run belonged to
+    pub fn with_profile(mut self, profile_index: i32, resolution: (i32, i32)) -> Self {
+        if let HlsKitError::CommandFailed(failure)
+        | HlsKitError::FfmpegFailure { failure, .. }
+        | HlsKitError::GstreamerError { failure, .. } = &mut self
+        {
+            failure.profile_index = Some(profile_index);
+            failure.resolution = Some(resolution);
+        }
+        self
+    }
+}
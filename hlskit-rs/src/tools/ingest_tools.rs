@@ -0,0 +1,156 @@
+// SPDX-License-Identifier: LGPL-3.0-only
+/*
+ * Copyright © 2025 The HlsKit Project
+ *
+ * This software is licensed under the GNU Lesser General Public License v3.0 (LGPLv3).
+ * All contributions adhere to the LGPLv3 and the HlsKit Contributor License Agreement (CLA).
+ * A copy of the LGPLv3 can be found at https://www.gnu.org/licenses/lgpl-3.0.html
+ *
+ * HlsKit Contributor License Agreement
+ *
+ * By contributing to or modifying HlsKit, you agree to the following terms:
+ *
+ * 1. Collective Ownership:
+ * The HlsKit project incorporates original code and all contributions as a collective work,
+ * licensed under LGPLv3. Once submitted, contributions become part of the shared HlsKit
+ * ecosystem and cannot be reclaimed, reassigned, or withdrawn. Contributions to your own
+ * forks remain yours unless submitted here, at which point they join this collective whole under LGPLv3.
+ *
+ * 2. Definition of Contribution:
+ * You are considered a contributor if you modify the library in any form (including forks,
+ * wrappers, libraries, or extensions that alter its behavior), whether or not you submit
+ * your changes directly to this repository. All such modifications are part of the broader
+ * HlsKit ecosystem and are subject to this CLA.
+ *
+ * 3. Distribution of Modifications:
+ * If you distribute a modified version of HlsKit, you must license your modifications under
+ * LGPLv3 (with source code available as required by the license) and ensure they are
+ * adoptable by the HlsKit ecosystem (publicly available and compatible).
+ *
+ * 4. Networked Use of Modifications:
+ * If you use a modified version of HlsKit in a networked application, you must provide the
+ * source code of your modifications under LGPLv3 and notify the HlsKit project
+ * (e.g., via email to [higashikataengels@icloud.com]). This does not apply to the use of
+ * the unmodified library in proprietary software, which remains permissible under LGPLv3.
+ *
+ * 5. Scope:
+ * These terms apply to all contributions and modifications derived from the HlsKit project.
+ * The use of the unmodified library in proprietary software is governed solely by the LGPLv3.
+ */
+
+use std::path::Path;
+
+use crate::{
+    models::{hls_video::HlsVideo, hls_video_processing_settings::HlsVideoProcessingSettings},
+    process_video_to_dir,
+    tools::{
+        hlskit_error::{HlsKitError, VideoValidatableErrors},
+        m3u8_tools::generate_master_playlist,
+        probe_tools::probe_hls_programs,
+        quality_tools::run_ffmpeg,
+    },
+    VideoInputType,
+};
+
+/// Segment duration of codec-copied renditions, matching the encoder's default
+const COPY_SEGMENT_SECONDS: u32 = 10;
+
+/// How a remote HLS asset becomes a new one
+#[derive(Debug, Clone, PartialEq)]
+pub enum RemoteIngestMode {
+    /// Re-segments every variant stream as it is, without re-encoding
+    CodecCopy,
+    /// Encodes the best variant stream into a new ladder
+    Reencode(Vec<HlsVideoProcessingSettings>),
+}
+
+/// Downloads the HLS asset behind `master_url` and packages it again into `output_dir`, for
+/// migrating content from other packagers. ffmpeg fetches the playlists and segments, so any
+/// protocol it supports works
+pub async fn repackage_remote_hls(
+    master_url: &str,
+    mode: RemoteIngestMode,
+    output_dir: &Path,
+) -> Result<HlsVideo, HlsKitError> {
+    std::fs::create_dir_all(output_dir)?;
+
+    match mode {
+        RemoteIngestMode::CodecCopy => copy_variants(master_url, output_dir).await?,
+        RemoteIngestMode::Reencode(profiles) => {
+            let workdir = tempfile::tempdir()?;
+            let source_path = workdir.path().join("source.mkv");
+            // Without mappings ffmpeg keeps the highest resolution video and the audio with
+            // the most channels among all the variants
+            let args = [
+                "-y",
+                "-loglevel",
+                "error",
+                "-i",
+                master_url,
+                "-c",
+                "copy",
+                &source_path.to_string_lossy(),
+            ]
+            .map(str::to_string);
+            run_ffmpeg(&args).await?;
+
+            let source = VideoInputType::FilePath(source_path.to_string_lossy().into_owned());
+            process_video_to_dir(source, profiles, output_dir).await?;
+        }
+    }
+
+    HlsVideo::load_from_dir(output_dir)
+}
+
+async fn copy_variants(master_url: &str, output_dir: &Path) -> Result<(), HlsKitError> {
+    let programs = probe_hls_programs(master_url).await?;
+    if programs.is_empty() {
+        return Err(VideoValidatableErrors::InvalidVideoInput {
+            error: format!("no video variant streams in {master_url}"),
+        }
+        .into());
+    }
+
+    let mut playlist_names = Vec::with_capacity(programs.len());
+    for (stream_index, (program_id, _)) in programs.iter().enumerate() {
+        let playlist_name = format!("playlist_{stream_index}.m3u8");
+        let args = [
+            "-y".to_string(),
+            "-loglevel".to_string(),
+            "error".to_string(),
+            "-i".to_string(),
+            master_url.to_string(),
+            "-map".to_string(),
+            format!("0:p:{program_id}"),
+            "-c".to_string(),
+            "copy".to_string(),
+            "-f".to_string(),
+            "hls".to_string(),
+            "-hls_time".to_string(),
+            COPY_SEGMENT_SECONDS.to_string(),
+            "-hls_playlist_type".to_string(),
+            "vod".to_string(),
+            "-hls_segment_filename".to_string(),
+            output_dir
+                .join(format!("data_{stream_index}_%03d.ts"))
+                .to_string_lossy()
+                .into_owned(),
+            output_dir
+                .join(&playlist_name)
+                .to_string_lossy()
+                .into_owned(),
+        ];
+        run_ffmpeg(&args).await?;
+        playlist_names.push(playlist_name);
+    }
+
+    let resolutions = programs.iter().map(|(_, resolution)| *resolution).collect();
+    generate_master_playlist(
+        output_dir,
+        resolutions,
+        playlist_names.iter().map(String::as_str).collect(),
+    )
+    .await?;
+
+    Ok(())
+}
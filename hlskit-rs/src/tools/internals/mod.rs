@@ -39,3 +39,6 @@
  */
 
 pub mod hls_output_config;
+pub(crate) mod pipeline;
+pub(crate) mod usage_sampler;
+pub(crate) mod workdir;
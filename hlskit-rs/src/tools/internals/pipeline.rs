@@ -0,0 +1,1702 @@
+// SPDX-License-Identifier: LGPL-3.0-only
+/*
+ * Copyright © 2025 The HlsKit Project
+ *
+ * This software is licensed under the GNU Lesser General Public License v3.0 (LGPLv3).
+ * All contributions adhere to the LGPLv3 and the HlsKit Contributor License Agreement (CLA).
+ * A copy of the LGPLv3 can be found at https://www.gnu.org/licenses/lgpl-3.0.html
+ *
+ * HlsKit Contributor License Agreement
+ *
+ * By contributing to or modifying HlsKit, you agree to the following terms:
+ *
+ * 1. Collective Ownership:
+ * The HlsKit project incorporates original code and all contributions as a collective work,
+ * licensed under LGPLv3. Once submitted, contributions become part of the shared HlsKit
+ * ecosystem and cannot be reclaimed, reassigned, or withdrawn. Contributions to your own
+ * forks remain yours unless submitted here, at which point they join this collective whole under LGPLv3.
+ *
+ * 2. Definition of Contribution:
+ * You are considered a contributor if you modify the library in any form (including forks,
+ * wrappers, libraries, or extensions that alter its behavior), whether or not you submit
+ * your changes directly to this repository. All such modifications are part of the broader
+ * HlsKit ecosystem and are subject to this CLA.
+ *
+ * 3. Distribution of Modifications:
+ * If you distribute a modified version of HlsKit, you must license your modifications under
+ * LGPLv3 (with source code available as required by the license) and ensure they are
+ * adoptable by the HlsKit ecosystem (publicly available and compatible).
+ *
+ * 4. Networked Use of Modifications:
+ * If you use a modified version of HlsKit in a networked application, you must provide the
+ * source code of your modifications under LGPLv3 and notify the HlsKit project
+ * (e.g., via email to [higashikataengels@icloud.com]). This does not apply to the use of
+ * the unmodified library in proprietary software, which remains permissible under LGPLv3.
+ *
+ * 5. Scope:
+ * These terms apply to all contributions and modifications derived from the HlsKit project.
+ * The use of the unmodified library in proprietary software is governed solely by the LGPLv3.
+ */
+
+use std::{
+    future::Future,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use bytes::Bytes;
+use futures::future::{join_all, try_join_all};
+use tokio::sync::{mpsc, Semaphore};
+use tracing::Instrument;
+
+#[cfg(feature = "encryption")]
+use crate::tools::encryption_tools::verify_encrypted_rendition;
+#[cfg(feature = "cache")]
+use crate::{
+    models::cache_key::CacheKey, tools::cache_tools::cache_key, traits::result_cache::ResultCache,
+};
+#[cfg(feature = "checksums")]
+use crate::{
+    models::checksum_manifest::{ChecksumManifest, CHECKSUM_MANIFEST_NAME},
+    tools::segment_tools::file_name_of,
+};
+use crate::{
+    models::{
+        chunked_encoding::ChunkedEncoding,
+        deinterlace_policy::DeinterlacePolicy,
+        frame_rate::FrameRate,
+        frame_rate_policy::FrameRatePolicy,
+        hls_artifact::HlsArtifact,
+        hls_video::{HlsVideo, MASTER_PLAYLIST_NAME},
+        hls_video_manifest::{HlsVideoManifest, HlsVideoResolutionManifest},
+        hls_video_processing_settings::{
+            EncodeMode, HlsVideoProcessingSettings, ProfileEncryption, SegmentType,
+        },
+        hls_video_report::RenditionReport,
+        job_event::JobEvent,
+        job_id::JobId,
+        key_material::KeyMaterial,
+        path_policy::PathPolicy,
+        processing_plan::ProcessingPlan,
+        profile_error::ProfileError,
+        resource_usage::ResourceUsage,
+        source_adjustments::SourceAdjustments,
+        source_info::SourceInfo,
+        upscale_policy::UpscalePolicy,
+        work_unit::{WorkUnit, WorkUnitResult},
+    },
+    tools::{
+        disk_tools::ensure_disk_space_for,
+        hlskit_error::{HlsKitError, VideoValidatableErrors},
+        internals::workdir::WorkdirGuard,
+        m3u8_tools::{master_playlist_for_variants, MasterPlaylistVariant},
+        output_validation::validate_output,
+        probe_tools::{
+            check_content, check_decodable, detect_interlacing, detect_scene_changes,
+            probe_keyframes, probe_source, probe_source_bytes,
+        },
+        processing_context::ProcessingContext,
+        quality_tools::measure_quality,
+        report_tools::{rendition_report, report_for_renditions, RemovedSegments},
+        secure_files::{create_private_dir, write_private_file},
+        segment_tools::read_resolution_from_manifest,
+        sink_tools::{stream_to_sink, write_master_playlist_to_sink, write_resolution_to_sink},
+    },
+    traits::{
+        output_sink::OutputSink,
+        sandbox_policy::SandboxScope,
+        video_processing_backend::{VideoProcessingBackend, STDIN_INPUT},
+        video_validatable::VideoValidatable,
+    },
+    VideoProcessorEncryptionSettings,
+};
+
+/// Everything a single processing run needs, shared by the public entry points
+pub(crate) struct ProcessingJob<'a, V, S> {
+    pub input: &'a S,
+    pub output_profiles: &'a [HlsVideoProcessingSettings],
+    pub encryption: Option<&'a VideoProcessorEncryptionSettings>,
+    pub backend: &'a V,
+    pub context: &'a ProcessingContext,
+}
+
+impl<V: VideoProcessingBackend, S: VideoValidatable> ProcessingJob<'_, V, S> {
+    /// Encodes into a temporary directory and loads the whole result into memory
+    pub async fn process(&self) -> Result<HlsVideo, HlsKitError> {
+        #[cfg(feature = "cache")]
+        if let Some(cache) = self.context.result_cache() {
+            return self.process_cached(cache).await;
+        }
+
+        self.process_with_workdir()
+            .await
+            .map(|(hls_video, _)| hls_video)
+    }
+
+    /// Like `process`, serving the video from `cache` when the same input was already
+    /// processed with the same settings. Cache failures are logged and the job encodes as usual
+    #[cfg(feature = "cache")]
+    async fn process_cached(&self, cache: &dyn ResultCache) -> Result<HlsVideo, HlsKitError> {
+        let key = match self.cache_key().await? {
+            Some(key) => key,
+            None => {
+                return self
+                    .process_with_workdir()
+                    .await
+                    .map(|(hls_video, _)| hls_video)
+            }
+        };
+
+        match cache.get(&key).await {
+            Ok(Some(hls_video)) => {
+                tracing::info!(%key, "Serving the job from the result cache");
+                self.context.emit(JobEvent::CacheHit {
+                    key: key.to_string(),
+                });
+                self.context.emit(JobEvent::JobCompleted {
+                    resolution_count: hls_video.resolutions.len(),
+                });
+                self.context.flush_notifications().await;
+                return Ok(hls_video);
+            }
+            Ok(None) => {}
+            Err(e) => tracing::warn!(%key, error = %e, "Failed to read the result cache"),
+        }
+
+        let (hls_video, _) = self.process_with_workdir().await?;
+        // Partial results would keep serving the failed profiles' absence
+        if hls_video.failed_profiles.is_empty() {
+            if let Err(e) = cache.put(&key, &hls_video).await {
+                tracing::warn!(%key, error = %e, "Failed to store the job in the result cache");
+            }
+        }
+
+        Ok(hls_video)
+    }
+
+    /// The cache key of the job, `None` when it encrypts: keys fetched per job or read from a
+    /// key file may change while the settings stay the same
+    #[cfg(feature = "cache")]
+    async fn cache_key(&self) -> Result<Option<CacheKey>, HlsKitError> {
+        let encrypts = self.context.key_provider().is_some()
+            || self
+                .output_profiles
+                .iter()
+                .any(|profile| profile.effective_encryption(self.encryption).is_some());
+        if encrypts {
+            return Ok(None);
+        }
+
+        if let Some(policy) = self.context.path_policy() {
+            self.check_paths(policy)?;
+        }
+        let input_guard = self.input.validate()?;
+        let input_path = match input_guard.temp_file.as_ref() {
+            Some(temp_file) => temp_file.path().to_path_buf(),
+            None => PathBuf::from(&input_guard.path),
+        };
+
+        cache_key(
+            &input_path,
+            self.backend.name(),
+            self.output_profiles,
+            self.context,
+        )
+        .await
+        .map(Some)
+    }
+
+    /// Like `process`, also returning the work directory when the context keeps it
+    pub async fn process_with_workdir(&self) -> Result<(HlsVideo, Option<PathBuf>), HlsKitError> {
+        let workdir = WorkdirGuard::new(
+            self.context.retention_policy(),
+            self.context.private_temp_files(),
+        )?;
+        let result = self.load_from_workdir(workdir.path()).await;
+        let kept_workdir = self.release_workdir(workdir, result.is_ok());
+
+        result.map(|hls_video| (hls_video, kept_workdir))
+    }
+
+    /// Encodes into a temporary directory, forwarding every file to `sink`
+    pub async fn process_to_sink<K: OutputSink>(&self, sink: &K) -> Result<(), HlsKitError> {
+        let workdir = WorkdirGuard::new(
+            self.context.retention_policy(),
+            self.context.private_temp_files(),
+        )?;
+        let result = self.encode_to_dir(workdir.path(), Some(sink), false).await;
+        self.release_workdir(workdir, result.is_ok());
+
+        result.map(|_| ())
+    }
+
+    async fn load_from_workdir(&self, output_dir_path: &Path) -> Result<HlsVideo, HlsKitError> {
+        let manifest = self
+            .encode_to_dir(output_dir_path, None::<&NoSink>, false)
+            .await?;
+
+        let mut resolutions = Vec::with_capacity(manifest.resolutions.len());
+        for resolution in &manifest.resolutions {
+            resolutions.push(read_resolution_from_manifest(resolution).await?);
+        }
+
+        Ok(HlsVideo {
+            master_m3u8_data: manifest.master_playlist,
+            resolutions,
+            failed_profiles: manifest.failed_profiles,
+            source_adjustments: manifest.source_adjustments,
+            resource_usage: manifest.resource_usage,
+            checksums: manifest.checksums,
+            report: manifest.report,
+        })
+    }
+
+    /// Applies the retention policy to the work directory, announcing it when kept
+    fn release_workdir(&self, workdir: WorkdirGuard, succeeded: bool) -> Option<PathBuf> {
+        let path = workdir.release(succeeded)?;
+        self.context
+            .emit(JobEvent::WorkdirKept { path: path.clone() });
+
+        Some(path)
+    }
+
+    /// Validates the input and collects the commands and outputs of every profile, running nothing
+    pub fn plan(&self, output_dir: &Path) -> Result<ProcessingPlan, HlsKitError> {
+        let input_guard = self.input.validate()?;
+
+        let input_path = match input_guard.temp_file.as_ref() {
+            Some(temp_file) => temp_file.path().to_string_lossy().to_string(),
+            None => input_guard.path.clone(),
+        };
+
+        let mut profiles = Vec::with_capacity(self.output_profiles.len());
+        for (index, profile) in self.output_profiles.iter().enumerate() {
+            let mut plan = self.backend.plan_profile(
+                &input_path,
+                profile,
+                output_dir,
+                index as i32,
+                profile.effective_encryption(self.encryption),
+                self.context,
+            )?;
+            self.context.hooks().run_before_profile(&mut plan)?;
+            plan.command = self.context.intercept_command(&plan.command)?;
+            profiles.push(plan);
+        }
+
+        Ok(ProcessingPlan {
+            output_dir: output_dir.to_path_buf(),
+            master_playlist_path: output_dir.join(MASTER_PLAYLIST_NAME),
+            profiles,
+        })
+    }
+
+    /// Probes the input and splits the job into work units, one per profile, or one per chunk
+    /// of the profiles `chunked_encoding` can split
+    pub async fn work_units(
+        &self,
+        chunked_encoding: Option<ChunkedEncoding>,
+    ) -> Result<Vec<WorkUnit>, HlsKitError> {
+        let Some(input_path) = self.input.source_path() else {
+            return Err(HlsKitError::ConfigError {
+                error: "work units need an input path or URL the workers can read".to_string(),
+            });
+        };
+
+        let source = probe_source(input_path).await?;
+        let profiles = self.profiles_for_source(&source)?;
+        let mut adjustments = self.source_adjustments(input_path, Some(&source)).await;
+        adjustments.tone_mapped = profiles
+            .iter()
+            .any(|profile| adjustments.tone_maps(profile));
+
+        let chunks = match (chunked_encoding, source.duration) {
+            (Some(chunked_encoding), Some(duration)) if chunked_encoding.applies_to(duration) => {
+                chunked_encoding.plan_chunks(&probe_keyframes(input_path).await?, duration)
+            }
+            _ => Vec::new(),
+        };
+
+        let mut units = Vec::new();
+        for (index, profile) in profiles.iter().enumerate() {
+            let unit = WorkUnit {
+                input: input_path.to_string(),
+                profile: HlsVideoProcessingSettings {
+                    encryption: ProfileEncryption::Inherit,
+                    ..profile.clone()
+                },
+                stream_index: index as i32,
+                chunk_index: 0,
+                chunk: None,
+                encryption: profile.effective_encryption(self.encryption).cloned(),
+                source_adjustments: adjustments.clone(),
+                source_duration: source.duration,
+                source_has_audio: Some(source.has_audio()),
+            };
+
+            // Same restrictions as the chunked encoding of the ffmpeg backend
+            let splits = chunks.len() > 1
+                && profile.encode_mode == EncodeMode::Transcode
+                && profile.segment_type == SegmentType::MpegTs
+                && adjustments.scene_keyframes.is_empty();
+            if !splits {
+                units.push(unit);
+                continue;
+            }
+            for (chunk_index, chunk) in chunks.iter().enumerate() {
+                units.push(WorkUnit {
+                    chunk_index,
+                    chunk: Some(*chunk),
+                    ..unit.clone()
+                });
+            }
+        }
+
+        Ok(units)
+    }
+
+    /// Encodes `unit` with this job's backend and context, in a work directory of its own, and
+    /// reads the rendition back. The job's input, profiles and encryption are not used
+    pub async fn process_work_unit(&self, unit: &WorkUnit) -> Result<WorkUnitResult, HlsKitError> {
+        let mut encryption = unit.encryption.clone();
+        let _staged_key = match &mut encryption {
+            Some(encryption) => StagedKey::with_iv(encryption)?,
+            None => None,
+        };
+        let profiles = [unit.profile.clone()];
+        let job = ProcessingJob {
+            output_profiles: &profiles,
+            encryption: encryption.as_ref(),
+            ..*self
+        };
+
+        let workdir = WorkdirGuard::new(
+            self.context.retention_policy(),
+            self.context.private_temp_files(),
+        )?;
+        let mut context = self
+            .context
+            .clone()
+            .with_job_tracking(1)
+            .with_source_adjustments(unit.source_adjustments.clone())
+            .with_sandbox_scope(job.sandbox_scope(&unit.input, workdir.path()));
+        if let Some(has_audio) = unit.source_has_audio {
+            context = context.with_source_audio(has_audio);
+        }
+        let duration = match unit.chunk {
+            Some(chunk) => chunk.duration.or_else(|| {
+                unit.source_duration
+                    .and_then(|duration| duration.checked_sub(chunk.start))
+            }),
+            None => unit.source_duration,
+        };
+        if let Some(duration) = duration {
+            context = context.with_source_duration(duration);
+        }
+        if let Some(chunk) = unit.chunk {
+            context = context.with_source_chunk(chunk);
+        }
+
+        let result = job.read_work_unit(unit, workdir.path(), &context).await;
+        self.release_workdir(workdir, result.is_ok());
+
+        result
+    }
+
+    async fn read_work_unit(
+        &self,
+        unit: &WorkUnit,
+        output_dir: &Path,
+        context: &ProcessingContext,
+    ) -> Result<WorkUnitResult, HlsKitError> {
+        let manifest = self
+            .encode_profile(
+                &unit.input,
+                &self.output_profiles[0],
+                unit.stream_index,
+                output_dir,
+                context,
+                None::<&NoSink>,
+            )
+            .await?;
+
+        Ok(WorkUnitResult {
+            stream_index: unit.stream_index,
+            chunk_index: unit.chunk_index,
+            resolution: read_resolution_from_manifest(&manifest).await?,
+            frame_rate: manifest.frame_rate,
+            video_range: manifest.video_range,
+            codecs: manifest.codecs,
+            resource_usage: manifest.resource_usage,
+        })
+    }
+
+    /// Encodes every profile into `output_dir` and writes the master playlist next to them
+    pub async fn process_to_dir<K: OutputSink>(
+        &self,
+        output_dir: &Path,
+        sink: Option<&K>,
+    ) -> Result<HlsVideoManifest, HlsKitError> {
+        self.encode_to_dir(output_dir, sink, true).await
+    }
+
+    /// Encodes every profile into `output_dir`, only writing the master playlist there when
+    /// `persist_master_playlist` is set or output validation needs it on disk
+    async fn encode_to_dir<K: OutputSink>(
+        &self,
+        output_dir: &Path,
+        sink: Option<&K>,
+        persist_master_playlist: bool,
+    ) -> Result<HlsVideoManifest, HlsKitError> {
+        let job_id = self.context.job_id().unwrap_or_else(JobId::next);
+        let mut context = self.context.clone().with_job_id(job_id);
+        if let Some(input) = self.piped_input() {
+            tracing::debug!("Piping the {} byte input to the encoders", input.len());
+            context = context.with_stdin_input(input);
+        }
+        let job = ProcessingJob {
+            context: &context,
+            ..*self
+        };
+
+        let span = tracing::info_span!(
+            "hls_job",
+            job_id = %job_id,
+            backend = self.backend.name(),
+            profiles = self.output_profiles.len(),
+        );
+
+        job.run_job(output_dir, sink, persist_master_playlist)
+            .instrument(span)
+            .await
+    }
+
+    /// Runs the whole job with its lifecycle events, timeouts and logging
+    async fn run_job<K: OutputSink>(
+        &self,
+        output_dir: &Path,
+        sink: Option<&K>,
+        persist_master_playlist: bool,
+    ) -> Result<HlsVideoManifest, HlsKitError> {
+        let started = Instant::now();
+        tracing::info!("Job started");
+        self.context.emit(JobEvent::JobStarted {
+            profile_count: self.output_profiles.len(),
+        });
+
+        let run = self.run_with_keys(output_dir, sink, persist_master_playlist);
+        let result = match self.context.job_timeout() {
+            Some(timeout) => {
+                tokio::time::timeout(timeout, run)
+                    .await
+                    .unwrap_or(Err(HlsKitError::Timeout {
+                        profile_index: None,
+                        elapsed: timeout,
+                    }))
+            }
+            None => run.await,
+        };
+
+        let elapsed_ms = started.elapsed().as_millis() as u64;
+        match &result {
+            Ok(manifest) => {
+                tracing::info!(elapsed_ms, "Job completed");
+                self.context.emit(JobEvent::JobCompleted {
+                    resolution_count: manifest.resolutions.len(),
+                })
+            }
+            Err(e) => {
+                tracing::error!(elapsed_ms, error = %e, "Job failed");
+                self.context.emit(JobEvent::JobFailed {
+                    error: e.to_string(),
+                })
+            }
+        }
+        self.context.flush_notifications().await;
+
+        result
+    }
+
+    /// Fetches the job's key from the context's key provider, when the job has no encryption
+    /// settings of its own, and gives every encrypted rendition without an IV a random one,
+    /// before running it
+    async fn run_with_keys<K: OutputSink>(
+        &self,
+        output_dir: &Path,
+        sink: Option<&K>,
+        persist_master_playlist: bool,
+    ) -> Result<HlsVideoManifest, HlsKitError> {
+        if let Some(policy) = self.context.path_policy() {
+            self.check_paths(policy)?;
+        }
+
+        let mut staged_keys = Vec::new();
+        let mut encrypted_key = None;
+
+        let mut encryption = self.encryption.cloned();
+        if let (None, Some(provider)) = (self.encryption, self.context.key_provider()) {
+            let job_id = self.context.job_id().unwrap_or_else(JobId::next);
+            let mut key = provider.fetch_key(job_id, 0).await?;
+            if key.iv.is_none() {
+                key.iv = Some(random_iv()?.into());
+            }
+            let (staged_key, settings) = StagedKey::write(&key)?;
+            tracing::info!(key_uri = %key.key_uri, "Fetched the encryption key");
+
+            staged_keys.push(staged_key);
+            encryption = Some(settings);
+            encrypted_key = key.encrypted_key.take();
+        }
+
+        if let Some(encryption) = &mut encryption {
+            staged_keys.extend(StagedKey::with_iv(encryption)?);
+        }
+        let mut profiles = self.output_profiles.to_vec();
+        for profile in &mut profiles {
+            if let ProfileEncryption::Custom(encryption) = &mut profile.encryption {
+                staged_keys.extend(StagedKey::with_iv(encryption)?);
+            }
+        }
+
+        let mut manifest = ProcessingJob {
+            output_profiles: &profiles,
+            encryption: encryption.as_ref(),
+            ..*self
+        }
+        .run_to_dir(output_dir, sink, staged_keys, persist_master_playlist)
+        .await?;
+        manifest.report.encrypted_key = encrypted_key;
+
+        Ok(manifest)
+    }
+
+    /// Checks the input path, key paths and key URLs of the job and its profiles against
+    /// `policy`, before any of them is opened
+    fn check_paths(&self, policy: &PathPolicy) -> Result<(), HlsKitError> {
+        if let Some(path) = self.input.source_path() {
+            policy.check_path(path)?;
+        }
+
+        let encryptions = self
+            .output_profiles
+            .iter()
+            .filter_map(|profile| profile.effective_encryption(self.encryption));
+        for encryption in encryptions {
+            policy.check_path(&encryption.encryption_key_path)?;
+            policy.check_url(&encryption.encryption_key_url)?;
+        }
+
+        Ok(())
+    }
+
+    async fn run_to_dir<K: OutputSink>(
+        &self,
+        output_dir: &Path,
+        sink: Option<&K>,
+        staged_keys: Vec<StagedKey>,
+        persist_master_playlist: bool,
+    ) -> Result<HlsVideoManifest, HlsKitError> {
+        let started = Instant::now();
+        if self.context.is_cancelled() {
+            return Err(HlsKitError::Cancelled);
+        }
+
+        // Keeps the copy of in-memory inputs out of the shared temp dir until the job is done
+        let private_dir = if self.context.private_temp_files() {
+            Some(create_private_dir("hlskit-job-")?)
+        } else {
+            None
+        };
+
+        // Piped inputs were validated before being attached to the context
+        let input_guard = match (self.context.stdin_input(), &private_dir) {
+            (Some(_), _) => None,
+            (None, Some(private_dir)) => Some(self.input.validate_in(private_dir.path())?),
+            (None, None) => Some(self.input.validate()?),
+        };
+
+        let input_path = match &input_guard {
+            Some(input_guard) => match input_guard.temp_file.as_ref() {
+                Some(temp_file) => temp_file.path().to_string_lossy().to_string(),
+                None => input_guard.path.clone(),
+            },
+            None => STDIN_INPUT.to_string(),
+        };
+
+        // One ffprobe pass serves input validation, ladder generation, progress and the master
+        // playlist; only deep validation, content QC and fitting the profiles to the source
+        // cannot do without it
+        let deep_validation = self.context.deep_validation();
+        let content_qc = self.context.content_qc();
+        let probe = match self.context.stdin_input() {
+            Some(input) => probe_source_bytes(input).await,
+            None => probe_source(&input_path).await,
+        };
+        let source = match probe {
+            Ok(source) => Some(source),
+            Err(e @ HlsKitError::VideoValidationError(_)) => return Err(e),
+            Err(HlsKitError::CommandExecutionError { error }) if deep_validation => {
+                return Err(VideoValidatableErrors::InvalidVideoInput { error }.into())
+            }
+            Err(e) if deep_validation || content_qc.is_some() || self.fits_profiles_to_source() => {
+                return Err(e)
+            }
+            Err(e) => {
+                tracing::debug!("Source analysis unavailable: {}", e);
+                None
+            }
+        };
+
+        if let Some(source) = source.as_ref().filter(|_| deep_validation) {
+            check_decodable(&input_path, source.duration).await?;
+        }
+
+        let content_check = match (content_qc, &source) {
+            (Some(qc), Some(source)) => {
+                let check = check_content(&input_path, source).await?;
+                tracing::info!("Content check: {}", check);
+                if qc.rejects(&check) {
+                    return Err(HlsKitError::ContentRejected { check });
+                }
+                Some(check)
+            }
+            _ => None,
+        };
+
+        let adjustments = self.source_adjustments(&input_path, source.as_ref()).await;
+
+        let mut manifest = match &source {
+            Some(source) => {
+                let mut profiles = self.profiles_for_source(source)?;
+                if let Some(per_title) = self.context.per_title() {
+                    profiles = per_title.optimize(&input_path, source, &profiles).await?;
+                }
+                let job = ProcessingJob {
+                    output_profiles: &profiles,
+                    ..*self
+                };
+                job.run_profiles(
+                    &input_path,
+                    Some(source),
+                    adjustments,
+                    output_dir,
+                    sink,
+                    RunScope {
+                        started,
+                        staged_keys,
+                        persist_master_playlist,
+                    },
+                )
+                .await
+            }
+            None => {
+                self.run_profiles(
+                    &input_path,
+                    source.as_ref(),
+                    adjustments,
+                    output_dir,
+                    sink,
+                    RunScope {
+                        started,
+                        staged_keys,
+                        persist_master_playlist,
+                    },
+                )
+                .await
+            }
+        }?;
+        manifest.content_check = content_check;
+
+        Ok(manifest)
+    }
+
+    /// Decides the corrections to apply to the source before encoding
+    async fn source_adjustments(
+        &self,
+        input_path: &str,
+        source: Option<&SourceInfo>,
+    ) -> SourceAdjustments {
+        let deinterlaced = match self.context.deinterlace_policy() {
+            DeinterlacePolicy::Always => true,
+            DeinterlacePolicy::Never => false,
+            DeinterlacePolicy::Auto => match source.and_then(|source| source.interlaced) {
+                Some(interlaced) => interlaced,
+                None if self.context.stdin_input().is_some() => {
+                    tracing::debug!("Interlacing detection unavailable for a piped input");
+                    false
+                }
+                None => detect_interlacing(input_path).await.unwrap_or_else(|e| {
+                    tracing::debug!("Interlacing detection unavailable: {}", e);
+                    false
+                }),
+            },
+        };
+
+        if deinterlaced {
+            tracing::info!("Deinterlacing the source before encoding");
+        }
+
+        let normalize_frame_rate = match self.context.frame_rate_policy() {
+            FrameRatePolicy::Auto => source.is_some_and(|source| source.variable_frame_rate),
+            FrameRatePolicy::Constant => true,
+            FrameRatePolicy::Passthrough => false,
+        };
+        let constant_frame_rate = source
+            .and_then(|source| source.frame_rate)
+            .filter(|_| normalize_frame_rate)
+            .map(FrameRate::snap);
+        if let Some(frame_rate) = constant_frame_rate {
+            tracing::info!("Normalizing the source to a constant {} fps", frame_rate);
+        }
+
+        SourceAdjustments {
+            deinterlaced,
+            hdr_source: source
+                .and_then(|source| source.hdr.as_ref())
+                .map(|hdr| hdr.transfer),
+            tone_mapped: false,
+            constant_frame_rate,
+            scene_keyframes: self.scene_keyframes(input_path, source).await,
+        }
+    }
+
+    /// Plans keyframes on the scene cuts of the source when scene-aware segmentation is on
+    async fn scene_keyframes(
+        &self,
+        input_path: &str,
+        source: Option<&SourceInfo>,
+    ) -> Vec<Duration> {
+        let Some(segmentation) = self.context.scene_segmentation() else {
+            return Vec::new();
+        };
+        let Some(duration) = source.and_then(|source| source.duration) else {
+            tracing::warn!(
+                "Scene-aware segmentation needs the source duration, using fixed segments"
+            );
+            return Vec::new();
+        };
+
+        match detect_scene_changes(input_path, segmentation.threshold).await {
+            Ok(scene_changes) => {
+                let keyframes = segmentation.plan_keyframes(&scene_changes, duration);
+                tracing::info!(
+                    "Found {} scene cuts, planned {} segment boundaries",
+                    scene_changes.len(),
+                    keyframes.len()
+                );
+                keyframes
+            }
+            Err(e) => {
+                tracing::warn!("Scene detection failed, using fixed segments: {}", e);
+                Vec::new()
+            }
+        }
+    }
+
+    /// The in-memory input to write to the encoders' stdin instead of a temp file, when the
+    /// backend reads stdin and only a single probe has to read the source besides the encoders
+    fn piped_input(&self) -> Option<Bytes> {
+        if !self.backend.reads_stdin()
+            || self.context.source_chunk().is_some()
+            || self.context.deep_validation()
+            || self.context.content_qc().is_some()
+            || self.context.per_title().is_some()
+            || self.context.scene_segmentation().is_some()
+            || !self.context.quality_metrics().is_empty()
+        {
+            return None;
+        }
+
+        // Invalid inputs are staged as usual, for the job to fail on their validation
+        let data = self.input.validate_in_memory()?.ok()?;
+        reads_sequentially(data).then(|| Bytes::copy_from_slice(data))
+    }
+
+    /// Whether the profiles cannot be chosen without probing the source
+    fn fits_profiles_to_source(&self) -> bool {
+        self.context.ladder_generator().is_some()
+            || self.context.upscale_policy() != UpscalePolicy::Allow
+            || self.context.per_title().is_some()
+            || self
+                .output_profiles
+                .iter()
+                .any(|profile| profile.encode_mode != EncodeMode::Transcode)
+    }
+
+    /// Generates the ladder when asked to, then applies the orientation and upscale policies
+    fn profiles_for_source(
+        &self,
+        source: &SourceInfo,
+    ) -> Result<Vec<HlsVideoProcessingSettings>, HlsKitError> {
+        let (width, height) = source.display_resolution();
+        let profiles = match self.context.ladder_generator() {
+            Some(generator) => {
+                let profiles = generator.generate(source);
+                tracing::info!(
+                    "Generated a {}-rung ladder for a {}x{} source",
+                    profiles.len(),
+                    width,
+                    height
+                );
+                profiles
+            }
+            None => self.output_profiles.to_vec(),
+        };
+        let profiles = resolve_encode_modes(profiles, source)?;
+        let profiles = self
+            .context
+            .orientation_policy()
+            .apply(&profiles, (width, height));
+
+        let fitted = self
+            .context
+            .upscale_policy()
+            .apply(&profiles, (width, height));
+        if fitted != profiles {
+            tracing::info!(
+                "{:?} upscale policy kept {} of {} profiles for a {}x{} source",
+                self.context.upscale_policy(),
+                fitted.len(),
+                profiles.len(),
+                width,
+                height
+            );
+        }
+        Ok(fitted)
+    }
+
+    /// Encodes every profile of the validated input and writes the master playlist
+    async fn run_profiles<K: OutputSink>(
+        &self,
+        input_path: &str,
+        source: Option<&SourceInfo>,
+        adjustments: SourceAdjustments,
+        output_dir: &Path,
+        sink: Option<&K>,
+        scope: RunScope,
+    ) -> Result<HlsVideoManifest, HlsKitError> {
+        let RunScope {
+            started,
+            staged_keys,
+            persist_master_playlist,
+        } = scope;
+        let mut adjustments = adjustments;
+        adjustments.tone_mapped = self
+            .output_profiles
+            .iter()
+            .any(|profile| adjustments.tone_maps(profile));
+        if adjustments.tone_mapped {
+            tracing::info!(
+                "Tone-mapping the {:?} HDR source for SDR renditions",
+                adjustments.hdr_source
+            );
+        }
+
+        if self.context.disk_preflight() {
+            let source_size = match self.context.stdin_input() {
+                Some(input) => input.len() as u64,
+                None => std::fs::metadata(input_path)?.len(),
+            };
+            ensure_disk_space_for(source_size, output_dir, self.output_profiles)?;
+        }
+
+        let mut context = self
+            .context
+            .clone()
+            .with_job_tracking(self.output_profiles.len())
+            .with_source_adjustments(adjustments.clone())
+            .with_sandbox_scope(self.sandbox_scope(input_path, output_dir));
+
+        if let Some(source) = source {
+            context = context.with_source_audio(source.has_audio());
+            if let Some(frame_rate) = source.frame_rate {
+                context = context.with_source_frame_rate(frame_rate);
+            }
+        }
+
+        if context.source_duration().is_none() {
+            match source.and_then(|source| source.duration) {
+                Some(duration) => context = context.with_source_duration(duration),
+                None if context.wants_progress() => {
+                    tracing::warn!("Progress percentages unavailable: unknown source duration")
+                }
+                None => {}
+            }
+        }
+
+        let ((mut resolutions, failed_profiles), removed_segments) = match sink {
+            // After-profile hooks may still change the files, so they are written once final
+            Some(sink) if !context.hooks().has_after_profile() => {
+                self.encode_streaming_to_sink(input_path, output_dir, &context, sink)
+                    .await?
+            }
+            _ => (
+                self.encode_renditions(input_path, output_dir, &context, sink)
+                    .await?,
+                RemovedSegments::new(),
+            ),
+        };
+        drop(staged_keys);
+
+        if !context.quality_metrics().is_empty() {
+            measure_renditions(input_path, source, &mut resolutions, &context).await;
+        }
+
+        let source_frame_rate = source.and_then(|source| source.frame_rate);
+        let mut rendition_reports: Vec<RenditionReport> = Vec::with_capacity(resolutions.len());
+        for result in &resolutions {
+            rendition_reports.push(rendition_report(result, &removed_segments).await);
+        }
+        let variants: Vec<MasterPlaylistVariant> = resolutions
+            .iter()
+            .zip(&rendition_reports)
+            .map(|(result, report)| MasterPlaylistVariant {
+                resolution: result.resolution,
+                uri: &result.playlist_name,
+                bandwidth: report.peak_bitrate,
+                average_bandwidth: Some(report.average_bitrate),
+                codecs: result.codecs.as_deref(),
+                frame_rate: result
+                    .frame_rate
+                    .map(|frame_rate| frame_rate.as_f64())
+                    .or(source_frame_rate),
+                video_range: result.video_range,
+            })
+            .collect();
+        let master_playlist = master_playlist_for_variants(&variants);
+
+        let mut manifest = HlsVideoManifest {
+            output_dir: output_dir.to_path_buf(),
+            master_playlist_path: output_dir.join(MASTER_PLAYLIST_NAME),
+            master_playlist: master_playlist.into(),
+            resource_usage: total_resource_usage(&resolutions, started.elapsed()),
+            resolutions,
+            failed_profiles,
+            source_adjustments: adjustments,
+            content_check: None,
+            checksums: None,
+            report: Default::default(),
+            validation: None,
+        };
+        manifest.report = report_for_renditions(
+            &manifest,
+            rendition_reports,
+            self.backend.name(),
+            context.job_id(),
+        );
+        context.hooks().run_after_master_playlist(&mut manifest)?;
+
+        if persist_master_playlist || context.output_validation().is_some() {
+            tokio::fs::write(&manifest.master_playlist_path, &manifest.master_playlist).await?;
+        }
+
+        if let Some(validation) = context.output_validation() {
+            let source_duration = source.and_then(|source| source.duration);
+            let report = validate_output(output_dir, source_duration, &validation).await?;
+            for finding in report.warnings() {
+                tracing::warn!("Output validation: {}", finding);
+            }
+            if !report.is_valid() {
+                tracing::error!("Output validation: {}", report);
+                if validation.fail_on_error {
+                    return Err(HlsKitError::OutputValidationFailed(Box::new(report)));
+                }
+            }
+            manifest.validation = Some(report);
+        }
+
+        #[cfg(feature = "checksums")]
+        if context.checksums() {
+            manifest.checksums = Some(write_checksum_manifest(&manifest)?);
+        }
+
+        if let Some(sink) = sink {
+            write_master_playlist_to_sink(&manifest.master_playlist, sink).await?;
+        }
+
+        Ok(manifest)
+    }
+
+    /// What the encoders may read and write: the input, the key files of every profile and
+    /// `output_dir`
+    fn sandbox_scope(&self, input_path: &str, output_dir: &Path) -> SandboxScope {
+        let mut sandbox_scope = SandboxScope {
+            readable: Some(input_path)
+                .filter(|input_path| *input_path != STDIN_INPUT)
+                .map(PathBuf::from)
+                .into_iter()
+                .collect(),
+            writable: vec![output_dir.to_path_buf()],
+        };
+        let encryptions = self
+            .output_profiles
+            .iter()
+            .filter_map(|profile| profile.effective_encryption(self.encryption));
+        for encryption in encryptions {
+            let key_path = PathBuf::from(&encryption.encryption_key_path);
+            // ffmpeg key info files name the key file on their second line
+            if let Some(key_file) = std::fs::read_to_string(&key_path)
+                .ok()
+                .and_then(|key_info| key_info.lines().nth(1).map(PathBuf::from))
+                .filter(|key_file| key_file.is_file())
+            {
+                sandbox_scope.readable.push(key_file);
+            }
+            sandbox_scope.readable.push(key_path);
+        }
+        sandbox_scope.readable.sort();
+        sandbox_scope.readable.dedup();
+
+        sandbox_scope
+    }
+
+    /// Encodes every profile, side by side or in a single backend run
+    async fn encode_renditions<K: OutputSink>(
+        &self,
+        input_path: &str,
+        output_dir: &Path,
+        context: &ProcessingContext,
+        sink: Option<&K>,
+    ) -> Result<(Vec<HlsVideoResolutionManifest>, Vec<ProfileError>), HlsKitError> {
+        if self.encodes_together(context) {
+            let resolutions = self
+                .encode_profiles_together(input_path, output_dir, context, sink)
+                .await?;
+            Ok((resolutions, Vec::new()))
+        } else {
+            self.encode_profiles(input_path, output_dir, context, sink)
+                .await
+        }
+    }
+
+    /// Encodes every profile while writing their segments to `sink` as the backends complete
+    /// them, each media playlist following its last segment. Returns the sizes of the segments
+    /// removed once written
+    async fn encode_streaming_to_sink<K: OutputSink>(
+        &self,
+        input_path: &str,
+        output_dir: &Path,
+        context: &ProcessingContext,
+        sink: &K,
+    ) -> Result<
+        (
+            (Vec<HlsVideoResolutionManifest>, Vec<ProfileError>),
+            RemovedSegments,
+        ),
+        HlsKitError,
+    > {
+        let remove_segments = context.removes_streamed_segments();
+        if remove_segments {
+            check_segment_removal(context)?;
+        }
+
+        let (uploads, received_uploads) = mpsc::unbounded_channel();
+        let (encoded, streamed) = tokio::join!(
+            self.encode_with_uploads::<K>(
+                input_path,
+                output_dir,
+                context.clone().with_sink_uploads(uploads),
+            ),
+            stream_to_sink(received_uploads, sink, remove_segments),
+        );
+
+        let encoded = encoded?;
+        Ok((encoded, streamed?))
+    }
+
+    /// Takes the context holding the upload sender, dropping it once encoded so the uploads
+    /// can complete
+    async fn encode_with_uploads<K: OutputSink>(
+        &self,
+        input_path: &str,
+        output_dir: &Path,
+        context: ProcessingContext,
+    ) -> Result<(Vec<HlsVideoResolutionManifest>, Vec<ProfileError>), HlsKitError> {
+        self.encode_renditions(input_path, output_dir, &context, None::<&K>)
+            .await
+    }
+
+    /// Whether the backend encodes every profile in one run. Per-profile failures, command edits
+    /// and concurrency limits need one run per profile
+    fn encodes_together(&self, context: &ProcessingContext) -> bool {
+        self.output_profiles.len() > 1
+            && !context.partial_results()
+            && !context.hooks().has_before_profile()
+            && context.max_concurrent_profiles().is_none()
+            && self.backend.encodes_together(self.output_profiles)
+    }
+
+    /// Encodes the profiles side by side, one backend run each
+    async fn encode_profiles<K: OutputSink>(
+        &self,
+        input_path: &str,
+        output_dir: &Path,
+        context: &ProcessingContext,
+        sink: Option<&K>,
+    ) -> Result<(Vec<HlsVideoResolutionManifest>, Vec<ProfileError>), HlsKitError> {
+        let semaphore = context.max_concurrent_profiles().map(Semaphore::new);
+
+        let mut tasks = Vec::with_capacity(self.output_profiles.len());
+        for (index, profile) in self.output_profiles.iter().enumerate() {
+            tasks.push(with_permit(
+                semaphore.as_ref(),
+                self.encode_profile(input_path, profile, index as i32, output_dir, context, sink),
+            ));
+        }
+
+        if context.partial_results() {
+            self.collect_partial_results(join_all(tasks).await, context)
+        } else {
+            Ok((try_join_all(tasks).await?, Vec::new()))
+        }
+    }
+
+    /// Encodes every profile in a single backend run, see
+    /// `VideoProcessingBackend::encode_profiles`
+    async fn encode_profiles_together<K: OutputSink>(
+        &self,
+        input_path: &str,
+        output_dir: &Path,
+        context: &ProcessingContext,
+        sink: Option<&K>,
+    ) -> Result<Vec<HlsVideoResolutionManifest>, HlsKitError> {
+        for (index, profile) in self.output_profiles.iter().enumerate() {
+            context.emit(JobEvent::ProfileStarted {
+                stream_index: index as i32,
+                resolution: profile.resolution,
+            });
+        }
+
+        let started = Instant::now();
+        let encode = self.backend.encode_profiles(
+            input_path.to_string(),
+            self.output_profiles,
+            output_dir,
+            self.encryption,
+            context,
+        );
+        // The profile timeout covers the whole run, every profile being encoded at once
+        let result =
+            match context.profile_timeout() {
+                Some(timeout) => tokio::time::timeout(timeout, encode).await.unwrap_or(Err(
+                    HlsKitError::Timeout {
+                        profile_index: None,
+                        elapsed: timeout,
+                    },
+                )),
+                None => encode.await,
+            };
+
+        let manifests = match result {
+            Ok(manifests) => manifests,
+            Err(e) => {
+                tracing::error!(error = %e, "Encoding every profile in one run failed");
+                for (index, profile) in self.output_profiles.iter().enumerate() {
+                    context.emit(JobEvent::ProfileFailed {
+                        stream_index: index as i32,
+                        resolution: profile.resolution,
+                        error: e.to_string(),
+                    });
+                }
+                return Err(e);
+            }
+        };
+        tracing::info!(
+            elapsed_ms = started.elapsed().as_millis() as u64,
+            profiles = manifests.len(),
+            "Profiles completed in one run"
+        );
+
+        let mut resolutions = Vec::with_capacity(manifests.len());
+        for ((index, profile), manifest) in self.output_profiles.iter().enumerate().zip(manifests) {
+            resolutions.push(
+                self.finish_profile(manifest, profile, index as i32, context, sink)
+                    .await?,
+            );
+        }
+
+        Ok(resolutions)
+    }
+
+    /// Splits the outcome of every profile into renditions and failures, failing the job only
+    /// when nothing succeeded or the whole job was stopped
+    fn collect_partial_results(
+        &self,
+        results: Vec<Result<HlsVideoResolutionManifest, HlsKitError>>,
+        context: &ProcessingContext,
+    ) -> Result<(Vec<HlsVideoResolutionManifest>, Vec<ProfileError>), HlsKitError> {
+        if context.is_cancelled() {
+            return Err(HlsKitError::Cancelled);
+        }
+        context.check_disk_quota()?;
+
+        let mut resolutions = Vec::with_capacity(results.len());
+        let mut failed_profiles = Vec::new();
+        let mut first_error = None;
+
+        for ((index, profile), result) in self.output_profiles.iter().enumerate().zip(results) {
+            match result {
+                Ok(resolution) => resolutions.push(resolution),
+                Err(e) => {
+                    failed_profiles.push(ProfileError::new(index as i32, profile.resolution, &e));
+                    first_error.get_or_insert(e);
+                }
+            }
+        }
+
+        match first_error {
+            Some(e) if resolutions.is_empty() => Err(e),
+            _ => {
+                if !failed_profiles.is_empty() {
+                    tracing::warn!(
+                        "{} of {} profiles failed, continuing with the rest",
+                        failed_profiles.len(),
+                        self.output_profiles.len()
+                    );
+                }
+                Ok((resolutions, failed_profiles))
+            }
+        }
+    }
+
+    /// Encodes a single profile inside its own tracing span
+    async fn encode_profile<K: OutputSink>(
+        &self,
+        input_path: &str,
+        profile: &HlsVideoProcessingSettings,
+        stream_index: i32,
+        output_dir: &Path,
+        context: &ProcessingContext,
+        sink: Option<&K>,
+    ) -> Result<HlsVideoResolutionManifest, HlsKitError> {
+        let span = tracing::info_span!(
+            "hls_profile",
+            job_id = tracing::field::Empty,
+            stream_index,
+            resolution = ?profile.resolution,
+            backend = self.backend.name(),
+        );
+        if let Some(job_id) = context.job_id() {
+            span.record("job_id", tracing::field::display(job_id));
+        }
+
+        let started = Instant::now();
+        let result = self
+            .run_profile(input_path, profile, stream_index, output_dir, context, sink)
+            .instrument(span.clone())
+            .await;
+
+        let elapsed_ms = started.elapsed().as_millis() as u64;
+        match &result {
+            Ok(manifest) => tracing::info!(
+                parent: &span,
+                elapsed_ms,
+                segments = manifest.segment_paths.len(),
+                cpu_ms = manifest.resource_usage.cpu_time().as_millis() as u64,
+                "Profile completed"
+            ),
+            Err(e) => {
+                tracing::error!(parent: &span, elapsed_ms, error = %e, "Profile failed");
+                context.emit(JobEvent::ProfileFailed {
+                    stream_index,
+                    resolution: profile.resolution,
+                    error: e.to_string(),
+                });
+            }
+        }
+
+        result
+    }
+
+    async fn run_profile<K: OutputSink>(
+        &self,
+        input_path: &str,
+        profile: &HlsVideoProcessingSettings,
+        stream_index: i32,
+        output_dir: &Path,
+        context: &ProcessingContext,
+        sink: Option<&K>,
+    ) -> Result<HlsVideoResolutionManifest, HlsKitError> {
+        context.emit(JobEvent::ProfileStarted {
+            stream_index,
+            resolution: profile.resolution,
+        });
+
+        let encode = async {
+            self.backend
+                .encode_profile(
+                    input_path.to_string(),
+                    profile,
+                    output_dir,
+                    stream_index,
+                    profile.effective_encryption(self.encryption),
+                    context,
+                )
+                .await
+                .map_err(|e| e.with_profile(stream_index, profile.resolution))
+        };
+
+        // Dropping a timed out encode kills its encoder process
+        let manifest = match context.profile_timeout() {
+            Some(timeout) => tokio::time::timeout(timeout, encode).await.map_err(|_| {
+                tracing::error!("Profile {} timed out after {:?}", stream_index, timeout);
+                HlsKitError::Timeout {
+                    profile_index: Some(stream_index),
+                    elapsed: timeout,
+                }
+            })??,
+            None => encode.await?,
+        };
+
+        self.finish_profile(manifest, profile, stream_index, context, sink)
+            .await
+    }
+
+    /// Completes an encoded profile: reports it, runs the after-profile hooks and forwards its
+    /// files to `sink`
+    async fn finish_profile<K: OutputSink>(
+        &self,
+        mut manifest: HlsVideoResolutionManifest,
+        profile: &HlsVideoProcessingSettings,
+        stream_index: i32,
+        context: &ProcessingContext,
+        sink: Option<&K>,
+    ) -> Result<HlsVideoResolutionManifest, HlsKitError> {
+        manifest.encryption_iv = profile
+            .effective_encryption(self.encryption)
+            .and_then(|encryption| encryption.iv.clone());
+
+        // Key files are only staged until every encoder exits, so verify now
+        #[cfg(feature = "encryption")]
+        if let (true, Some(encryption)) = (
+            context.verify_encryption(),
+            profile.effective_encryption(self.encryption),
+        ) {
+            verify_encrypted_rendition(&manifest.playlist_path, encryption)?;
+        }
+        context.check_disk_quota()?;
+        context.hooks().run_after_profile(&mut manifest)?;
+
+        match sink {
+            Some(sink) => write_resolution_to_sink(&manifest, stream_index, sink).await?,
+            None => context.upload_rendition(stream_index, &manifest),
+        }
+
+        context.emit(JobEvent::ProfileCompleted {
+            stream_index,
+            resolution: manifest.resolution,
+            segment_count: manifest.segment_paths.len(),
+        });
+
+        Ok(manifest)
+    }
+}
+
+/// Rejects the steps reading segments back after encoding, which removed segments would break
+fn check_segment_removal(context: &ProcessingContext) -> Result<(), HlsKitError> {
+    let readers = [
+        (context.output_validation().is_some(), "output validation"),
+        (!context.quality_metrics().is_empty(), "quality metrics"),
+        (context.checksums(), "checksums"),
+        (context.verify_encryption(), "encryption verification"),
+    ];
+
+    match readers.iter().find(|(enabled, _)| *enabled) {
+        Some((_, reader)) => Err(HlsKitError::ConfigError {
+            error: format!("streamed segment removal can't be combined with {reader}"),
+        }),
+        None => Ok(()),
+    }
+}
+
+/// Holds back `task` until the semaphore, when present, grants it a slot
+async fn with_permit<F: Future>(semaphore: Option<&Semaphore>, task: F) -> F::Output {
+    let _permit = match semaphore {
+        Some(semaphore) => Some(
+            semaphore
+                .acquire()
+                .await
+                .expect("semaphore is never closed"),
+        ),
+        None => None,
+    };
+
+    task.await
+}
+
+/// Whether a demuxer reading `data` front to back meets the index before the media. ISO BMFF
+/// needs its `moov` box ahead of `mdat`, the other containers are read in order anyway
+fn reads_sequentially(data: &[u8]) -> bool {
+    if data.get(4..8) != Some(b"ftyp".as_slice()) {
+        return true;
+    }
+
+    let mut offset = 0usize;
+    while let Some(header) = data.get(offset..offset + 8) {
+        match &header[4..8] {
+            b"moov" => return true,
+            b"mdat" => return false,
+            _ => {}
+        }
+
+        let size = match u32::from_be_bytes([header[0], header[1], header[2], header[3]]) {
+            // The box runs to the end of the file
+            0 => return false,
+            1 => match data.get(offset + 8..offset + 16) {
+                Some(large_size) => u64::from_be_bytes(large_size.try_into().expect("8 bytes")),
+                None => return false,
+            },
+            size => u64::from(size),
+        };
+        match usize::try_from(size)
+            .ok()
+            .filter(|size| *size >= 8)
+            .and_then(|size| offset.checked_add(size))
+        {
+            Some(next) => offset = next,
+            None => return false,
+        }
+    }
+
+    false
+}
+
+/// Scores each rendition against the source one at a time, logging failures
+async fn measure_renditions(
+    input_path: &str,
+    source: Option<&SourceInfo>,
+    resolutions: &mut [HlsVideoResolutionManifest],
+    context: &ProcessingContext,
+) {
+    let reference_resolution = source.map(SourceInfo::display_resolution);
+
+    for resolution in resolutions {
+        if context.is_cancelled() {
+            return;
+        }
+
+        match measure_quality(
+            input_path,
+            &resolution.playlist_path,
+            resolution.resolution,
+            reference_resolution,
+            context.quality_metrics(),
+        )
+        .await
+        {
+            Ok(scores) => {
+                tracing::info!("Quality of {}: {:?}", resolution.playlist_name, scores);
+                resolution.quality = Some(scores);
+            }
+            Err(e) => tracing::warn!(
+                "Could not measure the quality of {}: {}",
+                resolution.playlist_name,
+                e
+            ),
+        }
+    }
+}
+
+/// Hashes the master playlist, media playlists and segments of `manifest` and writes the
+/// sidecar next to the master playlist
+#[cfg(feature = "checksums")]
+fn write_checksum_manifest(manifest: &HlsVideoManifest) -> Result<ChecksumManifest, HlsKitError> {
+    let mut files = vec![(
+        MASTER_PLAYLIST_NAME.to_string(),
+        manifest.master_playlist.to_vec(),
+    )];
+    for resolution in &manifest.resolutions {
+        files.push((
+            resolution.playlist_name.clone(),
+            std::fs::read(&resolution.playlist_path)?,
+        ));
+        for segment_path in &resolution.segment_paths {
+            files.push((file_name_of(segment_path), std::fs::read(segment_path)?));
+        }
+    }
+
+    let checksums = ChecksumManifest::from_files(
+        files
+            .iter()
+            .map(|(name, data)| (name.as_str(), data.as_slice())),
+    );
+    std::fs::write(
+        manifest.output_dir.join(CHECKSUM_MANIFEST_NAME),
+        checksums.to_json(),
+    )?;
+
+    Ok(checksums)
+}
+
+/// Sums what every rendition's encoders consumed into the job's usage
+fn total_resource_usage(
+    resolutions: &[HlsVideoResolutionManifest],
+    wall_time: Duration,
+) -> ResourceUsage {
+    let mut total = ResourceUsage {
+        wall_time,
+        ..Default::default()
+    };
+    for resolution in resolutions {
+        total.merge_concurrent(&resolution.resource_usage);
+    }
+
+    total
+}
+
+/// Placeholder for pipelines that keep their output on disk only
+pub(crate) struct NoSink;
+
+impl OutputSink for NoSink {
+    async fn write_artifact(&self, _artifact: HlsArtifact) -> Result<(), HlsKitError> {
+        Ok(())
+    }
+}
+
+/// Settles `EncodeMode::Auto` on whether the source can be copied, giving copied profiles the
+/// source's resolution
+fn resolve_encode_modes(
+    profiles: Vec<HlsVideoProcessingSettings>,
+    source: &SourceInfo,
+) -> Result<Vec<HlsVideoProcessingSettings>, HlsKitError> {
+    let compatible = source.is_hls_compatible();
+
+    profiles
+        .into_iter()
+        .map(|mut profile| {
+            profile.encode_mode = match (profile.encode_mode, compatible) {
+                (EncodeMode::Passthrough, false) => {
+                    return Err(VideoValidatableErrors::InvalidVideoInput {
+                        error: format!(
+                            "{} source can't be copied into HLS, transcode it instead",
+                            source.video_codec.as_deref().unwrap_or("unknown")
+                        ),
+                    }
+                    .into())
+                }
+                (EncodeMode::Passthrough | EncodeMode::Auto, true) => EncodeMode::Passthrough,
+                (_, false) | (EncodeMode::Transcode, true) => EncodeMode::Transcode,
+            };
+            if profile.encode_mode == EncodeMode::Passthrough {
+                profile.resolution = source.display_resolution();
+            }
+            Ok(profile)
+        })
+        .collect()
+}
+
+/// State of one run handed down to [`ProcessingJob::run_profiles`]
+struct RunScope {
+    started: Instant,
+    /// Removed as soon as the encoders have exited
+    staged_keys: Vec<StagedKey>,
+    /// Whether the master playlist is written to the output directory
+    persist_master_playlist: bool,
+}
+
+/// Key files written to a private directory for the encoders. The key file, if any, is
+/// overwritten and the directory removed on drop.
+struct StagedKey {
+    dir: Option<tempfile::TempDir>,
+    key_path: Option<PathBuf>,
+}
+
+impl StagedKey {
+    /// Writes the key and an ffmpeg key info file pointing to it
+    fn write(key: &KeyMaterial) -> Result<(Self, VideoProcessorEncryptionSettings), HlsKitError> {
+        let dir = create_private_dir("hlskit-key-")?;
+        let iv = key.iv.as_ref().map(|iv| hex_iv(iv.expose()));
+
+        let key_path = dir.path().join("content.key");
+        write_private_file(&key_path, key.key.expose())?;
+
+        let key_info_path = dir.path().join("key_info");
+        let mut key_info = format!("{}\n{}\n", key.key_uri, key_path.display());
+        if let Some(iv) = &iv {
+            key_info.push_str(&format!("{iv}\n"));
+        }
+        write_private_file(&key_info_path, key_info.as_bytes())?;
+
+        let settings = VideoProcessorEncryptionSettings {
+            encryption_key_url: key.key_uri.clone(),
+            encryption_key_path: key_info_path.to_string_lossy().into_owned(),
+            iv,
+        };
+
+        let staged_key = Self {
+            dir: Some(dir),
+            key_path: Some(key_path),
+        };
+
+        Ok((staged_key, settings))
+    }
+
+    /// Fills in the IV of `encryption`, a random one unless set. Backends only read the IV
+    /// from the third line of the key info file, so key info files without one are copied with
+    /// the IV added; an IV already in the key info file wins.
+    fn with_iv(
+        encryption: &mut VideoProcessorEncryptionSettings,
+    ) -> Result<Option<Self>, HlsKitError> {
+        let key_info = std::fs::read_to_string(&encryption.encryption_key_path)?;
+        let mut lines = key_info.lines().map(str::trim);
+        let (Some(key_uri), Some(key_path)) = (lines.next(), lines.next()) else {
+            return Err(HlsKitError::EncryptionError {
+                error: format!(
+                    "{} is not an ffmpeg key info file",
+                    encryption.encryption_key_path
+                ),
+            });
+        };
+        if let Some(iv) = lines.next().filter(|iv| !iv.is_empty()) {
+            encryption.iv = Some(iv.to_string());
+            return Ok(None);
+        }
+
+        let iv = match &encryption.iv {
+            Some(iv) => iv.trim_start_matches("0x").to_string(),
+            None => hex_iv(&random_iv()?),
+        };
+        let dir = create_private_dir("hlskit-key-")?;
+        let key_info_path = dir.path().join("key_info");
+        write_private_file(
+            &key_info_path,
+            format!("{key_uri}\n{key_path}\n{iv}\n").as_bytes(),
+        )?;
+
+        encryption.encryption_key_path = key_info_path.to_string_lossy().into_owned();
+        encryption.iv = Some(iv);
+
+        Ok(Some(Self {
+            dir: Some(dir),
+            key_path: None,
+        }))
+    }
+}
+
+impl Drop for StagedKey {
+    fn drop(&mut self) {
+        if let Some(Err(e)) = self
+            .key_path
+            .as_ref()
+            .map(|path| std::fs::write(path, [0; 16]))
+        {
+            tracing::warn!("Failed to overwrite the staged encryption key: {}", e);
+        }
+        if let Some(Err(e)) = self.dir.take().map(|dir| dir.close()) {
+            tracing::warn!("Failed to remove the staged encryption key: {}", e);
+        }
+    }
+}
+
+fn random_iv() -> Result<[u8; 16], HlsKitError> {
+    let mut iv = [0; 16];
+    getrandom::fill(&mut iv).map_err(|e| HlsKitError::EncryptionError {
+        error: format!("Failed to generate an IV: {e}"),
+    })?;
+
+    Ok(iv)
+}
+
+fn hex_iv(iv: &[u8; 16]) -> String {
+    iv.iter().map(|byte| format!("{byte:02x}")).collect()
+}
@@ -0,0 +1,134 @@
+// SPDX-License-Identifier: LGPL-3.0-only
+/*
+ * Copyright © 2025 The HlsKit Project
+ *
+ * This software is licensed under the GNU Lesser General Public License v3.0 (LGPLv3).
+ * All contributions adhere to the LGPLv3 and the HlsKit Contributor License Agreement (CLA).
+ * A copy of the LGPLv3 can be found at https://www.gnu.org/licenses/lgpl-3.0.html
+ *
+ * HlsKit Contributor License Agreement
+ *
+ * By contributing to or modifying HlsKit, you agree to the following terms:
+ *
+ * 1. Collective Ownership:
+ * The HlsKit project incorporates original code and all contributions as a collective work,
+ * licensed under LGPLv3. Once submitted, contributions become part of the shared HlsKit
+ * ecosystem and cannot be reclaimed, reassigned, or withdrawn. Contributions to your own
+ * forks remain yours unless submitted here, at which point they join this collective whole under LGPLv3.
+ *
+ * 2. Definition of Contribution:
+ * You are considered a contributor if you modify the library in any form (including forks,
+ * wrappers, libraries, or extensions that alter its behavior), whether or not you submit
+ * your changes directly to this repository. All such modifications are part of the broader
+ * HlsKit ecosystem and are subject to this CLA.
+ *
+ * 3. Distribution of Modifications:
+ * If you distribute a modified version of HlsKit, you must license your modifications under
+ * LGPLv3 (with source code available as required by the license) and ensure they are
+ * adoptable by the HlsKit ecosystem (publicly available and compatible).
+ *
+ * 4. Networked Use of Modifications:
+ * If you use a modified version of HlsKit in a networked application, you must provide the
+ * source code of your modifications under LGPLv3 and notify the HlsKit project
+ * (e.g., via email to [higashikataengels@icloud.com]). This does not apply to the use of
+ * the unmodified library in proprietary software, which remains permissible under LGPLv3.
+ *
+ * 5. Scope:
+ * These terms apply to all contributions and modifications derived from the HlsKit project.
+ * The use of the unmodified library in proprietary software is governed solely by the LGPLv3.
+ */
+
+use std::time::{Duration, Instant};
+
+use crate::models::resource_usage::ResourceUsage;
+
+/// How often a running encoder's CPU time and memory are sampled
+const SAMPLE_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Follows the resource usage of a running process through `/proc`
+///
+/// The exited process is reaped by tokio before its final numbers can be read, so CPU time
+/// is accurate to the last sample; the peak RSS is the kernel's own high-water mark.
+#[derive(Debug)]
+pub(crate) struct UsageSampler {
+    pid: Option<u32>,
+    started: Instant,
+    usage: ResourceUsage,
+}
+
+impl UsageSampler {
+    pub(crate) fn new(pid: Option<u32>) -> Self {
+        Self {
+            pid,
+            started: Instant::now(),
+            usage: ResourceUsage::default(),
+        }
+    }
+
+    /// Samples the process until the future is dropped
+    pub(crate) async fn run(&mut self) {
+        loop {
+            self.sample();
+            tokio::time::sleep(SAMPLE_INTERVAL).await;
+        }
+    }
+
+    pub(crate) fn finish(mut self) -> ResourceUsage {
+        self.usage.wall_time = self.started.elapsed();
+        self.usage
+    }
+
+    #[cfg(target_os = "linux")]
+    fn sample(&mut self) {
+        let Some(pid) = self.pid else {
+            return;
+        };
+
+        if let Some((user_time, system_time)) = read_cpu_times(pid) {
+            self.usage.user_time = user_time;
+            self.usage.system_time = system_time;
+        }
+
+        if let Some(peak) = read_peak_rss(pid) {
+            self.usage.peak_rss_bytes = Some(self.usage.peak_rss_bytes.unwrap_or(0).max(peak));
+        }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn sample(&mut self) {}
+}
+
+/// User and system CPU time from fields 14 and 15 of `/proc/<pid>/stat`
+#[cfg(target_os = "linux")]
+fn read_cpu_times(pid: u32) -> Option<(Duration, Duration)> {
+    let stat = std::fs::read_to_string(format!("/proc/{pid}/stat")).ok()?;
+    // The command name may contain spaces, the fields we want come after its closing paren
+    let mut fields = stat.rsplit_once(')')?.1.split_whitespace().skip(11);
+    let user_ticks: u64 = fields.next()?.parse().ok()?;
+    let system_ticks: u64 = fields.next()?.parse().ok()?;
+
+    // SAFETY: sysconf has no preconditions
+    let ticks_per_second = unsafe { libc::sysconf(libc::_SC_CLK_TCK) };
+    if ticks_per_second <= 0 {
+        return None;
+    }
+
+    let to_duration = |ticks: u64| Duration::from_secs_f64(ticks as f64 / ticks_per_second as f64);
+    Some((to_duration(user_ticks), to_duration(system_ticks)))
+}
+
+/// The `VmHWM` line of `/proc/<pid>/status`, in bytes
+#[cfg(target_os = "linux")]
+fn read_peak_rss(pid: u32) -> Option<u64> {
+    let status = std::fs::read_to_string(format!("/proc/{pid}/status")).ok()?;
+    let kilobytes: u64 = status
+        .lines()
+        .find_map(|line| line.strip_prefix("VmHWM:"))?
+        .trim()
+        .trim_end_matches("kB")
+        .trim()
+        .parse()
+        .ok()?;
+
+    Some(kilobytes * 1024)
+}
@@ -0,0 +1,103 @@
+// SPDX-License-Identifier: LGPL-3.0-only
+/*
+ * Copyright © 2025 The HlsKit Project
+ *
+ * This software is licensed under the GNU Lesser General Public License v3.0 (LGPLv3).
+ * All contributions adhere to the LGPLv3 and the HlsKit Contributor License Agreement (CLA).
+ * A copy of the LGPLv3 can be found at https://www.gnu.org/licenses/lgpl-3.0.html
+ *
+ * HlsKit Contributor License Agreement
+ *
+ * By contributing to or modifying HlsKit, you agree to the following terms:
+ *
+ * 1. Collective Ownership:
+ * The HlsKit project incorporates original code and all contributions as a collective work,
+ * licensed under LGPLv3. Once submitted, contributions become part of the shared HlsKit
+ * ecosystem and cannot be reclaimed, reassigned, or withdrawn. Contributions to your own
+ * forks remain yours unless submitted here, at which point they join this collective whole under LGPLv3.
+ *
+ * 2. Definition of Contribution:
+ * You are considered a contributor if you modify the library in any form (including forks,
+ * wrappers, libraries, or extensions that alter its behavior), whether or not you submit
+ * your changes directly to this repository. All such modifications are part of the broader
+ * HlsKit ecosystem and are subject to this CLA.
+ *
+ * 3. Distribution of Modifications:
+ * If you distribute a modified version of HlsKit, you must license your modifications under
+ * LGPLv3 (with source code available as required by the license) and ensure they are
+ * adoptable by the HlsKit ecosystem (publicly available and compatible).
+ *
+ * 4. Networked Use of Modifications:
+ * If you use a modified version of HlsKit in a networked application, you must provide the
+ * source code of your modifications under LGPLv3 and notify the HlsKit project
+ * (e.g., via email to [higashikataengels@icloud.com]). This does not apply to the use of
+ * the unmodified library in proprietary software, which remains permissible under LGPLv3.
+ *
+ * 5. Scope:
+ * These terms apply to all contributions and modifications derived from the HlsKit project.
+ * The use of the unmodified library in proprietary software is governed solely by the LGPLv3.
+ */
+
+use std::path::{Path, PathBuf};
+
+use tempfile::TempDir;
+
+use crate::{models::retention_policy::RetentionPolicy, tools::secure_files::create_private_dir};
+
+/// Owns a job's temporary work directory and applies the retention policy when released
+///
+/// A guard dropped without being released, e.g. when the job future is cancelled or panics,
+/// is treated as a failed job.
+pub(crate) struct WorkdirGuard {
+    dir: Option<TempDir>,
+    policy: RetentionPolicy,
+}
+
+impl WorkdirGuard {
+    /// Creates the work directory, readable by the current user only when `private` is set
+    pub fn new(policy: RetentionPolicy, private: bool) -> std::io::Result<Self> {
+        let dir = if private {
+            create_private_dir("hlskit-work-")?
+        } else {
+            TempDir::new()?
+        };
+
+        Ok(Self {
+            dir: Some(dir),
+            policy,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        self.dir
+            .as_ref()
+            .map(TempDir::path)
+            .expect("work directory is only taken on release")
+    }
+
+    /// Cleans up or keeps the directory, returning its path when it was kept
+    pub fn release(mut self, succeeded: bool) -> Option<PathBuf> {
+        self.apply(succeeded)
+    }
+
+    fn apply(&mut self, succeeded: bool) -> Option<PathBuf> {
+        let dir = self.dir.take()?;
+
+        if !self.policy.keeps(succeeded) {
+            if let Err(e) = dir.close() {
+                tracing::warn!("Failed to remove work directory: {}", e);
+            }
+            return None;
+        }
+
+        let path = dir.keep();
+        tracing::info!("Keeping work directory {}", path.display());
+        Some(path)
+    }
+}
+
+impl Drop for WorkdirGuard {
+    fn drop(&mut self) {
+        self.apply(false);
+    }
+}
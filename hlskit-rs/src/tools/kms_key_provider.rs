@@ -0,0 +1,129 @@
+// SPDX-License-Identifier: LGPL-3.0-only
+/*
+ * Copyright © 2025 The HlsKit Project
+ *
+ * This software is licensed under the GNU Lesser General Public License v3.0 (LGPLv3).
+ * All contributions adhere to the LGPLv3 and the HlsKit Contributor License Agreement (CLA).
+ * A copy of the LGPLv3 can be found at https://www.gnu.org/licenses/lgpl-3.0.html
+ *
+ * HlsKit Contributor License Agreement
+ *
+ * By contributing to or modifying HlsKit, you agree to the following terms:
+ *
+ * 1. Collective Ownership:
+ * The HlsKit project incorporates original code and all contributions as a collective work,
+ * licensed under LGPLv3. Once submitted, contributions become part of the shared HlsKit
+ * ecosystem and cannot be reclaimed, reassigned, or withdrawn. Contributions to your own
+ * forks remain yours unless submitted here, at which point they join this collective whole under LGPLv3.
+ *
+ * 2. Definition of Contribution:
+ * You are considered a contributor if you modify the library in any form (including forks,
+ * wrappers, libraries, or extensions that alter its behavior), whether or not you submit
+ * your changes directly to this repository. All such modifications are part of the broader
+ * HlsKit ecosystem and are subject to this CLA.
+ *
+ * 3. Distribution of Modifications:
+ * If you distribute a modified version of HlsKit, you must license your modifications under
+ * LGPLv3 (with source code available as required by the license) and ensure they are
+ * adoptable by the HlsKit ecosystem (publicly available and compatible).
+ *
+ * 4. Networked Use of Modifications:
+ * If you use a modified version of HlsKit in a networked application, you must provide the
+ * source code of your modifications under LGPLv3 and notify the HlsKit project
+ * (e.g., via email to [higashikataengels@icloud.com]). This does not apply to the use of
+ * the unmodified library in proprietary software, which remains permissible under LGPLv3.
+ *
+ * 5. Scope:
+ * These terms apply to all contributions and modifications derived from the HlsKit project.
+ * The use of the unmodified library in proprietary software is governed solely by the LGPLv3.
+ */
+
+use aws_sdk_kms::{primitives::Blob, types::DataKeySpec, Client};
+
+use crate::{
+    models::{job_id::JobId, key_material::KeyMaterial, secret::SecretKey},
+    tools::hlskit_error::HlsKitError,
+    traits::key_provider::{KeyFuture, KeyProvider},
+};
+
+/// Generates a fresh AES-128 data key under a KMS key for every job. The KMS-encrypted copy of
+/// the key is returned in the job's `HlsVideoReport::encrypted_key`, to be stored with the
+/// asset and decrypted by the key server with [`KmsKeyProvider::decrypt_key`].
+#[derive(Debug, Clone)]
+pub struct KmsKeyProvider {
+    client: Client,
+    key_id: String,
+    key_uri: String,
+}
+
+impl KmsKeyProvider {
+    /// `key_uri` is where players fetch the key, `{job_id}` is replaced with the job id
+    pub fn new(client: Client, key_id: &str, key_uri: &str) -> Self {
+        Self {
+            client,
+            key_id: key_id.to_string(),
+            key_uri: key_uri.to_string(),
+        }
+    }
+
+    /// Builds the client from `config`, e.g. one `aws_config::load_from_env` shared with an
+    /// `S3Sink`
+    pub fn from_config(config: &aws_config::SdkConfig, key_id: &str, key_uri: &str) -> Self {
+        Self::new(Client::new(config), key_id, key_uri)
+    }
+
+    /// Decrypts a data key returned in `HlsVideoReport::encrypted_key`
+    pub async fn decrypt_key(&self, encrypted_key: &[u8]) -> Result<SecretKey, HlsKitError> {
+        let output = self
+            .client
+            .decrypt()
+            .key_id(&self.key_id)
+            .ciphertext_blob(Blob::new(encrypted_key))
+            .send()
+            .await
+            .map_err(|e| HlsKitError::EncryptionError {
+                error: format!("KMS Decrypt failed: {e}"),
+            })?;
+
+        data_key(output.plaintext())
+    }
+
+    async fn generate_key(&self, job_id: JobId) -> Result<KeyMaterial, HlsKitError> {
+        let output = self
+            .client
+            .generate_data_key()
+            .key_id(&self.key_id)
+            .key_spec(DataKeySpec::Aes128)
+            .send()
+            .await
+            .map_err(|e| HlsKitError::EncryptionError {
+                error: format!("KMS GenerateDataKey failed: {e}"),
+            })?;
+
+        let key = data_key(output.plaintext())?;
+        let encrypted_key = output
+            .ciphertext_blob()
+            .map(|blob| blob.as_ref().to_vec())
+            .ok_or_else(|| HlsKitError::EncryptionError {
+                error: "KMS returned no encrypted data key".to_string(),
+            })?;
+        let key_uri = self.key_uri.replace("{job_id}", &job_id.0.to_string());
+
+        Ok(KeyMaterial::new(key, key_uri).with_encrypted_key(encrypted_key))
+    }
+}
+
+impl KeyProvider for KmsKeyProvider {
+    fn fetch_key(&self, job_id: JobId, _key_index: u32) -> KeyFuture<'_> {
+        Box::pin(self.generate_key(job_id))
+    }
+}
+
+fn data_key(plaintext: Option<&Blob>) -> Result<SecretKey, HlsKitError> {
+    plaintext
+        .and_then(|blob| <[u8; 16]>::try_from(blob.as_ref()).ok())
+        .map(SecretKey::new)
+        .ok_or_else(|| HlsKitError::EncryptionError {
+            error: "KMS returned no 16 byte data key".to_string(),
+        })
+}
@@ -0,0 +1,208 @@
+// SPDX-License-Identifier: LGPL-3.0-only
+/*
+ * Copyright © 2025 The HlsKit Project
+ *
+ * This software is licensed under the GNU Lesser General Public License v3.0 (LGPLv3).
+ * All contributions adhere to the LGPLv3 and the HlsKit Contributor License Agreement (CLA).
+ * A copy of the LGPLv3 can be found at https://www.gnu.org/licenses/lgpl-3.0.html
+ *
+ * HlsKit Contributor License Agreement
+ *
+ * By contributing to or modifying HlsKit, you agree to the following terms:
+ *
+ * 1. Collective Ownership:
+ * The HlsKit project incorporates original code and all contributions as a collective work,
+ * licensed under LGPLv3. Once submitted, contributions become part of the shared HlsKit
+ * ecosystem and cannot be reclaimed, reassigned, or withdrawn. Contributions to your own
+ * forks remain yours unless submitted here, at which point they join this collective whole under LGPLv3.
+ *
+ * 2. Definition of Contribution:
+ * You are considered a contributor if you modify the library in any form (including forks,
+ * wrappers, libraries, or extensions that alter its behavior), whether or not you submit
+ * your changes directly to this repository. All such modifications are part of the broader
+ * HlsKit ecosystem and are subject to this CLA.
+ *
+ * 3. Distribution of Modifications:
+ * If you distribute a modified version of HlsKit, you must license your modifications under
+ * LGPLv3 (with source code available as required by the license) and ensure they are
+ * adoptable by the HlsKit ecosystem (publicly available and compatible).
+ *
+ * 4. Networked Use of Modifications:
+ * If you use a modified version of HlsKit in a networked application, you must provide the
+ * source code of your modifications under LGPLv3 and notify the HlsKit project
+ * (e.g., via email to [higashikataengels@icloud.com]). This does not apply to the use of
+ * the unmodified library in proprietary software, which remains permissible under LGPLv3.
+ *
+ * 5. Scope:
+ * These terms apply to all contributions and modifications derived from the HlsKit project.
+ * The use of the unmodified library in proprietary software is governed solely by the LGPLv3.
+ */
+
+use crate::models::{
+    hls_video_processing_settings::{
+        FfmpegVideoProcessingPreset, HlsVideoAudioBitrate, HlsVideoAudioCodec,
+        HlsVideoProcessingSettings,
+    },
+    source_info::SourceInfo,
+};
+
+/// Rendition heights (the short side, i.e. the width of portrait renditions) considered by
+/// default, with their CRF, largest first
+const DEFAULT_RUNGS: [(i32, i32); 8] = [
+    (2160, 20),
+    (1440, 21),
+    (1080, 22),
+    (720, 23),
+    (540, 24),
+    (480, 24),
+    (360, 26),
+    (240, 28),
+];
+
+/// Below this many bits per pixel per frame the source has little detail left to preserve
+const LOW_BITS_PER_PIXEL: f64 = 0.05;
+
+/// Builds an encoding ladder fitted to a probed source: no rung above the source resolution,
+/// the source aspect ratio kept, and CRFs adjusted to its frame rate and bitrate
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LadderGenerator {
+    rungs: Vec<(i32, i32)>,
+    max_rungs: usize,
+    preset: FfmpegVideoProcessingPreset,
+}
+
+impl Default for LadderGenerator {
+    fn default() -> Self {
+        Self {
+            rungs: DEFAULT_RUNGS.to_vec(),
+            max_rungs: 5,
+            preset: FfmpegVideoProcessingPreset::Medium,
+        }
+    }
+}
+
+impl LadderGenerator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replaces the candidate `(short side, CRF)` rungs
+    pub fn with_rungs(mut self, rungs: Vec<(i32, i32)>) -> Self {
+        self.rungs = rungs;
+        self.rungs
+            .sort_by_key(|&(height, _)| std::cmp::Reverse(height));
+        self
+    }
+
+    pub fn with_max_rungs(mut self, max_rungs: usize) -> Self {
+        self.max_rungs = max_rungs.max(1);
+        self
+    }
+
+    pub fn with_preset(mut self, preset: FfmpegVideoProcessingPreset) -> Self {
+        self.preset = preset;
+        self
+    }
+
+    /// The recommended renditions for `source`, largest first
+    pub fn generate(&self, source: &SourceInfo) -> Vec<HlsVideoProcessingSettings> {
+        let (source_width, source_height) = source.display_resolution();
+        if source_width <= 0 || source_height <= 0 {
+            return Vec::new();
+        }
+
+        let crf_offset = self.crf_offset(source);
+        let portrait = source_height > source_width;
+        // Rungs are named after the short side, so a portrait 1080x1920 source is "1080p"
+        let (source_short, source_long) = if portrait {
+            (source_width, source_height)
+        } else {
+            (source_height, source_width)
+        };
+        let aspect_ratio = source_long as f64 / source_short as f64;
+
+        let mut rungs: Vec<(i32, i32)> = self
+            .rungs
+            .iter()
+            .filter(|(short_side, _)| *short_side <= source_short)
+            .take(self.max_rungs)
+            .copied()
+            .collect();
+
+        // Non-standard sizes below the top rung are also offered at their native resolution,
+        // with the CRF of the rung just above them
+        let native_crf = self
+            .rungs
+            .iter()
+            .rev()
+            .find(|(short_side, _)| *short_side > source_short)
+            .map(|(_, crf)| *crf);
+        if let Some(crf) = native_crf {
+            if !rungs
+                .iter()
+                .any(|(short_side, _)| *short_side == source_short)
+            {
+                rungs.insert(0, (source_short, crf));
+                rungs.truncate(self.max_rungs);
+            }
+        }
+
+        rungs
+            .into_iter()
+            .map(|(short_side, crf)| {
+                let long_side = if short_side == source_short {
+                    source_long
+                } else {
+                    even((short_side as f64 * aspect_ratio).round() as i32)
+                };
+                let resolution = if portrait {
+                    (even(short_side), long_side)
+                } else {
+                    (long_side, even(short_side))
+                };
+
+                HlsVideoProcessingSettings::new(
+                    resolution,
+                    (crf + crf_offset).clamp(0, 51),
+                    Some(HlsVideoAudioCodec::Aac),
+                    Some(audio_bitrate_for(short_side)),
+                    self.preset,
+                )
+            })
+            .collect()
+    }
+
+    /// Spends more bits on high frame rates and fewer on already starved sources
+    fn crf_offset(&self, source: &SourceInfo) -> i32 {
+        let mut offset = 0;
+        let frame_rate = source.frame_rate.unwrap_or(30.0);
+
+        if frame_rate > 40.0 {
+            offset -= 1;
+        }
+
+        if let Some(bitrate) = source.bitrate {
+            let (width, height) = source.display_resolution();
+            let bits_per_pixel = bitrate as f64 / (width as f64 * height as f64 * frame_rate);
+            if bits_per_pixel < LOW_BITS_PER_PIXEL {
+                offset += 2;
+            }
+        }
+
+        offset
+    }
+}
+
+/// Encoders need even dimensions for 4:2:0 chroma subsampling
+pub(crate) fn even(value: i32) -> i32 {
+    (value / 2 * 2).max(2)
+}
+
+fn audio_bitrate_for(height: i32) -> HlsVideoAudioBitrate {
+    match height {
+        1080.. => HlsVideoAudioBitrate::High,
+        480.. => HlsVideoAudioBitrate::Medium,
+        _ => HlsVideoAudioBitrate::Low,
+    }
+}
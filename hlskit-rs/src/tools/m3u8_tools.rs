@@ -38,52 +38,360 @@
  * The use of the unmodified library in proprietary software is governed solely by the LGPLv3.
  */
 
-use std::{
-    fs::File,
-    io::{Read, Write},
-    path::Path,
-};
+use std::{fmt::Write, path::Path};
 
-use super::hlskit_error::HlsKitError;
+use super::{hlskit_error::HlsKitError, report_tools::playlist_bitrates};
+use crate::models::{
+    dynamic_range::VideoRange,
+    hls_video::MASTER_PLAYLIST_NAME,
+    playlist::{
+        ByteRange, InitSection, MasterPlaylist, MediaPlaylist, MediaRendition, MediaSegment,
+        SegmentKey, VariantStream,
+    },
+};
 
 pub async fn generate_master_playlist(
     output_dir: &Path,
     resolutions: Vec<(i32, i32)>,
     playlist_filenames: Vec<&str>,
 ) -> Result<Vec<u8>, HlsKitError> {
-    if !output_dir.exists() {
+    let mut variants = Vec::with_capacity(resolutions.len());
+    for (resolution, uri) in resolutions.into_iter().zip(playlist_filenames) {
+        let (average_bandwidth, bandwidth) = playlist_bitrates(&output_dir.join(uri)).await;
+        variants.push(MasterPlaylistVariant {
+            resolution,
+            uri,
+            bandwidth,
+            average_bandwidth: Some(average_bandwidth),
+            codecs: None,
+            frame_rate: None,
+            video_range: VideoRange::Sdr,
+        });
+    }
+
+    generate_master_playlist_for_variants(output_dir, &variants).await
+}
+
+/// A variant stream of the master playlist
+#[derive(Debug, Clone, PartialEq)]
+pub struct MasterPlaylistVariant<'a> {
+    pub resolution: (i32, i32),
+    /// URI of the media playlist
+    pub uri: &'a str,
+    /// Peak segment bitrate, in bits per second
+    pub bandwidth: u64,
+    /// Average bitrate over the whole rendition, in bits per second
+    pub average_bandwidth: Option<u64>,
+    /// RFC 6381 codecs of the rendition, e.g. `avc1.640028,mp4a.40.2`
+    pub codecs: Option<&'a str>,
+    pub frame_rate: Option<f64>,
+    /// Written as `VIDEO-RANGE` when HDR, SDR being the default
+    pub video_range: VideoRange,
+}
+
+/// Like [`generate_master_playlist`], also advertising `CODECS`, `FRAME-RATE` and `VIDEO-RANGE`
+pub async fn generate_master_playlist_for_variants(
+    output_dir: &Path,
+    variants: &[MasterPlaylistVariant<'_>],
+) -> Result<Vec<u8>, HlsKitError> {
+    if !tokio::fs::try_exists(output_dir).await.unwrap_or(false) {
         return Err(HlsKitError::FileNotFound {
             file_path: output_dir.to_string_lossy().into_owned(),
         });
     }
 
-    let master_playlist_path = output_dir.join("master.m3u8");
+    let master_playlist = master_playlist_for_variants(variants);
+    tokio::fs::write(output_dir.join(MASTER_PLAYLIST_NAME), &master_playlist).await?;
+
+    Ok(master_playlist.into_bytes())
+}
+
+/// The master playlist advertising `variants`, in order
+pub fn master_playlist_for_variants(variants: &[MasterPlaylistVariant<'_>]) -> String {
+    // Writing into a String can't fail
+    let mut master_playlist = String::from("#EXTM3U\n#EXT-X-INDEPENDENT-SEGMENTS\n");
+    for variant in variants {
+        let (width, height) = variant.resolution;
+
+        let _ = write!(
+            master_playlist,
+            "#EXT-X-STREAM-INF:BANDWIDTH={}",
+            variant.bandwidth
+        );
+        if let Some(average_bandwidth) = variant.average_bandwidth {
+            let _ = write!(master_playlist, ",AVERAGE-BANDWIDTH={average_bandwidth}");
+        }
+        if let Some(codecs) = variant.codecs {
+            let _ = write!(master_playlist, ",CODECS=\"{codecs}\"");
+        }
+        let _ = write!(master_playlist, ",RESOLUTION={width}x{height}");
+        if let Some(frame_rate) = variant.frame_rate {
+            let _ = write!(master_playlist, ",FRAME-RATE={frame_rate:.3}");
+        }
+        if variant.video_range != VideoRange::Sdr {
+            let _ = write!(
+                master_playlist,
+                ",VIDEO-RANGE={}",
+                variant.video_range.as_str()
+            );
+        }
+        let _ = writeln!(master_playlist);
+        let _ = writeln!(master_playlist, "{}", variant.uri);
+        println!("[HlsKit] Master playlist created for {width}x{height}");
+    }
+
+    master_playlist
+}
+
+/// Parses a master playlist, failing on a media playlist or malformed variants
+pub fn parse_master(playlist: &str) -> Result<MasterPlaylist, HlsKitError> {
+    let mut master = MasterPlaylist::default();
+    let mut pending_variant: Option<(usize, VariantStream)> = None;
+
+    for (line_number, line) in playlist_lines(playlist)? {
+        let error = |error: String| HlsKitError::PlaylistParseError {
+            line: line_number,
+            error,
+        };
+
+        if !line.starts_with('#') {
+            let (_, mut variant) = pending_variant
+                .take()
+                .ok_or_else(|| error(format!("URI {line} without #EXT-X-STREAM-INF")))?;
+            variant.uri = line.to_string();
+            master.variants.push(variant);
+            continue;
+        }
+
+        let (tag, value) = line.split_once(':').unwrap_or((line, ""));
+        match tag {
+            "#EXT-X-VERSION" => master.version = Some(parse_number(value).map_err(error)?),
+            "#EXT-X-INDEPENDENT-SEGMENTS" => master.independent_segments = true,
+            "#EXT-X-STREAM-INF" => {
+                let variant = parse_variant(value).map_err(error)?;
+                pending_variant = Some((line_number, variant));
+            }
+            "#EXT-X-I-FRAME-STREAM-INF" => {
+                let mut variant = parse_variant(value).map_err(error)?;
+                variant.uri = variant
+                    .attribute("URI")
+                    .ok_or_else(|| error("I-frame variant without URI".to_string()))?
+                    .to_string();
+                master.i_frame_variants.push(variant);
+            }
+            "#EXT-X-MEDIA" => master
+                .media
+                .push(parse_media_rendition(value).map_err(error)?),
+            "#EXTINF" | "#EXT-X-TARGETDURATION" => {
+                return Err(error("media playlist tag in a master playlist".to_string()))
+            }
+            _ if tag.starts_with("#EXT") => master.other_tags.push(line.to_string()),
+            _ => {}
+        }
+    }
+
+    if let Some((line, _)) = pending_variant {
+        return Err(HlsKitError::PlaylistParseError {
+            line,
+            error: "#EXT-X-STREAM-INF without URI".to_string(),
+        });
+    }
+
+    Ok(master)
+}
 
-    {
-        // Scope for the write handle
-        let mut master_playlist_handler = File::create(&master_playlist_path)?;
+/// Parses a media playlist, failing on a master playlist or malformed segments
+pub fn parse_media(playlist: &str) -> Result<MediaPlaylist, HlsKitError> {
+    let mut media = MediaPlaylist::default();
+    let mut target_duration = None;
+    let mut key = None;
+    let mut pending = MediaSegment::default();
+    let mut pending_duration = false;
 
-        writeln!(master_playlist_handler, "#EXTM3U")?;
+    for (line_number, line) in playlist_lines(playlist)? {
+        let error = |error: String| HlsKitError::PlaylistParseError {
+            line: line_number,
+            error,
+        };
 
-        for (index, (width, height)) in resolutions.iter().enumerate() {
-            let raw_path = playlist_filenames[index];
-            let bandwidth = (index + 1) * 1_500_000;
+        if !line.starts_with('#') {
+            if !pending_duration {
+                return Err(error(format!("segment {line} without #EXTINF")));
+            }
+            pending.uri = line.to_string();
+            pending.key = key.clone();
+            media.segments.push(std::mem::take(&mut pending));
+            pending_duration = false;
+            continue;
+        }
 
-            writeln!(
-                master_playlist_handler,
-                "#EXT-X-STREAM-INF:BANDWIDTH={bandwidth},RESOLUTION={width}x{height}"
-            )?;
-            writeln!(master_playlist_handler, "{raw_path}")?;
-            println!("[HlsKit] Master playlist created for {width}x{height}");
+        let (tag, value) = line.split_once(':').unwrap_or((line, ""));
+        match tag {
+            "#EXT-X-VERSION" => media.version = Some(parse_number(value).map_err(error)?),
+            "#EXT-X-TARGETDURATION" => target_duration = Some(parse_number(value).map_err(error)?),
+            "#EXT-X-MEDIA-SEQUENCE" => media.media_sequence = parse_number(value).map_err(error)?,
+            "#EXT-X-PLAYLIST-TYPE" => media.playlist_type = Some(value.to_string()),
+            "#EXT-X-INDEPENDENT-SEGMENTS" => media.independent_segments = true,
+            "#EXT-X-ENDLIST" => media.end_list = true,
+            "#EXT-X-DISCONTINUITY" => pending.discontinuity = true,
+            "#EXT-X-PROGRAM-DATE-TIME" => pending.program_date_time = Some(value.to_string()),
+            "#EXT-X-BYTERANGE" => {
+                pending.byte_range = Some(parse_byte_range(value).map_err(error)?)
+            }
+            "#EXTINF" => {
+                let (duration, title) = value.split_once(',').unwrap_or((value, ""));
+                pending.duration = duration
+                    .trim()
+                    .parse()
+                    .map_err(|_| error(format!("invalid segment duration {duration:?}")))?;
+                pending.title = Some(title.trim())
+                    .filter(|title| !title.is_empty())
+                    .map(str::to_string);
+                pending_duration = true;
+            }
+            "#EXT-X-KEY" => {
+                let attributes = parse_attribute_list(value);
+                let attribute = |name: &str| find_attribute(&attributes, name).map(str::to_string);
+                let method = attribute("METHOD")
+                    .ok_or_else(|| error("#EXT-X-KEY without METHOD".to_string()))?;
+                key = (method != "NONE").then(|| SegmentKey {
+                    method,
+                    uri: attribute("URI"),
+                    iv: attribute("IV"),
+                    key_format: attribute("KEYFORMAT"),
+                });
+            }
+            "#EXT-X-MAP" => {
+                let attributes = parse_attribute_list(value);
+                media.map = Some(InitSection {
+                    uri: find_attribute(&attributes, "URI")
+                        .ok_or_else(|| error("#EXT-X-MAP without URI".to_string()))?
+                        .to_string(),
+                    byte_range: find_attribute(&attributes, "BYTERANGE")
+                        .map(parse_byte_range)
+                        .transpose()
+                        .map_err(error)?,
+                });
+            }
+            "#EXT-X-STREAM-INF" | "#EXT-X-I-FRAME-STREAM-INF" | "#EXT-X-MEDIA" => {
+                return Err(error("master playlist tag in a media playlist".to_string()))
+            }
+            _ if tag.starts_with("#EXT") => media.other_tags.push(line.to_string()),
+            _ => {}
         }
+    }
+
+    media.target_duration = target_duration.ok_or(HlsKitError::PlaylistParseError {
+        line: 1,
+        error: "no #EXT-X-TARGETDURATION".to_string(),
+    })?;
+
+    Ok(media)
+}
 
-        master_playlist_handler.flush()?;
+/// The non-empty lines after the `#EXTM3U` header, with their 1-based line numbers
+fn playlist_lines(playlist: &str) -> Result<Vec<(usize, &str)>, HlsKitError> {
+    let mut lines = playlist
+        .lines()
+        .enumerate()
+        .map(|(index, line)| (index + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty());
+
+    match lines.next() {
+        Some((_, "#EXTM3U")) => Ok(lines.collect()),
+        first => Err(HlsKitError::PlaylistParseError {
+            line: first.map_or(1, |(line, _)| line),
+            error: "missing #EXTM3U header".to_string(),
+        }),
     }
+}
+
+fn parse_number<T: std::str::FromStr>(value: &str) -> Result<T, String> {
+    value
+        .trim()
+        .parse()
+        .map_err(|_| format!("invalid number {value:?}"))
+}
+
+/// `<length>[@<offset>]`
+fn parse_byte_range(value: &str) -> Result<ByteRange, String> {
+    let (length, offset) = match value.split_once('@') {
+        Some((length, offset)) => (length, Some(parse_number(offset)?)),
+        None => (value, None),
+    };
+
+    Ok(ByteRange {
+        length: parse_number(length)?,
+        offset,
+    })
+}
+
+fn parse_variant(value: &str) -> Result<VariantStream, String> {
+    let attributes = parse_attribute_list(value);
+    let attribute = |name: &str| find_attribute(&attributes, name);
+
+    Ok(VariantStream {
+        uri: String::new(),
+        bandwidth: parse_number(attribute("BANDWIDTH").ok_or("variant without BANDWIDTH")?)?,
+        average_bandwidth: attribute("AVERAGE-BANDWIDTH")
+            .map(parse_number)
+            .transpose()?,
+        resolution: attribute("RESOLUTION")
+            .map(|resolution| {
+                let (width, height) = resolution
+                    .split_once('x')
+                    .ok_or_else(|| format!("invalid resolution {resolution:?}"))?;
+                Ok::<_, String>((parse_number(width)?, parse_number(height)?))
+            })
+            .transpose()?,
+        codecs: attribute("CODECS").map(str::to_string),
+        frame_rate: attribute("FRAME-RATE").map(parse_number).transpose()?,
+        video_range: attribute("VIDEO-RANGE").map(str::to_string),
+        audio: attribute("AUDIO").map(str::to_string),
+        subtitles: attribute("SUBTITLES").map(str::to_string),
+        attributes: attributes.clone(),
+    })
+}
 
-    // Reopen the file for reading
-    let mut master_playlist_handler = File::open(&master_playlist_path)?;
-    let mut master_playlist_buffer = Vec::new();
-    master_playlist_handler.read_to_end(&mut master_playlist_buffer)?;
+fn parse_media_rendition(value: &str) -> Result<MediaRendition, String> {
+    let attributes = parse_attribute_list(value);
+    let attribute = |name: &str| find_attribute(&attributes, name).map(str::to_string);
+    let required = |name: &str| attribute(name).ok_or(format!("#EXT-X-MEDIA without {name}"));
+
+    Ok(MediaRendition {
+        media_type: required("TYPE")?,
+        group_id: required("GROUP-ID")?,
+        name: required("NAME")?,
+        language: attribute("LANGUAGE"),
+        uri: attribute("URI"),
+        default: attribute("DEFAULT").as_deref() == Some("YES"),
+        autoselect: attribute("AUTOSELECT").as_deref() == Some("YES"),
+        channels: attribute("CHANNELS"),
+        attributes: attributes.clone(),
+    })
+}
+
+fn find_attribute<'a>(attributes: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    attributes
+        .iter()
+        .find(|(key, _)| key == name)
+        .map(|(_, value)| value.as_str())
+}
+
+/// Splits an attribute list into `(name, value)` pairs, unquoting quoted values (which may
+/// contain commas)
+pub(crate) fn parse_attribute_list(attributes: &str) -> Vec<(String, String)> {
+    let mut parsed = Vec::new();
+    let mut rest = attributes.trim();
+
+    while let Some((key, value)) = rest.split_once('=') {
+        let (value, remainder) = match value.strip_prefix('"') {
+            Some(quoted) => quoted.split_once('"').unwrap_or((quoted, "")),
+            None => value.split_once(',').unwrap_or((value, "")),
+        };
+        parsed.push((key.trim().to_string(), value.to_string()));
+        rest = remainder.trim_start_matches(',').trim();
+    }
 
-    Ok(master_playlist_buffer)
+    parsed
 }
@@ -38,10 +38,55 @@
  * The use of the unmodified library in proprietary software is governed solely by the LGPLv3.
  */
 
+#[cfg(feature = "archive")]
+pub mod archive_tools;
+#[cfg(feature = "test-support")]
+pub mod backend_conformance;
+pub mod binary_tools;
+pub mod bubblewrap_sandbox;
+#[cfg(feature = "cache")]
+pub mod cache_tools;
+pub mod chunk_tools;
+#[cfg(feature = "encryption")]
+pub mod clear_key_provider;
+pub mod codec_tools;
 pub mod command_runner;
+pub mod concat_tools;
+pub mod diff_tools;
+#[cfg(feature = "cache")]
+pub mod dir_result_cache;
+pub mod disk_tools;
+#[cfg(feature = "encryption")]
+pub mod encryption_tools;
 pub mod ffmpeg_command_builder;
+pub mod ffmpeg_progress;
+pub mod ffmpeg_stderr;
 pub mod gstreamer_command_builder;
+pub mod gstreamer_progress;
+pub mod gstreamer_stderr;
+pub mod hls_lint;
 pub mod hlskit_error;
+pub mod ingest_tools;
 pub mod internals;
+#[cfg(feature = "kms")]
+pub mod kms_key_provider;
+pub mod ladder_generator;
 pub mod m3u8_tools;
+pub mod output_validation;
+pub mod per_title;
+pub mod probe_tools;
+pub mod processing_context;
+pub mod processing_hooks;
+pub mod quality_tools;
+pub mod remux_tools;
+pub mod rendition_tools;
+pub mod report_tools;
+pub mod rewrite_tools;
+pub mod secure_files;
 pub mod segment_tools;
+#[cfg(feature = "signing")]
+pub mod signing;
+pub mod sink_tools;
+#[cfg(feature = "webhooks")]
+pub mod webhook_notifier;
+pub mod work_units;
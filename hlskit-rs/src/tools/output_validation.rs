@@ -0,0 +1,344 @@
+// SPDX-License-Identifier: LGPL-3.0-only
+/*
+ * Copyright © 2025 The HlsKit Project
+ *
+ * This software is licensed under the GNU Lesser General Public License v3.0 (LGPLv3).
+ * All contributions adhere to the LGPLv3 and the HlsKit Contributor License Agreement (CLA).
+ * A copy of the LGPLv3 can be found at https://www.gnu.org/licenses/lgpl-3.0.html
+ *
+ * HlsKit Contributor License Agreement
+ *
+ * By contributing to or modifying HlsKit, you agree to the following terms:
+ *
+ * 1. Collective Ownership:
+ * The HlsKit project incorporates original code and all contributions as a collective work,
+ * licensed under LGPLv3. Once submitted, contributions become part of the shared HlsKit
+ * ecosystem and cannot be reclaimed, reassigned, or withdrawn. Contributions to your own
+ * forks remain yours unless submitted here, at which point they join this collective whole under LGPLv3.
+ *
+ * 2. Definition of Contribution:
+ * You are considered a contributor if you modify the library in any form (including forks,
+ * wrappers, libraries, or extensions that alter its behavior), whether or not you submit
+ * your changes directly to this repository. All such modifications are part of the broader
+ * HlsKit ecosystem and are subject to this CLA.
+ *
+ * 3. Distribution of Modifications:
+ * If you distribute a modified version of HlsKit, you must license your modifications under
+ * LGPLv3 (with source code available as required by the license) and ensure they are
+ * adoptable by the HlsKit ecosystem (publicly available and compatible).
+ *
+ * 4. Networked Use of Modifications:
+ * If you use a modified version of HlsKit in a networked application, you must provide the
+ * source code of your modifications under LGPLv3 and notify the HlsKit project
+ * (e.g., via email to [higashikataengels@icloud.com]). This does not apply to the use of
+ * the unmodified library in proprietary software, which remains permissible under LGPLv3.
+ *
+ * 5. Scope:
+ * These terms apply to all contributions and modifications derived from the HlsKit project.
+ * The use of the unmodified library in proprietary software is governed solely by the LGPLv3.
+ */
+
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use crate::{
+    models::{
+        hls_video::MASTER_PLAYLIST_NAME,
+        validation_report::{FindingSeverity, ValidationReport},
+    },
+    tools::{
+        hlskit_error::HlsKitError,
+        m3u8_tools::{parse_master, parse_media},
+        probe_tools::count_decoded_frames,
+    },
+};
+
+/// Checks run on the produced asset before it is published: every playlist parses, every
+/// segment exists and is non-empty, the renditions last as long as the source and as each
+/// other, and a sample of segments decodes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OutputValidation {
+    /// Segments of each rendition decoded with ffprobe, spread over the rendition
+    pub decoded_segments: usize,
+    /// Allowed gap between the duration of a rendition and the source's
+    pub duration_tolerance: Duration,
+    /// Allowed gap between the segment boundaries and total durations of two renditions
+    pub drift_tolerance: Duration,
+    /// How renditions drifting apart, or with different segment counts, are reported. Players
+    /// switching between such renditions skip or repeat content
+    pub drift_severity: FindingSeverity,
+    /// Fails the job, before the master playlist reaches the sink, when errors are found
+    pub fail_on_error: bool,
+}
+
+impl Default for OutputValidation {
+    fn default() -> Self {
+        Self {
+            decoded_segments: 3,
+            duration_tolerance: Duration::from_secs(1),
+            drift_tolerance: Duration::from_millis(500),
+            drift_severity: FindingSeverity::Error,
+            fail_on_error: true,
+        }
+    }
+}
+
+impl OutputValidation {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_decoded_segments(mut self, decoded_segments: usize) -> Self {
+        self.decoded_segments = decoded_segments;
+        self
+    }
+
+    pub fn with_duration_tolerance(mut self, tolerance: Duration) -> Self {
+        self.duration_tolerance = tolerance;
+        self
+    }
+
+    /// Reports drifting renditions with `severity`, e.g. `FindingSeverity::Warning` to
+    /// publish them anyway
+    pub fn with_drift_check(mut self, tolerance: Duration, severity: FindingSeverity) -> Self {
+        self.drift_tolerance = tolerance;
+        self.drift_severity = severity;
+        self
+    }
+
+    /// Only reports the findings instead of failing the job
+    pub fn report_only(mut self) -> Self {
+        self.fail_on_error = false;
+        self
+    }
+}
+
+/// Validates the HLS asset in `output_dir`, comparing rendition durations to
+/// `source_duration` when known
+pub async fn validate_output(
+    output_dir: &Path,
+    source_duration: Option<Duration>,
+    options: &OutputValidation,
+) -> Result<ValidationReport, HlsKitError> {
+    let mut report = ValidationReport::default();
+
+    let master_playlist_path = output_dir.join(MASTER_PLAYLIST_NAME);
+    let master_playlist = match tokio::fs::read_to_string(&master_playlist_path).await {
+        Ok(master_playlist) => master_playlist,
+        Err(e) => {
+            report.error(
+                Some(master_playlist_path),
+                format!("unreadable master playlist: {e}"),
+            );
+            return Ok(report);
+        }
+    };
+    report.playlists_checked += 1;
+
+    let variants = match parse_master(&master_playlist) {
+        Ok(master) => master.variants,
+        Err(e) => {
+            report.error(
+                Some(master_playlist_path),
+                format!("malformed master playlist: {e}"),
+            );
+            return Ok(report);
+        }
+    };
+    if variants.is_empty() {
+        report.error(Some(master_playlist_path), "no variant streams");
+    }
+
+    let mut renditions = Vec::with_capacity(variants.len());
+    for variant in variants {
+        let playlist_path = output_dir.join(variant.uri);
+        if let Some(durations) =
+            validate_media_playlist(&playlist_path, source_duration, options, &mut report).await?
+        {
+            renditions.push((playlist_path, durations));
+        }
+    }
+    check_drift(&renditions, options, &mut report);
+
+    Ok(report)
+}
+
+/// Compares the segment count, segment boundaries and total duration of every rendition with
+/// the first one's
+fn check_drift(
+    renditions: &[(PathBuf, Vec<f64>)],
+    options: &OutputValidation,
+    report: &mut ValidationReport,
+) {
+    let Some(((reference_path, reference), others)) = renditions.split_first() else {
+        return;
+    };
+    let tolerance = options.drift_tolerance.as_secs_f64();
+    let reference_name = reference_path
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy();
+
+    for (path, durations) in others {
+        let mut findings = Vec::new();
+        if durations.len() != reference.len() {
+            findings.push(format!(
+                "{} segments where {reference_name} has {}",
+                durations.len(),
+                reference.len()
+            ));
+        }
+
+        let (mut reference_end, mut end) = (0.0, 0.0);
+        if let Some(index) = reference.iter().zip(durations).position(|(a, b)| {
+            reference_end += a;
+            end += b;
+            (reference_end - end).abs() > tolerance
+        }) {
+            findings.push(format!(
+                "segment {index} ends at {end:.3}s, {reference_name}'s at {reference_end:.3}s"
+            ));
+        }
+
+        let total = durations.iter().sum::<f64>();
+        let reference_total = reference.iter().sum::<f64>();
+        if (total - reference_total).abs() > tolerance {
+            findings.push(format!(
+                "lasts {total:.3}s, {reference_name} {reference_total:.3}s"
+            ));
+        }
+
+        for message in findings {
+            match options.drift_severity {
+                FindingSeverity::Warning => report.warning(Some(path.clone()), message),
+                FindingSeverity::Error => report.error(Some(path.clone()), message),
+            }
+        }
+    }
+}
+
+/// Validates a media playlist and its segments, returning its segment durations when it
+/// could be read
+async fn validate_media_playlist(
+    playlist_path: &Path,
+    source_duration: Option<Duration>,
+    options: &OutputValidation,
+    report: &mut ValidationReport,
+) -> Result<Option<Vec<f64>>, HlsKitError> {
+    let path = Some(playlist_path.to_path_buf());
+    let playlist = match tokio::fs::read_to_string(playlist_path).await {
+        Ok(playlist) => playlist,
+        Err(e) => {
+            report.error(path, format!("unreadable media playlist: {e}"));
+            return Ok(None);
+        }
+    };
+    report.playlists_checked += 1;
+
+    let playlist = match parse_media(&playlist) {
+        Ok(playlist) => playlist,
+        Err(e) => {
+            report.error(path, format!("malformed media playlist: {e}"));
+            return Ok(None);
+        }
+    };
+    if !playlist.end_list {
+        report.warning(
+            path.clone(),
+            "no #EXT-X-ENDLIST, players will treat it as live",
+        );
+    }
+    if playlist.segments.is_empty() {
+        report.error(path.clone(), "no segments");
+        return Ok(None);
+    }
+
+    let durations: Vec<f64> = playlist
+        .segments
+        .iter()
+        .map(|segment| segment.duration)
+        .collect();
+
+    let total = durations.iter().sum::<f64>();
+    if let Some(source_duration) = source_duration {
+        let drift = (total - source_duration.as_secs_f64()).abs();
+        if drift > options.duration_tolerance.as_secs_f64() {
+            report.error(
+                path.clone(),
+                format!(
+                    "lasts {total:.3}s but the source lasts {:.3}s",
+                    source_duration.as_secs_f64()
+                ),
+            );
+        }
+    }
+
+    let playlist_dir = playlist_path.parent().unwrap_or(Path::new("."));
+    let mut present_segments = Vec::with_capacity(playlist.segments.len());
+    for segment in &playlist.segments {
+        let segment_path = segment_path(playlist_dir, &segment.uri);
+        report.segments_checked += 1;
+        match tokio::fs::metadata(&segment_path).await {
+            Ok(metadata) if metadata.len() == 0 => {
+                report.error(Some(segment_path), "empty segment");
+            }
+            Ok(_) => present_segments.push(segment_path),
+            Err(e) => report.error(Some(segment_path), format!("missing segment: {e}")),
+        }
+    }
+
+    // Encrypted segments can't be decoded without fetching the key
+    if playlist
+        .segments
+        .iter()
+        .any(|segment| segment.key.is_some())
+    {
+        if options.decoded_segments > 0 {
+            report.warning(path, "encrypted segments were not decoded");
+        }
+        return Ok(Some(durations));
+    }
+
+    // fMP4 segments only decode behind their initialization section
+    if playlist.map.is_some() {
+        if options.decoded_segments > 0 {
+            report.warning(path, "fMP4 segments were not decoded");
+        }
+        return Ok(Some(durations));
+    }
+
+    for segment_path in sample(&present_segments, options.decoded_segments) {
+        report.segments_decoded += 1;
+        match count_decoded_frames(&segment_path.to_string_lossy(), "%+#1").await {
+            Ok(0) => report.error(Some(segment_path.clone()), "no decodable video frames"),
+            Ok(_) => {}
+            Err(e @ HlsKitError::BinaryNotFound { .. }) => return Err(e),
+            Err(e) => report.error(Some(segment_path.clone()), format!("decoding failed: {e}")),
+        }
+    }
+
+    Ok(Some(durations))
+}
+
+/// Where the segment at `uri` lives, segments referenced by URL being looked up by file name
+fn segment_path(playlist_dir: &Path, uri: &str) -> PathBuf {
+    match uri.rsplit_once("://") {
+        Some((_, rest)) => playlist_dir.join(rest.rsplit('/').next().unwrap_or(rest)),
+        None => playlist_dir.join(uri),
+    }
+}
+
+/// Up to `count` items spread evenly from first to last
+fn sample<T>(items: &[T], count: usize) -> Vec<&T> {
+    match count {
+        0 => Vec::new(),
+        _ if count >= items.len() => items.iter().collect(),
+        1 => vec![&items[0]],
+        _ => (0..count)
+            .map(|i| &items[i * (items.len() - 1) / (count - 1)])
+            .collect(),
+    }
+}
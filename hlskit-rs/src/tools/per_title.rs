@@ -0,0 +1,297 @@
+// SPDX-License-Identifier: LGPL-3.0-only
+/*
+ * Copyright © 2025 The HlsKit Project
+ *
+ * This software is licensed under the GNU Lesser General Public License v3.0 (LGPLv3).
+ * All contributions adhere to the LGPLv3 and the HlsKit Contributor License Agreement (CLA).
+ * A copy of the LGPLv3 can be found at https://www.gnu.org/licenses/lgpl-3.0.html
+ *
+ * HlsKit Contributor License Agreement
+ *
+ * By contributing to or modifying HlsKit, you agree to the following terms:
+ *
+ * 1. Collective Ownership:
+ * The HlsKit project incorporates original code and all contributions as a collective work,
+ * licensed under LGPLv3. Once submitted, contributions become part of the shared HlsKit
+ * ecosystem and cannot be reclaimed, reassigned, or withdrawn. Contributions to your own
+ * forks remain yours unless submitted here, at which point they join this collective whole under LGPLv3.
+ *
+ * 2. Definition of Contribution:
+ * You are considered a contributor if you modify the library in any form (including forks,
+ * wrappers, libraries, or extensions that alter its behavior), whether or not you submit
+ * your changes directly to this repository. All such modifications are part of the broader
+ * HlsKit ecosystem and are subject to this CLA.
+ *
+ * 3. Distribution of Modifications:
+ * If you distribute a modified version of HlsKit, you must license your modifications under
+ * LGPLv3 (with source code available as required by the license) and ensure they are
+ * adoptable by the HlsKit ecosystem (publicly available and compatible).
+ *
+ * 4. Networked Use of Modifications:
+ * If you use a modified version of HlsKit in a networked application, you must provide the
+ * source code of your modifications under LGPLv3 and notify the HlsKit project
+ * (e.g., via email to [higashikataengels@icloud.com]). This does not apply to the use of
+ * the unmodified library in proprietary software, which remains permissible under LGPLv3.
+ *
+ * 5. Scope:
+ * These terms apply to all contributions and modifications derived from the HlsKit project.
+ * The use of the unmodified library in proprietary software is governed solely by the LGPLv3.
+ */
+
+use std::{path::Path, time::Duration};
+
+use crate::{
+    models::{
+        hls_video_processing_settings::HlsVideoProcessingSettings, quality_scores::QualityMetric,
+        source_info::SourceInfo,
+    },
+    tools::{
+        hlskit_error::HlsKitError,
+        quality_tools::{measure_quality_of_section, run_ffmpeg},
+        secure_files::create_private_dir,
+    },
+};
+
+/// Tunes the CRF of every rendition to the title being encoded: short sections sampled across
+/// the source are encoded at candidate CRFs and scored with VMAF, and each rendition gets the
+/// highest CRF (the smallest output) still reaching the target score. Easy content such as
+/// slides or animation ends up far smaller than with a fixed CRF, at the cost of a few extra
+/// encodes per rendition.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PerTitle {
+    target_vmaf: f64,
+    sample_count: usize,
+    sample_duration: Duration,
+    crf_range: (i32, i32),
+}
+
+impl Default for PerTitle {
+    fn default() -> Self {
+        Self {
+            target_vmaf: 93.0,
+            sample_count: 3,
+            sample_duration: Duration::from_secs(4),
+            crf_range: (18, 34),
+        }
+    }
+}
+
+impl PerTitle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Mean VMAF score each rendition must reach on the samples, 93 by default
+    pub fn with_target_vmaf(mut self, target_vmaf: f64) -> Self {
+        self.target_vmaf = target_vmaf.clamp(0.0, 100.0);
+        self
+    }
+
+    /// Sections sampled across the source, 3 by default
+    pub fn with_sample_count(mut self, sample_count: usize) -> Self {
+        self.sample_count = sample_count.max(1);
+        self
+    }
+
+    pub fn with_sample_duration(mut self, sample_duration: Duration) -> Self {
+        self.sample_duration = sample_duration.max(Duration::from_secs(1));
+        self
+    }
+
+    /// Lowest and highest CRF considered, 18 to 34 by default
+    pub fn with_crf_range(mut self, min_crf: i32, max_crf: i32) -> Self {
+        self.crf_range = (min_crf.min(max_crf), min_crf.max(max_crf));
+        self
+    }
+
+    pub fn target_vmaf(&self) -> f64 {
+        self.target_vmaf
+    }
+
+    pub fn crf_range(&self) -> (i32, i32) {
+        self.crf_range
+    }
+
+    /// Returns `profiles` with the CRF picked for this title. Renditions whose samples cannot be
+    /// encoded or scored keep their configured CRF.
+    pub async fn optimize(
+        &self,
+        input: &str,
+        source: &SourceInfo,
+        profiles: &[HlsVideoProcessingSettings],
+    ) -> Result<Vec<HlsVideoProcessingSettings>, HlsKitError> {
+        let sample_dir = create_private_dir("hlskit-per-title-")?;
+        let sections = self.sample_sections(source.duration);
+        let reference_resolution = source.display_resolution();
+
+        let mut optimized = Vec::with_capacity(profiles.len());
+        for (index, profile) in profiles.iter().enumerate() {
+            let sample_path = sample_dir.path().join(format!("sample_{index}.mp4"));
+            let mut profile = profile.clone();
+
+            match self
+                .search_crf(
+                    input,
+                    &profile,
+                    &sections,
+                    reference_resolution,
+                    &sample_path,
+                )
+                .await
+            {
+                Ok(crf) => {
+                    tracing::info!(
+                        "Per-title CRF for {}x{}: {} (configured {})",
+                        profile.resolution.0,
+                        profile.resolution.1,
+                        crf,
+                        profile.constant_rate_factor
+                    );
+                    profile.constant_rate_factor = crf;
+                }
+                Err(e) => tracing::warn!(
+                    "Per-title analysis of {}x{} failed, keeping CRF {}: {}",
+                    profile.resolution.0,
+                    profile.resolution.1,
+                    profile.constant_rate_factor,
+                    e
+                ),
+            }
+            optimized.push(profile);
+        }
+
+        Ok(optimized)
+    }
+
+    /// `(start, duration)` of the sections to sample, spread evenly over the source
+    fn sample_sections(&self, source_duration: Option<Duration>) -> Vec<(Duration, Duration)> {
+        let duration = match source_duration {
+            Some(duration) if duration > self.sample_duration => duration,
+            Some(duration) => return vec![(Duration::ZERO, duration)],
+            None => return vec![(Duration::ZERO, self.sample_duration)],
+        };
+
+        let count = self.sample_count as u32;
+        (1..=count)
+            .map(|i| {
+                let center = duration * i / (count + 1);
+                let start = center
+                    .saturating_sub(self.sample_duration / 2)
+                    .min(duration - self.sample_duration);
+                (start, self.sample_duration)
+            })
+            .collect()
+    }
+
+    /// Binary search for the highest CRF whose samples reach the target, assuming quality only
+    /// drops as the CRF rises
+    async fn search_crf(
+        &self,
+        input: &str,
+        profile: &HlsVideoProcessingSettings,
+        sections: &[(Duration, Duration)],
+        reference_resolution: (i32, i32),
+        sample_path: &Path,
+    ) -> Result<i32, HlsKitError> {
+        let (mut low, mut high) = self.crf_range;
+        let mut best = low;
+
+        while low <= high {
+            let crf = low + (high - low) / 2;
+            let score = self
+                .score_crf(
+                    input,
+                    profile,
+                    crf,
+                    sections,
+                    reference_resolution,
+                    sample_path,
+                )
+                .await?;
+            tracing::debug!(
+                "Per-title: {}x{} at CRF {} scores VMAF {:.2}",
+                profile.resolution.0,
+                profile.resolution.1,
+                crf,
+                score
+            );
+
+            if score >= self.target_vmaf {
+                best = crf;
+                low = crf + 1;
+            } else {
+                high = crf - 1;
+            }
+        }
+
+        Ok(best)
+    }
+
+    /// Mean VMAF of the samples encoded at `crf`
+    async fn score_crf(
+        &self,
+        input: &str,
+        profile: &HlsVideoProcessingSettings,
+        crf: i32,
+        sections: &[(Duration, Duration)],
+        reference_resolution: (i32, i32),
+        sample_path: &Path,
+    ) -> Result<f64, HlsKitError> {
+        let mut total = 0.0;
+        for &(start, duration) in sections {
+            encode_sample(input, profile, crf, start, duration, sample_path).await?;
+            let scores = measure_quality_of_section(
+                input,
+                Some((start, duration)),
+                sample_path,
+                profile.resolution,
+                Some(reference_resolution),
+                &[QualityMetric::Vmaf],
+            )
+            .await?;
+            total += scores
+                .vmaf
+                .ok_or_else(|| HlsKitError::CommandExecutionError {
+                    error: "ffmpeg reported no VMAF score, is it built with libvmaf?".to_string(),
+                })?;
+        }
+
+        Ok(total / sections.len() as f64)
+    }
+}
+
+/// Encodes `duration` of the source from `start` the way `profile` would, without audio
+async fn encode_sample(
+    input: &str,
+    profile: &HlsVideoProcessingSettings,
+    crf: i32,
+    start: Duration,
+    duration: Duration,
+    output: &Path,
+) -> Result<(), HlsKitError> {
+    let (width, height) = profile.resolution;
+    let args = [
+        "-hide_banner".to_string(),
+        "-nostats".to_string(),
+        "-y".to_string(),
+        "-ss".to_string(),
+        format!("{:.3}", start.as_secs_f64()),
+        "-t".to_string(),
+        format!("{:.3}", duration.as_secs_f64()),
+        "-i".to_string(),
+        input.to_string(),
+        "-vf".to_string(),
+        format!("scale={width}:{height}"),
+        "-c:v".to_string(),
+        "libx264".to_string(),
+        "-preset".to_string(),
+        profile.preset.value().to_string(),
+        "-crf".to_string(),
+        crf.to_string(),
+        "-an".to_string(),
+        output.to_string_lossy().to_string(),
+    ];
+
+    run_ffmpeg(&args).await.map(|_| ())
+}
@@ -0,0 +1,596 @@
+// SPDX-License-Identifier: LGPL-3.0-only
+/*
+ * Copyright © 2025 The HlsKit Project
+ *
+ * This software is licensed under the GNU Lesser General Public License v3.0 (LGPLv3).
+ * All contributions adhere to the LGPLv3 and the HlsKit Contributor License Agreement (CLA).
+ * A copy of the LGPLv3 can be found at https://www.gnu.org/licenses/lgpl-3.0.html
+ *
+ * HlsKit Contributor License Agreement
+ *
+ * By contributing to or modifying HlsKit, you agree to the following terms:
+ *
+ * 1. Collective Ownership:
+ * The HlsKit project incorporates original code and all contributions as a collective work,
+ * licensed under LGPLv3. Once submitted, contributions become part of the shared HlsKit
+ * ecosystem and cannot be reclaimed, reassigned, or withdrawn. Contributions to your own
+ * forks remain yours unless submitted here, at which point they join this collective whole under LGPLv3.
+ *
+ * 2. Definition of Contribution:
+ * You are considered a contributor if you modify the library in any form (including forks,
+ * wrappers, libraries, or extensions that alter its behavior), whether or not you submit
+ * your changes directly to this repository. All such modifications are part of the broader
+ * HlsKit ecosystem and are subject to this CLA.
+ *
+ * 3. Distribution of Modifications:
+ * If you distribute a modified version of HlsKit, you must license your modifications under
+ * LGPLv3 (with source code available as required by the license) and ensure they are
+ * adoptable by the HlsKit ecosystem (publicly available and compatible).
+ *
+ * 4. Networked Use of Modifications:
+ * If you use a modified version of HlsKit in a networked application, you must provide the
+ * source code of your modifications under LGPLv3 and notify the HlsKit project
+ * (e.g., via email to [higashikataengels@icloud.com]). This does not apply to the use of
+ * the unmodified library in proprietary software, which remains permissible under LGPLv3.
+ *
+ * 5. Scope:
+ * These terms apply to all contributions and modifications derived from the HlsKit project.
+ * The use of the unmodified library in proprietary software is governed solely by the LGPLv3.
+ */
+
+use std::{collections::HashMap, process::Stdio, time::Duration};
+
+use serde_json::Value;
+use tokio::{io::AsyncWriteExt, process::Command};
+
+use crate::{
+    models::{
+        content_check::ContentCheck,
+        source_info::{HdrMetadata, HdrTransfer, SourceInfo, StreamInfo, StreamKind},
+    },
+    tools::{
+        binary_tools::{resolve_binary, FFMPEG_PATH_ENV, FFPROBE_PATH_ENV},
+        hlskit_error::{HlsKitError, VideoValidatableErrors},
+        quality_tools::run_ffmpeg,
+    },
+};
+
+/// Reads the container duration of `input` using ffprobe
+pub async fn probe_duration(input: &str) -> Result<Duration, HlsKitError> {
+    let output = run_ffprobe(&[
+        "-v",
+        "error",
+        "-show_entries",
+        "format=duration",
+        "-of",
+        "default=noprint_wrappers=1:nokey=1",
+        input,
+    ])
+    .await?;
+
+    parse_duration(output.trim()).ok_or_else(|| HlsKitError::CommandExecutionError {
+        error: "ffprobe did not report a duration".to_string(),
+    })
+}
+
+/// Analyzes `input` with ffprobe: container, streams, codecs and the resolution, frame rate,
+/// bit depth, rotation and HDR signalling of its first video stream
+pub async fn probe_source(input: &str) -> Result<SourceInfo, HlsKitError> {
+    probe_source_with_stdin(input, None).await
+}
+
+/// Like `probe_source`, reading the source from `data` written to ffprobe's stdin
+pub async fn probe_source_bytes(data: &[u8]) -> Result<SourceInfo, HlsKitError> {
+    probe_source_with_stdin("pipe:0", Some(data)).await
+}
+
+async fn probe_source_with_stdin(
+    input: &str,
+    stdin: Option<&[u8]>,
+) -> Result<SourceInfo, HlsKitError> {
+    let output = run_ffprobe_with_stdin(
+        &[
+            "-v",
+            "error",
+            "-print_format",
+            "json",
+            "-show_format",
+            "-show_streams",
+            input,
+        ],
+        stdin,
+    )
+    .await?;
+
+    let report: Value =
+        serde_json::from_str(&output).map_err(|e| HlsKitError::CommandExecutionError {
+            error: format!("Failed to parse ffprobe output: {e}"),
+        })?;
+
+    parse_source_info(&report).ok_or_else(|| {
+        VideoValidatableErrors::InvalidVideoInput {
+            error: format!("ffprobe found no video stream in {input}"),
+        }
+        .into()
+    })
+}
+
+/// Gap between the average and base frame rates, in frames per second, above which a
+/// stream is considered variable frame rate
+const VFR_TOLERANCE: f64 = 0.01;
+
+/// Frames decoded from the start of the video stream by [`check_decodable`]
+const DECODE_CHECK_FRAMES: usize = 5;
+
+/// Confirms that ffprobe can decode frames from both the start and, when `duration` is known,
+/// the last second of the first video stream of `input`. Catches truncated and corrupt files
+/// whose headers look fine.
+pub async fn check_decodable(input: &str, duration: Option<Duration>) -> Result<(), HlsKitError> {
+    let mut intervals = vec![format!("%+#{DECODE_CHECK_FRAMES}")];
+    if let Some(duration) = duration.filter(|duration| *duration > Duration::from_secs(2)) {
+        let tail_start = duration
+            .saturating_sub(Duration::from_secs(1))
+            .as_secs_f64();
+        intervals.push(format!("{tail_start:.3}%+#1"));
+    }
+
+    for interval in intervals {
+        let decoded_frames = count_decoded_frames(input, &interval)
+            .await
+            .map_err(|e| match e {
+                HlsKitError::CommandExecutionError { error } => {
+                    VideoValidatableErrors::InvalidVideoInput { error }.into()
+                }
+                e => e,
+            })?;
+
+        if decoded_frames == 0 {
+            return Err(VideoValidatableErrors::InvalidVideoInput {
+                error: format!("No decodable video frames in {input} at interval {interval}"),
+            }
+            .into());
+        }
+    }
+
+    Ok(())
+}
+
+pub(crate) async fn count_decoded_frames(input: &str, interval: &str) -> Result<u64, HlsKitError> {
+    let output = run_ffprobe(&[
+        "-v",
+        "error",
+        "-select_streams",
+        "v:0",
+        "-read_intervals",
+        interval,
+        "-count_frames",
+        "-show_entries",
+        "stream=nb_read_frames",
+        "-of",
+        "default=noprint_wrappers=1:nokey=1",
+        input,
+    ])
+    .await?;
+
+    Ok(output.trim().parse().unwrap_or_default())
+}
+
+/// Frames inspected by [`detect_interlacing`]
+const IDET_FRAMES: usize = 300;
+
+/// Runs ffmpeg's `idet` filter over the first frames of `input`, for sources that don't
+/// declare their field order. Returns whether most of them are interlaced.
+pub async fn detect_interlacing(input: &str) -> Result<bool, HlsKitError> {
+    let binary = resolve_binary(FFMPEG_PATH_ENV, "ffmpeg");
+    let frames = IDET_FRAMES.to_string();
+    let output = Command::new(&binary)
+        .args([
+            "-hide_banner",
+            "-nostats",
+            "-i",
+            input,
+            "-map",
+            "0:v:0",
+            "-frames:v",
+            &frames,
+            "-vf",
+            "idet",
+            "-an",
+            "-f",
+            "null",
+            "-",
+        ])
+        .kill_on_drop(true)
+        .output()
+        .await
+        .map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => HlsKitError::BinaryNotFound {
+                binary: binary.to_string_lossy().to_string(),
+            },
+            _ => HlsKitError::CommandExecutionError {
+                error: format!("Failed to spawn ffmpeg: {e}"),
+            },
+        })?;
+
+    // idet reports on stderr, e.g.
+    // [Parsed_idet_0 @ 0x..] Multi frame detection: TFF: 12 BFF: 0 Progressive: 280 Undetermined: 8
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let counts = stderr
+        .lines()
+        .find_map(|line| line.split_once("Multi frame detection:"))
+        .map(|(_, counts)| parse_idet_counts(counts))
+        .ok_or_else(|| HlsKitError::CommandExecutionError {
+            error: format!("idet reported nothing for {input}"),
+        })?;
+
+    let interlaced = counts.get("TFF").unwrap_or(&0) + counts.get("BFF").unwrap_or(&0);
+    Ok(interlaced > *counts.get("Progressive").unwrap_or(&0))
+}
+
+/// Runs ffmpeg's scene detection over `input`, returning the time of every frame whose scene
+/// change score exceeds `threshold` (0 to 1)
+pub async fn detect_scene_changes(
+    input: &str,
+    threshold: f64,
+) -> Result<Vec<Duration>, HlsKitError> {
+    let args = [
+        "-hide_banner".to_string(),
+        "-nostats".to_string(),
+        "-i".to_string(),
+        input.to_string(),
+        "-map".to_string(),
+        "0:v:0".to_string(),
+        "-vf".to_string(),
+        format!("select='gt(scene,{threshold})',showinfo"),
+        "-an".to_string(),
+        "-f".to_string(),
+        "null".to_string(),
+        "-".to_string(),
+    ];
+    let stderr = run_ffmpeg(&args).await?;
+
+    // showinfo logs every selected frame, e.g.
+    // [Parsed_showinfo_1 @ 0x..] n:   0 pts: 305305 pts_time:10.1768 duration: 1001 ...
+    Ok(stderr
+        .lines()
+        .filter(|line| line.contains("Parsed_showinfo"))
+        .filter_map(|line| line.split_once("pts_time:"))
+        .filter_map(|(_, rest)| rest.split_whitespace().next()?.parse::<f64>().ok())
+        .filter(|seconds| seconds.is_finite() && *seconds >= 0.0)
+        .map(Duration::from_secs_f64)
+        .collect())
+}
+
+/// Lists the keyframe times of the first video stream of `input`, from its packet flags
+/// without decoding anything
+pub async fn probe_keyframes(input: &str) -> Result<Vec<Duration>, HlsKitError> {
+    let output = run_ffprobe(&[
+        "-v",
+        "error",
+        "-select_streams",
+        "v:0",
+        "-show_entries",
+        "packet=pts_time,flags",
+        "-of",
+        "csv=p=0",
+        input,
+    ])
+    .await?;
+
+    // One packet per line, e.g. `10.010000,K__`
+    let mut keyframes: Vec<Duration> = output
+        .lines()
+        .filter_map(|line| line.trim().split_once(','))
+        .filter(|(_, flags)| flags.starts_with('K'))
+        .filter_map(|(pts_time, _)| parse_duration(pts_time))
+        .collect();
+    keyframes.sort();
+    keyframes.dedup();
+
+    Ok(keyframes)
+}
+
+/// Measures the black and silent stretches of `source` in one decoding pass, with ffmpeg's
+/// `blackdetect` and `silencedetect` filters
+pub async fn check_content(input: &str, source: &SourceInfo) -> Result<ContentCheck, HlsKitError> {
+    let source_duration = source
+        .duration
+        .ok_or_else(|| HlsKitError::CommandExecutionError {
+            error: format!("Unknown duration of {input}, cannot check its content"),
+        })?;
+
+    let mut args: Vec<String> = [
+        "-hide_banner",
+        "-nostats",
+        "-i",
+        input,
+        "-map",
+        "0:v:0",
+        "-vf",
+        "blackdetect=d=0.1:pix_th=0.10",
+    ]
+    .iter()
+    .map(|arg| arg.to_string())
+    .collect();
+    if source.has_audio() {
+        args.extend(
+            ["-map", "0:a:0", "-af", "silencedetect=n=-60dB:d=0.5"]
+                .iter()
+                .map(|arg| arg.to_string()),
+        );
+    }
+    args.extend(["-f", "null", "-"].iter().map(|arg| arg.to_string()));
+    let stderr = run_ffmpeg(&args).await?;
+
+    // e.g. [blackdetect @ 0x..] black_start:0 black_end:12.5 black_duration:12.5
+    let black_seconds: f64 = stderr
+        .lines()
+        .filter_map(|line| line.split_once("black_duration:"))
+        .filter_map(|(_, value)| value.split_whitespace().next()?.parse::<f64>().ok())
+        .sum();
+
+    let silent_duration = source
+        .has_audio()
+        .then(|| Duration::from_secs_f64(parse_silent_seconds(&stderr, source_duration)));
+
+    Ok(ContentCheck {
+        source_duration,
+        black_duration: Duration::from_secs_f64(black_seconds.max(0.0)),
+        silent_duration,
+    })
+}
+
+/// Lists the variant streams of the HLS master playlist at `master_url`, which ffmpeg exposes
+/// as programs, as `(program id, video resolution)`
+pub(crate) async fn probe_hls_programs(
+    master_url: &str,
+) -> Result<Vec<(u64, (i32, i32))>, HlsKitError> {
+    let output = run_ffprobe(&[
+        "-v",
+        "error",
+        "-print_format",
+        "json",
+        "-show_entries",
+        "program=program_id:program_stream=codec_type,width,height",
+        master_url,
+    ])
+    .await?;
+
+    let report: Value =
+        serde_json::from_str(&output).map_err(|e| HlsKitError::CommandExecutionError {
+            error: format!("Failed to parse ffprobe output: {e}"),
+        })?;
+
+    let programs = report["programs"].as_array().into_iter().flatten();
+    Ok(programs
+        .filter_map(|program| {
+            let video = program["streams"]
+                .as_array()?
+                .iter()
+                .find(|stream| str_field(stream, "codec_type") == Some("video"))?;
+            let resolution = (
+                number_field(video, "width")? as i32,
+                number_field(video, "height")? as i32,
+            );
+            Some((number_field(program, "program_id")?, resolution))
+        })
+        .collect())
+}
+
+/// Sums the silences reported by silencedetect, e.g.
+/// `[silencedetect @ 0x..] silence_end: 12.5 | silence_duration: 12.5`. A trailing
+/// `silence_start` without an end lasts until the end of the source.
+fn parse_silent_seconds(stderr: &str, source_duration: Duration) -> f64 {
+    let first_number = |value: &str| value.split_whitespace().next()?.parse::<f64>().ok();
+
+    let mut silent_seconds = 0.0;
+    let mut open_start = None;
+    for line in stderr.lines() {
+        if let Some((_, value)) = line.split_once("silence_start:") {
+            open_start = first_number(value);
+        } else if let Some((_, value)) = line.split_once("silence_duration:") {
+            silent_seconds += first_number(value).unwrap_or_default();
+            open_start = None;
+        }
+    }
+    if let Some(start) = open_start {
+        silent_seconds += (source_duration.as_secs_f64() - start).max(0.0);
+    }
+
+    silent_seconds.max(0.0)
+}
+
+fn parse_idet_counts(counts: &str) -> HashMap<&str, u64> {
+    let tokens: Vec<&str> = counts.split_whitespace().collect();
+    tokens
+        .chunks(2)
+        .filter_map(|pair| match pair {
+            [label, count] => Some((label.trim_end_matches(':'), count.parse().ok()?)),
+            _ => None,
+        })
+        .collect()
+}
+
+fn parse_source_info(report: &Value) -> Option<SourceInfo> {
+    let streams = report["streams"].as_array().map_or(&[][..], Vec::as_slice);
+    let video = streams
+        .iter()
+        .find(|stream| stream["codec_type"] == "video")?;
+    let format = &report["format"];
+
+    let width = video["width"].as_i64()?;
+    let height = video["height"].as_i64()?;
+    let average_rate = str_field(video, "avg_frame_rate").and_then(parse_frame_rate);
+    let base_rate = str_field(video, "r_frame_rate").and_then(parse_frame_rate);
+
+    Some(SourceInfo {
+        resolution: (width as i32, height as i32),
+        frame_rate: average_rate.or(base_rate),
+        variable_frame_rate: matches!(
+            (average_rate, base_rate),
+            (Some(average), Some(base)) if (average - base).abs() > VFR_TOLERANCE
+        ),
+        bitrate: number_field(video, "bit_rate").or_else(|| number_field(format, "bit_rate")),
+        duration: str_field(format, "duration")
+            .or_else(|| str_field(video, "duration"))
+            .and_then(parse_duration),
+        format_name: str_field(format, "format_name").map(str::to_string),
+        video_codec: str_field(video, "codec_name").map(str::to_string),
+        bit_depth: parse_bit_depth(video),
+        rotation: parse_rotation(video),
+        interlaced: match str_field(video, "field_order") {
+            Some("progressive") => Some(false),
+            Some("tt" | "bb" | "tb" | "bt") => Some(true),
+            _ => None,
+        },
+        hdr: parse_hdr(video),
+        streams: streams.iter().map(parse_stream).collect(),
+    })
+}
+
+fn parse_stream(stream: &Value) -> StreamInfo {
+    StreamInfo {
+        index: stream["index"].as_u64().unwrap_or_default() as u32,
+        kind: StreamKind::from_codec_type(str_field(stream, "codec_type").unwrap_or_default()),
+        codec: str_field(stream, "codec_name").map(str::to_string),
+        bitrate: number_field(stream, "bit_rate"),
+        language: str_field(&stream["tags"], "language").map(str::to_string),
+        channels: stream["channels"].as_u64().map(|channels| channels as u32),
+        sample_rate: number_field(stream, "sample_rate").map(|rate| rate as u32),
+    }
+}
+
+/// Prefers the exact sample size, falling back to the one implied by the pixel format
+fn parse_bit_depth(video: &Value) -> Option<u8> {
+    if let Some(bits) = number_field(video, "bits_per_raw_sample") {
+        return Some(bits as u8);
+    }
+
+    let pix_fmt = str_field(video, "pix_fmt")?;
+    Some(if pix_fmt.contains("12") {
+        12
+    } else if pix_fmt.contains("10") {
+        10
+    } else {
+        8
+    })
+}
+
+/// Reads the display matrix (or the legacy `rotate` tag) as a clockwise rotation
+fn parse_rotation(video: &Value) -> u16 {
+    let display_matrix_rotation = video["side_data_list"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .find_map(|side_data| side_data["rotation"].as_f64())
+        .map(|rotation| -rotation);
+    let rotation = display_matrix_rotation
+        .or_else(|| number_field(&video["tags"], "rotate").map(|rotate| rotate as f64))
+        .unwrap_or_default();
+
+    ((rotation.round() as i64).rem_euclid(360) / 90 * 90) as u16
+}
+
+fn parse_hdr(video: &Value) -> Option<HdrMetadata> {
+    let transfer = HdrTransfer::from_color_transfer(str_field(video, "color_transfer")?)?;
+    let light_level = video["side_data_list"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .find(|side_data| side_data["side_data_type"] == "Content light level metadata");
+
+    Some(HdrMetadata {
+        transfer,
+        color_primaries: str_field(video, "color_primaries").map(str::to_string),
+        color_space: str_field(video, "color_space").map(str::to_string),
+        max_content_light_level: light_level
+            .and_then(|side_data| side_data["max_content"].as_u64())
+            .map(|nits| nits as u32),
+        max_frame_average_light_level: light_level
+            .and_then(|side_data| side_data["max_average"].as_u64())
+            .map(|nits| nits as u32),
+    })
+}
+
+fn str_field<'a>(value: &'a Value, key: &str) -> Option<&'a str> {
+    value[key].as_str().filter(|field| !field.is_empty())
+}
+
+/// ffprobe prints most numbers as strings, e.g. `"bit_rate": "5000000"`
+fn number_field(value: &Value, key: &str) -> Option<u64> {
+    match &value[key] {
+        Value::String(field) => field.parse::<f64>().ok().map(|number| number as u64),
+        field => field.as_u64(),
+    }
+}
+
+async fn run_ffprobe(args: &[&str]) -> Result<String, HlsKitError> {
+    run_ffprobe_with_stdin(args, None).await
+}
+
+/// Runs ffprobe with `stdin` written to its standard input, closed right away when `None`
+async fn run_ffprobe_with_stdin(
+    args: &[&str],
+    stdin: Option<&[u8]>,
+) -> Result<String, HlsKitError> {
+    let binary = resolve_binary(FFPROBE_PATH_ENV, "ffprobe");
+    let spawn_error = |e: std::io::Error| match e.kind() {
+        std::io::ErrorKind::NotFound => HlsKitError::BinaryNotFound {
+            binary: binary.to_string_lossy().to_string(),
+        },
+        _ => HlsKitError::CommandExecutionError {
+            error: format!("Failed to spawn ffprobe: {e}"),
+        },
+    };
+
+    let mut process = Command::new(&binary)
+        .args(args)
+        .stdin(if stdin.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(spawn_error)?;
+
+    let pipe = process.stdin.take();
+    let write_stdin = async move {
+        if let (Some(mut pipe), Some(stdin)) = (pipe, stdin) {
+            // ffprobe stops reading once it has seen enough of the source
+            let _ = pipe.write_all(stdin).await;
+        }
+    };
+    let (_, output) = futures::join!(write_stdin, process.wait_with_output());
+    let output = output.map_err(spawn_error)?;
+
+    if !output.status.success() {
+        return Err(HlsKitError::CommandExecutionError {
+            error: format!(
+                "ffprobe failed: {}",
+                String::from_utf8_lossy(&output.stderr)
+            ),
+        });
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+fn parse_duration(value: &str) -> Option<Duration> {
+    value
+        .parse::<f64>()
+        .ok()
+        .filter(|seconds| seconds.is_finite() && *seconds >= 0.0)
+        .map(Duration::from_secs_f64)
+}
+
+/// Parses ffprobe's `30000/1001` style rates
+fn parse_frame_rate(value: &str) -> Option<f64> {
+    let (numerator, denominator) = value.split_once('/').unwrap_or((value, "1"));
+    let numerator: f64 = numerator.parse().ok()?;
+    let denominator: f64 = denominator.parse().ok()?;
+
+    (denominator > 0.0 && numerator > 0.0).then(|| numerator / denominator)
+}
@@ -0,0 +1,976 @@
+// SPDX-License-Identifier: LGPL-3.0-only
+/*
+ * Copyright © 2025 The HlsKit Project
+ *
+ * This software is licensed under the GNU Lesser General Public License v3.0 (LGPLv3).
+ * All contributions adhere to the LGPLv3 and the HlsKit Contributor License Agreement (CLA).
+ * A copy of the LGPLv3 can be found at https://www.gnu.org/licenses/lgpl-3.0.html
+ *
+ * HlsKit Contributor License Agreement
+ *
+ * By contributing to or modifying HlsKit, you agree to the following terms:
+ *
+ * 1. Collective Ownership:
+ * The HlsKit project incorporates original code and all contributions as a collective work,
+ * licensed under LGPLv3. Once submitted, contributions become part of the shared HlsKit
+ * ecosystem and cannot be reclaimed, reassigned, or withdrawn. Contributions to your own
+ * forks remain yours unless submitted here, at which point they join this collective whole under LGPLv3.
+ *
+ * 2. Definition of Contribution:
+ * You are considered a contributor if you modify the library in any form (including forks,
+ * wrappers, libraries, or extensions that alter its behavior), whether or not you submit
+ * your changes directly to this repository. All such modifications are part of the broader
+ * HlsKit ecosystem and are subject to this CLA.
+ *
+ * 3. Distribution of Modifications:
+ * If you distribute a modified version of HlsKit, you must license your modifications under
+ * LGPLv3 (with source code available as required by the license) and ensure they are
+ * adoptable by the HlsKit ecosystem (publicly available and compatible).
+ *
+ * 4. Networked Use of Modifications:
+ * If you use a modified version of HlsKit in a networked application, you must provide the
+ * source code of your modifications under LGPLv3 and notify the HlsKit project
+ * (e.g., via email to [higashikataengels@icloud.com]). This does not apply to the use of
+ * the unmodified library in proprietary software, which remains permissible under LGPLv3.
+ *
+ * 5. Scope:
+ * These terms apply to all contributions and modifications derived from the HlsKit project.
+ * The use of the unmodified library in proprietary software is governed solely by the LGPLv3.
+ */
+
+use std::{
+    collections::HashMap,
+    fmt, fs,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use bytes::Bytes;
+use tokio::sync::mpsc::UnboundedSender;
+pub use tokio_util::sync::CancellationToken;
+
+use crate::{
+    models::{
+        audio_sync::AudioSync,
+        chunked_encoding::SourceChunk,
+        content_check::ContentQc,
+        deinterlace_policy::DeinterlacePolicy,
+        frame_rate_policy::FrameRatePolicy,
+        hls_video_manifest::HlsVideoResolutionManifest,
+        job_event::JobEvent,
+        job_id::JobId,
+        orientation_policy::OrientationPolicy,
+        path_policy::PathPolicy,
+        profile_progress::{JobProgress, ProfileProgress},
+        quality_scores::QualityMetric,
+        resource_limits::ResourceLimits,
+        retention_policy::RetentionPolicy,
+        scene_segmentation::SceneSegmentation,
+        source_adjustments::SourceAdjustments,
+        upscale_policy::UpscalePolicy,
+    },
+    tools::{
+        hlskit_error::HlsKitError, ladder_generator::LadderGenerator,
+        output_validation::OutputValidation, per_title::PerTitle,
+        processing_hooks::ProcessingHooks, segment_tools::file_name_of, sink_tools::SinkUpload,
+    },
+    traits::{
+        command_interceptor::CommandInterceptor,
+        key_provider::KeyProvider,
+        sandbox_policy::{SandboxPolicy, SandboxScope},
+    },
+};
+
+#[cfg(feature = "webhooks")]
+use crate::tools::webhook_notifier::WebhookNotifier;
+#[cfg(feature = "cache")]
+use crate::traits::result_cache::ResultCache;
+
+pub type JobEventHandler = Arc<dyn Fn(JobEvent) + Send + Sync>;
+
+/// Job-wide state shared by every profile encode of a single processing run
+#[derive(Clone, Default)]
+pub struct ProcessingContext {
+    event_handlers: Vec<JobEventHandler>,
+    source_duration: Option<Duration>,
+    source_has_audio: Option<bool>,
+    source_frame_rate: Option<f64>,
+    job_progress: Option<Arc<Mutex<JobProgressTracker>>>,
+    cancellation_token: Option<CancellationToken>,
+    profile_timeout: Option<Duration>,
+    job_timeout: Option<Duration>,
+    max_concurrent_profiles: Option<usize>,
+    resource_limits: ResourceLimits,
+    skip_disk_preflight: bool,
+    disk_quota: Option<u64>,
+    disk_usage: Option<Arc<DiskUsageTracker>>,
+    retention_policy: RetentionPolicy,
+    hooks: ProcessingHooks,
+    command_interceptors: Vec<Arc<dyn CommandInterceptor>>,
+    private_temp_files: bool,
+    partial_results: bool,
+    deep_validation: bool,
+    ladder_generator: Option<LadderGenerator>,
+    upscale_policy: UpscalePolicy,
+    orientation_policy: OrientationPolicy,
+    deinterlace_policy: DeinterlacePolicy,
+    frame_rate_policy: FrameRatePolicy,
+    audio_sync: AudioSync,
+    quality_metrics: Vec<QualityMetric>,
+    per_title: Option<PerTitle>,
+    scene_segmentation: Option<SceneSegmentation>,
+    output_validation: Option<OutputValidation>,
+    content_qc: Option<ContentQc>,
+    checksums: bool,
+    verify_encryption: bool,
+    source_adjustments: SourceAdjustments,
+    source_chunk: Option<SourceChunk>,
+    sandbox: Option<Arc<dyn SandboxPolicy>>,
+    sandbox_scope: SandboxScope,
+    job_id: Option<JobId>,
+    key_provider: Option<Arc<dyn KeyProvider>>,
+    path_policy: Option<PathPolicy>,
+    sink_uploads: Option<UnboundedSender<SinkUpload>>,
+    remove_streamed_segments: bool,
+    stdin_input: Option<Bytes>,
+    #[cfg(feature = "webhooks")]
+    webhooks: Vec<Arc<WebhookNotifier>>,
+    #[cfg(feature = "cache")]
+    result_cache: Option<Arc<dyn ResultCache>>,
+}
+
+/// Bytes of segments written by the current job, checked against the disk quota
+#[derive(Debug, Default)]
+struct DiskUsageTracker {
+    used: AtomicU64,
+    exceeded: CancellationToken,
+}
+
+#[derive(Debug, Default)]
+struct JobProgressTracker {
+    profile_count: usize,
+    profiles: HashMap<i32, ProfileProgress>,
+}
+
+impl JobProgressTracker {
+    fn update(&mut self, progress: &ProfileProgress) -> JobProgress {
+        self.profiles
+            .insert(progress.stream_index, progress.clone());
+
+        let profile_count = self.profile_count.max(self.profiles.len()).max(1) as f64;
+        let percent_sum: f64 = self.profiles.values().filter_map(|p| p.percent).sum();
+        let speeds: Vec<f64> = self.profiles.values().filter_map(|p| p.speed).collect();
+
+        JobProgress {
+            percent: self
+                .profiles
+                .values()
+                .any(|p| p.percent.is_some())
+                .then(|| (percent_sum / profile_count).clamp(0.0, 100.0)),
+            // Profiles run side by side, so the job finishes with its slowest profile
+            eta: self.profiles.values().filter_map(|p| p.eta).max(),
+            speed: (!speeds.is_empty()).then(|| speeds.iter().sum::<f64>() / speeds.len() as f64),
+        }
+    }
+}
+
+impl ProcessingContext {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_event_handler<F>(mut self, handler: F) -> Self
+    where
+        F: Fn(JobEvent) + Send + Sync + 'static,
+    {
+        self.event_handlers.push(Arc::new(handler));
+        self
+    }
+
+    /// Registers a handler only interested in profile progress updates
+    pub fn with_progress_callback<F>(self, callback: F) -> Self
+    where
+        F: Fn(ProfileProgress) + Send + Sync + 'static,
+    {
+        self.with_event_handler(move |event| {
+            if let JobEvent::ProfileProgress(progress) = event {
+                callback(progress);
+            }
+        })
+    }
+
+    pub fn with_source_duration(mut self, duration: Duration) -> Self {
+        self.source_duration = Some(duration);
+        self
+    }
+
+    /// Records whether the probed source has an audio stream
+    pub fn with_source_audio(mut self, has_audio: bool) -> Self {
+        self.source_has_audio = Some(has_audio);
+        self
+    }
+
+    /// Records the average frame rate of the probed source
+    pub fn with_source_frame_rate(mut self, frame_rate: f64) -> Self {
+        self.source_frame_rate = Some(frame_rate);
+        self
+    }
+
+    /// Aborts the job, killing running encoders, once `token` is cancelled
+    pub fn with_cancellation_token(mut self, token: CancellationToken) -> Self {
+        self.cancellation_token = Some(token);
+        self
+    }
+
+    pub fn cancellation_token(&self) -> Option<&CancellationToken> {
+        self.cancellation_token.as_ref()
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancellation_token
+            .as_ref()
+            .is_some_and(CancellationToken::is_cancelled)
+    }
+
+    /// Resolves once the job is cancelled, never if it has no cancellation token
+    pub async fn cancelled(&self) {
+        match &self.cancellation_token {
+            Some(token) => token.cancelled().await,
+            None => std::future::pending().await,
+        }
+    }
+
+    /// Kills a profile encode that runs longer than `timeout`
+    pub fn with_profile_timeout(mut self, timeout: Duration) -> Self {
+        self.profile_timeout = Some(timeout);
+        self
+    }
+
+    /// Aborts the whole job once it runs longer than `timeout`
+    pub fn with_job_timeout(mut self, timeout: Duration) -> Self {
+        self.job_timeout = Some(timeout);
+        self
+    }
+
+    /// Bounds how many profiles are encoded at the same time
+    pub fn with_max_concurrent_profiles(mut self, limit: usize) -> Self {
+        self.max_concurrent_profiles = Some(limit.max(1));
+        self
+    }
+
+    pub fn max_concurrent_profiles(&self) -> Option<usize> {
+        self.max_concurrent_profiles
+    }
+
+    /// Constrains the threads, priority and CPUs of every encoder process
+    pub fn with_resource_limits(mut self, limits: ResourceLimits) -> Self {
+        self.resource_limits = limits;
+        self
+    }
+
+    pub fn resource_limits(&self) -> &ResourceLimits {
+        &self.resource_limits
+    }
+
+    /// Skips checking that the output volume has room for the estimated output
+    pub fn without_disk_preflight(mut self) -> Self {
+        self.skip_disk_preflight = true;
+        self
+    }
+
+    pub fn disk_preflight(&self) -> bool {
+        !self.skip_disk_preflight
+    }
+
+    /// Kills the job once its segments take more than `bytes` on disk
+    pub fn with_disk_quota(mut self, bytes: u64) -> Self {
+        self.disk_quota = Some(bytes);
+        self
+    }
+
+    pub fn disk_quota(&self) -> Option<u64> {
+        self.disk_quota
+    }
+
+    /// Fails with `DiskQuotaExceeded` if the segments written so far exceed the quota
+    pub fn check_disk_quota(&self) -> Result<(), HlsKitError> {
+        match (self.disk_quota, &self.disk_usage) {
+            (Some(quota), Some(usage)) if usage.exceeded.is_cancelled() => {
+                Err(HlsKitError::DiskQuotaExceeded {
+                    quota,
+                    used: usage.used.load(Ordering::Relaxed),
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Resolves once the disk quota is exceeded, never if the job has no quota
+    pub async fn disk_quota_exceeded(&self) {
+        match &self.disk_usage {
+            Some(usage) => usage.exceeded.cancelled().await,
+            None => std::future::pending().await,
+        }
+    }
+
+    /// Decides when the temporary work directory is left on disk
+    pub fn with_retention_policy(mut self, policy: RetentionPolicy) -> Self {
+        self.retention_policy = policy;
+        self
+    }
+
+    /// Shorthand for `RetentionPolicy::KeepAlways` or `RetentionPolicy::AlwaysClean`
+    pub fn with_keep_workdir(self, keep: bool) -> Self {
+        self.with_retention_policy(if keep {
+            RetentionPolicy::KeepAlways
+        } else {
+            RetentionPolicy::AlwaysClean
+        })
+    }
+
+    pub fn retention_policy(&self) -> RetentionPolicy {
+        self.retention_policy
+    }
+
+    /// Replaces the callbacks run before and after each profile and the master playlist
+    pub fn with_hooks(mut self, hooks: ProcessingHooks) -> Self {
+        self.hooks = hooks;
+        self
+    }
+
+    /// Applies `update` to the registered hooks
+    pub fn map_hooks(mut self, update: impl FnOnce(ProcessingHooks) -> ProcessingHooks) -> Self {
+        self.hooks = update(self.hooks);
+        self
+    }
+
+    pub fn hooks(&self) -> &ProcessingHooks {
+        &self.hooks
+    }
+
+    /// Lets `interceptor` rewrite or veto every encoder command before it is spawned
+    pub fn with_command_interceptor<I: CommandInterceptor + 'static>(
+        mut self,
+        interceptor: I,
+    ) -> Self {
+        self.command_interceptors.push(Arc::new(interceptor));
+        self
+    }
+
+    /// Runs the interceptors over `command` in registration order
+    pub fn intercept_command(&self, command: &[String]) -> Result<Vec<String>, HlsKitError> {
+        let mut command = command.to_vec();
+        for interceptor in &self.command_interceptors {
+            interceptor.intercept(&mut command)?;
+        }
+
+        if command.is_empty() {
+            return Err(HlsKitError::CommandRejected {
+                error: "interceptor left an empty command".to_string(),
+            });
+        }
+
+        Ok(command)
+    }
+
+    /// Keeps temporary input copies and work directories in private per-job directories
+    pub fn with_private_temp_files(mut self, private: bool) -> Self {
+        self.private_temp_files = private;
+        self
+    }
+
+    pub fn private_temp_files(&self) -> bool {
+        self.private_temp_files
+    }
+
+    /// Has ffprobe decode the start and end of the video stream before encoding, rejecting
+    /// truncated or undecodable inputs that pass the magic-byte check
+    pub fn with_deep_validation(mut self, deep_validation: bool) -> Self {
+        self.deep_validation = deep_validation;
+        self
+    }
+
+    pub fn deep_validation(&self) -> bool {
+        self.deep_validation
+    }
+
+    /// Lets the job succeed with the profiles that encoded when others fail, reporting the
+    /// failures in `failed_profiles` instead of aborting
+    pub fn with_partial_results(mut self, partial_results: bool) -> Self {
+        self.partial_results = partial_results;
+        self
+    }
+
+    pub fn partial_results(&self) -> bool {
+        self.partial_results
+    }
+
+    /// Probes the input and encodes the ladder `generator` fits to it instead of the job's
+    /// own profiles
+    pub fn with_ladder_generator(mut self, generator: LadderGenerator) -> Self {
+        self.ladder_generator = Some(generator);
+        self
+    }
+
+    pub fn without_ladder_generator(mut self) -> Self {
+        self.ladder_generator = None;
+        self
+    }
+
+    pub fn ladder_generator(&self) -> Option<&LadderGenerator> {
+        self.ladder_generator.as_ref()
+    }
+
+    /// Drops or clamps profiles larger than the probed source, see `UpscalePolicy`
+    pub fn with_upscale_policy(mut self, policy: UpscalePolicy) -> Self {
+        self.upscale_policy = policy;
+        self
+    }
+
+    pub fn upscale_policy(&self) -> UpscalePolicy {
+        self.upscale_policy
+    }
+
+    /// Adapts profile dimensions to portrait or landscape sources, see `OrientationPolicy`
+    pub fn with_orientation_policy(mut self, policy: OrientationPolicy) -> Self {
+        self.orientation_policy = policy;
+        self
+    }
+
+    pub fn orientation_policy(&self) -> OrientationPolicy {
+        self.orientation_policy
+    }
+
+    pub fn with_deinterlace_policy(mut self, policy: DeinterlacePolicy) -> Self {
+        self.deinterlace_policy = policy;
+        self
+    }
+
+    pub fn deinterlace_policy(&self) -> DeinterlacePolicy {
+        self.deinterlace_policy
+    }
+
+    pub fn with_frame_rate_policy(mut self, policy: FrameRatePolicy) -> Self {
+        self.frame_rate_policy = policy;
+        self
+    }
+
+    pub fn frame_rate_policy(&self) -> FrameRatePolicy {
+        self.frame_rate_policy
+    }
+
+    /// Resamples drifting audio and/or shifts it against the video, see `AudioSync`
+    pub fn with_audio_sync(mut self, audio_sync: AudioSync) -> Self {
+        self.audio_sync = audio_sync;
+        self
+    }
+
+    pub fn audio_sync(&self) -> AudioSync {
+        self.audio_sync
+    }
+
+    /// Scores every rendition against the source once encoded
+    pub fn with_quality_metrics(mut self, metrics: &[QualityMetric]) -> Self {
+        self.quality_metrics = metrics.to_vec();
+        self
+    }
+
+    pub fn quality_metrics(&self) -> &[QualityMetric] {
+        &self.quality_metrics
+    }
+
+    /// Tunes the CRF of every rendition to the source before encoding, see `PerTitle`
+    pub fn with_per_title(mut self, per_title: PerTitle) -> Self {
+        self.per_title = Some(per_title);
+        self
+    }
+
+    pub fn per_title(&self) -> Option<&PerTitle> {
+        self.per_title.as_ref()
+    }
+
+    /// Aligns keyframes and segment boundaries with scene cuts, see `SceneSegmentation`
+    pub fn with_scene_segmentation(mut self, segmentation: SceneSegmentation) -> Self {
+        self.scene_segmentation = Some(segmentation);
+        self
+    }
+
+    pub fn scene_segmentation(&self) -> Option<SceneSegmentation> {
+        self.scene_segmentation
+    }
+
+    /// Validates the produced asset before the master playlist is published
+    pub fn with_output_validation(mut self, validation: OutputValidation) -> Self {
+        self.output_validation = Some(validation);
+        self
+    }
+
+    pub fn output_validation(&self) -> Option<OutputValidation> {
+        self.output_validation
+    }
+
+    /// Checks the source for black video and silent audio before encoding
+    pub fn with_content_qc(mut self, qc: ContentQc) -> Self {
+        self.content_qc = Some(qc);
+        self
+    }
+
+    pub fn content_qc(&self) -> Option<ContentQc> {
+        self.content_qc
+    }
+
+    /// Hashes every file of the output into a checksum manifest
+    #[cfg(feature = "checksums")]
+    pub fn with_checksums(mut self, checksums: bool) -> Self {
+        self.checksums = checksums;
+        self
+    }
+
+    pub fn checksums(&self) -> bool {
+        self.checksums
+    }
+
+    /// Test-decrypts the first and last segment of every encrypted rendition once encoded
+    #[cfg(feature = "encryption")]
+    pub fn with_encryption_verification(mut self, verify: bool) -> Self {
+        self.verify_encryption = verify;
+        self
+    }
+
+    pub fn verify_encryption(&self) -> bool {
+        self.verify_encryption
+    }
+
+    /// Records the corrections decided during preflight, applied by the backends
+    pub fn with_source_adjustments(mut self, adjustments: SourceAdjustments) -> Self {
+        self.source_adjustments = adjustments;
+        self
+    }
+
+    pub fn source_adjustments(&self) -> &SourceAdjustments {
+        &self.source_adjustments
+    }
+
+    /// Encodes only `chunk` of the source, e.g. for a distributed work unit. Backends that
+    /// can't seek into the source refuse to encode
+    pub fn with_source_chunk(mut self, chunk: SourceChunk) -> Self {
+        self.source_chunk = Some(chunk);
+        self
+    }
+
+    pub fn source_chunk(&self) -> Option<SourceChunk> {
+        self.source_chunk
+    }
+
+    /// Spawns every encoder through `sandbox`
+    pub fn with_sandbox<P: SandboxPolicy + 'static>(mut self, sandbox: P) -> Self {
+        self.sandbox = Some(Arc::new(sandbox));
+        self
+    }
+
+    /// Sets the paths the sandboxed encoders of this job may read and write
+    pub fn with_sandbox_scope(mut self, scope: SandboxScope) -> Self {
+        self.sandbox_scope = scope;
+        self
+    }
+
+    /// Wraps `command` in the sandbox, if any
+    pub fn sandbox_command(&self, command: Vec<String>) -> Result<Vec<String>, HlsKitError> {
+        match &self.sandbox {
+            Some(sandbox) => sandbox.wrap(command, &self.sandbox_scope),
+            None => Ok(command),
+        }
+    }
+
+    /// Tags the job's logs and spans with `job_id` instead of a freshly allocated one
+    pub fn with_job_id(mut self, job_id: JobId) -> Self {
+        self.job_id = Some(job_id);
+        self
+    }
+
+    pub fn job_id(&self) -> Option<JobId> {
+        self.job_id
+    }
+
+    /// Encrypts the segments with a key fetched from `provider` when the job starts, unless
+    /// the job has its own encryption settings
+    pub fn with_key_provider<P: KeyProvider + 'static>(mut self, provider: P) -> Self {
+        self.key_provider = Some(Arc::new(provider));
+        self
+    }
+
+    pub fn key_provider(&self) -> Option<&dyn KeyProvider> {
+        self.key_provider.as_deref()
+    }
+
+    /// Rejects jobs whose input path, key paths or key URLs break `policy`
+    pub fn with_path_policy(mut self, policy: PathPolicy) -> Self {
+        self.path_policy = Some(policy);
+        self
+    }
+
+    pub fn path_policy(&self) -> Option<&PathPolicy> {
+        self.path_policy.as_ref()
+    }
+
+    /// Deletes every segment streamed into the output sink once stored, so the work directory
+    /// only holds the segments being encoded. Output validation, quality metrics, checksums and
+    /// encryption verification read the segments back and can't be combined with it
+    pub fn with_streamed_segment_removal(mut self, remove: bool) -> Self {
+        self.remove_streamed_segments = remove;
+        self
+    }
+
+    pub fn removes_streamed_segments(&self) -> bool {
+        self.remove_streamed_segments
+    }
+
+    /// Writes `input` to the stdin of every command run for the job, for backends encoding
+    /// `STDIN_INPUT`
+    pub(crate) fn with_stdin_input(mut self, input: Bytes) -> Self {
+        self.stdin_input = Some(input);
+        self
+    }
+
+    pub fn stdin_input(&self) -> Option<&Bytes> {
+        self.stdin_input.as_ref()
+    }
+
+    /// Hands the segments reported by the backends and the finished renditions to `uploads`
+    pub(crate) fn with_sink_uploads(mut self, uploads: UnboundedSender<SinkUpload>) -> Self {
+        self.sink_uploads = Some(uploads);
+        self
+    }
+
+    /// Queues a finished rendition for the output sink when streaming to one
+    pub(crate) fn upload_rendition(
+        &self,
+        stream_index: i32,
+        manifest: &HlsVideoResolutionManifest,
+    ) {
+        if let Some(uploads) = &self.sink_uploads {
+            let _ = uploads.send(SinkUpload::Rendition {
+                stream_index,
+                manifest: Box::new(manifest.clone()),
+            });
+        }
+    }
+
+    /// Sends the job's lifecycle events to `notifier`
+    #[cfg(feature = "webhooks")]
+    pub fn with_webhook(mut self, notifier: WebhookNotifier) -> Self {
+        self.webhooks.push(Arc::new(notifier));
+        self
+    }
+
+    /// Serves jobs whose input and settings were already processed from `cache`, storing every
+    /// other successful job in it. Jobs with encryption bypass the cache, their keys may change
+    #[cfg(feature = "cache")]
+    pub fn with_result_cache<C: ResultCache + 'static>(mut self, cache: C) -> Self {
+        self.result_cache = Some(Arc::new(cache));
+        self
+    }
+
+    #[cfg(feature = "cache")]
+    pub fn result_cache(&self) -> Option<&dyn ResultCache> {
+        self.result_cache.as_deref()
+    }
+
+    /// Waits for queued webhook deliveries; a no-op when no webhook is configured
+    pub async fn flush_notifications(&self) {
+        #[cfg(feature = "webhooks")]
+        for webhook in &self.webhooks {
+            webhook.flush().await;
+        }
+    }
+
+    pub fn profile_timeout(&self) -> Option<Duration> {
+        self.profile_timeout
+    }
+
+    pub fn job_timeout(&self) -> Option<Duration> {
+        self.job_timeout
+    }
+
+    /// Starts aggregating progress for a new job made of `profile_count` profiles
+    pub fn with_job_tracking(mut self, profile_count: usize) -> Self {
+        self.job_progress = Some(Arc::new(Mutex::new(JobProgressTracker {
+            profile_count,
+            profiles: HashMap::new(),
+        })));
+        self.disk_usage = self
+            .disk_quota
+            .map(|_| Arc::new(DiskUsageTracker::default()));
+        self
+    }
+
+    pub fn source_duration(&self) -> Option<Duration> {
+        self.source_duration
+    }
+
+    /// Whether the source has audio, `None` when it couldn't be probed
+    pub fn source_has_audio(&self) -> Option<bool> {
+        self.source_has_audio
+    }
+
+    /// Average frame rate of the source, `None` when it couldn't be probed
+    pub fn source_frame_rate(&self) -> Option<f64> {
+        self.source_frame_rate
+    }
+
+    /// Whether backends should bother collecting progress information
+    pub fn wants_progress(&self) -> bool {
+        !self.event_handlers.is_empty()
+    }
+
+    /// Whether backends should report segments while they are being written
+    pub fn wants_segment_updates(&self) -> bool {
+        self.wants_progress() || self.disk_usage.is_some() || self.sink_uploads.is_some()
+    }
+
+    /// Percentage of the source covered by `out_time`, when the source duration is known
+    pub fn percent_of(&self, out_time: Duration) -> Option<f64> {
+        let total = self.source_duration?.as_secs_f64();
+        if total <= 0.0 {
+            return None;
+        }
+        Some((out_time.as_secs_f64() / total * 100.0).clamp(0.0, 100.0))
+    }
+
+    pub fn emit(&self, event: JobEvent) {
+        #[cfg(feature = "webhooks")]
+        for webhook in &self.webhooks {
+            webhook.notify(self.job_id, &event);
+        }
+
+        for handler in &self.event_handlers {
+            handler(event.clone());
+        }
+    }
+
+    /// Estimated remaining time given how far the encode got and its realtime speed multiplier
+    pub fn eta_of(&self, out_time: Duration, speed: Option<f64>) -> Option<Duration> {
+        let remaining = self.source_duration?.checked_sub(out_time)?;
+        let speed = speed.filter(|speed| *speed > 0.0)?;
+        Some(Duration::from_secs_f64(remaining.as_secs_f64() / speed))
+    }
+
+    pub fn report_progress(&self, mut progress: ProfileProgress) {
+        if progress.eta.is_none() {
+            progress.eta = if progress.percent == Some(100.0) {
+                Some(Duration::ZERO)
+            } else {
+                self.eta_of(progress.out_time, progress.speed)
+            };
+        }
+
+        let job_progress = self.job_progress.as_ref().and_then(|tracker| {
+            tracker
+                .lock()
+                .ok()
+                .map(|mut tracker| tracker.update(&progress))
+        });
+
+        self.emit(JobEvent::ProfileProgress(progress));
+
+        if let Some(job_progress) = job_progress {
+            self.emit(JobEvent::JobProgress(job_progress));
+        }
+    }
+
+    pub fn report_segments_written(&self, stream_index: i32, segment_paths: Vec<PathBuf>) {
+        for segment_path in segment_paths {
+            if let (Some(quota), Some(usage)) = (self.disk_quota, &self.disk_usage) {
+                let size = fs::metadata(&segment_path).map_or(0, |metadata| metadata.len());
+                let used = usage.used.fetch_add(size, Ordering::Relaxed) + size;
+                if used > quota && !usage.exceeded.is_cancelled() {
+                    tracing::error!("Disk quota of {} bytes exceeded ({} bytes)", quota, used);
+                    usage.exceeded.cancel();
+                }
+            }
+
+            self.emit(JobEvent::SegmentWritten {
+                stream_index,
+                segment_name: file_name_of(&segment_path),
+            });
+
+            if let Some(uploads) = &self.sink_uploads {
+                let _ = uploads.send(SinkUpload::Segment {
+                    stream_index,
+                    path: segment_path,
+                });
+            }
+        }
+    }
+}
+
+impl fmt::Debug for ProcessingContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut debug = f.debug_struct("ProcessingContext");
+        debug
+            .field("event_handlers", &self.event_handlers.len())
+            .field("source_duration", &self.source_duration)
+            .field("source_has_audio", &self.source_has_audio)
+            .field("source_frame_rate", &self.source_frame_rate)
+            .field("job_progress", &self.job_progress)
+            .field("cancellation_token", &self.cancellation_token)
+            .field("profile_timeout", &self.profile_timeout)
+            .field("job_timeout", &self.job_timeout)
+            .field("max_concurrent_profiles", &self.max_concurrent_profiles)
+            .field("resource_limits", &self.resource_limits)
+            .field("skip_disk_preflight", &self.skip_disk_preflight)
+            .field("disk_quota", &self.disk_quota)
+            .field("disk_usage", &self.disk_usage)
+            .field("retention_policy", &self.retention_policy)
+            .field("hooks", &self.hooks)
+            .field("command_interceptors", &self.command_interceptors.len())
+            .field("private_temp_files", &self.private_temp_files)
+            .field("partial_results", &self.partial_results)
+            .field("deep_validation", &self.deep_validation)
+            .field("ladder_generator", &self.ladder_generator)
+            .field("upscale_policy", &self.upscale_policy)
+            .field("orientation_policy", &self.orientation_policy)
+            .field("deinterlace_policy", &self.deinterlace_policy)
+            .field("frame_rate_policy", &self.frame_rate_policy)
+            .field("audio_sync", &self.audio_sync)
+            .field("quality_metrics", &self.quality_metrics)
+            .field("per_title", &self.per_title)
+            .field("scene_segmentation", &self.scene_segmentation)
+            .field("output_validation", &self.output_validation)
+            .field("content_qc", &self.content_qc)
+            .field("checksums", &self.checksums)
+            .field("verify_encryption", &self.verify_encryption)
+            .field("source_adjustments", &self.source_adjustments)
+            .field("source_chunk", &self.source_chunk)
+            .field("sandbox", &self.sandbox.is_some())
+            .field("sandbox_scope", &self.sandbox_scope)
+            .field("job_id", &self.job_id)
+            .field("key_provider", &self.key_provider.is_some())
+            .field("path_policy", &self.path_policy)
+            .field("sink_uploads", &self.sink_uploads.is_some())
+            .field("remove_streamed_segments", &self.remove_streamed_segments)
+            .field("stdin_input", &self.stdin_input.as_ref().map(Bytes::len));
+
+        #[cfg(feature = "webhooks")]
+        debug.field(
+            "webhooks",
+            &self.webhooks.iter().map(|w| w.url()).collect::<Vec<_>>(),
+        );
+
+        #[cfg(feature = "cache")]
+        debug.field("result_cache", &self.result_cache.is_some());
+
+        debug.finish()
+    }
+}
+
+impl PartialEq for ProcessingContext {
+    fn eq(&self, other: &Self) -> bool {
+        self.event_handlers.len() == other.event_handlers.len()
+            && self
+                .event_handlers
+                .iter()
+                .zip(&other.event_handlers)
+                .all(|(a, b)| Arc::ptr_eq(a, b))
+            && self.source_duration == other.source_duration
+            && self.source_has_audio == other.source_has_audio
+            && self.source_frame_rate == other.source_frame_rate
+            && match (&self.job_progress, &other.job_progress) {
+                (Some(a), Some(b)) => Arc::ptr_eq(a, b),
+                (None, None) => true,
+                _ => false,
+            }
+            && self.cancellation_token.is_some() == other.cancellation_token.is_some()
+            && self.profile_timeout == other.profile_timeout
+            && self.job_timeout == other.job_timeout
+            && self.max_concurrent_profiles == other.max_concurrent_profiles
+            && self.resource_limits == other.resource_limits
+            && self.skip_disk_preflight == other.skip_disk_preflight
+            && self.disk_quota == other.disk_quota
+            && self.retention_policy == other.retention_policy
+            && self.hooks == other.hooks
+            && self.command_interceptors.len() == other.command_interceptors.len()
+            && self
+                .command_interceptors
+                .iter()
+                .zip(&other.command_interceptors)
+                .all(|(a, b)| Arc::ptr_eq(a, b))
+            && self.private_temp_files == other.private_temp_files
+            && self.partial_results == other.partial_results
+            && self.deep_validation == other.deep_validation
+            && self.ladder_generator == other.ladder_generator
+            && self.upscale_policy == other.upscale_policy
+            && self.orientation_policy == other.orientation_policy
+            && self.deinterlace_policy == other.deinterlace_policy
+            && self.frame_rate_policy == other.frame_rate_policy
+            && self.audio_sync == other.audio_sync
+            && self.quality_metrics == other.quality_metrics
+            && self.per_title == other.per_title
+            && self.scene_segmentation == other.scene_segmentation
+            && self.output_validation == other.output_validation
+            && self.content_qc == other.content_qc
+            && self.checksums == other.checksums
+            && self.verify_encryption == other.verify_encryption
+            && self.source_adjustments == other.source_adjustments
+            && self.source_chunk == other.source_chunk
+            && match (&self.sandbox, &other.sandbox) {
+                (Some(a), Some(b)) => Arc::ptr_eq(a, b),
+                (None, None) => true,
+                _ => false,
+            }
+            && self.sandbox_scope == other.sandbox_scope
+            && self.job_id == other.job_id
+            && match (&self.key_provider, &other.key_provider) {
+                (Some(a), Some(b)) => Arc::ptr_eq(a, b),
+                (None, None) => true,
+                _ => false,
+            }
+            && self.path_policy == other.path_policy
+            && match (&self.sink_uploads, &other.sink_uploads) {
+                (Some(a), Some(b)) => a.same_channel(b),
+                (None, None) => true,
+                _ => false,
+            }
+            && self.remove_streamed_segments == other.remove_streamed_segments
+            && self.stdin_input == other.stdin_input
+            && self.webhooks_eq(other)
+            && self.result_cache_eq(other)
+    }
+}
+
+impl ProcessingContext {
+    #[cfg(feature = "webhooks")]
+    fn webhooks_eq(&self, other: &Self) -> bool {
+        self.webhooks.len() == other.webhooks.len()
+            && self
+                .webhooks
+                .iter()
+                .zip(&other.webhooks)
+                .all(|(a, b)| Arc::ptr_eq(a, b))
+    }
+
+    #[cfg(not(feature = "webhooks"))]
+    fn webhooks_eq(&self, _other: &Self) -> bool {
+        true
+    }
+
+    #[cfg(feature = "cache")]
+    fn result_cache_eq(&self, other: &Self) -> bool {
+        match (&self.result_cache, &other.result_cache) {
+            (Some(a), Some(b)) => Arc::ptr_eq(a, b),
+            (None, None) => true,
+            _ => false,
+        }
+    }
+
+    #[cfg(not(feature = "cache"))]
+    fn result_cache_eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl Eq for ProcessingContext {}
@@ -0,0 +1,154 @@
+// SPDX-License-Identifier: LGPL-3.0-only
+/*
+ * Copyright © 2025 The HlsKit Project
+ *
+ * This software is licensed under the GNU Lesser General Public License v3.0 (LGPLv3).
+ * All contributions adhere to the LGPLv3 and the HlsKit Contributor License Agreement (CLA).
+ * A copy of the LGPLv3 can be found at https://www.gnu.org/licenses/lgpl-3.0.html
+ *
+ * HlsKit Contributor License Agreement
+ *
+ * By contributing to or modifying HlsKit, you agree to the following terms:
+ *
+ * 1. Collective Ownership:
+ * The HlsKit project incorporates original code and all contributions as a collective work,
+ * licensed under LGPLv3. Once submitted, contributions become part of the shared HlsKit
+ * ecosystem and cannot be reclaimed, reassigned, or withdrawn. Contributions to your own
+ * forks remain yours unless submitted here, at which point they join this collective whole under LGPLv3.
+ *
+ * 2. Definition of Contribution:
+ * You are considered a contributor if you modify the library in any form (including forks,
+ * wrappers, libraries, or extensions that alter its behavior), whether or not you submit
+ * your changes directly to this repository. All such modifications are part of the broader
+ * HlsKit ecosystem and are subject to this CLA.
+ *
+ * 3. Distribution of Modifications:
+ * If you distribute a modified version of HlsKit, you must license your modifications under
+ * LGPLv3 (with source code available as required by the license) and ensure they are
+ * adoptable by the HlsKit ecosystem (publicly available and compatible).
+ *
+ * 4. Networked Use of Modifications:
+ * If you use a modified version of HlsKit in a networked application, you must provide the
+ * source code of your modifications under LGPLv3 and notify the HlsKit project
+ * (e.g., via email to [higashikataengels@icloud.com]). This does not apply to the use of
+ * the unmodified library in proprietary software, which remains permissible under LGPLv3.
+ *
+ * 5. Scope:
+ * These terms apply to all contributions and modifications derived from the HlsKit project.
+ * The use of the unmodified library in proprietary software is governed solely by the LGPLv3.
+ */
+
+use std::{fmt, sync::Arc};
+
+use crate::{
+    models::{
+        hls_video_manifest::{HlsVideoManifest, HlsVideoResolutionManifest},
+        processing_plan::ProfilePlan,
+    },
+    tools::hlskit_error::HlsKitError,
+};
+
+pub type BeforeProfileHook = Arc<dyn Fn(&mut ProfilePlan) -> Result<(), HlsKitError> + Send + Sync>;
+pub type AfterProfileHook =
+    Arc<dyn Fn(&mut HlsVideoResolutionManifest) -> Result<(), HlsKitError> + Send + Sync>;
+pub type AfterMasterPlaylistHook =
+    Arc<dyn Fn(&mut HlsVideoManifest) -> Result<(), HlsKitError> + Send + Sync>;
+
+/// User callbacks run at fixed points of a job, in registration order
+///
+/// A hook returning an error fails the job with that error.
+#[derive(Clone, Default)]
+pub struct ProcessingHooks {
+    before_profile: Vec<BeforeProfileHook>,
+    after_profile: Vec<AfterProfileHook>,
+    after_master_playlist: Vec<AfterMasterPlaylistHook>,
+}
+
+impl ProcessingHooks {
+    /// Runs before a profile's command is executed, with the command and output layout editable
+    pub fn with_before_profile<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&mut ProfilePlan) -> Result<(), HlsKitError> + Send + Sync + 'static,
+    {
+        self.before_profile.push(Arc::new(hook));
+        self
+    }
+
+    /// Runs once a profile is encoded, before its files reach the sink
+    pub fn with_after_profile<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&mut HlsVideoResolutionManifest) -> Result<(), HlsKitError> + Send + Sync + 'static,
+    {
+        self.after_profile.push(Arc::new(hook));
+        self
+    }
+
+    /// Runs once the master playlist is built, before it is written to disk or reaches the sink
+    pub fn with_after_master_playlist<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&mut HlsVideoManifest) -> Result<(), HlsKitError> + Send + Sync + 'static,
+    {
+        self.after_master_playlist.push(Arc::new(hook));
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.before_profile.is_empty()
+            && self.after_profile.is_empty()
+            && self.after_master_playlist.is_empty()
+    }
+
+    pub fn has_before_profile(&self) -> bool {
+        !self.before_profile.is_empty()
+    }
+
+    pub fn has_after_profile(&self) -> bool {
+        !self.after_profile.is_empty()
+    }
+
+    pub fn run_before_profile(&self, plan: &mut ProfilePlan) -> Result<(), HlsKitError> {
+        self.before_profile.iter().try_for_each(|hook| hook(plan))
+    }
+
+    pub fn run_after_profile(
+        &self,
+        manifest: &mut HlsVideoResolutionManifest,
+    ) -> Result<(), HlsKitError> {
+        self.after_profile
+            .iter()
+            .try_for_each(|hook| hook(manifest))
+    }
+
+    pub fn run_after_master_playlist(
+        &self,
+        manifest: &mut HlsVideoManifest,
+    ) -> Result<(), HlsKitError> {
+        self.after_master_playlist
+            .iter()
+            .try_for_each(|hook| hook(manifest))
+    }
+}
+
+impl fmt::Debug for ProcessingHooks {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ProcessingHooks")
+            .field("before_profile", &self.before_profile.len())
+            .field("after_profile", &self.after_profile.len())
+            .field("after_master_playlist", &self.after_master_playlist.len())
+            .finish()
+    }
+}
+
+impl PartialEq for ProcessingHooks {
+    fn eq(&self, other: &Self) -> bool {
+        fn same<T: ?Sized>(a: &[Arc<T>], b: &[Arc<T>]) -> bool {
+            a.len() == b.len() && a.iter().zip(b).all(|(a, b)| Arc::ptr_eq(a, b))
+        }
+
+        same(&self.before_profile, &other.before_profile)
+            && same(&self.after_profile, &other.after_profile)
+            && same(&self.after_master_playlist, &other.after_master_playlist)
+    }
+}
+
+impl Eq for ProcessingHooks {}
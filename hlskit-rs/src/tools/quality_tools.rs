@@ -0,0 +1,214 @@
+// SPDX-License-Identifier: LGPL-3.0-only
+/*
+ * Copyright © 2025 The HlsKit Project
+ *
+ * This software is licensed under the GNU Lesser General Public License v3.0 (LGPLv3).
+ * All contributions adhere to the LGPLv3 and the HlsKit Contributor License Agreement (CLA).
+ * A copy of the LGPLv3 can be found at https://www.gnu.org/licenses/lgpl-3.0.html
+ *
+ * HlsKit Contributor License Agreement
+ *
+ * By contributing to or modifying HlsKit, you agree to the following terms:
+ *
+ * 1. Collective Ownership:
+ * The HlsKit project incorporates original code and all contributions as a collective work,
+ * licensed under LGPLv3. Once submitted, contributions become part of the shared HlsKit
+ * ecosystem and cannot be reclaimed, reassigned, or withdrawn. Contributions to your own
+ * forks remain yours unless submitted here, at which point they join this collective whole under LGPLv3.
+ *
+ * 2. Definition of Contribution:
+ * You are considered a contributor if you modify the library in any form (including forks,
+ * wrappers, libraries, or extensions that alter its behavior), whether or not you submit
+ * your changes directly to this repository. All such modifications are part of the broader
+ * HlsKit ecosystem and are subject to this CLA.
+ *
+ * 3. Distribution of Modifications:
+ * If you distribute a modified version of HlsKit, you must license your modifications under
+ * LGPLv3 (with source code available as required by the license) and ensure they are
+ * adoptable by the HlsKit ecosystem (publicly available and compatible).
+ *
+ * 4. Networked Use of Modifications:
+ * If you use a modified version of HlsKit in a networked application, you must provide the
+ * source code of your modifications under LGPLv3 and notify the HlsKit project
+ * (e.g., via email to [higashikataengels@icloud.com]). This does not apply to the use of
+ * the unmodified library in proprietary software, which remains permissible under LGPLv3.
+ *
+ * 5. Scope:
+ * These terms apply to all contributions and modifications derived from the HlsKit project.
+ * The use of the unmodified library in proprietary software is governed solely by the LGPLv3.
+ */
+
+use std::{path::Path, time::Duration};
+
+use tokio::process::Command;
+
+use crate::{
+    models::quality_scores::{QualityMetric, QualityScores},
+    tools::{
+        binary_tools::{resolve_binary, FFMPEG_PATH_ENV},
+        hlskit_error::HlsKitError,
+    },
+};
+
+/// Scores `rendition` (a media playlist or video file) against `source` with every metric in
+/// `metrics`. The rendition is scaled back up to `reference_resolution` when given, so scores
+/// include the detail lost to downscaling, otherwise the source is scaled down to the
+/// rendition.
+pub async fn measure_quality(
+    source: &str,
+    rendition: &Path,
+    rendition_resolution: (i32, i32),
+    reference_resolution: Option<(i32, i32)>,
+    metrics: &[QualityMetric],
+) -> Result<QualityScores, HlsKitError> {
+    measure_quality_of_section(
+        source,
+        None,
+        rendition,
+        rendition_resolution,
+        reference_resolution,
+        metrics,
+    )
+    .await
+}
+
+/// Like [`measure_quality`], comparing against the `(start, duration)` section of the source
+/// when given, for renditions encoded from that section only
+pub(crate) async fn measure_quality_of_section(
+    source: &str,
+    section: Option<(Duration, Duration)>,
+    rendition: &Path,
+    rendition_resolution: (i32, i32),
+    reference_resolution: Option<(i32, i32)>,
+    metrics: &[QualityMetric],
+) -> Result<QualityScores, HlsKitError> {
+    if metrics.is_empty() {
+        return Ok(QualityScores::default());
+    }
+
+    let mut args = vec![
+        "-hide_banner".to_string(),
+        "-nostats".to_string(),
+        "-i".to_string(),
+        rendition.to_string_lossy().to_string(),
+    ];
+    if let Some((start, duration)) = section {
+        args.extend([
+            "-ss".to_string(),
+            format!("{:.3}", start.as_secs_f64()),
+            "-t".to_string(),
+            format!("{:.3}", duration.as_secs_f64()),
+        ]);
+    }
+    args.extend([
+        "-i".to_string(),
+        source.to_string(),
+        "-lavfi".to_string(),
+        quality_filter_graph(rendition_resolution, reference_resolution, metrics),
+        "-f".to_string(),
+        "null".to_string(),
+        "-".to_string(),
+    ]);
+
+    let stderr = run_ffmpeg(&args).await?;
+    Ok(parse_quality_report(&stderr))
+}
+
+/// Runs ffmpeg to completion, returning its stderr where filters print their reports
+pub(crate) async fn run_ffmpeg(args: &[String]) -> Result<String, HlsKitError> {
+    let binary = resolve_binary(FFMPEG_PATH_ENV, "ffmpeg");
+    let output = Command::new(&binary)
+        .args(args)
+        .kill_on_drop(true)
+        .output()
+        .await
+        .map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => HlsKitError::BinaryNotFound {
+                binary: binary.to_string_lossy().to_string(),
+            },
+            _ => HlsKitError::CommandExecutionError {
+                error: format!("Failed to spawn ffmpeg: {e}"),
+            },
+        })?;
+
+    let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+    if !output.status.success() {
+        return Err(HlsKitError::CommandExecutionError {
+            error: format!("ffmpeg failed: {stderr}"),
+        });
+    }
+
+    Ok(stderr)
+}
+
+/// Compares `[0:v]` (the rendition) against `[1:v]` (the source) with one filter per metric
+fn quality_filter_graph(
+    (width, height): (i32, i32),
+    reference_resolution: Option<(i32, i32)>,
+    metrics: &[QualityMetric],
+) -> String {
+    let (distorted_scale, reference_scale) = match reference_resolution {
+        Some((ref_width, ref_height)) => (
+            format!("scale={ref_width}:{ref_height}:flags=bicubic,"),
+            String::new(),
+        ),
+        None => (
+            String::new(),
+            format!("scale={width}:{height}:flags=bicubic,"),
+        ),
+    };
+
+    let count = metrics.len();
+    let mut graph = format!(
+        "[0:v]{distorted_scale}setpts=PTS-STARTPTS,split={count}{};\
+         [1:v]{reference_scale}setpts=PTS-STARTPTS,split={count}{}",
+        labels("dist", count),
+        labels("ref", count),
+    );
+
+    for (index, metric) in metrics.iter().enumerate() {
+        graph.push_str(&format!(
+            ";[dist{index}][ref{index}]{}",
+            metric.filter_name()
+        ));
+    }
+
+    graph
+}
+
+fn labels(prefix: &str, count: usize) -> String {
+    (0..count)
+        .map(|index| format!("[{prefix}{index}]"))
+        .collect()
+}
+
+/// Reads the summary lines the metric filters print when they finish, e.g.
+/// `VMAF score: 93.41`, `PSNR y:.. average:41.20 ..` and `SSIM Y:.. All:0.987 (18.9)`
+fn parse_quality_report(stderr: &str) -> QualityScores {
+    let mut scores = QualityScores::default();
+
+    for line in stderr.lines() {
+        if let Some((_, value)) = line.split_once("VMAF score:") {
+            scores.vmaf = parse_score(value);
+        } else if line.contains("PSNR ") {
+            if let Some((_, value)) = line.split_once("average:") {
+                scores.psnr = parse_score(value);
+            }
+        } else if line.contains("SSIM ") {
+            if let Some((_, value)) = line.split_once("All:") {
+                scores.ssim = parse_score(value);
+            }
+        }
+    }
+
+    scores
+}
+
+fn parse_score(value: &str) -> Option<f64> {
+    value
+        .split_whitespace()
+        .next()?
+        .parse::<f64>()
+        .ok()
+        .filter(|score| !score.is_nan())
+}
@@ -0,0 +1,119 @@
+// SPDX-License-Identifier: LGPL-3.0-only
+/*
+ * Copyright © 2025 The HlsKit Project
+ *
+ * This software is licensed under the GNU Lesser General Public License v3.0 (LGPLv3).
+ * All contributions adhere to the LGPLv3 and the HlsKit Contributor License Agreement (CLA).
+ * A copy of the LGPLv3 can be found at https://www.gnu.org/licenses/lgpl-3.0.html
+ *
+ * HlsKit Contributor License Agreement
+ *
+ * By contributing to or modifying HlsKit, you agree to the following terms:
+ *
+ * 1. Collective Ownership:
+ * The HlsKit project incorporates original code and all contributions as a collective work,
+ * licensed under LGPLv3. Once submitted, contributions become part of the shared HlsKit
+ * ecosystem and cannot be reclaimed, reassigned, or withdrawn. Contributions to your own
+ * forks remain yours unless submitted here, at which point they join this collective whole under LGPLv3.
+ *
+ * 2. Definition of Contribution:
+ * You are considered a contributor if you modify the library in any form (including forks,
+ * wrappers, libraries, or extensions that alter its behavior), whether or not you submit
+ * your changes directly to this repository. All such modifications are part of the broader
+ * HlsKit ecosystem and are subject to this CLA.
+ *
+ * 3. Distribution of Modifications:
+ * If you distribute a modified version of HlsKit, you must license your modifications under
+ * LGPLv3 (with source code available as required by the license) and ensure they are
+ * adoptable by the HlsKit ecosystem (publicly available and compatible).
+ *
+ * 4. Networked Use of Modifications:
+ * If you use a modified version of HlsKit in a networked application, you must provide the
+ * source code of your modifications under LGPLv3 and notify the HlsKit project
+ * (e.g., via email to [higashikataengels@icloud.com]). This does not apply to the use of
+ * the unmodified library in proprietary software, which remains permissible under LGPLv3.
+ *
+ * 5. Scope:
+ * These terms apply to all contributions and modifications derived from the HlsKit project.
+ * The use of the unmodified library in proprietary software is governed solely by the LGPLv3.
+ */
+
+use std::path::Path;
+
+use crate::{
+    models::hls_video::{HlsVideo, MASTER_PLAYLIST_NAME},
+    tools::{hlskit_error::HlsKitError, m3u8_tools::parse_master, quality_tools::run_ffmpeg},
+};
+
+/// Concatenates the segments of the `resolution` rendition of `video` into a progressive MP4
+/// at `output_path`, copying the streams without re-encoding
+pub async fn hls_to_mp4(
+    video: &HlsVideo,
+    resolution: (i32, i32),
+    output_path: &Path,
+) -> Result<(), HlsKitError> {
+    let rendition = video
+        .resolutions
+        .iter()
+        .find(|rendition| rendition.resolution == resolution)
+        .ok_or_else(|| HlsKitError::FileNotFound {
+            file_path: format!("{}x{} rendition", resolution.0, resolution.1),
+        })?;
+
+    let workdir = tempfile::tempdir()?;
+    video.write_to_dir(workdir.path())?;
+    remux_playlist(&workdir.path().join(&rendition.playlist_name), output_path).await
+}
+
+/// [`hls_to_mp4`], returning the MP4 instead of writing it
+pub async fn hls_to_mp4_bytes(
+    video: &HlsVideo,
+    resolution: (i32, i32),
+) -> Result<Vec<u8>, HlsKitError> {
+    let workdir = tempfile::tempdir()?;
+    let output_path = workdir.path().join("output.mp4");
+    hls_to_mp4(video, resolution, &output_path).await?;
+
+    Ok(tokio::fs::read(output_path).await?)
+}
+
+/// [`hls_to_mp4`] on the asset in `dir`, reading the rendition in place
+pub async fn hls_dir_to_mp4(
+    dir: &Path,
+    resolution: (i32, i32),
+    output_path: &Path,
+) -> Result<(), HlsKitError> {
+    let master_playlist = tokio::fs::read_to_string(dir.join(MASTER_PLAYLIST_NAME)).await?;
+    let variant = parse_master(&master_playlist)?
+        .variants
+        .into_iter()
+        .find(|variant| variant.resolution == Some(resolution))
+        .ok_or_else(|| HlsKitError::FileNotFound {
+            file_path: format!("{}x{} rendition", resolution.0, resolution.1),
+        })?;
+
+    remux_playlist(&dir.join(variant.uri), output_path).await
+}
+
+async fn remux_playlist(playlist_path: &Path, output_path: &Path) -> Result<(), HlsKitError> {
+    let args = [
+        "-y",
+        "-loglevel",
+        "error",
+        "-allowed_extensions",
+        "ALL",
+        "-i",
+        &playlist_path.to_string_lossy(),
+        "-map",
+        "0",
+        "-c",
+        "copy",
+        "-movflags",
+        "+faststart",
+        &output_path.to_string_lossy(),
+    ]
+    .map(str::to_string);
+
+    run_ffmpeg(&args).await?;
+    Ok(())
+}
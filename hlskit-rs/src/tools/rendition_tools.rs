@@ -0,0 +1,172 @@
+// SPDX-License-Identifier: LGPL-3.0-only
+/*
+ * Copyright © 2025 The HlsKit Project
+ *
+ * This software is licensed under the GNU Lesser General Public License v3.0 (LGPLv3).
+ * All contributions adhere to the LGPLv3 and the HlsKit Contributor License Agreement (CLA).
+ * A copy of the LGPLv3 can be found at https://www.gnu.org/licenses/lgpl-3.0.html
+ *
+ * HlsKit Contributor License Agreement
+ *
+ * By contributing to or modifying HlsKit, you agree to the following terms:
+ *
+ * 1. Collective Ownership:
+ * The HlsKit project incorporates original code and all contributions as a collective work,
+ * licensed under LGPLv3. Once submitted, contributions become part of the shared HlsKit
+ * ecosystem and cannot be reclaimed, reassigned, or withdrawn. Contributions to your own
+ * forks remain yours unless submitted here, at which point they join this collective whole under LGPLv3.
+ *
+ * 2. Definition of Contribution:
+ * You are considered a contributor if you modify the library in any form (including forks,
+ * wrappers, libraries, or extensions that alter its behavior), whether or not you submit
+ * your changes directly to this repository. All such modifications are part of the broader
+ * HlsKit ecosystem and are subject to this CLA.
+ *
+ * 3. Distribution of Modifications:
+ * If you distribute a modified version of HlsKit, you must license your modifications under
+ * LGPLv3 (with source code available as required by the license) and ensure they are
+ * adoptable by the HlsKit ecosystem (publicly available and compatible).
+ *
+ * 4. Networked Use of Modifications:
+ * If you use a modified version of HlsKit in a networked application, you must provide the
+ * source code of your modifications under LGPLv3 and notify the HlsKit project
+ * (e.g., via email to [higashikataengels@icloud.com]). This does not apply to the use of
+ * the unmodified library in proprietary software, which remains permissible under LGPLv3.
+ *
+ * 5. Scope:
+ * These terms apply to all contributions and modifications derived from the HlsKit project.
+ * The use of the unmodified library in proprietary software is governed solely by the LGPLv3.
+ */
+
+use bytes::Bytes;
+
+use crate::{
+    backends::ffmpeg_backend::FfmpegBackend,
+    models::{hls_video::HlsVideo, hls_video_processing_settings::HlsVideoProcessingSettings},
+    tools::{
+        hlskit_error::HlsKitError, internals::pipeline::ProcessingJob,
+        processing_context::ProcessingContext, rewrite_tools,
+    },
+    VideoInputType,
+};
+
+/// Encodes `profile` from `input` and adds it to `video` as a new rendition, leaving the
+/// existing ones untouched and only extending the master playlist
+pub async fn add_rendition(
+    video: &mut HlsVideo,
+    input: VideoInputType,
+    profile: HlsVideoProcessingSettings,
+) -> Result<(), HlsKitError> {
+    let encoded = encode_rendition(&input, profile).await?;
+    merge_rendition(video, encoded, None)
+}
+
+/// Like [`add_rendition`], dropping the rendition at `resolution` from `video` first
+pub async fn replace_rendition(
+    video: &mut HlsVideo,
+    resolution: (i32, i32),
+    input: VideoInputType,
+    profile: HlsVideoProcessingSettings,
+) -> Result<(), HlsKitError> {
+    if !video
+        .resolutions
+        .iter()
+        .any(|rendition| rendition.resolution == resolution)
+    {
+        return Err(HlsKitError::FileNotFound {
+            file_path: format!("{}x{} rendition", resolution.0, resolution.1),
+        });
+    }
+
+    let encoded = encode_rendition(&input, profile).await?;
+    merge_rendition(video, encoded, Some(resolution))
+}
+
+async fn encode_rendition(
+    input: &VideoInputType,
+    profile: HlsVideoProcessingSettings,
+) -> Result<HlsVideo, HlsKitError> {
+    let backend = FfmpegBackend::default();
+    ProcessingJob {
+        input,
+        output_profiles: &[profile],
+        encryption: None,
+        backend: &backend,
+        context: &ProcessingContext::default(),
+    }
+    .process()
+    .await
+}
+
+/// Moves the single rendition of `encoded` into `video` under the next free stream index,
+/// removing the rendition at `replaced` if any
+fn merge_rendition(
+    video: &mut HlsVideo,
+    mut encoded: HlsVideo,
+    replaced: Option<(i32, i32)>,
+) -> Result<(), HlsKitError> {
+    let encoded_master = String::from_utf8_lossy(&encoded.master_m3u8_data).into_owned();
+    let stream_inf = encoded_master
+        .lines()
+        .find(|line| line.starts_with("#EXT-X-STREAM-INF:"))
+        .ok_or_else(|| HlsKitError::CommandExecutionError {
+            error: "the new rendition has no variant stream".to_string(),
+        })?
+        .to_string();
+
+    let removed_playlist = replaced.and_then(|resolution| {
+        let index = video
+            .resolutions
+            .iter()
+            .position(|rendition| rendition.resolution == resolution)?;
+        Some(video.resolutions.remove(index).playlist_name)
+    });
+
+    // Stream indexes name the files, as `playlist_{index}.m3u8` and `data_{index}_%03d.ts`
+    let stream_index = video
+        .resolutions
+        .iter()
+        .map(|rendition| &rendition.playlist_name)
+        .chain(&removed_playlist)
+        .filter_map(|name| {
+            name.strip_prefix("playlist_")?
+                .strip_suffix(".m3u8")?
+                .parse::<usize>()
+                .ok()
+        })
+        .max()
+        .map_or(video.resolutions.len(), |index| index + 1);
+
+    rewrite_tools::rename_segments(&mut encoded, |name| {
+        name.replacen("data_0_", &format!("data_{stream_index}_"), 1)
+    });
+    rewrite_tools::rename_playlists(&mut encoded, |_| format!("playlist_{stream_index}.m3u8"));
+    let Some(rendition) = encoded.resolutions.pop() else {
+        return Err(HlsKitError::CommandExecutionError {
+            error: "the new rendition has no media playlist".to_string(),
+        });
+    };
+
+    let master = String::from_utf8_lossy(&video.master_m3u8_data).into_owned();
+    let mut lines: Vec<&str> = master.lines().collect();
+    if let Some(removed_playlist) = &removed_playlist {
+        if let Some(index) = lines
+            .iter()
+            .position(|line| line.trim() == removed_playlist)
+        {
+            lines.remove(index);
+            if index > 0 && lines[index - 1].starts_with("#EXT-X-STREAM-INF:") {
+                lines.remove(index - 1);
+            }
+        }
+    }
+    let mut master = lines.join("\n");
+    master.push('\n');
+    master.push_str(&format!("{stream_inf}\n{}\n", rendition.playlist_name));
+
+    video.master_m3u8_data = Bytes::from(master);
+    video.resolutions.push(rendition);
+    video.checksums = None;
+
+    Ok(())
+}
@@ -0,0 +1,190 @@
+// SPDX-License-Identifier: LGPL-3.0-only
+/*
+ * Copyright © 2025 The HlsKit Project
+ *
+ * This software is licensed under the GNU Lesser General Public License v3.0 (LGPLv3).
+ * All contributions adhere to the LGPLv3 and the HlsKit Contributor License Agreement (CLA).
+ * A copy of the LGPLv3 can be found at https://www.gnu.org/licenses/lgpl-3.0.html
+ *
+ * HlsKit Contributor License Agreement
+ *
+ * By contributing to or modifying HlsKit, you agree to the following terms:
+ *
+ * 1. Collective Ownership:
+ * The HlsKit project incorporates original code and all contributions as a collective work,
+ * licensed under LGPLv3. Once submitted, contributions become part of the shared HlsKit
+ * ecosystem and cannot be reclaimed, reassigned, or withdrawn. Contributions to your own
+ * forks remain yours unless submitted here, at which point they join this collective whole under LGPLv3.
+ *
+ * 2. Definition of Contribution:
+ * You are considered a contributor if you modify the library in any form (including forks,
+ * wrappers, libraries, or extensions that alter its behavior), whether or not you submit
+ * your changes directly to this repository. All such modifications are part of the broader
+ * HlsKit ecosystem and are subject to this CLA.
+ *
+ * 3. Distribution of Modifications:
+ * If you distribute a modified version of HlsKit, you must license your modifications under
+ * LGPLv3 (with source code available as required by the license) and ensure they are
+ * adoptable by the HlsKit ecosystem (publicly available and compatible).
+ *
+ * 4. Networked Use of Modifications:
+ * If you use a modified version of HlsKit in a networked application, you must provide the
+ * source code of your modifications under LGPLv3 and notify the HlsKit project
+ * (e.g., via email to [higashikataengels@icloud.com]). This does not apply to the use of
+ * the unmodified library in proprietary software, which remains permissible under LGPLv3.
+ *
+ * 5. Scope:
+ * These terms apply to all contributions and modifications derived from the HlsKit project.
+ * The use of the unmodified library in proprietary software is governed solely by the LGPLv3.
+ */
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    models::{
+        hls_video::HlsVideoResolution,
+        hls_video_manifest::{HlsVideoManifest, HlsVideoResolutionManifest},
+        hls_video_report::{HlsVideoReport, RenditionReport},
+        job_id::JobId,
+    },
+    tools::m3u8_tools::parse_media,
+};
+
+/// Sizes of the segments deleted while the job ran, such as those removed once streamed to a
+/// sink, by path
+pub(crate) type RemovedSegments = HashMap<PathBuf, u64>;
+
+/// Summarizes a finished job from its manifest and the files it wrote
+pub async fn build_report(
+    manifest: &HlsVideoManifest,
+    backend: &str,
+    job_id: Option<JobId>,
+) -> HlsVideoReport {
+    let removed = RemovedSegments::new();
+    let mut renditions = Vec::with_capacity(manifest.resolutions.len());
+    for rendition in &manifest.resolutions {
+        renditions.push(rendition_report(rendition, &removed).await);
+    }
+
+    report_for_renditions(manifest, renditions, backend, job_id)
+}
+
+/// Like [`build_report`], with the reports of the renditions already built
+pub(crate) fn report_for_renditions(
+    manifest: &HlsVideoManifest,
+    renditions: Vec<RenditionReport>,
+    backend: &str,
+    job_id: Option<JobId>,
+) -> HlsVideoReport {
+    HlsVideoReport {
+        job_id,
+        backend: backend.to_string(),
+        wall_time: manifest.resource_usage.wall_time,
+        total_bytes: renditions
+            .iter()
+            .map(|rendition| rendition.total_bytes)
+            .sum(),
+        renditions,
+        failed_profiles: manifest.failed_profiles.len(),
+        encrypted_key: None,
+    }
+}
+
+/// Reports on `rendition` from its files, taking the sizes of `removed` segments from there
+pub(crate) async fn rendition_report(
+    rendition: &HlsVideoResolutionManifest,
+    removed: &RemovedSegments,
+) -> RenditionReport {
+    let segments = measure_segments(&rendition.playlist_path, removed).await;
+    let (average_bitrate, peak_bitrate) = segment_bitrates(&segments);
+
+    RenditionReport {
+        resolution: rendition.resolution,
+        playlist_name: rendition.playlist_name.clone(),
+        encoder: rendition.encoder.clone(),
+        encode_wall_time: rendition.resource_usage.wall_time,
+        segment_count: segments.len(),
+        total_bytes: segments.iter().map(|(_, size)| size).sum(),
+        average_bitrate,
+        peak_bitrate,
+        encryption_iv: rendition.encryption_iv.clone(),
+    }
+}
+
+/// Average and peak bitrates of an in-memory rendition, measured the same way as in
+/// [`RenditionReport`]
+pub fn resolution_bitrates(resolution: &HlsVideoResolution) -> (u64, u64) {
+    let playlist = String::from_utf8_lossy(&resolution.playlist_data);
+    let Ok(playlist) = parse_media(&playlist) else {
+        return (0, 0);
+    };
+
+    let segments: Vec<(f64, u64)> = playlist
+        .segments
+        .iter()
+        .map(|segment| {
+            let size = match &segment.byte_range {
+                Some(byte_range) => byte_range.length,
+                None => resolution
+                    .segments
+                    .iter()
+                    .find(|data| data.segment_name == segment.uri)
+                    .map_or(0, |data| data.segment_data.len() as u64),
+            };
+            (segment.duration, size)
+        })
+        .collect();
+
+    segment_bitrates(&segments)
+}
+
+/// Average and peak bitrates of the media playlist at `playlist_path`, measured from the
+/// segment files next to it
+pub(crate) async fn playlist_bitrates(playlist_path: &Path) -> (u64, u64) {
+    segment_bitrates(&measure_segments(playlist_path, &RemovedSegments::new()).await)
+}
+
+/// The `(duration, size)` of every segment of the media playlist at `playlist_path`, sizes
+/// taken from the files next to it or from `removed`
+async fn measure_segments(playlist_path: &Path, removed: &RemovedSegments) -> Vec<(f64, u64)> {
+    let playlist = tokio::fs::read_to_string(playlist_path)
+        .await
+        .unwrap_or_default();
+    let playlist = parse_media(&playlist).unwrap_or_default();
+    let playlist_dir = playlist_path.parent().unwrap_or(Path::new("."));
+
+    let mut segments = Vec::with_capacity(playlist.segments.len());
+    for segment in &playlist.segments {
+        let path = playlist_dir.join(&segment.uri);
+        let size = match (&segment.byte_range, removed.get(&path)) {
+            (Some(byte_range), _) => byte_range.length,
+            (None, Some(size)) => *size,
+            (None, None) => tokio::fs::metadata(&path)
+                .await
+                .map_or(0, |metadata| metadata.len()),
+        };
+        segments.push((segment.duration, size));
+    }
+
+    segments
+}
+
+/// Average and peak bitrates, in bits per second, of `(duration, size)` segments
+fn segment_bitrates(segments: &[(f64, u64)]) -> (u64, u64) {
+    let bitrate = |bytes: u64, seconds: f64| match seconds > 0.0 {
+        true => (bytes as f64 * 8.0 / seconds) as u64,
+        false => 0,
+    };
+    let total_bytes = segments.iter().map(|(_, size)| size).sum::<u64>();
+    let total_seconds = segments.iter().map(|(duration, _)| duration).sum::<f64>();
+
+    let peak_bitrate = segments
+        .iter()
+        .map(|&(duration, size)| bitrate(size, duration))
+        .max()
+        .unwrap_or_default();
+    (bitrate(total_bytes, total_seconds), peak_bitrate)
+}
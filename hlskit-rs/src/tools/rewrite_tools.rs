@@ -0,0 +1,261 @@
+// SPDX-License-Identifier: LGPL-3.0-only
+/*
+ * Copyright © 2025 The HlsKit Project
+ *
+ * This software is licensed under the GNU Lesser General Public License v3.0 (LGPLv3).
+ * All contributions adhere to the LGPLv3 and the HlsKit Contributor License Agreement (CLA).
+ * A copy of the LGPLv3 can be found at https://www.gnu.org/licenses/lgpl-3.0.html
+ *
+ * HlsKit Contributor License Agreement
+ *
+ * By contributing to or modifying HlsKit, you agree to the following terms:
+ *
+ * 1. Collective Ownership:
+ * The HlsKit project incorporates original code and all contributions as a collective work,
+ * licensed under LGPLv3. Once submitted, contributions become part of the shared HlsKit
+ * ecosystem and cannot be reclaimed, reassigned, or withdrawn. Contributions to your own
+ * forks remain yours unless submitted here, at which point they join this collective whole under LGPLv3.
+ *
+ * 2. Definition of Contribution:
+ * You are considered a contributor if you modify the library in any form (including forks,
+ * wrappers, libraries, or extensions that alter its behavior), whether or not you submit
+ * your changes directly to this repository. All such modifications are part of the broader
+ * HlsKit ecosystem and are subject to this CLA.
+ *
+ * 3. Distribution of Modifications:
+ * If you distribute a modified version of HlsKit, you must license your modifications under
+ * LGPLv3 (with source code available as required by the license) and ensure they are
+ * adoptable by the HlsKit ecosystem (publicly available and compatible).
+ *
+ * 4. Networked Use of Modifications:
+ * If you use a modified version of HlsKit in a networked application, you must provide the
+ * source code of your modifications under LGPLv3 and notify the HlsKit project
+ * (e.g., via email to [higashikataengels@icloud.com]). This does not apply to the use of
+ * the unmodified library in proprietary software, which remains permissible under LGPLv3.
+ *
+ * 5. Scope:
+ * These terms apply to all contributions and modifications derived from the HlsKit project.
+ * The use of the unmodified library in proprietary software is governed solely by the LGPLv3.
+ */
+
+use std::time::SystemTime;
+
+use bytes::Bytes;
+
+use crate::{
+    models::hls_video::{HlsVideo, HlsVideoResolution},
+    tools::hlskit_error::HlsKitError,
+    traits::url_signer::UrlSigner,
+};
+
+/// Rewrites every URI of a playlist, the URI lines of variants and segments as well as the
+/// `URI="..."` attributes of `#EXT-X-KEY`, `#EXT-X-MAP`, `#EXT-X-MEDIA` and
+/// `#EXT-X-I-FRAME-STREAM-INF`, leaving everything else untouched
+pub fn rewrite_playlist_uris(playlist: &str, mut rewrite: impl FnMut(&str) -> String) -> String {
+    let mut rewritten = String::with_capacity(playlist.len());
+
+    for line in playlist.lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            rewritten.push_str(line);
+        } else if !trimmed.starts_with('#') {
+            rewritten.push_str(&rewrite(trimmed));
+        } else {
+            rewritten.push_str(&rewrite_uri_attribute(line, &mut rewrite));
+        }
+        rewritten.push('\n');
+    }
+
+    rewritten
+}
+
+fn rewrite_uri_attribute(line: &str, rewrite: &mut impl FnMut(&str) -> String) -> String {
+    let Some(start) = line
+        .find(":URI=\"")
+        .or_else(|| line.find(",URI=\""))
+        .map(|index| index + ",URI=\"".len())
+    else {
+        return line.to_string();
+    };
+    let Some(length) = line[start..].find('"') else {
+        return line.to_string();
+    };
+
+    format!(
+        "{}{}{}",
+        &line[..start],
+        rewrite(&line[start..start + length]),
+        &line[start + length..]
+    )
+}
+
+/// Prefixes the segment URIs of every media playlist with `base_url`, e.g. a CDN host, the
+/// segments keeping their names
+pub fn prefix_segment_uris(video: &mut HlsVideo, base_url: &str) {
+    let base_url = base_url.trim_end_matches('/');
+    video.checksums = None;
+
+    for resolution in &mut video.resolutions {
+        rewrite_media_playlist(resolution, |uri| match uri.contains("://") {
+            true => uri.to_string(),
+            false => format!("{base_url}/{uri}"),
+        });
+    }
+}
+
+/// Replaces every relative URI of the master and media playlists with the URL under
+/// `base_url` signed by `signer`, valid until `expires_at`, for tokenized delivery. Absolute
+/// URIs, e.g. key server URLs, are left as they are
+pub fn sign_uris(
+    video: &mut HlsVideo,
+    base_url: &str,
+    signer: &dyn UrlSigner,
+    expires_at: SystemTime,
+) -> Result<(), HlsKitError> {
+    let base_url = base_url.trim_end_matches('/');
+    let mut first_error = None;
+    let mut sign = |uri: &str, playlist_dir: &str| {
+        if uri.contains(':') {
+            return uri.to_string();
+        }
+
+        let path = match playlist_dir {
+            "" => uri.to_string(),
+            dir => format!("{dir}/{uri}"),
+        };
+        signer
+            .sign_url(&format!("{base_url}/{path}"), expires_at)
+            .unwrap_or_else(|e| {
+                first_error.get_or_insert(e);
+                uri.to_string()
+            })
+    };
+    video.checksums = None;
+
+    for resolution in &mut video.resolutions {
+        let playlist_dir = parent_dir(&resolution.playlist_name).to_string();
+        rewrite_media_playlist(resolution, |uri| sign(uri, &playlist_dir));
+    }
+    rewrite_master_playlist(video, |uri| sign(uri, ""));
+
+    first_error.map_or(Ok(()), Err)
+}
+
+/// Renames every segment with `rename`, called with the segment's path relative to the asset
+/// root, updating the media playlists referencing them
+pub fn rename_segments(video: &mut HlsVideo, mut rename: impl FnMut(&str) -> String) {
+    video.checksums = None;
+    for resolution in &mut video.resolutions {
+        let playlist_dir = parent_dir(&resolution.playlist_name).to_string();
+        let mut renames = Vec::with_capacity(resolution.segments.len());
+
+        for segment in &mut resolution.segments {
+            let renamed = rename(&segment.segment_name);
+            renames.push((
+                relative_uri(&playlist_dir, &segment.segment_name),
+                relative_uri(&playlist_dir, &renamed),
+            ));
+            segment.segment_name = renamed;
+        }
+
+        rewrite_media_playlist(resolution, |uri| {
+            renames
+                .iter()
+                .find_map(|(old, new)| replace_uri(uri, old, new))
+                .unwrap_or_else(|| uri.to_string())
+        });
+    }
+}
+
+/// Moves each rendition, playlist and segments, into the subdirectory returned by `dir_for`,
+/// updating the master playlist. Media playlists are unchanged as their segments stay next
+/// to them
+pub fn move_to_subdirectories(
+    video: &mut HlsVideo,
+    mut dir_for: impl FnMut(&HlsVideoResolution) -> String,
+) {
+    let mut renames = Vec::with_capacity(video.resolutions.len());
+    video.checksums = None;
+
+    for resolution in &mut video.resolutions {
+        let dir = dir_for(resolution);
+        let dir = dir.trim_matches('/');
+        let playlist_name = format!("{dir}/{}", resolution.playlist_name);
+
+        for segment in &mut resolution.segments {
+            segment.segment_name = format!("{dir}/{}", segment.segment_name);
+        }
+        renames.push((
+            std::mem::replace(&mut resolution.playlist_name, playlist_name.clone()),
+            playlist_name,
+        ));
+    }
+
+    rewrite_master_playlist(video, |uri| {
+        renames
+            .iter()
+            .find_map(|(old, new)| replace_uri(uri, old, new))
+            .unwrap_or_else(|| uri.to_string())
+    });
+}
+
+/// Renames every media playlist with `rename`, updating the master playlist
+pub fn rename_playlists(video: &mut HlsVideo, mut rename: impl FnMut(&str) -> String) {
+    let mut renames = Vec::with_capacity(video.resolutions.len());
+    video.checksums = None;
+
+    for resolution in &mut video.resolutions {
+        let renamed = rename(&resolution.playlist_name);
+        renames.push((
+            std::mem::replace(&mut resolution.playlist_name, renamed.clone()),
+            renamed,
+        ));
+    }
+
+    rewrite_master_playlist(video, |uri| {
+        renames
+            .iter()
+            .find_map(|(old, new)| replace_uri(uri, old, new))
+            .unwrap_or_else(|| uri.to_string())
+    });
+}
+
+fn rewrite_media_playlist(
+    resolution: &mut HlsVideoResolution,
+    rewrite: impl FnMut(&str) -> String,
+) {
+    let playlist = String::from_utf8_lossy(&resolution.playlist_data);
+    resolution.playlist_data = Bytes::from(rewrite_playlist_uris(&playlist, rewrite));
+}
+
+fn rewrite_master_playlist(video: &mut HlsVideo, rewrite: impl FnMut(&str) -> String) {
+    let playlist = String::from_utf8_lossy(&video.master_m3u8_data);
+    video.master_m3u8_data = Bytes::from(rewrite_playlist_uris(&playlist, rewrite));
+}
+
+/// `uri` with `old` replaced by `new`, when it references `old` directly or, for absolute
+/// URLs such as those written with a base URL, as its trailing path
+fn replace_uri(uri: &str, old: &str, new: &str) -> Option<String> {
+    if uri == old {
+        return Some(new.to_string());
+    }
+
+    let base = uri.strip_suffix(old)?;
+    (uri.contains("://") && base.ends_with('/')).then(|| format!("{base}{new}"))
+}
+
+fn parent_dir(path: &str) -> &str {
+    path.rsplit_once('/').map_or("", |(dir, _)| dir)
+}
+
+/// `path`, relative to the asset root, as referenced from a playlist in `playlist_dir`
+pub(crate) fn relative_uri(playlist_dir: &str, path: &str) -> String {
+    match playlist_dir {
+        "" => path.to_string(),
+        dir => path
+            .strip_prefix(dir)
+            .and_then(|rest| rest.strip_prefix('/'))
+            .unwrap_or(path)
+            .to_string(),
+    }
+}
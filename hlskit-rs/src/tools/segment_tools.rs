@@ -38,49 +38,338 @@
  * The use of the unmodified library in proprietary software is governed solely by the LGPLv3.
  */
 
-use std::{fs::File, io::Read, path::PathBuf};
+use std::path::{Component, Path, PathBuf};
+
+use bytes::Bytes;
+use futures::{stream, StreamExt, TryStreamExt};
 
 use crate::{
-    models::hls_video::{HlsVideoResolution, HlsVideoSegment},
-    tools::hlskit_error::HlsKitError,
+    models::{
+        checksum_manifest::{ChecksumManifest, CHECKSUM_MANIFEST_NAME},
+        dynamic_range::VideoRange,
+        hls_video::{HlsVideo, HlsVideoResolution, HlsVideoSegment, MASTER_PLAYLIST_NAME},
+        hls_video_manifest::HlsVideoResolutionManifest,
+        hls_video_report::HlsVideoReport,
+        resource_usage::ResourceUsage,
+        source_adjustments::SourceAdjustments,
+    },
+    tools::{
+        hlskit_error::HlsKitError,
+        m3u8_tools::{parse_master, parse_media},
+    },
 };
 
-pub fn read_playlist_and_segments(
+/// Segment files read at once when loading a rendition into memory
+const SEGMENT_READ_CONCURRENCY: usize = 8;
+
+pub async fn read_playlist_and_segments(
     playlist_filename: &str,
-    segment_filename: &str,
     resolution: (i32, i32),
     stream_index: i32,
 ) -> Result<HlsVideoResolution, HlsKitError> {
-    let mut resolution = HlsVideoResolution {
+    let manifest = HlsVideoResolutionManifest {
         resolution,
         playlist_name: format!("playlist_{stream_index}.m3u8"),
-        playlist_data: Vec::new(),
-        segments: Vec::new(),
+        playlist_path: PathBuf::from(playlist_filename),
+        segment_paths: playlist_segment_paths(Path::new(playlist_filename)).await?,
+        video_range: VideoRange::Sdr,
+        frame_rate: None,
+        quality: None,
+        encoder: String::new(),
+        codecs: None,
+        resource_usage: ResourceUsage::default(),
+        encryption_iv: None,
     };
 
-    // Read the playlist file
-    let mut playlist_file = File::open(playlist_filename)?;
-    playlist_file.read_to_end(&mut resolution.playlist_data)?;
+    read_resolution_from_manifest(&manifest).await
+}
+
+/// The initialization section and segments referenced by the media playlist at `playlist_path`,
+/// in playlist order
+pub async fn playlist_segment_paths(playlist_path: &Path) -> Result<Vec<PathBuf>, HlsKitError> {
+    let media = parse_media(&tokio::fs::read_to_string(playlist_path).await?)?;
+    let playlist_dir = playlist_path.parent().unwrap_or(Path::new(""));
+
+    let mut segment_paths: Vec<PathBuf> = Vec::with_capacity(media.segments.len() + 1);
+    for uri in media
+        .map
+        .map(|map| map.uri)
+        .into_iter()
+        .chain(media.segments.into_iter().map(|segment| segment.uri))
+    {
+        let segment_path = resolve_local_uri(playlist_dir, &uri)?;
+        // Byte-range playlists reference the same file once per segment
+        if !segment_paths.contains(&segment_path) {
+            segment_paths.push(segment_path);
+        }
+    }
+
+    Ok(segment_paths)
+}
+
+pub(crate) fn segment_path_for(segment_filename: &str, segment_index: usize) -> PathBuf {
+    PathBuf::from(segment_filename.replace("%03d", &format!("{segment_index:03}")))
+}
+
+/// Tracks the segments of a running encode. A segment is reported once the encoder has moved on
+/// to the next one, so consumers never see a file that is still being written.
+#[derive(Debug, Clone)]
+pub struct SegmentWatcher {
+    segment_filename: String,
+    next_index: usize,
+    init_segment: Option<PathBuf>,
+}
+
+impl SegmentWatcher {
+    pub fn new(segment_filename: &str) -> Self {
+        Self {
+            segment_filename: segment_filename.to_string(),
+            next_index: 0,
+            init_segment: None,
+        }
+    }
+
+    /// Reports the fMP4 initialization section at `init_segment` along with the first segment
+    pub fn with_init_segment(mut self, init_segment: Option<PathBuf>) -> Self {
+        self.init_segment = init_segment;
+        self
+    }
+
+    /// Returns the segments completed since the previous poll
+    pub fn poll(&mut self) -> Vec<PathBuf> {
+        let mut completed = Vec::new();
+
+        while segment_path_for(&self.segment_filename, self.next_index + 1).exists() {
+            completed.push(segment_path_for(&self.segment_filename, self.next_index));
+            self.next_index += 1;
+        }
+
+        if !completed.is_empty() {
+            if let Some(init_segment) = self.init_segment.take() {
+                completed.insert(0, init_segment);
+            }
+        }
+
+        completed
+    }
+
+    /// Returns every remaining segment, to be called once the encoder has exited
+    pub fn finish(&mut self) -> Vec<PathBuf> {
+        let mut completed: Vec<PathBuf> = self
+            .init_segment
+            .take()
+            .filter(|init_segment| init_segment.exists())
+            .into_iter()
+            .collect();
+
+        loop {
+            let segment_path = segment_path_for(&self.segment_filename, self.next_index);
+            if !segment_path.exists() {
+                break;
+            }
+
+            completed.push(segment_path);
+            self.next_index += 1;
+        }
+
+        completed
+    }
+}
+
+/// Reads the playlist and segments of an encoded rendition, a few segments at a time
+pub async fn read_resolution_from_manifest(
+    manifest: &HlsVideoResolutionManifest,
+) -> Result<HlsVideoResolution, HlsKitError> {
+    let playlist_data = tokio::fs::read(&manifest.playlist_path).await?;
+
+    let mut reads = Vec::with_capacity(manifest.segment_paths.len());
+    for segment_path in &manifest.segment_paths {
+        reads.push(read_segment(segment_path));
+    }
+    let segments = stream::iter(reads)
+        .buffered(SEGMENT_READ_CONCURRENCY)
+        .try_collect()
+        .await?;
+
+    Ok(HlsVideoResolution {
+        resolution: manifest.resolution,
+        playlist_name: manifest.playlist_name.clone(),
+        playlist_data: playlist_data.into(),
+        segments,
+        quality: manifest.quality,
+    })
+}
+
+async fn read_segment(segment_path: &Path) -> Result<HlsVideoSegment, HlsKitError> {
+    Ok(HlsVideoSegment {
+        segment_name: file_name_of(segment_path),
+        segment_data: tokio::fs::read(segment_path).await?.into(),
+    })
+}
+
+/// Reads an existing HLS asset (master playlist, media playlists and segments) from `dir`
+pub fn load_hls_video_from_dir(dir: &Path) -> Result<HlsVideo, HlsKitError> {
+    let master_m3u8_data = read_existing_file(&dir.join(MASTER_PLAYLIST_NAME))?;
+    let master_playlist = String::from_utf8_lossy(&master_m3u8_data);
+
+    let mut resolutions = Vec::new();
+    for variant in parse_master(&master_playlist)?.variants {
+        let playlist_name = variant.uri;
+        let playlist_path = resolve_local_uri(dir, &playlist_name)?;
+        let playlist_data = read_existing_file(&playlist_path)?;
+        let playlist_dir = playlist_path.parent().unwrap_or(dir).to_path_buf();
+        // Segment names are relative to `dir`, like every other file of the asset
+        let name_prefix = playlist_name
+            .rsplit_once('/')
+            .map_or(String::new(), |(playlist_dir, _)| {
+                format!("{playlist_dir}/")
+            });
+
+        let playlist = parse_media(&String::from_utf8_lossy(&playlist_data))?;
+        let segment_uris = playlist
+            .map
+            .map(|map| map.uri)
+            .into_iter()
+            .chain(playlist.segments.into_iter().map(|segment| segment.uri));
 
-    // Read all segment files
-    let mut segment_index = 0;
-    loop {
-        let segment_path = segment_filename.replace("%03d", &format!("{segment_index:03}"));
-        if !PathBuf::from(&segment_path).exists() {
-            break;
+        let mut segments: Vec<HlsVideoSegment> = Vec::new();
+        for segment_uri in segment_uris {
+            // Byte ranges of a single file list it once per range
+            let segment_name = format!("{name_prefix}{segment_uri}");
+            if segments
+                .iter()
+                .any(|segment| segment.segment_name == segment_name)
+            {
+                continue;
+            }
+            let segment_path = resolve_local_uri(&playlist_dir, &segment_uri)?;
+            segments.push(HlsVideoSegment {
+                segment_name,
+                segment_data: read_existing_file(&segment_path)?,
+            });
         }
 
-        let mut segment_file = File::open(&segment_path)?;
-        let mut segment_data = Vec::new();
-        segment_file.read_to_end(&mut segment_data)?;
+        resolutions.push(HlsVideoResolution {
+            resolution: variant.resolution.unwrap_or_default(),
+            playlist_name,
+            playlist_data,
+            segments,
+            quality: None,
+        });
+    }
+
+    let checksum_path = dir.join(CHECKSUM_MANIFEST_NAME);
+    let checksums = match checksum_path.is_file() {
+        true => Some(ChecksumManifest::from_json(&std::fs::read_to_string(
+            checksum_path,
+        )?)?),
+        false => None,
+    };
+
+    Ok(HlsVideo {
+        master_m3u8_data,
+        resolutions,
+        failed_profiles: Vec::new(),
+        source_adjustments: SourceAdjustments::default(),
+        resource_usage: ResourceUsage::default(),
+        checksums,
+        report: HlsVideoReport::default(),
+    })
+}
+
+/// The file `uri` refers to under `base_dir`. URLs, absolute paths and `..` components are
+/// rejected, so a playlist can't reach files outside the asset
+fn resolve_local_uri(base_dir: &Path, uri: &str) -> Result<PathBuf, HlsKitError> {
+    if uri.contains("://") {
+        return Err(HlsKitError::FileNotFound {
+            file_path: uri.to_string(),
+        });
+    }
+
+    let escapes = Path::new(uri)
+        .components()
+        .any(|component| !matches!(component, Component::Normal(_) | Component::CurDir));
+    if escapes {
+        return Err(HlsKitError::UnsupportedOperation {
+            error: format!("playlist URI {uri:?} points outside the asset directory"),
+        });
+    }
+
+    Ok(base_dir.join(uri))
+}
 
-        let segment = HlsVideoSegment {
-            segment_name: format!("data_{stream_index}_{segment_index:03}.ts"),
-            segment_data,
-        };
-        resolution.segments.push(segment);
-        segment_index += 1;
+fn read_existing_file(path: &Path) -> Result<Bytes, HlsKitError> {
+    if !path.is_file() {
+        return Err(HlsKitError::FileNotFound {
+            file_path: path.to_string_lossy().into_owned(),
+        });
     }
 
-    Ok(resolution)
+    Ok(std::fs::read(path)?.into())
+}
+
+pub(crate) fn file_name_of(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MEDIA_PLAYLIST: &str =
+        "#EXTM3U\n#EXT-X-TARGETDURATION:6\n#EXTINF:6.0,\ndata_0_000.ts\n#EXT-X-ENDLIST\n";
+
+    fn write_asset(dir: &Path, variant_uri: &str, segment_uri: &str) {
+        let master = format!(
+            "#EXTM3U\n#EXT-X-STREAM-INF:BANDWIDTH=1000,RESOLUTION=640x360\n{variant_uri}\n"
+        );
+        std::fs::write(dir.join(MASTER_PLAYLIST_NAME), master).unwrap();
+        std::fs::write(
+            dir.join("playlist_0.m3u8"),
+            MEDIA_PLAYLIST.replace("data_0_000.ts", segment_uri),
+        )
+        .unwrap();
+        std::fs::write(dir.join("data_0_000.ts"), [0x47; 188]).unwrap();
+    }
+
+    #[test]
+    fn loads_an_asset_within_the_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        write_asset(dir.path(), "playlist_0.m3u8", "./data_0_000.ts");
+
+        let video = load_hls_video_from_dir(dir.path()).unwrap();
+        assert_eq!(video.resolutions[0].segments.len(), 1);
+    }
+
+    #[test]
+    fn rejects_uris_leaving_the_directory() {
+        let parent = tempfile::tempdir().unwrap();
+        let dir = parent.path().join("asset");
+        std::fs::create_dir(&dir).unwrap();
+        std::fs::write(parent.path().join("secret.ts"), b"secret").unwrap();
+        std::fs::copy(
+            parent.path().join("secret.ts"),
+            parent.path().join("secret.m3u8"),
+        )
+        .unwrap();
+
+        let secret = parent.path().join("secret.ts");
+        for (variant_uri, segment_uri) in [
+            ("../secret.m3u8", "data_0_000.ts"),
+            ("playlist_0.m3u8", "../secret.ts"),
+            ("playlist_0.m3u8", "segments/../../secret.ts"),
+            ("playlist_0.m3u8", secret.to_str().unwrap()),
+        ] {
+            write_asset(&dir, variant_uri, segment_uri);
+            assert!(
+                matches!(
+                    load_hls_video_from_dir(&dir),
+                    Err(HlsKitError::UnsupportedOperation { .. })
+                ),
+                "{variant_uri} / {segment_uri} should be rejected"
+            );
+        }
+    }
 }
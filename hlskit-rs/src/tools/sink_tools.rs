@@ -0,0 +1,207 @@
+// SPDX-License-Identifier: LGPL-3.0-only
+/*
+ * Copyright © 2025 The HlsKit Project
+ *
+ * This software is licensed under the GNU Lesser General Public License v3.0 (LGPLv3).
+ * All contributions adhere to the LGPLv3 and the HlsKit Contributor License Agreement (CLA).
+ * A copy of the LGPLv3 can be found at https://www.gnu.org/licenses/lgpl-3.0.html
+ *
+ * HlsKit Contributor License Agreement
+ *
+ * By contributing to or modifying HlsKit, you agree to the following terms:
+ *
+ * 1. Collective Ownership:
+ * The HlsKit project incorporates original code and all contributions as a collective work,
+ * licensed under LGPLv3. Once submitted, contributions become part of the shared HlsKit
+ * ecosystem and cannot be reclaimed, reassigned, or withdrawn. Contributions to your own
+ * forks remain yours unless submitted here, at which point they join this collective whole under LGPLv3.
+ *
+ * 2. Definition of Contribution:
+ * You are considered a contributor if you modify the library in any form (including forks,
+ * wrappers, libraries, or extensions that alter its behavior), whether or not you submit
+ * your changes directly to this repository. All such modifications are part of the broader
+ * HlsKit ecosystem and are subject to this CLA.
+ *
+ * 3. Distribution of Modifications:
+ * If you distribute a modified version of HlsKit, you must license your modifications under
+ * LGPLv3 (with source code available as required by the license) and ensure they are
+ * adoptable by the HlsKit ecosystem (publicly available and compatible).
+ *
+ * 4. Networked Use of Modifications:
+ * If you use a modified version of HlsKit in a networked application, you must provide the
+ * source code of your modifications under LGPLv3 and notify the HlsKit project
+ * (e.g., via email to [higashikataengels@icloud.com]). This does not apply to the use of
+ * the unmodified library in proprietary software, which remains permissible under LGPLv3.
+ *
+ * 5. Scope:
+ * These terms apply to all contributions and modifications derived from the HlsKit project.
+ * The use of the unmodified library in proprietary software is governed solely by the LGPLv3.
+ */
+
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+};
+
+use bytes::Bytes;
+use futures::{stream, stream::FuturesUnordered, StreamExt, TryStreamExt};
+use tokio::sync::mpsc::UnboundedReceiver;
+
+use crate::{
+    models::{
+        hls_artifact::{HlsArtifact, HlsArtifactKind},
+        hls_video::MASTER_PLAYLIST_NAME,
+        hls_video_manifest::HlsVideoResolutionManifest,
+    },
+    tools::{
+        hlskit_error::HlsKitError, report_tools::RemovedSegments, segment_tools::file_name_of,
+    },
+    traits::output_sink::OutputSink,
+};
+
+const SINK_WRITE_CONCURRENCY: usize = 4;
+
+/// Forwards a rendition to the sink, segments first so the playlist never references missing files
+pub async fn write_resolution_to_sink<K: OutputSink>(
+    manifest: &HlsVideoResolutionManifest,
+    stream_index: i32,
+    sink: &K,
+) -> Result<(), HlsKitError> {
+    let mut uploads = Vec::with_capacity(manifest.segment_paths.len());
+    for segment_path in &manifest.segment_paths {
+        uploads.push(write_segment_to_sink(segment_path, stream_index, sink));
+    }
+
+    stream::iter(uploads)
+        .buffer_unordered(SINK_WRITE_CONCURRENCY)
+        .try_collect::<()>()
+        .await?;
+
+    let playlist = HlsArtifact {
+        name: manifest.playlist_name.clone(),
+        kind: HlsArtifactKind::MediaPlaylist,
+        stream_index: Some(stream_index),
+        data: tokio::fs::read(&manifest.playlist_path).await?.into(),
+    };
+    sink.write_artifact(playlist).await
+}
+
+async fn write_segment_to_sink<K: OutputSink>(
+    segment_path: &Path,
+    stream_index: i32,
+    sink: &K,
+) -> Result<(), HlsKitError> {
+    let artifact = HlsArtifact {
+        name: file_name_of(segment_path),
+        kind: HlsArtifactKind::Segment,
+        stream_index: Some(stream_index),
+        data: tokio::fs::read(segment_path).await?.into(),
+    };
+    sink.write_artifact(artifact).await
+}
+
+/// A file handed to `stream_to_sink` while the renditions are being encoded
+#[derive(Debug)]
+pub(crate) enum SinkUpload {
+    Segment {
+        stream_index: i32,
+        path: PathBuf,
+    },
+    /// A finished rendition, its playlist only written once all its segments are
+    Rendition {
+        stream_index: i32,
+        manifest: Box<HlsVideoResolutionManifest>,
+    },
+}
+
+/// Writes the files received on `uploads` to `sink` as they come, until every sender is
+/// dropped. Segments are written side by side, each media playlist once every segment queued
+/// before it is stored. With `remove_segments`, segments are deleted once stored, their sizes
+/// returned for the report
+pub(crate) async fn stream_to_sink<K: OutputSink>(
+    mut uploads: UnboundedReceiver<SinkUpload>,
+    sink: &K,
+    remove_segments: bool,
+) -> Result<RemovedSegments, HlsKitError> {
+    let mut queued = HashSet::new();
+    let mut pending = FuturesUnordered::new();
+    let mut removed = RemovedSegments::new();
+
+    loop {
+        tokio::select! {
+            Some(result) = pending.next(), if !pending.is_empty() => removed.extend(result?),
+            upload = uploads.recv() => match upload {
+                Some(SinkUpload::Segment { stream_index, path }) => {
+                    if queued.insert(path.clone()) {
+                        if pending.len() >= SINK_WRITE_CONCURRENCY {
+                            if let Some(result) = pending.next().await {
+                                removed.extend(result?);
+                            }
+                        }
+                        pending.push(stream_segment_to_sink(path, stream_index, sink, remove_segments));
+                    }
+                }
+                // Backends that don't report their segments have them written now
+                Some(SinkUpload::Rendition { stream_index, manifest }) => {
+                    for path in &manifest.segment_paths {
+                        if queued.insert(path.clone()) {
+                            pending.push(stream_segment_to_sink(
+                                path.clone(),
+                                stream_index,
+                                sink,
+                                remove_segments,
+                            ));
+                        }
+                    }
+                    while let Some(result) = pending.next().await {
+                        removed.extend(result?);
+                    }
+
+                    let playlist = HlsArtifact {
+                        name: manifest.playlist_name.clone(),
+                        kind: HlsArtifactKind::MediaPlaylist,
+                        stream_index: Some(stream_index),
+                        data: tokio::fs::read(&manifest.playlist_path).await?.into(),
+                    };
+                    sink.write_artifact(playlist).await?;
+                }
+                None => break,
+            },
+        }
+    }
+
+    while let Some(result) = pending.next().await {
+        removed.extend(result?);
+    }
+
+    Ok(removed)
+}
+
+async fn stream_segment_to_sink<K: OutputSink>(
+    path: PathBuf,
+    stream_index: i32,
+    sink: &K,
+    remove_segment: bool,
+) -> Result<Option<(PathBuf, u64)>, HlsKitError> {
+    write_segment_to_sink(&path, stream_index, sink).await?;
+    if !remove_segment {
+        return Ok(None);
+    }
+
+    let size = tokio::fs::metadata(&path).await?.len();
+    tokio::fs::remove_file(&path).await?;
+    Ok(Some((path, size)))
+}
+
+pub async fn write_master_playlist_to_sink<K: OutputSink>(
+    master_playlist: &Bytes,
+    sink: &K,
+) -> Result<(), HlsKitError> {
+    let master_playlist = HlsArtifact {
+        name: MASTER_PLAYLIST_NAME.to_string(),
+        kind: HlsArtifactKind::MasterPlaylist,
+        stream_index: None,
+        data: master_playlist.clone(),
+    };
+    sink.write_artifact(master_playlist).await
+}
@@ -0,0 +1,360 @@
+// SPDX-License-Identifier: LGPL-3.0-only
+/*
+ * Copyright © 2025 The HlsKit Project
+ *
+ * This software is licensed under the GNU Lesser General Public License v3.0 (LGPLv3).
+ * All contributions adhere to the LGPLv3 and the HlsKit Contributor License Agreement (CLA).
+ * A copy of the LGPLv3 can be found at https://www.gnu.org/licenses/lgpl-3.0.html
+ *
+ * HlsKit Contributor License Agreement
+ *
+ * By contributing to or modifying HlsKit, you agree to the following terms:
+ *
+ * 1. Collective Ownership:
+ * The HlsKit project incorporates original code and all contributions as a collective work,
+ * licensed under LGPLv3. Once submitted, contributions become part of the shared HlsKit
+ * ecosystem and cannot be reclaimed, reassigned, or withdrawn. Contributions to your own
+ * forks remain yours unless submitted here, at which point they join this collective whole under LGPLv3.
+ *
+ * 2. Definition of Contribution:
+ * You are considered a contributor if you modify the library in any form (including forks,
+ * wrappers, libraries, or extensions that alter its behavior), whether or not you submit
+ * your changes directly to this repository. All such modifications are part of the broader
+ * HlsKit ecosystem and are subject to this CLA.
+ *
+ * 3. Distribution of Modifications:
+ * If you distribute a modified version of HlsKit, you must license your modifications under
+ * LGPLv3 (with source code available as required by the license) and ensure they are
+ * adoptable by the HlsKit ecosystem (publicly available and compatible).
+ *
+ * 4. Networked Use of Modifications:
+ * If you use a modified version of HlsKit in a networked application, you must provide the
+ * source code of your modifications under LGPLv3 and notify the HlsKit project
+ * (e.g., via email to [higashikataengels@icloud.com]). This does not apply to the use of
+ * the unmodified library in proprietary software, which remains permissible under LGPLv3.
+ *
+ * 5. Scope:
+ * These terms apply to all contributions and modifications derived from the HlsKit project.
+ * The use of the unmodified library in proprietary software is governed solely by the LGPLv3.
+ */
+
+use std::{
+    sync::Mutex,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use hmac::{Hmac, Mac};
+use serde_json::{json, Value};
+use sha2::Sha256;
+use tokio::sync::{mpsc, oneshot};
+
+use crate::models::{job_event::JobEvent, job_id::JobId};
+
+/// Header carrying the hex HMAC-SHA256 of the request body, prefixed with `sha256=`
+pub const SIGNATURE_HEADER: &str = "X-HlsKit-Signature";
+/// Header carrying the event name, e.g. `job.completed`
+pub const EVENT_HEADER: &str = "X-HlsKit-Event";
+
+/// POSTs job lifecycle events as JSON to a URL, retrying failed deliveries
+///
+/// Only `job.started`, `profile.completed`, `profile.failed`, `job.completed` and `job.failed`
+/// are sent.
+/// Deliveries happen in order on a background task, spawned again on the current runtime
+/// once the previous one is gone; a job waits for its pending deliveries before returning.
+#[derive(Debug)]
+pub struct WebhookNotifier {
+    config: WebhookConfig,
+    sender: Mutex<Option<mpsc::UnboundedSender<Delivery>>>,
+}
+
+#[derive(Debug, Clone)]
+struct WebhookConfig {
+    url: String,
+    signing_secret: Option<Vec<u8>>,
+    max_retries: u32,
+    retry_delay: Duration,
+    timeout: Duration,
+    client: reqwest::Client,
+}
+
+#[derive(Debug)]
+enum Delivery {
+    Event { name: &'static str, body: Vec<u8> },
+    Flush(oneshot::Sender<()>),
+}
+
+impl WebhookNotifier {
+    pub fn new(url: &str) -> Self {
+        Self {
+            config: WebhookConfig {
+                url: url.to_string(),
+                signing_secret: None,
+                max_retries: 3,
+                retry_delay: Duration::from_secs(1),
+                timeout: Duration::from_secs(10),
+                client: reqwest::Client::new(),
+            },
+            sender: Mutex::new(None),
+        }
+    }
+
+    /// Signs every request body with HMAC-SHA256 using `secret`
+    pub fn with_signing_secret(mut self, secret: &[u8]) -> Self {
+        self.config.signing_secret = Some(secret.to_vec());
+        self
+    }
+
+    pub fn with_max_retries(mut self, max_retries: u32) -> Self {
+        self.config.max_retries = max_retries;
+        self
+    }
+
+    /// Delay before the first retry, doubled after every further failed attempt
+    pub fn with_retry_delay(mut self, delay: Duration) -> Self {
+        self.config.retry_delay = delay;
+        self
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.config.timeout = timeout;
+        self
+    }
+
+    pub fn url(&self) -> &str {
+        &self.config.url
+    }
+
+    /// Queues `event` for delivery if it is a lifecycle event worth notifying
+    pub fn notify(&self, job_id: Option<JobId>, event: &JobEvent) {
+        let Some((name, payload)) = payload_of(job_id, event) else {
+            return;
+        };
+
+        let Some(sender) = self.sender() else {
+            tracing::warn!("Webhook event {} dropped: no tokio runtime", name);
+            return;
+        };
+
+        let body = payload.to_string().into_bytes();
+        if sender.send(Delivery::Event { name, body }).is_err() {
+            tracing::warn!("Webhook event {} dropped: delivery task stopped", name);
+        }
+    }
+
+    /// Waits until every event queued so far was delivered or given up on
+    pub async fn flush(&self) {
+        let Some(sender) = self.sender.lock().unwrap().clone() else {
+            return;
+        };
+
+        let (done, flushed) = oneshot::channel();
+        if sender.send(Delivery::Flush(done)).is_ok() {
+            let _ = flushed.await;
+        }
+    }
+
+    fn sender(&self) -> Option<mpsc::UnboundedSender<Delivery>> {
+        let mut current = self.sender.lock().unwrap();
+        // The delivery task dies with the runtime it was spawned on, so a notifier reused
+        // across runtimes needs a new one
+        if let Some(sender) = current.as_ref().filter(|sender| !sender.is_closed()) {
+            return Some(sender.clone());
+        }
+
+        let runtime = tokio::runtime::Handle::try_current().ok()?;
+        let (sender, receiver) = mpsc::unbounded_channel();
+        runtime.spawn(deliver_all(self.config.clone(), receiver));
+        *current = Some(sender.clone());
+        Some(sender)
+    }
+}
+
+async fn deliver_all(config: WebhookConfig, mut receiver: mpsc::UnboundedReceiver<Delivery>) {
+    while let Some(delivery) = receiver.recv().await {
+        match delivery {
+            Delivery::Event { name, body } => deliver(&config, name, body).await,
+            Delivery::Flush(done) => {
+                let _ = done.send(());
+            }
+        }
+    }
+}
+
+async fn deliver(config: &WebhookConfig, name: &'static str, body: Vec<u8>) {
+    let signature = config
+        .signing_secret
+        .as_deref()
+        .map(|secret| sign(secret, &body));
+
+    for attempt in 0..=config.max_retries {
+        if attempt > 0 {
+            tokio::time::sleep(config.retry_delay * 2u32.saturating_pow(attempt - 1)).await;
+        }
+
+        let mut request = config
+            .client
+            .post(&config.url)
+            .timeout(config.timeout)
+            .header("Content-Type", "application/json")
+            .header(EVENT_HEADER, name)
+            .body(body.clone());
+        if let Some(signature) = &signature {
+            request = request.header(SIGNATURE_HEADER, signature);
+        }
+
+        match request.send().await {
+            Ok(response) if response.status().is_success() => return,
+            Ok(response) => {
+                let status = response.status();
+                tracing::warn!(
+                    "Webhook {} to {} answered {} (attempt {})",
+                    name,
+                    config.url,
+                    status,
+                    attempt + 1
+                );
+                // Client errors won't go away by sending the same request again
+                if status.is_client_error() && status != reqwest::StatusCode::TOO_MANY_REQUESTS {
+                    return;
+                }
+            }
+            Err(e) => tracing::warn!(
+                "Webhook {} to {} failed: {} (attempt {})",
+                name,
+                config.url,
+                e,
+                attempt + 1
+            ),
+        }
+    }
+
+    tracing::error!("Giving up on webhook {} to {}", name, config.url);
+}
+
+fn sign(secret: &[u8], body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret).expect("HMAC accepts keys of any length");
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+fn payload_of(job_id: Option<JobId>, event: &JobEvent) -> Option<(&'static str, Value)> {
+    let (name, mut payload) = match event {
+        JobEvent::JobStarted { profile_count } => {
+            ("job.started", json!({ "profile_count": profile_count }))
+        }
+        JobEvent::ProfileCompleted {
+            stream_index,
+            resolution,
+            segment_count,
+        } => (
+            "profile.completed",
+            json!({
+                "stream_index": stream_index,
+                "resolution": [resolution.0, resolution.1],
+                "segment_count": segment_count,
+            }),
+        ),
+        JobEvent::ProfileFailed {
+            stream_index,
+            resolution,
+            error,
+        } => (
+            "profile.failed",
+            json!({
+                "stream_index": stream_index,
+                "resolution": [resolution.0, resolution.1],
+                "error": error,
+            }),
+        ),
+        JobEvent::JobCompleted { resolution_count } => (
+            "job.completed",
+            json!({ "resolution_count": resolution_count }),
+        ),
+        JobEvent::JobFailed { error } => ("job.failed", json!({ "error": error })),
+        _ => return None,
+    };
+
+    payload["event"] = json!(name);
+    payload["job_id"] = json!(job_id.map(|id| id.to_string()));
+    payload["timestamp"] = json!(SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs()));
+
+    Some((name, payload))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{Read, Write},
+        net::TcpListener,
+        sync::mpsc as std_mpsc,
+        thread,
+    };
+
+    use super::*;
+
+    /// Answers every request with `204 No Content`, reporting each event name it received
+    fn serve() -> (String, std_mpsc::Receiver<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let (events, received) = std_mpsc::channel();
+
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut request = Vec::new();
+                let mut buffer = [0; 4096];
+                // Headers and the small JSON body arrive well before the connection stalls
+                while !request.ends_with(b"}") {
+                    let read = stream.read(&mut buffer).unwrap();
+                    if read == 0 {
+                        break;
+                    }
+                    request.extend_from_slice(&buffer[..read]);
+                }
+
+                let request = String::from_utf8_lossy(&request).to_lowercase();
+                let event = request
+                    .lines()
+                    .find_map(|line| line.strip_prefix("x-hlskit-event: "))
+                    .unwrap_or_default()
+                    .trim()
+                    .to_string();
+                let _ = stream.write_all(b"HTTP/1.1 204 No Content\r\nConnection: close\r\n\r\n");
+                let _ = events.send(event);
+            }
+        });
+
+        (url, received)
+    }
+
+    fn notify_on_new_runtime(notifier: &WebhookNotifier, event: JobEvent) {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        runtime.block_on(async {
+            notifier.notify(None, &event);
+            notifier.flush().await;
+        });
+    }
+
+    #[test]
+    fn delivers_across_runtimes() {
+        let (url, received) = serve();
+        let notifier = WebhookNotifier::new(&url).with_max_retries(0);
+
+        notify_on_new_runtime(&notifier, JobEvent::JobStarted { profile_count: 1 });
+        notify_on_new_runtime(
+            &notifier,
+            JobEvent::JobCompleted {
+                resolution_count: 1,
+            },
+        );
+
+        let timeout = Duration::from_secs(5);
+        assert_eq!(received.recv_timeout(timeout).unwrap(), "job.started");
+        assert_eq!(received.recv_timeout(timeout).unwrap(), "job.completed");
+    }
+}
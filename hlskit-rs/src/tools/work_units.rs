@@ -0,0 +1,126 @@
+// SPDX-License-Identifier: LGPL-3.0-only
+/*
+ * Copyright © 2025 The HlsKit Project
+ *
+ * This software is licensed under the GNU Lesser General Public License v3.0 (LGPLv3).
+ * All contributions adhere to the LGPLv3 and the HlsKit Contributor License Agreement (CLA).
+ * A copy of the LGPLv3 can be found at https://www.gnu.org/licenses/lgpl-3.0.html
+ *
+ * HlsKit Contributor License Agreement
+ *
+ * By contributing to or modifying HlsKit, you agree to the following terms:
+ *
+ * 1. Collective Ownership:
+ * The HlsKit project incorporates original code and all contributions as a collective work,
+ * licensed under LGPLv3. Once submitted, contributions become part of the shared HlsKit
+ * ecosystem and cannot be reclaimed, reassigned, or withdrawn. Contributions to your own
+ * forks remain yours unless submitted here, at which point they join this collective whole under LGPLv3.
+ *
+ * 2. Definition of Contribution:
+ * You are considered a contributor if you modify the library in any form (including forks,
+ * wrappers, libraries, or extensions that alter its behavior), whether or not you submit
+ * your changes directly to this repository. All such modifications are part of the broader
+ * HlsKit ecosystem and are subject to this CLA.
+ *
+ * 3. Distribution of Modifications:
+ * If you distribute a modified version of HlsKit, you must license your modifications under
+ * LGPLv3 (with source code available as required by the license) and ensure they are
+ * adoptable by the HlsKit ecosystem (publicly available and compatible).
+ *
+ * 4. Networked Use of Modifications:
+ * If you use a modified version of HlsKit in a networked application, you must provide the
+ * source code of your modifications under LGPLv3 and notify the HlsKit project
+ * (e.g., via email to [higashikataengels@icloud.com]). This does not apply to the use of
+ * the unmodified library in proprietary software, which remains permissible under LGPLv3.
+ *
+ * 5. Scope:
+ * These terms apply to all contributions and modifications derived from the HlsKit project.
+ * The use of the unmodified library in proprietary software is governed solely by the LGPLv3.
+ */
+
+use bytes::Bytes;
+
+use crate::{
+    models::{
+        hls_video::{HlsVideo, HlsVideoResolution},
+        resource_usage::ResourceUsage,
+        work_unit::WorkUnitResult,
+    },
+    tools::{
+        concat_tools::concatenate_renditions,
+        hlskit_error::{HlsKitError, VideoValidatableErrors},
+        m3u8_tools::{master_playlist_for_variants, MasterPlaylistVariant},
+        report_tools::resolution_bitrates,
+    },
+};
+
+/// Assembles the `HlsVideo` of a job from the results of all its work units, in any order.
+/// The chunks of a profile are joined into one rendition, separated by
+/// `#EXT-X-DISCONTINUITY`, their segments prefixed with `{chunk_index:03}_`
+pub fn merge_results(mut results: Vec<WorkUnitResult>) -> Result<HlsVideo, HlsKitError> {
+    results.sort_by_key(|result| (result.stream_index, result.chunk_index));
+
+    let mut renditions: Vec<Vec<WorkUnitResult>> = Vec::new();
+    for result in results {
+        match renditions.last_mut() {
+            Some(chunks) if chunks[0].stream_index == result.stream_index => chunks.push(result),
+            _ => renditions.push(vec![result]),
+        }
+    }
+    if renditions.is_empty() {
+        return Err(invalid_results("no work unit results to merge".to_string()));
+    }
+
+    let mut resolutions = Vec::with_capacity(renditions.len());
+    let mut resource_usage = ResourceUsage::default();
+    for chunks in &renditions {
+        for (index, chunk) in chunks.iter().enumerate() {
+            if chunk.chunk_index != index {
+                return Err(invalid_results(format!(
+                    "chunk {} of stream {} is missing or duplicated",
+                    index, chunk.stream_index
+                )));
+            }
+            resource_usage.merge_concurrent(&chunk.resource_usage);
+        }
+        resolutions.push(merge_chunks(chunks)?);
+    }
+
+    let variants: Vec<MasterPlaylistVariant> = renditions
+        .iter()
+        .zip(&resolutions)
+        .map(|(chunks, resolution)| {
+            let (average_bandwidth, bandwidth) = resolution_bitrates(resolution);
+            MasterPlaylistVariant {
+                resolution: resolution.resolution,
+                uri: &resolution.playlist_name,
+                bandwidth,
+                average_bandwidth: Some(average_bandwidth),
+                codecs: chunks[0].codecs.as_deref(),
+                frame_rate: chunks[0].frame_rate.map(|frame_rate| frame_rate.as_f64()),
+                video_range: chunks[0].video_range,
+            }
+        })
+        .collect();
+    let master_m3u8_data = Bytes::from(master_playlist_for_variants(&variants));
+
+    Ok(HlsVideo {
+        master_m3u8_data,
+        resolutions,
+        resource_usage,
+        ..Default::default()
+    })
+}
+
+fn merge_chunks(chunks: &[WorkUnitResult]) -> Result<HlsVideoResolution, HlsKitError> {
+    if let [whole] = chunks {
+        return Ok(whole.resolution.clone());
+    }
+
+    let parts: Vec<&HlsVideoResolution> = chunks.iter().map(|chunk| &chunk.resolution).collect();
+    concatenate_renditions(&parts)
+}
+
+fn invalid_results(error: String) -> HlsKitError {
+    VideoValidatableErrors::InvalidVideoInput { error }.into()
+}
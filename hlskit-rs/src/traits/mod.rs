@@ -38,5 +38,12 @@
  * The use of the unmodified library in proprietary software is governed solely by the LGPLv3.
  */
 
+pub mod command_interceptor;
+pub mod key_provider;
+pub mod output_sink;
+#[cfg(feature = "cache")]
+pub mod result_cache;
+pub mod sandbox_policy;
+pub mod url_signer;
 pub mod video_processing_backend;
 pub mod video_validatable;
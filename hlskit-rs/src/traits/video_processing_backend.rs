@@ -42,13 +42,82 @@ use std::path::Path;
 
 use crate::{
     models::{
-        hls_video::HlsVideoResolution, hls_video_processing_settings::HlsVideoProcessingSettings,
+        hls_video::HlsVideoResolution, hls_video_manifest::HlsVideoResolutionManifest,
+        hls_video_processing_settings::HlsVideoProcessingSettings, processing_plan::ProfilePlan,
+    },
+    tools::{
+        hlskit_error::HlsKitError, processing_context::ProcessingContext,
+        segment_tools::read_resolution_from_manifest,
     },
-    tools::hlskit_error::HlsKitError,
     VideoProcessorEncryptionSettings,
 };
 
-pub trait VideoProcessingBackend {
+/// Input handed to backends in place of a path when the source is written to their stdin
+pub const STDIN_INPUT: &str = "pipe:0";
+
+pub trait VideoProcessingBackend: Send + Sync {
+    /// Short name identifying the backend in logs and tracing spans
+    fn name(&self) -> &'static str {
+        std::any::type_name::<Self>()
+    }
+
+    /// Whether the backend can encode `STDIN_INPUT`, reading in-memory inputs from the
+    /// `ProcessingContext::stdin_input` written to its commands instead of a temp file
+    fn reads_stdin(&self) -> bool {
+        false
+    }
+
+    /// Builds the command `encode_profile` would run for a profile, without running it
+    fn plan_profile(
+        &self,
+        _input: &str,
+        _profile: &HlsVideoProcessingSettings,
+        _output_dir: &Path,
+        _stream_index: i32,
+        _encryption: Option<&VideoProcessorEncryptionSettings>,
+        _context: &ProcessingContext,
+    ) -> Result<ProfilePlan, HlsKitError> {
+        Err(HlsKitError::UnsupportedOperation {
+            error: "this backend cannot plan encodes".to_string(),
+        })
+    }
+
+    /// Encodes a single profile into `output_dir`, leaving the playlist and segments on disk
+    fn encode_profile(
+        &self,
+        input: String,
+        profile: &HlsVideoProcessingSettings,
+        output_dir: &Path,
+        stream_index: i32,
+        encryption: Option<&VideoProcessorEncryptionSettings>,
+        context: &ProcessingContext,
+    ) -> impl std::future::Future<Output = Result<HlsVideoResolutionManifest, HlsKitError>> + Send;
+
+    /// Whether `encode_profiles` can encode these profiles in a single run, e.g. decoding the
+    /// source once for every rendition
+    fn encodes_together(&self, _profiles: &[HlsVideoProcessingSettings]) -> bool {
+        false
+    }
+
+    /// Encodes every profile in a single run, stream indexes following the order of
+    /// `profiles`. Only called when `encodes_together` agrees
+    fn encode_profiles(
+        &self,
+        _input: String,
+        _profiles: &[HlsVideoProcessingSettings],
+        _output_dir: &Path,
+        _encryption: Option<&VideoProcessorEncryptionSettings>,
+        _context: &ProcessingContext,
+    ) -> impl std::future::Future<Output = Result<Vec<HlsVideoResolutionManifest>, HlsKitError>> + Send
+    {
+        async {
+            Err(HlsKitError::UnsupportedOperation {
+                error: "this backend encodes profile by profile".to_string(),
+            })
+        }
+    }
+
+    /// Encodes a single profile and reads the produced playlist and segments into memory
     fn process_profile(
         &self,
         input: String,
@@ -56,5 +125,21 @@ pub trait VideoProcessingBackend {
         output_dir: &Path,
         stream_index: i32,
         encryption: Option<&VideoProcessorEncryptionSettings>,
-    ) -> impl std::future::Future<Output = Result<HlsVideoResolution, HlsKitError>>;
+        context: &ProcessingContext,
+    ) -> impl std::future::Future<Output = Result<HlsVideoResolution, HlsKitError>> + Send {
+        async move {
+            let manifest = self
+                .encode_profile(
+                    input,
+                    profile,
+                    output_dir,
+                    stream_index,
+                    encryption,
+                    context,
+                )
+                .await?;
+
+            read_resolution_from_manifest(&manifest).await
+        }
+    }
 }
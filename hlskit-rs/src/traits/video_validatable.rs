@@ -38,10 +38,28 @@
  * The use of the unmodified library in proprietary software is governed solely by the LGPLv3.
  */
 
+use std::path::Path;
+
 use crate::tools::hlskit_error::VideoValidatableErrors;
 
 pub trait VideoValidatable {
     fn validate(&self) -> Result<VideoInputPathGuard, VideoValidatableErrors>;
+
+    /// Like `validate`, creating any temporary copy of the input inside `temp_dir`
+    fn validate_in(&self, _temp_dir: &Path) -> Result<VideoInputPathGuard, VideoValidatableErrors> {
+        self.validate()
+    }
+
+    /// The user-supplied path read by `validate`, if any, checked against the `PathPolicy`
+    fn source_path(&self) -> Option<&str> {
+        None
+    }
+
+    /// The input's bytes, checked like `validate` checks them, when the input is held in
+    /// memory. Lets backends reading stdin skip the temp file `validate` writes
+    fn validate_in_memory(&self) -> Option<Result<&[u8], VideoValidatableErrors>> {
+        None
+    }
 }
 
 pub struct VideoInputPathGuard {